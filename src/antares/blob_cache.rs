@@ -0,0 +1,323 @@
+//! Content-addressed blob cache shared by all CL layers.
+//!
+//! Blobs downloaded for one CL layer are kept under `{root}/{oid[..2]}/{oid}` and
+//! materialized into every layer that needs them by hardlink, falling back to a
+//! reflink/copy when the layer lives on another filesystem. CL layers are read-only
+//! lowers, so sharing the inode between layers is safe.
+//!
+//! Entries are written to a staging file and renamed into place, so concurrent inserts of
+//! the same OID race harmlessly. [`BlobCache::gc`] drops entries outside a caller-provided
+//! live set; materialized hardlinks keep their data either way.
+
+use std::{
+    collections::HashSet,
+    fs, io,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use uuid::Uuid;
+
+use super::snapshot::{self, CopyMethod};
+
+/// How a cached blob ended up in a CL layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Materialized {
+    Hardlink,
+    Copy(CopyMethod),
+}
+
+/// Counters from one garbage-collection pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlobGcStats {
+    pub kept: u64,
+    pub removed: u64,
+    pub removed_bytes: u64,
+}
+
+/// Filesystem-backed blob cache keyed by OID.
+#[derive(Debug, Clone)]
+pub struct BlobCache {
+    root: PathBuf,
+}
+
+impl BlobCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Canonical form of `oid` used as the cache key, or `None` if it cannot be cached.
+    ///
+    /// Strips the `sha1:` prefix used by the files-list and rejects anything that is not a
+    /// plain hex id, so a key can never escape the cache root.
+    pub fn key(oid: &str) -> Option<String> {
+        let oid = oid.trim().trim_start_matches("sha1:");
+        (oid.len() >= 4 && oid.bytes().all(|b| b.is_ascii_hexdigit()))
+            .then(|| oid.to_ascii_lowercase())
+    }
+
+    fn blob_path(&self, key: &str) -> PathBuf {
+        self.root.join(&key[..2]).join(key)
+    }
+
+    /// Whether the blob is cached.
+    pub fn contains(&self, oid: &str) -> bool {
+        Self::key(oid).is_some_and(|key| self.blob_path(&key).is_file())
+    }
+
+    /// Store `data` under `oid`. Inserting an OID that is already cached is a no-op.
+    pub fn insert(&self, oid: &str, data: &[u8]) -> io::Result<()> {
+        let key = Self::key(oid).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("invalid oid {oid:?}"))
+        })?;
+        let path = self.blob_path(&key);
+        if path.is_file() {
+            return Ok(());
+        }
+        let dir = path.parent().expect("blob path has a parent");
+        fs::create_dir_all(dir)?;
+        let staging = dir.join(format!(".{}.{}.tmp", key, Uuid::new_v4()));
+        let result = fs::write(&staging, data).and_then(|_| fs::rename(&staging, &path));
+        if result.is_err() {
+            let _ = fs::remove_file(&staging);
+        }
+        result
+    }
+
+    /// Place the cached blob `oid` at `dest`, replacing whatever is there.
+    ///
+    /// Parents of `dest` are followed; layer builds check them first with
+    /// [`super::cl_manifest::entry_path`]. Returns `Ok(None)` on a cache miss.
+    pub fn materialize(&self, oid: &str, dest: &Path) -> io::Result<Option<Materialized>> {
+        let Some(key) = Self::key(oid) else {
+            return Ok(None);
+        };
+        let src = self.blob_path(&key);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if fs::symlink_metadata(dest).is_ok() {
+            fs::remove_file(dest)?;
+        }
+        match fs::hard_link(&src, dest) {
+            Ok(()) => Ok(Some(Materialized::Hardlink)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) if is_link_unsupported(&e) => match snapshot::copy_file(&src, dest) {
+                Ok(method) => Ok(Some(Materialized::Copy(method))),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    let _ = fs::remove_file(dest);
+                    Ok(None)
+                }
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Like [`Self::materialize`], but `dest` ends up with permission bits `perm`.
+    ///
+    /// The blob is only hardlinked when the cached copy already has `perm`; otherwise
+    /// `dest` gets a private copy, since a chmod through a hardlink would change the mode
+    /// in every layer sharing the blob.
+    pub fn materialize_with_mode(
+        &self,
+        oid: &str,
+        dest: &Path,
+        perm: u32,
+    ) -> io::Result<Option<Materialized>> {
+        let Some(key) = Self::key(oid) else {
+            return Ok(None);
+        };
+        let src = self.blob_path(&key);
+        match fs::metadata(&src) {
+            Ok(meta) if meta.permissions().mode() & 0o7777 == perm => {
+                return self.materialize(oid, dest)
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if fs::symlink_metadata(dest).is_ok() {
+            fs::remove_file(dest)?;
+        }
+        match snapshot::copy_file(&src, dest) {
+            Ok(method) => {
+                fs::set_permissions(dest, fs::Permissions::from_mode(perm))?;
+                Ok(Some(Materialized::Copy(method)))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let _ = fs::remove_file(dest);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Contents of the cached blob `oid`, or `None` on a cache miss.
+    pub fn read(&self, oid: &str) -> io::Result<Option<Vec<u8>>> {
+        let Some(key) = Self::key(oid) else {
+            return Ok(None);
+        };
+        match fs::read(self.blob_path(&key)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Remove every cached blob whose key is not in `live` (keys as returned by [`Self::key`]).
+    pub fn gc(&self, live: &HashSet<String>) -> io::Result<BlobGcStats> {
+        let mut stats = BlobGcStats::default();
+        if !self.root.exists() {
+            return Ok(stats);
+        }
+        for shard in fs::read_dir(&self.root)? {
+            let shard = shard?;
+            if !shard.file_type()?.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(shard.path())? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                // Staging files belong to in-flight inserts.
+                if name.starts_with('.') || live.contains(&name) {
+                    stats.kept += 1;
+                    continue;
+                }
+                let len = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                match fs::remove_file(entry.path()) {
+                    Ok(()) => {
+                        stats.removed += 1;
+                        stats.removed_bytes += len;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            // Drop shards left empty; fails harmlessly if another insert got there first.
+            let _ = fs::remove_dir(shard.path());
+        }
+        Ok(stats)
+    }
+}
+
+fn is_link_unsupported(e: &io::Error) -> bool {
+    matches!(
+        e.raw_os_error(),
+        Some(libc::EXDEV) | Some(libc::EPERM) | Some(libc::EMLINK) | Some(libc::EOPNOTSUPP)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::MetadataExt;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_key_rejects_non_hex_oids() {
+        assert_eq!(BlobCache::key("sha1:ABCdef01").as_deref(), Some("abcdef01"));
+        assert!(BlobCache::key("../../etc/passwd").is_none());
+        assert!(BlobCache::key("ab").is_none());
+        assert!(BlobCache::key("").is_none());
+    }
+
+    #[test]
+    fn test_materialize_hardlinks_shared_blob() {
+        let tmp = tempdir().unwrap();
+        let cache = BlobCache::new(tmp.path().join("blobs"));
+        let a = tmp.path().join("cl-a/src/lib.rs");
+        let b = tmp.path().join("cl-b/src/lib.rs");
+
+        assert_eq!(cache.materialize("deadbeef", &a).unwrap(), None);
+        cache.insert("sha1:deadbeef", b"fn main() {}").unwrap();
+        assert!(cache.contains("deadbeef"));
+        // A second insert of the same OID leaves the entry alone.
+        cache.insert("deadbeef", b"ignored").unwrap();
+
+        fs::create_dir_all(b.parent().unwrap()).unwrap();
+        fs::write(&b, b"stale").unwrap();
+        assert_eq!(
+            cache.materialize("deadbeef", &a).unwrap(),
+            Some(Materialized::Hardlink)
+        );
+        assert_eq!(
+            cache.materialize("deadbeef", &b).unwrap(),
+            Some(Materialized::Hardlink)
+        );
+        assert_eq!(fs::read(&b).unwrap(), b"fn main() {}");
+        assert_eq!(fs::metadata(&a).unwrap().nlink(), 3);
+    }
+
+    #[test]
+    fn test_materialize_with_mode_never_chmods_the_shared_blob() {
+        let tmp = tempdir().unwrap();
+        let cache = BlobCache::new(tmp.path().join("blobs"));
+        cache.insert("cafe01", b"#!/bin/sh\n").unwrap();
+        let cached_perm = fs::metadata(cache.blob_path("cafe01")).unwrap().mode() & 0o7777;
+
+        let script = tmp.path().join("cl/run.sh");
+        assert!(matches!(
+            cache
+                .materialize_with_mode("cafe01", &script, 0o755)
+                .unwrap(),
+            Some(Materialized::Copy(_))
+        ));
+        assert_eq!(fs::metadata(&script).unwrap().mode() & 0o7777, 0o755);
+        assert_eq!(
+            fs::metadata(cache.blob_path("cafe01")).unwrap().mode() & 0o7777,
+            cached_perm
+        );
+
+        let plain = tmp.path().join("cl/plain");
+        assert_eq!(
+            cache
+                .materialize_with_mode("cafe01", &plain, cached_perm)
+                .unwrap(),
+            Some(Materialized::Hardlink)
+        );
+        assert_eq!(cache.read("cafe01").unwrap().unwrap(), b"#!/bin/sh\n");
+        assert_eq!(cache.read("cafe02").unwrap(), None);
+        assert_eq!(
+            cache
+                .materialize_with_mode("cafe02", &plain, 0o755)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_gc_keeps_live_blobs_only() {
+        let tmp = tempdir().unwrap();
+        let cache = BlobCache::new(tmp.path());
+        cache.insert("aaaa01", b"live").unwrap();
+        cache.insert("bbbb02", b"dead!").unwrap();
+        let layer = tmp.path().join("layer");
+        cache.materialize("bbbb02", &layer).unwrap();
+
+        let live = HashSet::from(["aaaa01".to_string()]);
+        let stats = cache.gc(&live).unwrap();
+        assert_eq!(
+            stats,
+            BlobGcStats {
+                kept: 1,
+                removed: 1,
+                removed_bytes: 5
+            }
+        );
+        assert!(cache.contains("aaaa01"));
+        assert!(!cache.contains("bbbb02"));
+        assert!(!tmp.path().join("bb").exists());
+        // Layers that linked the blob keep their copy.
+        assert_eq!(fs::read(&layer).unwrap(), b"dead!");
+    }
+}
@@ -0,0 +1,343 @@
+//! On-disk checkpoints of Antares upper layers.
+//!
+//! Layout under the checkpoint root:
+//!
+//! ```text
+//! {root}/{mount_id}/{checkpoint_id}/meta.toml
+//! {root}/{mount_id}/{checkpoint_id}/upper/...
+//! ```
+//!
+//! A checkpoint is first written to a hidden staging directory and renamed into place,
+//! so a crash mid-copy never leaves a half-populated checkpoint behind.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::snapshot;
+
+const META_FILE: &str = "meta.toml";
+const UPPER_DIR: &str = "upper";
+
+/// Metadata describing one checkpoint of a mount's upper layer.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CheckpointInfo {
+    pub checkpoint_id: Uuid,
+    pub mount_id: Uuid,
+    /// Optional caller-provided label (e.g. the build step name).
+    #[serde(default)]
+    pub label: Option<String>,
+    /// CL the mount was on when the checkpoint was taken. Restore keeps the current CL.
+    #[serde(default)]
+    pub cl: Option<String>,
+    /// Logical size of the checkpointed upper layer in bytes.
+    pub size_bytes: u64,
+    pub created_at_epoch_ms: u64,
+}
+
+/// Filesystem-backed checkpoint storage shared by all mounts.
+#[derive(Debug, Clone)]
+pub struct CheckpointStore {
+    root: PathBuf,
+}
+
+impl CheckpointStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn mount_dir(&self, mount_id: Uuid) -> PathBuf {
+        self.root.join(mount_id.to_string())
+    }
+
+    fn checkpoint_dir(&self, mount_id: Uuid, checkpoint_id: Uuid) -> PathBuf {
+        self.mount_dir(mount_id).join(checkpoint_id.to_string())
+    }
+
+    /// Directory holding the checkpointed upper layer contents.
+    pub fn upper_path(&self, mount_id: Uuid, checkpoint_id: Uuid) -> PathBuf {
+        self.checkpoint_dir(mount_id, checkpoint_id).join(UPPER_DIR)
+    }
+
+    /// Copy `upper_dir` into a new checkpoint for `mount_id`.
+    ///
+    /// The caller is responsible for quiescing writers first.
+    pub fn create(
+        &self,
+        mount_id: Uuid,
+        upper_dir: &Path,
+        label: Option<String>,
+        cl: Option<String>,
+    ) -> io::Result<CheckpointInfo> {
+        self.create_with(mount_id, label, cl, |dst| {
+            snapshot::clone_tree(upper_dir, dst)
+        })
+    }
+
+    /// Create a checkpoint whose upper layer is written by `fill` into an empty directory.
+    ///
+    /// Used for uppers that do not live on disk (e.g. in-memory layers).
+    pub fn create_with(
+        &self,
+        mount_id: Uuid,
+        label: Option<String>,
+        cl: Option<String>,
+        fill: impl FnOnce(&Path) -> io::Result<snapshot::CloneStats>,
+    ) -> io::Result<CheckpointInfo> {
+        let checkpoint_id = Uuid::new_v4();
+        let mount_dir = self.mount_dir(mount_id);
+        fs::create_dir_all(&mount_dir)?;
+
+        let staging = mount_dir.join(format!(".{}.tmp", checkpoint_id));
+        let result = (|| {
+            let stats = fill(&staging.join(UPPER_DIR))?;
+            let info = CheckpointInfo {
+                checkpoint_id,
+                mount_id,
+                label,
+                cl,
+                size_bytes: stats.bytes,
+                created_at_epoch_ms: epoch_ms(),
+            };
+            write_meta(&staging, &info)?;
+            fs::rename(&staging, self.checkpoint_dir(mount_id, checkpoint_id))?;
+            Ok(info)
+        })();
+        if result.is_err() {
+            let _ = fs::remove_dir_all(&staging);
+        }
+        result
+    }
+
+    /// List the checkpoints of a mount, oldest first.
+    pub fn list(&self, mount_id: Uuid) -> io::Result<Vec<CheckpointInfo>> {
+        let mount_dir = self.mount_dir(mount_id);
+        if !mount_dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut out = Vec::new();
+        for entry in fs::read_dir(&mount_dir)? {
+            let entry = entry?;
+            // Skip staging directories and anything that is not a finished checkpoint.
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            match read_meta(&entry.path()) {
+                Ok(info) => out.push(info),
+                Err(e) => tracing::warn!(
+                    path = ?entry.path(),
+                    error = %e,
+                    "checkpoint store: skipping unreadable checkpoint"
+                ),
+            }
+        }
+        out.sort_by_key(|c| (c.created_at_epoch_ms, c.checkpoint_id));
+        Ok(out)
+    }
+
+    /// Look up a single checkpoint. Returns `Ok(None)` if it does not exist.
+    pub fn get(&self, mount_id: Uuid, checkpoint_id: Uuid) -> io::Result<Option<CheckpointInfo>> {
+        let dir = self.checkpoint_dir(mount_id, checkpoint_id);
+        if !dir.exists() {
+            return Ok(None);
+        }
+        read_meta(&dir).map(Some)
+    }
+
+    /// Delete a checkpoint. Returns its metadata, or `Ok(None)` if it did not exist.
+    pub fn delete(
+        &self,
+        mount_id: Uuid,
+        checkpoint_id: Uuid,
+    ) -> io::Result<Option<CheckpointInfo>> {
+        let Some(info) = self.get(mount_id, checkpoint_id)? else {
+            return Ok(None);
+        };
+        fs::remove_dir_all(self.checkpoint_dir(mount_id, checkpoint_id))?;
+        Ok(Some(info))
+    }
+
+    /// Remove every checkpoint belonging to a mount.
+    pub fn delete_all(&self, mount_id: Uuid) -> io::Result<()> {
+        let dir = self.mount_dir(mount_id);
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Replace `upper_dir` with the contents of a checkpoint.
+    ///
+    /// The current upper is moved aside first and put back if the copy fails, so the
+    /// mount never ends up with a partially restored layer. The mount must be quiesced.
+    pub fn restore_into(
+        &self,
+        mount_id: Uuid,
+        checkpoint_id: Uuid,
+        upper_dir: &Path,
+    ) -> io::Result<()> {
+        let src = self.upper_path(mount_id, checkpoint_id);
+        if !src.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("checkpoint {} has no upper layer", checkpoint_id),
+            ));
+        }
+        let file_name = upper_dir
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "upper_dir has no name"))?;
+        let backup = upper_dir.with_file_name(format!(
+            ".{}.pre-restore-{}",
+            file_name.to_string_lossy(),
+            checkpoint_id
+        ));
+        let _ = fs::remove_dir_all(&backup);
+        fs::rename(upper_dir, &backup)?;
+        match snapshot::clone_tree(&src, upper_dir) {
+            Ok(_) => {
+                if let Err(e) = fs::remove_dir_all(&backup) {
+                    tracing::warn!(path = ?backup, error = %e, "failed to remove pre-restore upper");
+                }
+                Ok(())
+            }
+            Err(e) => {
+                let _ = fs::remove_dir_all(upper_dir);
+                fs::rename(&backup, upper_dir)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Total logical bytes held by a mount's checkpoints.
+    pub fn used_bytes(&self, mount_id: Uuid) -> io::Result<u64> {
+        Ok(self.list(mount_id)?.iter().map(|c| c.size_bytes).sum())
+    }
+}
+
+fn write_meta(dir: &Path, info: &CheckpointInfo) -> io::Result<()> {
+    let data = toml::to_string_pretty(info)
+        .map_err(|e| io::Error::other(format!("encode checkpoint meta: {e}")))?;
+    fs::write(dir.join(META_FILE), data)
+}
+
+fn read_meta(dir: &Path) -> io::Result<CheckpointInfo> {
+    let content = fs::read_to_string(dir.join(META_FILE))?;
+    toml::from_str(&content).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("parse checkpoint meta: {e}"),
+        )
+    })
+}
+
+fn epoch_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_create_list_delete() {
+        let root = tempdir().unwrap();
+        let upper = root.path().join("upper");
+        fs::create_dir_all(upper.join("out")).unwrap();
+        fs::write(upper.join("out/a.o"), b"12345").unwrap();
+
+        let store = CheckpointStore::new(root.path().join("checkpoints"));
+        let mount_id = Uuid::new_v4();
+
+        let first = store
+            .create(mount_id, &upper, Some("step-1".into()), None)
+            .unwrap();
+        assert_eq!(first.size_bytes, 5);
+        assert_eq!(
+            fs::read(
+                store
+                    .upper_path(mount_id, first.checkpoint_id)
+                    .join("out/a.o")
+            )
+            .unwrap(),
+            b"12345"
+        );
+
+        fs::write(upper.join("out/b.o"), b"678").unwrap();
+        let second = store.create(mount_id, &upper, None, None).unwrap();
+        assert_eq!(second.size_bytes, 8);
+
+        let listed = store.list(mount_id).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(store.used_bytes(mount_id).unwrap(), 13);
+
+        let removed = store.delete(mount_id, first.checkpoint_id).unwrap();
+        assert_eq!(removed, Some(first.clone()));
+        assert!(store.get(mount_id, first.checkpoint_id).unwrap().is_none());
+        assert_eq!(store.list(mount_id).unwrap(), vec![second]);
+
+        store.delete_all(mount_id).unwrap();
+        assert!(store.list(mount_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_checkpoint_restore_replaces_upper() {
+        let root = tempdir().unwrap();
+        let upper = root.path().join("upper");
+        fs::create_dir_all(&upper).unwrap();
+        fs::write(upper.join("keep"), b"v1").unwrap();
+
+        let store = CheckpointStore::new(root.path().join("checkpoints"));
+        let mount_id = Uuid::new_v4();
+        let ckpt = store.create(mount_id, &upper, None, None).unwrap();
+
+        fs::write(upper.join("keep"), b"v2").unwrap();
+        fs::write(upper.join("junk"), b"x").unwrap();
+
+        store
+            .restore_into(mount_id, ckpt.checkpoint_id, &upper)
+            .unwrap();
+        assert_eq!(fs::read(upper.join("keep")).unwrap(), b"v1");
+        assert!(!upper.join("junk").exists());
+        // The checkpoint itself is still usable after a restore.
+        assert!(store.get(mount_id, ckpt.checkpoint_id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_checkpoint_restore_missing_keeps_upper() {
+        let root = tempdir().unwrap();
+        let upper = root.path().join("upper");
+        fs::create_dir_all(&upper).unwrap();
+        fs::write(upper.join("f"), b"data").unwrap();
+
+        let store = CheckpointStore::new(root.path().join("checkpoints"));
+        let err = store
+            .restore_into(Uuid::new_v4(), Uuid::new_v4(), &upper)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(fs::read(upper.join("f")).unwrap(), b"data");
+    }
+
+    #[test]
+    fn test_checkpoint_list_ignores_staging_dirs() {
+        let root = tempdir().unwrap();
+        let store = CheckpointStore::new(root.path());
+        let mount_id = Uuid::new_v4();
+        fs::create_dir_all(root.path().join(mount_id.to_string()).join(".partial.tmp")).unwrap();
+
+        assert!(store.list(mount_id).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,720 @@
+//! Manifests for incremental CL layer rebuilds.
+//!
+//! Every CL layer directory gets a sidecar `{cl_dir}.manifest.toml` recording the
+//! `(path, sha, action, mode)` it was built from. On the next build the new files-list is diffed
+//! against it, so only changed paths are downloaded, whited out or removed. The sidecar
+//! sits next to the layer rather than inside it so it never shows up in the mount.
+//!
+//! The manifest is removed before a layer is touched and written back only once the
+//! build succeeds; a layer without a manifest is always rebuilt from scratch.
+//!
+//! Rebuilds of a live mount go through a staging directory: [`stage`] hardlinks the
+//! current layer to `{cl_dir}.staging`, the build updates that copy, and [`commit`] swaps
+//! it in while keeping the old layer as `{cl_dir}.previous` until [`finish`] or
+//! [`rollback`]. A failed build therefore never touches the layer the mount is using.
+//!
+//! Besides files a layer holds symlinks (mode `120000`, the blob is the link target),
+//! whiteouts for deleted paths and opaque directories. A deleted directory that the CL
+//! fills again becomes opaque, hiding everything the lower layers have below it.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::CString,
+    fs, io,
+    os::unix::ffi::OsStrExt,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::snapshot;
+
+const MANIFEST_SUFFIX: &str = ".manifest.toml";
+const STAGING_SUFFIX: &str = ".staging";
+const PREVIOUS_SUFFIX: &str = ".previous";
+
+/// Git mode of an executable file.
+pub const MODE_EXECUTABLE: u32 = 0o100755;
+/// Git mode of a symlink.
+pub const MODE_SYMLINK: u32 = 0o120000;
+/// Action of an opaque directory entry.
+pub const ACTION_OPAQUE: &str = "opaque";
+/// Xattr marking a layer directory as opaque to the layers below it.
+pub const OPAQUE_XATTR: &str = "user.overlay.opaque";
+
+/// What a CL layer holds at one path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClManifestEntry {
+    /// Blob id from the files-list (ignored for deletions and opaque directories).
+    pub sha: String,
+    /// `new`, `modified`, `deleted` or `opaque`.
+    pub action: String,
+    /// Git mode for executables and symlinks; `None` for regular files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+}
+
+/// Kind of node a manifest entry puts into the layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClEntryKind {
+    File {
+        executable: bool,
+    },
+    /// Symlink whose target is the blob contents.
+    Symlink,
+    Whiteout,
+    OpaqueDir,
+}
+
+impl ClManifestEntry {
+    /// Whether the layer holds a whiteout rather than file contents.
+    pub fn is_whiteout(&self) -> bool {
+        self.action == "deleted"
+    }
+
+    pub fn kind(&self) -> ClEntryKind {
+        if self.is_whiteout() {
+            return ClEntryKind::Whiteout;
+        }
+        if self.action == ACTION_OPAQUE {
+            return ClEntryKind::OpaqueDir;
+        }
+        match self.mode {
+            Some(MODE_SYMLINK) => ClEntryKind::Symlink,
+            Some(mode) => ClEntryKind::File {
+                executable: mode & 0o111 != 0,
+            },
+            None => ClEntryKind::File { executable: false },
+        }
+    }
+}
+
+/// Normalize a git mode from the files-list: regular files map to `None`.
+pub fn normalize_mode(mode: u32) -> Option<u32> {
+    match mode {
+        MODE_SYMLINK => Some(MODE_SYMLINK),
+        m if m & 0o111 != 0 => Some(MODE_EXECUTABLE),
+        _ => None,
+    }
+}
+
+/// Contents of one CL layer directory, keyed by path relative to the layer root.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClManifest {
+    /// CL the layer was last built from.
+    pub cl: String,
+    #[serde(default)]
+    pub files: BTreeMap<String, ClManifestEntry>,
+}
+
+impl ClManifest {
+    /// Resolve whiteouts that have other entries below them.
+    ///
+    /// A deleted path the CL fills again (a deleted directory with new files, or a file
+    /// replaced by a directory) becomes an opaque directory and the whiteouts below it
+    /// are dropped. A deleted directory with nothing new below it stays a whiteout and
+    /// swallows the entries below it.
+    pub fn resolve_deleted_dirs(&mut self) {
+        // Deepest first, so nested deletions are settled before their parents.
+        let mut deleted: Vec<String> = self
+            .files
+            .iter()
+            .filter(|(_, e)| e.is_whiteout())
+            .map(|(p, _)| p.clone())
+            .collect();
+        deleted.sort_by_key(|p| std::cmp::Reverse(p.matches('/').count()));
+
+        for dir in deleted {
+            let prefix = format!("{dir}/");
+            let below: Vec<String> = self
+                .files
+                .range(prefix.clone()..)
+                .take_while(|(p, _)| p.starts_with(&prefix))
+                .map(|(p, _)| p.clone())
+                .collect();
+            if below.is_empty() {
+                continue;
+            }
+            let refilled = below.iter().any(|p| !self.files[p].is_whiteout());
+            for p in &below {
+                if !refilled || self.files[p].is_whiteout() {
+                    self.files.remove(p);
+                }
+            }
+            if refilled {
+                self.files.insert(
+                    dir,
+                    ClManifestEntry {
+                        action: ACTION_OPAQUE.to_string(),
+                        ..ClManifestEntry::default()
+                    },
+                );
+            }
+        }
+    }
+
+    /// An entry nested below a symlink entry, as `(entry, symlink)`. Placing it would
+    /// write through the link, possibly outside the layer.
+    pub fn entry_below_symlink(&self) -> Option<(&str, &str)> {
+        let normal = |path: &str| -> PathBuf {
+            Path::new(path)
+                .components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .collect()
+        };
+        let links: HashMap<PathBuf, &str> = self
+            .files
+            .iter()
+            .filter(|(_, e)| e.kind() == ClEntryKind::Symlink)
+            .map(|(path, _)| (normal(path), path.as_str()))
+            .collect();
+        if links.is_empty() {
+            return None;
+        }
+        self.files.keys().find_map(|path| {
+            let normalized = normal(path);
+            normalized
+                .ancestors()
+                .skip(1)
+                .find_map(|ancestor| links.get(ancestor))
+                .map(|link| (path.as_str(), *link))
+        })
+    }
+}
+
+/// Path of `rel` in `cl_dir`, creating its missing parent directories.
+///
+/// Every parent below `cl_dir` is checked with `symlink_metadata`: one that exists but is
+/// not a directory (e.g. a symlink) fails the call, so placing an entry never follows a
+/// link out of the layer.
+pub fn entry_path(cl_dir: &Path, rel: &str) -> io::Result<PathBuf> {
+    let rel = Path::new(rel);
+    let mut dir = cl_dir.to_path_buf();
+    for component in rel.parent().into_iter().flat_map(Path::components) {
+        match component {
+            Component::Normal(name) => dir.push(name),
+            Component::CurDir => continue,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} leaves the layer", rel.display()),
+                ))
+            }
+        }
+        // Parents are created concurrently by parallel downloads; losing the race is fine.
+        let created = match fs::create_dir(&dir) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => false,
+            other => other.map(|()| true)?,
+        };
+        if !created && !fs::symlink_metadata(&dir)?.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a directory", dir.display()),
+            ));
+        }
+    }
+    Ok(cl_dir.join(rel))
+}
+
+/// Work needed to turn a layer built from one manifest into another.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ClDiff {
+    /// Paths to remove first: gone from the new manifest, or about to be replaced.
+    pub remove: Vec<String>,
+    /// Paths whose blob must be downloaded.
+    pub fetch: Vec<(String, String)>,
+    /// Paths that need a whiteout.
+    pub whiteout: Vec<String>,
+    /// Directories to mark opaque.
+    pub opaque: Vec<String>,
+    /// Paths left in place.
+    pub unchanged: usize,
+}
+
+impl ClDiff {
+    /// Compute the changes from `old` to `new`. Without `old`, everything is built.
+    pub fn between(old: Option<&ClManifest>, new: &ClManifest) -> Self {
+        let empty = BTreeMap::new();
+        let old_files = old.map(|m| &m.files).unwrap_or(&empty);
+        let mut diff = ClDiff::default();
+
+        for (path, entry) in old_files {
+            if new.files.get(path) != Some(entry) {
+                diff.remove.push(path.clone());
+            }
+        }
+        for (path, entry) in &new.files {
+            if old_files.get(path) == Some(entry) {
+                diff.unchanged += 1;
+            } else if entry.is_whiteout() {
+                diff.whiteout.push(path.clone());
+            } else if entry.kind() == ClEntryKind::OpaqueDir {
+                diff.opaque.push(path.clone());
+            } else {
+                diff.fetch.push((path.clone(), entry.sha.clone()));
+            }
+        }
+        diff
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Sidecar manifest path for `cl_dir`.
+pub fn manifest_path(cl_dir: &Path) -> PathBuf {
+    with_suffix(cl_dir, MANIFEST_SUFFIX)
+}
+
+/// Read the manifest of `cl_dir`. Missing or unreadable manifests yield `None`.
+pub fn load(cl_dir: &Path) -> Option<ClManifest> {
+    let path = manifest_path(cl_dir);
+    let content = fs::read_to_string(&path).ok()?;
+    match toml::from_str(&content) {
+        Ok(manifest) => Some(manifest),
+        Err(e) => {
+            tracing::warn!(path = ?path, error = %e, "ignoring unreadable CL manifest");
+            None
+        }
+    }
+}
+
+/// Write the manifest of `cl_dir` atomically.
+pub fn save(cl_dir: &Path, manifest: &ClManifest) -> io::Result<()> {
+    let data = toml::to_string(manifest)
+        .map_err(|e| io::Error::other(format!("encode CL manifest: {e}")))?;
+    let path = manifest_path(cl_dir);
+    let mut tmp = path.clone().into_os_string();
+    tmp.push(".tmp");
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, &path)
+}
+
+/// Forget the manifest of `cl_dir`, forcing the next build to start from scratch.
+pub fn invalidate(cl_dir: &Path) -> io::Result<()> {
+    match fs::remove_file(manifest_path(cl_dir)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Copy the manifest of `src` to `dst`, if there is one.
+pub fn copy(src: &Path, dst: &Path) -> io::Result<()> {
+    match fs::copy(manifest_path(src), manifest_path(dst)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Remove a CL layer directory together with its manifest.
+pub fn remove_layer(cl_dir: &Path) -> io::Result<()> {
+    invalidate(cl_dir)?;
+    match fs::remove_dir_all(cl_dir) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Move a layer directory and its manifest to `to`, replacing nothing.
+fn rename_layer(from: &Path, to: &Path) -> io::Result<()> {
+    fs::rename(from, to)?;
+    match fs::rename(manifest_path(from), manifest_path(to)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Prepare `{cl_dir}.staging` for rebuilding `cl_dir` and return its path.
+///
+/// A layer with a manifest is hardlinked so the build stays incremental; otherwise the
+/// staging directory is left absent and gets built from scratch.
+pub fn stage(cl_dir: &Path) -> io::Result<PathBuf> {
+    let staging = with_suffix(cl_dir, STAGING_SUFFIX);
+    remove_layer(&staging)?;
+    if cl_dir.is_dir() && load(cl_dir).is_some() {
+        snapshot::link_tree(cl_dir, &staging)?;
+        copy(cl_dir, &staging)?;
+    }
+    Ok(staging)
+}
+
+/// Replace `cl_dir` with the built `staging` layer, keeping the old one for [`rollback`].
+pub fn commit(staging: &Path, cl_dir: &Path) -> io::Result<()> {
+    let previous = with_suffix(cl_dir, PREVIOUS_SUFFIX);
+    remove_layer(&previous)?;
+    if cl_dir.exists() {
+        rename_layer(cl_dir, &previous)?;
+    }
+    if let Err(e) = rename_layer(staging, cl_dir) {
+        let _ = rollback(cl_dir);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Retire `cl_dir` (e.g. a layer the new stack no longer uses) so [`rollback`] can restore it.
+pub fn retire(cl_dir: &Path) -> io::Result<()> {
+    let previous = with_suffix(cl_dir, PREVIOUS_SUFFIX);
+    remove_layer(&previous)?;
+    if cl_dir.exists() {
+        rename_layer(cl_dir, &previous)?;
+    }
+    Ok(())
+}
+
+/// Put back the layer saved by [`commit`] or [`retire`], if any.
+pub fn rollback(cl_dir: &Path) -> io::Result<()> {
+    let previous = with_suffix(cl_dir, PREVIOUS_SUFFIX);
+    if !previous.exists() {
+        return Ok(());
+    }
+    remove_layer(cl_dir)?;
+    rename_layer(&previous, cl_dir)
+}
+
+/// Drop the layer saved by [`commit`] or [`retire`] once the new stack is in use.
+pub fn finish(cl_dir: &Path) -> io::Result<()> {
+    remove_layer(&with_suffix(cl_dir, PREVIOUS_SUFFIX))
+}
+
+/// Remove `rel` from the layer, then any parent directories left empty by it.
+///
+/// Directories are only opaque-dir entries: they lose their marker and are removed once
+/// empty, since the entries below them are tracked on their own. Opaque parents are kept.
+pub fn remove_entry(cl_dir: &Path, rel: &str) -> io::Result<()> {
+    let path = cl_dir.join(rel);
+    match fs::symlink_metadata(&path) {
+        Ok(meta) if meta.is_dir() => {
+            clear_opaque(&path)?;
+            if fs::remove_dir(&path).is_err() {
+                return Ok(());
+            }
+        }
+        Ok(_) => fs::remove_file(&path)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let mut parent = path.parent();
+    while let Some(dir) = parent {
+        if dir == cl_dir || is_opaque(dir) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+        parent = dir.parent();
+    }
+    Ok(())
+}
+
+fn c_path(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Create `dir` if needed and mark it opaque, replacing any non-directory at its path.
+pub fn set_opaque(dir: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(dir) {
+        Ok(meta) if !meta.is_dir() => fs::remove_file(dir)?,
+        _ => {}
+    }
+    fs::create_dir_all(dir)?;
+    let path = c_path(dir)?;
+    let name = CString::new(OPAQUE_XATTR).expect("xattr name has no NUL");
+    // SAFETY: both strings are NUL-terminated and the value buffer is live.
+    let res = unsafe {
+        libc::lsetxattr(
+            path.as_ptr(),
+            name.as_ptr(),
+            b"y".as_ptr() as *const libc::c_void,
+            1,
+            0,
+        )
+    };
+    if res != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Whether `dir` carries the opaque marker.
+pub fn is_opaque(dir: &Path) -> bool {
+    let (Ok(path), Ok(name)) = (c_path(dir), CString::new(OPAQUE_XATTR)) else {
+        return false;
+    };
+    let mut value = [0u8; 1];
+    // SAFETY: `value` has room for the one byte requested.
+    let len = unsafe {
+        libc::lgetxattr(
+            path.as_ptr(),
+            name.as_ptr(),
+            value.as_mut_ptr() as *mut libc::c_void,
+            value.len(),
+        )
+    };
+    len == 1 && value[0].eq_ignore_ascii_case(&b'y')
+}
+
+fn clear_opaque(dir: &Path) -> io::Result<()> {
+    let path = c_path(dir)?;
+    let name = CString::new(OPAQUE_XATTR).expect("xattr name has no NUL");
+    // SAFETY: both strings are NUL-terminated.
+    let res = unsafe { libc::lremovexattr(path.as_ptr(), name.as_ptr()) };
+    if res != 0 {
+        let err = io::Error::last_os_error();
+        // Filesystems without user xattrs cannot hold a marker in the first place.
+        if !matches!(err.raw_os_error(), Some(libc::ENODATA | libc::ENOTSUP)) {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn manifest(files: &[(&str, &str, &str)]) -> ClManifest {
+        ClManifest {
+            cl: "CL1".into(),
+            files: files
+                .iter()
+                .map(|(path, sha, action)| {
+                    (
+                        path.to_string(),
+                        ClManifestEntry {
+                            sha: sha.to_string(),
+                            action: action.to_string(),
+                            mode: None,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff_only_touches_changed_paths() {
+        let old = manifest(&[
+            ("a.rs", "1", "modified"),
+            ("b.rs", "2", "new"),
+            ("gone.rs", "3", "new"),
+            ("c.rs", "", "deleted"),
+        ]);
+        let new = manifest(&[
+            ("a.rs", "1", "modified"),
+            ("b.rs", "9", "modified"),
+            ("c.rs", "4", "new"),
+            ("d.rs", "", "deleted"),
+        ]);
+
+        let diff = ClDiff::between(Some(&old), &new);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.remove, ["b.rs", "c.rs", "gone.rs"]);
+        assert_eq!(
+            diff.fetch,
+            [("b.rs".into(), "9".into()), ("c.rs".into(), "4".into())]
+        );
+        assert_eq!(diff.whiteout, ["d.rs"]);
+
+        let full = ClDiff::between(None, &new);
+        assert!(full.remove.is_empty());
+        assert_eq!(full.fetch.len() + full.whiteout.len(), 4);
+    }
+
+    #[test]
+    fn test_entries_below_symlinks_are_found_and_not_placed() {
+        let mut wanted = manifest(&[("evil/cron.d/x", "s2", "new"), ("ok/a.rs", "s3", "new")]);
+        assert_eq!(wanted.entry_below_symlink(), None);
+        wanted.files.insert(
+            "evil".into(),
+            ClManifestEntry {
+                sha: "s1".into(),
+                action: "new".into(),
+                mode: Some(MODE_SYMLINK),
+            },
+        );
+        assert_eq!(
+            wanted.entry_below_symlink(),
+            Some(("evil/cron.d/x", "evil"))
+        );
+
+        // Placing in either order never writes through the link.
+        let root = tempdir().unwrap();
+        let cl_dir = root.path().join("cl");
+        let outside = root.path().join("outside");
+        fs::create_dir_all(&cl_dir).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, cl_dir.join("evil")).unwrap();
+        assert!(entry_path(&cl_dir, "evil/cron.d/x").is_err());
+        assert!(entry_path(&cl_dir, "evil/x").is_err());
+        assert!(!outside.join("cron.d").exists());
+        assert_eq!(
+            entry_path(&cl_dir, "ok/deep/a.rs").unwrap(),
+            cl_dir.join("ok/deep/a.rs")
+        );
+        assert!(cl_dir.join("ok/deep").is_dir());
+    }
+
+    #[test]
+    fn test_diff_rebuilds_mode_changes() {
+        let old = manifest(&[("run.sh", "1", "new"), ("link", "2", "new")]);
+        let mut new = old.clone();
+        new.files.get_mut("run.sh").unwrap().mode = normalize_mode(0o100755);
+        new.files.get_mut("link").unwrap().mode = normalize_mode(0o120000);
+        new.files.insert(
+            "dir".into(),
+            ClManifestEntry {
+                action: ACTION_OPAQUE.into(),
+                ..ClManifestEntry::default()
+            },
+        );
+
+        let diff = ClDiff::between(Some(&old), &new);
+        assert_eq!(diff.remove, ["link", "run.sh"]);
+        assert_eq!(diff.fetch.len(), 2);
+        assert_eq!(diff.opaque, ["dir"]);
+        assert_eq!(new.files["link"].kind(), ClEntryKind::Symlink);
+        assert_eq!(
+            new.files["run.sh"].kind(),
+            ClEntryKind::File { executable: true }
+        );
+        assert_eq!(normalize_mode(0o100644), None);
+        // Old manifests without modes stay equal to regular files.
+        assert_eq!(ClDiff::between(Some(&old), &old).unchanged, 2);
+    }
+
+    #[test]
+    fn test_resolve_deleted_dirs() {
+        let mut m = manifest(&[
+            ("gone", "", "deleted"),
+            ("gone/a.rs", "", "deleted"),
+            ("gone/sub", "", "deleted"),
+            ("gone/sub/b.rs", "", "deleted"),
+            ("refilled", "", "deleted"),
+            ("refilled/old.rs", "", "deleted"),
+            ("refilled/new.rs", "1", "new"),
+            ("nested/dir", "", "deleted"),
+            ("nested/dir/inner", "", "deleted"),
+            ("nested/dir/inner/x.rs", "2", "new"),
+            ("file.rs", "", "deleted"),
+        ]);
+        m.resolve_deleted_dirs();
+
+        let paths: Vec<(&str, &str)> = m
+            .files
+            .iter()
+            .map(|(p, e)| (p.as_str(), e.action.as_str()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("file.rs", "deleted"),
+                ("gone", "deleted"),
+                ("nested/dir", ACTION_OPAQUE),
+                ("nested/dir/inner", ACTION_OPAQUE),
+                ("nested/dir/inner/x.rs", "new"),
+                ("refilled", ACTION_OPAQUE),
+                ("refilled/new.rs", "new"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_opaque_dir_entries() {
+        let tmp = tempdir().unwrap();
+        let cl_dir = tmp.path().join("cl");
+        fs::create_dir_all(&cl_dir).unwrap();
+        let dir = cl_dir.join("refilled");
+        if set_opaque(&dir).is_err() {
+            eprintln!("skip test_opaque_dir_entries: user xattrs unsupported");
+            return;
+        }
+        assert!(is_opaque(&dir));
+        fs::write(dir.join("a.rs"), b"a").unwrap();
+
+        // Removing the last child keeps the opaque parent.
+        remove_entry(&cl_dir, "refilled/a.rs").unwrap();
+        assert!(is_opaque(&dir));
+
+        // Removing the opaque entry itself drops the marker and the empty directory.
+        fs::write(dir.join("b.rs"), b"b").unwrap();
+        remove_entry(&cl_dir, "refilled").unwrap();
+        assert!(!is_opaque(&dir));
+        assert!(dir.join("b.rs").exists());
+        remove_entry(&cl_dir, "refilled/b.rs").unwrap();
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_manifest_roundtrip_and_layer_removal() {
+        let tmp = tempdir().unwrap();
+        let cl_dir = tmp.path().join("cl");
+        fs::create_dir_all(cl_dir.join("src/deep")).unwrap();
+        fs::write(cl_dir.join("src/deep/a.rs"), b"a").unwrap();
+        fs::write(cl_dir.join("keep.rs"), b"k").unwrap();
+
+        assert!(load(&cl_dir).is_none());
+        let m = manifest(&[("src/deep/a.rs", "1", "new"), ("keep.rs", "2", "new")]);
+        save(&cl_dir, &m).unwrap();
+        assert_eq!(load(&cl_dir), Some(m));
+        // The manifest must not appear inside the layer itself.
+        assert!(!cl_dir.join(MANIFEST_SUFFIX).exists());
+
+        remove_entry(&cl_dir, "src/deep/a.rs").unwrap();
+        assert!(!cl_dir.join("src").exists());
+        assert!(cl_dir.join("keep.rs").exists());
+        remove_entry(&cl_dir, "missing.rs").unwrap();
+
+        let fork = tmp.path().join("fork");
+        copy(&cl_dir, &fork).unwrap();
+        assert!(load(&fork).is_some());
+
+        remove_layer(&cl_dir).unwrap();
+        assert!(!cl_dir.exists());
+        assert!(load(&cl_dir).is_none());
+        remove_layer(&cl_dir).unwrap();
+    }
+
+    #[test]
+    fn test_stage_commit_and_rollback() {
+        let tmp = tempdir().unwrap();
+        let cl_dir = tmp.path().join("cl");
+        fs::create_dir_all(&cl_dir).unwrap();
+        fs::write(cl_dir.join("a.rs"), b"old").unwrap();
+        save(&cl_dir, &manifest(&[("a.rs", "1", "new")])).unwrap();
+
+        let staging = stage(&cl_dir).unwrap();
+        assert_eq!(load(&staging), load(&cl_dir));
+        fs::remove_file(staging.join("a.rs")).unwrap();
+        fs::write(staging.join("a.rs"), b"new").unwrap();
+        save(&staging, &manifest(&[("a.rs", "2", "modified")])).unwrap();
+        // The live layer is untouched until commit.
+        assert_eq!(fs::read(cl_dir.join("a.rs")).unwrap(), b"old");
+
+        commit(&staging, &cl_dir).unwrap();
+        assert_eq!(fs::read(cl_dir.join("a.rs")).unwrap(), b"new");
+        assert_eq!(load(&cl_dir).unwrap().files["a.rs"].sha, "2");
+        assert!(!staging.exists());
+
+        rollback(&cl_dir).unwrap();
+        assert_eq!(fs::read(cl_dir.join("a.rs")).unwrap(), b"old");
+        assert_eq!(load(&cl_dir).unwrap().files["a.rs"].sha, "1");
+
+        retire(&cl_dir).unwrap();
+        assert!(!cl_dir.exists());
+        rollback(&cl_dir).unwrap();
+        assert!(cl_dir.exists());
+        retire(&cl_dir).unwrap();
+        finish(&cl_dir).unwrap();
+        assert!(!cl_dir.exists());
+        assert!(!tmp.path().join("cl.previous").exists());
+
+        // Without a manifest nothing is staged; the build starts from scratch.
+        fs::create_dir_all(&cl_dir).unwrap();
+        let staging = stage(&cl_dir).unwrap();
+        assert!(!staging.exists());
+    }
+}
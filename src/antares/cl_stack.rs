@@ -0,0 +1,153 @@
+//! Conflict reporting for stacked CL layers.
+//!
+//! A mount may stack several CL passthrough layers above Dicfuse. They are applied in
+//! order, so when two CLs touch the same path the later one wins. [`find_conflicts`]
+//! walks the layer directories and reports, for every path supplied by more than one CL,
+//! which CL the overlay serves it from.
+//!
+//! Only non-directory entries are reported: files, symlinks and whiteouts. A file or
+//! whiteout in a higher layer also shadows everything a lower layer has below that path,
+//! and a directory in a higher layer shadows a lower file of the same name.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// A path provided by several CLs of one mount.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClConflict {
+    /// Path relative to the mount root.
+    pub path: String,
+    /// CL whose version the mount exposes.
+    pub cl: String,
+    /// Lower CLs whose version of `path` is hidden, highest first.
+    pub shadowed: Vec<String>,
+}
+
+/// Conflicts between `layers`, given bottom first as `(cl, layer_dir)`.
+///
+/// Results are sorted by path. Missing layer directories are treated as empty.
+pub fn find_conflicts(layers: &[(String, PathBuf)]) -> io::Result<Vec<ClConflict>> {
+    // Topmost layer providing each path seen so far, split by kind.
+    let mut leaves: HashMap<PathBuf, usize> = HashMap::new();
+    let mut dirs: HashMap<PathBuf, usize> = HashMap::new();
+    let mut conflicts: BTreeMap<PathBuf, (usize, Vec<String>)> = BTreeMap::new();
+
+    for (idx, (cl, dir)) in layers.iter().enumerate().rev() {
+        let (layer_leaves, layer_dirs) = walk(dir)?;
+        for rel in layer_leaves {
+            let winner = rel
+                .ancestors()
+                .filter(|p| !p.as_os_str().is_empty())
+                .find_map(|p| leaves.get(p))
+                .or_else(|| dirs.get(&rel))
+                .copied();
+            if let Some(winner) = winner {
+                let (_, shadowed) = conflicts
+                    .entry(rel.clone())
+                    .or_insert_with(|| (winner, Vec::new()));
+                shadowed.push(cl.clone());
+            }
+            leaves.entry(rel).or_insert(idx);
+        }
+        for rel in layer_dirs {
+            dirs.entry(rel).or_insert(idx);
+        }
+    }
+
+    Ok(conflicts
+        .into_iter()
+        .map(|(path, (winner, shadowed))| ClConflict {
+            path: path.to_string_lossy().into_owned(),
+            cl: layers[winner].0.clone(),
+            shadowed,
+        })
+        .collect())
+}
+
+/// Relative paths under `root`, split into non-directory entries and directories.
+fn walk(root: &Path) -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let (mut leaves, mut dirs) = (Vec::new(), Vec::new());
+    if !root.exists() {
+        return Ok((leaves, dirs));
+    }
+    let mut stack = vec![PathBuf::new()];
+    while let Some(rel) = stack.pop() {
+        for entry in fs::read_dir(root.join(&rel))? {
+            let entry = entry?;
+            let child = rel.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                dirs.push(child.clone());
+                stack.push(child);
+            } else {
+                leaves.push(child);
+            }
+        }
+    }
+    Ok((leaves, dirs))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn write(root: &Path, rel: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, rel).unwrap();
+    }
+
+    #[test]
+    fn test_later_cl_wins_and_lower_ones_are_listed() {
+        let tmp = tempdir().unwrap();
+        let layers: Vec<(String, PathBuf)> = ["base", "mid", "top"]
+            .iter()
+            .map(|cl| (cl.to_string(), tmp.path().join(cl)))
+            .collect();
+        write(&layers[0].1, "lib/a.rs");
+        write(&layers[0].1, "lib/only_base.rs");
+        write(&layers[1].1, "lib/a.rs");
+        write(&layers[1].1, "app/main.rs");
+        write(&layers[2].1, "lib/a.rs");
+
+        let conflicts = find_conflicts(&layers).unwrap();
+        assert_eq!(
+            conflicts,
+            vec![ClConflict {
+                path: "lib/a.rs".into(),
+                cl: "top".into(),
+                shadowed: vec!["mid".into(), "base".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_file_shadows_lower_subtree() {
+        let tmp = tempdir().unwrap();
+        let layers = vec![
+            ("base".to_string(), tmp.path().join("base")),
+            ("top".to_string(), tmp.path().join("top")),
+            ("missing".to_string(), tmp.path().join("missing")),
+        ];
+        write(&layers[0].1, "gen/x.rs");
+        write(&layers[0].1, "gen/y.rs");
+        // The top CL replaces the `gen` directory with a file.
+        write(&layers[1].1, "gen");
+        // ...and a directory where the base CL had a file.
+        write(&layers[0].1, "BUCK");
+        write(&layers[1].1, "BUCK/inner");
+
+        let conflicts = find_conflicts(&layers).unwrap();
+        let paths: Vec<_> = conflicts.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, ["BUCK", "gen/x.rs", "gen/y.rs"]);
+        assert!(conflicts
+            .iter()
+            .all(|c| c.cl == "top" && c.shadowed == ["base"]));
+    }
+}
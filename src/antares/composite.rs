@@ -0,0 +1,825 @@
+//! Read-only lower layer that serves several layers side by side.
+//!
+//! [`CompositeLayer`] places each bound layer at a mount-relative path. The directories
+//! leading to the bindings are synthesized, and every other path does not exist. Antares
+//! uses it to stack the per-`base_path` Dicfuse views of a composite mount under a single
+//! overlay, so a build that needs a handful of subtrees never pays for the whole monorepo.
+//!
+//! Each bound layer keeps its own inode space: its inodes are tagged with the binding's
+//! index in the top 16 bits. Synthetic directories use the untagged range, starting with
+//! the root.
+
+use std::{
+    collections::BTreeMap,
+    ffi::{OsStr, OsString},
+    io,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::StreamExt;
+use libfuse_fs::unionfs::layer::Layer;
+use rfuse3::{
+    raw::{
+        prelude::*,
+        reply::{DirectoryEntry, DirectoryEntryPlus},
+    },
+    Inode, Result, Timestamp,
+};
+
+const ROOT_INODE: Inode = 1;
+const TAG_SHIFT: u32 = 48;
+const INNER_MASK: u64 = (1 << TAG_SHIFT) - 1;
+const TTL: Duration = Duration::from_secs(1);
+
+/// Entry of a synthetic directory.
+#[derive(Debug, Clone, Copy)]
+enum Child {
+    Dir(Inode),
+    Binding(usize),
+}
+
+#[derive(Debug, Default)]
+struct SyntheticDir {
+    parent: Inode,
+    children: BTreeMap<OsString, Child>,
+}
+
+struct Binding {
+    target: PathBuf,
+    layer: Arc<dyn Layer>,
+    /// Synthetic directory holding the binding's root.
+    parent: Inode,
+}
+
+/// Where an inode of the composite tree lives.
+#[derive(Debug, Clone, Copy)]
+enum Node {
+    Synthetic(Inode),
+    Bound(usize, Inode),
+}
+
+/// Whether one of two mount-relative (or two monorepo) paths contains the other.
+pub fn overlaps(a: &Path, b: &Path) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// Layers served at mount-relative paths, with nothing outside them.
+pub struct CompositeLayer {
+    /// Synthetic directories; inode `n` is `dirs[n - 1]`.
+    dirs: Vec<SyntheticDir>,
+    bindings: Vec<Binding>,
+    uid: u32,
+    gid: u32,
+    created: Timestamp,
+}
+
+impl CompositeLayer {
+    /// Serve every `(target, layer)` pair at `target`.
+    ///
+    /// Targets must be non-empty relative paths, and none may contain another.
+    pub fn new(bindings: Vec<(PathBuf, Arc<dyn Layer>)>) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        if bindings.is_empty() {
+            return Err(invalid(
+                "a composite layer needs at least one binding".into(),
+            ));
+        }
+        for (i, (target, _)) in bindings.iter().enumerate() {
+            let normal = target
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+            if !normal || target.as_os_str().is_empty() {
+                return Err(invalid(format!("invalid binding target {:?}", target)));
+            }
+            if let Some((other, _)) = bindings[..i].iter().find(|(o, _)| overlaps(o, target)) {
+                return Err(invalid(format!(
+                    "binding targets {:?} and {:?} overlap",
+                    other, target
+                )));
+            }
+        }
+
+        let mut dirs = vec![SyntheticDir {
+            parent: ROOT_INODE,
+            ..SyntheticDir::default()
+        }];
+        let mut bound = Vec::with_capacity(bindings.len());
+        for (index, (target, layer)) in bindings.into_iter().enumerate() {
+            let mut names: Vec<OsString> = target
+                .components()
+                .map(|c| c.as_os_str().to_os_string())
+                .collect();
+            let leaf = names.pop().expect("target is not empty");
+            let mut dir = ROOT_INODE;
+            for name in names {
+                let next = match dirs[dir as usize - 1].children.get(&name) {
+                    Some(Child::Dir(ino)) => *ino,
+                    // Ruled out by the overlap check above.
+                    Some(Child::Binding(_)) => unreachable!("binding targets overlap"),
+                    None => {
+                        dirs.push(SyntheticDir {
+                            parent: dir,
+                            ..SyntheticDir::default()
+                        });
+                        let ino = dirs.len() as Inode;
+                        dirs[dir as usize - 1]
+                            .children
+                            .insert(name, Child::Dir(ino));
+                        ino
+                    }
+                };
+                dir = next;
+            }
+            dirs[dir as usize - 1]
+                .children
+                .insert(leaf, Child::Binding(index));
+            bound.push(Binding {
+                target,
+                layer,
+                parent: dir,
+            });
+        }
+
+        // SAFETY: getuid/getgid cannot fail.
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        Ok(Self {
+            dirs,
+            bindings: bound,
+            uid,
+            gid,
+            created: Timestamp::from(SystemTime::now()),
+        })
+    }
+
+    /// Mount-relative paths of the bindings, in the order they were given.
+    pub fn targets(&self) -> impl Iterator<Item = &Path> {
+        self.bindings.iter().map(|b| b.target.as_path())
+    }
+
+    fn node(&self, inode: Inode) -> Result<Node> {
+        match inode >> TAG_SHIFT {
+            0 if inode >= ROOT_INODE && inode as usize <= self.dirs.len() => {
+                Ok(Node::Synthetic(inode))
+            }
+            0 => Err(libc::ENOENT.into()),
+            tag if (tag as usize) <= self.bindings.len() => {
+                Ok(Node::Bound(tag as usize - 1, inode & INNER_MASK))
+            }
+            _ => Err(libc::ENOENT.into()),
+        }
+    }
+
+    fn dir(&self, inode: Inode) -> &SyntheticDir {
+        &self.dirs[inode as usize - 1]
+    }
+
+    /// Composite inode of `inner` in binding `index`.
+    fn tag(index: usize, inner: Inode) -> Result<Inode> {
+        if inner > INNER_MASK {
+            return Err(libc::EOVERFLOW.into());
+        }
+        Ok(((index as u64 + 1) << TAG_SHIFT) | inner)
+    }
+
+    fn layer(&self, index: usize) -> &dyn Layer {
+        self.bindings[index].layer.as_ref()
+    }
+
+    fn synthetic_attr(&self, inode: Inode) -> FileAttr {
+        FileAttr {
+            ino: inode,
+            size: 0,
+            blocks: 0,
+            atime: self.created,
+            mtime: self.created,
+            ctime: self.created,
+            kind: FileType::Directory,
+            perm: 0o755,
+            nlink: 2,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: 4096,
+        }
+    }
+
+    /// Attributes of `child` as seen through the composite tree.
+    async fn child_attr(&self, req: Request, child: Child) -> Result<(FileAttr, Duration)> {
+        match child {
+            Child::Dir(ino) => Ok((self.synthetic_attr(ino), TTL)),
+            Child::Binding(index) => {
+                let layer = self.layer(index);
+                let reply = layer.getattr(req, layer.root_inode(), None, 0).await?;
+                let mut attr = reply.attr;
+                attr.ino = Self::tag(index, layer.root_inode())?;
+                Ok((attr, reply.ttl))
+            }
+        }
+    }
+
+    /// Inode of `child`, without asking the bound layer.
+    fn child_inode(&self, child: Child) -> Result<Inode> {
+        match child {
+            Child::Dir(ino) => Ok(ino),
+            Child::Binding(index) => Self::tag(index, self.layer(index).root_inode()),
+        }
+    }
+
+    /// Composite inode of a readdir entry of `parent` (an inode of binding `index`).
+    fn entry_inode(
+        &self,
+        index: usize,
+        parent: Inode,
+        name: &OsStr,
+        inner: Inode,
+    ) -> Result<Inode> {
+        if name == ".." && parent == self.layer(index).root_inode() {
+            return Ok(self.bindings[index].parent);
+        }
+        Self::tag(index, inner)
+    }
+
+    fn read_only<T>() -> Result<T> {
+        Err(libc::EROFS.into())
+    }
+}
+
+impl Filesystem for CompositeLayer {
+    async fn init(&self, _req: Request) -> Result<ReplyInit> {
+        Ok(ReplyInit::default())
+    }
+
+    async fn destroy(&self, _req: Request) {}
+
+    async fn lookup(&self, req: Request, parent: Inode, name: &OsStr) -> Result<ReplyEntry> {
+        match self.node(parent)? {
+            Node::Synthetic(dir) => {
+                let child = *self
+                    .dir(dir)
+                    .children
+                    .get(name)
+                    .ok_or_else(|| rfuse3::Errno::from(libc::ENOENT))?;
+                let (attr, ttl) = self.child_attr(req, child).await?;
+                Ok(ReplyEntry {
+                    ttl,
+                    attr,
+                    generation: 0,
+                })
+            }
+            Node::Bound(index, inner) => {
+                let mut entry = self.layer(index).lookup(req, inner, name).await?;
+                entry.attr.ino = Self::tag(index, entry.attr.ino)?;
+                Ok(entry)
+            }
+        }
+    }
+
+    async fn forget(&self, req: Request, inode: Inode, nlookup: u64) {
+        if let Ok(Node::Bound(index, inner)) = self.node(inode) {
+            self.layer(index).forget(req, inner, nlookup).await;
+        }
+    }
+
+    async fn batch_forget(&self, req: Request, inodes: &[(Inode, u64)]) {
+        for &(inode, nlookup) in inodes {
+            self.forget(req, inode, nlookup).await;
+        }
+    }
+
+    async fn getattr(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: Option<u64>,
+        flags: u32,
+    ) -> Result<ReplyAttr> {
+        match self.node(inode)? {
+            Node::Synthetic(ino) => Ok(ReplyAttr {
+                ttl: TTL,
+                attr: self.synthetic_attr(ino),
+            }),
+            Node::Bound(index, inner) => {
+                let mut reply = self.layer(index).getattr(req, inner, fh, flags).await?;
+                reply.attr.ino = inode;
+                Ok(reply)
+            }
+        }
+    }
+
+    async fn setattr(
+        &self,
+        _req: Request,
+        _inode: Inode,
+        _fh: Option<u64>,
+        _set_attr: SetAttr,
+    ) -> Result<ReplyAttr> {
+        Self::read_only()
+    }
+
+    async fn readlink(&self, req: Request, inode: Inode) -> Result<ReplyData> {
+        match self.node(inode)? {
+            Node::Synthetic(_) => Err(libc::EINVAL.into()),
+            Node::Bound(index, inner) => self.layer(index).readlink(req, inner).await,
+        }
+    }
+
+    async fn mknod(
+        &self,
+        _req: Request,
+        _parent: Inode,
+        _name: &OsStr,
+        _mode: u32,
+        _rdev: u32,
+    ) -> Result<ReplyEntry> {
+        Self::read_only()
+    }
+
+    async fn mkdir(
+        &self,
+        _req: Request,
+        _parent: Inode,
+        _name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+    ) -> Result<ReplyEntry> {
+        Self::read_only()
+    }
+
+    async fn unlink(&self, _req: Request, _parent: Inode, _name: &OsStr) -> Result<()> {
+        Self::read_only()
+    }
+
+    async fn rmdir(&self, _req: Request, _parent: Inode, _name: &OsStr) -> Result<()> {
+        Self::read_only()
+    }
+
+    async fn open(&self, req: Request, inode: Inode, flags: u32) -> Result<ReplyOpen> {
+        match self.node(inode)? {
+            Node::Synthetic(_) => Err(libc::EISDIR.into()),
+            Node::Bound(index, inner) => self.layer(index).open(req, inner, flags).await,
+        }
+    }
+
+    async fn read(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: u64,
+        offset: u64,
+        size: u32,
+    ) -> Result<ReplyData> {
+        match self.node(inode)? {
+            Node::Synthetic(_) => Err(libc::EISDIR.into()),
+            Node::Bound(index, inner) => self.layer(index).read(req, inner, fh, offset, size).await,
+        }
+    }
+
+    async fn statfs(&self, req: Request, inode: Inode) -> Result<ReplyStatFs> {
+        let (index, inner) = match self.node(inode)? {
+            Node::Synthetic(_) => (0, self.layer(0).root_inode()),
+            Node::Bound(index, inner) => (index, inner),
+        };
+        self.layer(index).statfs(req, inner).await
+    }
+
+    async fn release(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: u64,
+        flags: u32,
+        lock_owner: u64,
+        flush: bool,
+    ) -> Result<()> {
+        match self.node(inode)? {
+            Node::Synthetic(_) => Ok(()),
+            Node::Bound(index, inner) => {
+                self.layer(index)
+                    .release(req, inner, fh, flags, lock_owner, flush)
+                    .await
+            }
+        }
+    }
+
+    async fn fsync(&self, _req: Request, _inode: Inode, _fh: u64, _datasync: bool) -> Result<()> {
+        Ok(())
+    }
+
+    async fn getxattr(
+        &self,
+        req: Request,
+        inode: Inode,
+        name: &OsStr,
+        size: u32,
+    ) -> Result<ReplyXAttr> {
+        match self.node(inode)? {
+            // Synthetic directories are never opaque.
+            Node::Synthetic(_) => Err(libc::ENODATA.into()),
+            Node::Bound(index, inner) => self.layer(index).getxattr(req, inner, name, size).await,
+        }
+    }
+
+    async fn listxattr(&self, req: Request, inode: Inode, size: u32) -> Result<ReplyXAttr> {
+        match self.node(inode)? {
+            Node::Synthetic(_) if size == 0 => Ok(ReplyXAttr::Size(0)),
+            Node::Synthetic(_) => Ok(ReplyXAttr::Data(Bytes::new())),
+            Node::Bound(index, inner) => self.layer(index).listxattr(req, inner, size).await,
+        }
+    }
+
+    async fn flush(&self, req: Request, inode: Inode, fh: u64, lock_owner: u64) -> Result<()> {
+        match self.node(inode)? {
+            Node::Synthetic(_) => Ok(()),
+            Node::Bound(index, inner) => self.layer(index).flush(req, inner, fh, lock_owner).await,
+        }
+    }
+
+    async fn opendir(&self, req: Request, inode: Inode, flags: u32) -> Result<ReplyOpen> {
+        match self.node(inode)? {
+            Node::Synthetic(_) => Ok(ReplyOpen { fh: 0, flags: 0 }),
+            Node::Bound(index, inner) => self.layer(index).opendir(req, inner, flags).await,
+        }
+    }
+
+    async fn readdir<'a>(
+        &'a self,
+        req: Request,
+        parent: Inode,
+        fh: u64,
+        offset: i64,
+    ) -> Result<ReplyDirectory<impl futures::Stream<Item = Result<DirectoryEntry>> + Send + 'a>>
+    {
+        let mut out: Vec<Result<DirectoryEntry>> = Vec::new();
+        match self.node(parent)? {
+            Node::Synthetic(dir) => {
+                // Offset 0 is ".", 1 is "..", 2.. are children in name order.
+                let dots = [(parent, "."), (self.dir(dir).parent, "..")];
+                for (idx, (ino, name)) in dots.into_iter().enumerate() {
+                    if (idx as i64) >= offset {
+                        out.push(Ok(DirectoryEntry {
+                            inode: ino,
+                            kind: FileType::Directory,
+                            name: name.into(),
+                            offset: idx as i64 + 1,
+                        }));
+                    }
+                }
+                for (idx, (name, &child)) in self.dir(dir).children.iter().enumerate() {
+                    let entry_offset = idx as i64 + 2;
+                    if entry_offset < offset {
+                        continue;
+                    }
+                    out.push(Ok(DirectoryEntry {
+                        inode: self.child_inode(child)?,
+                        kind: FileType::Directory,
+                        name: name.clone(),
+                        offset: entry_offset + 1,
+                    }));
+                }
+            }
+            Node::Bound(index, inner) => {
+                let reply = self.layer(index).readdir(req, inner, fh, offset).await?;
+                let entries: Vec<_> = reply.entries.collect().await;
+                for entry in entries {
+                    out.push(entry.and_then(|mut e| {
+                        e.inode = self.entry_inode(index, inner, &e.name, e.inode)?;
+                        Ok(e)
+                    }));
+                }
+            }
+        }
+        Ok(ReplyDirectory {
+            entries: futures::stream::iter(out),
+        })
+    }
+
+    async fn readdirplus<'a>(
+        &'a self,
+        req: Request,
+        parent: Inode,
+        fh: u64,
+        offset: u64,
+        lock_owner: u64,
+    ) -> Result<
+        ReplyDirectoryPlus<impl futures::Stream<Item = Result<DirectoryEntryPlus>> + Send + 'a>,
+    > {
+        let mut out: Vec<Result<DirectoryEntryPlus>> = Vec::new();
+        match self.node(parent)? {
+            Node::Synthetic(dir) => {
+                let parent_dir = self.dir(dir).parent;
+                let dots = [(parent, "."), (parent_dir, "..")];
+                for (idx, (ino, name)) in dots.into_iter().enumerate() {
+                    if (idx as u64) >= offset {
+                        out.push(Ok(DirectoryEntryPlus {
+                            inode: ino,
+                            generation: 0,
+                            kind: FileType::Directory,
+                            name: name.into(),
+                            offset: idx as i64 + 1,
+                            attr: self.synthetic_attr(ino),
+                            entry_ttl: TTL,
+                            attr_ttl: TTL,
+                        }));
+                    }
+                }
+                for (idx, (name, &child)) in self.dir(dir).children.iter().enumerate() {
+                    let entry_offset = idx as u64 + 2;
+                    if entry_offset < offset {
+                        continue;
+                    }
+                    let (attr, ttl) = self.child_attr(req, child).await?;
+                    out.push(Ok(DirectoryEntryPlus {
+                        inode: attr.ino,
+                        generation: 0,
+                        kind: attr.kind,
+                        name: name.clone(),
+                        offset: entry_offset as i64 + 1,
+                        attr,
+                        entry_ttl: ttl,
+                        attr_ttl: ttl,
+                    }));
+                }
+            }
+            Node::Bound(index, inner) => {
+                let reply = self
+                    .layer(index)
+                    .readdirplus(req, inner, fh, offset, lock_owner)
+                    .await?;
+                let entries: Vec<_> = reply.entries.collect().await;
+                for entry in entries {
+                    out.push(entry.and_then(|mut e| {
+                        e.inode = self.entry_inode(index, inner, &e.name, e.inode)?;
+                        e.attr.ino = e.inode;
+                        Ok(e)
+                    }));
+                }
+            }
+        }
+        Ok(ReplyDirectoryPlus {
+            entries: futures::stream::iter(out),
+        })
+    }
+
+    async fn releasedir(&self, req: Request, inode: Inode, fh: u64, flags: u32) -> Result<()> {
+        match self.node(inode)? {
+            Node::Synthetic(_) => Ok(()),
+            Node::Bound(index, inner) => self.layer(index).releasedir(req, inner, fh, flags).await,
+        }
+    }
+
+    async fn getlk(
+        &self,
+        _req: Request,
+        _inode: Inode,
+        _fh: u64,
+        _lock_owner: u64,
+        start: u64,
+        end: u64,
+        _type: u32,
+        _pid: u32,
+    ) -> Result<ReplyLock> {
+        // Lower layers are only read through the overlay, which never locks them.
+        Ok(ReplyLock {
+            start,
+            end,
+            r#type: libc::F_UNLCK as u32,
+            pid: 0,
+        })
+    }
+
+    async fn setlk(
+        &self,
+        _req: Request,
+        _inode: Inode,
+        _fh: u64,
+        _lock_owner: u64,
+        _start: u64,
+        _end: u64,
+        _type: u32,
+        _pid: u32,
+        _block: bool,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn access(&self, req: Request, inode: Inode, mask: u32) -> Result<()> {
+        match self.node(inode)? {
+            Node::Synthetic(_) => Ok(()),
+            Node::Bound(index, inner) => self.layer(index).access(req, inner, mask).await,
+        }
+    }
+
+    async fn create(
+        &self,
+        _req: Request,
+        _parent: Inode,
+        _name: &OsStr,
+        _mode: u32,
+        _flags: u32,
+    ) -> Result<ReplyCreated> {
+        Self::read_only()
+    }
+
+    async fn lseek(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: u64,
+        offset: u64,
+        whence: u32,
+    ) -> Result<ReplyLSeek> {
+        match self.node(inode)? {
+            Node::Synthetic(_) => Err(libc::EISDIR.into()),
+            Node::Bound(index, inner) => {
+                self.layer(index)
+                    .lseek(req, inner, fh, offset, whence)
+                    .await
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Layer for CompositeLayer {
+    fn root_inode(&self) -> Inode {
+        ROOT_INODE
+    }
+
+    async fn getattr_with_mapping(
+        &self,
+        inode: Inode,
+        handle: Option<u64>,
+        mapping: bool,
+    ) -> io::Result<(libc::stat64, Duration)> {
+        let ino = match self.node(inode).map_err(io::Error::from)? {
+            Node::Synthetic(ino) => ino,
+            Node::Bound(index, inner) => {
+                let (mut stat, ttl) = self
+                    .layer(index)
+                    .getattr_with_mapping(inner, handle, mapping)
+                    .await?;
+                stat.st_ino = inode;
+                return Ok((stat, ttl));
+            }
+        };
+        let attr = self.synthetic_attr(ino);
+
+        // SAFETY: stat64 is plain old data; zeroed() also covers platform padding fields.
+        let mut stat: libc::stat64 = unsafe { std::mem::zeroed() };
+        stat.st_ino = attr.ino;
+        stat.st_nlink = attr.nlink as _;
+        stat.st_mode = libc::S_IFDIR | attr.perm as libc::mode_t;
+        stat.st_uid = attr.uid;
+        stat.st_gid = attr.gid;
+        stat.st_blksize = attr.blksize as _;
+        stat.st_atime = attr.atime.sec;
+        stat.st_atime_nsec = attr.atime.nsec.into();
+        stat.st_mtime = attr.mtime.sec;
+        stat.st_mtime_nsec = attr.mtime.nsec.into();
+        stat.st_ctime = attr.ctime.sec;
+        stat.st_ctime_nsec = attr.ctime.nsec.into();
+        Ok((stat, TTL))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::antares::{mem_upper::MemUpperLayer, quota::QuotaLimits};
+
+    fn name(s: &str) -> &OsStr {
+        OsStr::new(s)
+    }
+
+    async fn layer_with_file(file: &str, data: &[u8]) -> Arc<MemUpperLayer> {
+        let layer = Arc::new(MemUpperLayer::new(QuotaLimits::default()));
+        let req = Request::default();
+        let created = layer
+            .create(
+                req,
+                layer.root_inode(),
+                name(file),
+                libc::S_IFREG | 0o644,
+                libc::O_WRONLY as u32,
+            )
+            .await
+            .unwrap();
+        layer
+            .write(req, created.attr.ino, created.fh, 0, data, 0, 0)
+            .await
+            .unwrap();
+        layer
+            .release(req, created.attr.ino, created.fh, 0, 0, false)
+            .await
+            .unwrap();
+        layer
+    }
+
+    async fn lookup_path(fs: &CompositeLayer, path: &str) -> Result<ReplyEntry> {
+        let mut entry = fs
+            .getattr(Request::default(), ROOT_INODE, None, 0)
+            .await
+            .map(|a| ReplyEntry {
+                ttl: a.ttl,
+                attr: a.attr,
+                generation: 0,
+            })?;
+        for part in path.split('/') {
+            entry = fs
+                .lookup(Request::default(), entry.attr.ino, name(part))
+                .await?;
+        }
+        Ok(entry)
+    }
+
+    async fn list(fs: &CompositeLayer, inode: Inode) -> Vec<(String, Inode)> {
+        let reply = fs.readdir(Request::default(), inode, 0, 0).await.unwrap();
+        reply
+            .entries
+            .map(|e| {
+                let e = e.unwrap();
+                (e.name.to_string_lossy().into_owned(), e.inode)
+            })
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_bindings_are_served_at_their_targets() {
+        let foo = layer_with_file("main.rs", b"fn main() {}").await;
+        let common = layer_with_file("lib.rs", b"pub fn common() {}").await;
+        let fs = CompositeLayer::new(vec![
+            (PathBuf::from("services/foo"), foo as Arc<dyn Layer>),
+            (PathBuf::from("libs/common"), common as Arc<dyn Layer>),
+        ])
+        .unwrap();
+        let req = Request::default();
+
+        let names: Vec<String> = list(&fs, ROOT_INODE)
+            .await
+            .into_iter()
+            .map(|e| e.0)
+            .collect();
+        assert_eq!(names, [".", "..", "libs", "services"]);
+
+        let file = lookup_path(&fs, "libs/common/lib.rs").await.unwrap();
+        assert_eq!(file.attr.ino >> TAG_SHIFT, 2);
+        let data = fs.read(req, file.attr.ino, 0, 0, 4096).await.unwrap();
+        assert_eq!(data.data.as_ref(), b"pub fn common() {}");
+        let (stat, _) = fs
+            .getattr_with_mapping(file.attr.ino, None, false)
+            .await
+            .unwrap();
+        assert_eq!(stat.st_ino, file.attr.ino);
+
+        // `..` of a binding root leads back into the synthetic tree.
+        let foo_root = lookup_path(&fs, "services/foo").await.unwrap();
+        let services = lookup_path(&fs, "services").await.unwrap();
+        let entries = list(&fs, foo_root.attr.ino).await;
+        assert!(entries.contains(&("..".to_string(), services.attr.ino)));
+        assert!(entries.iter().any(|(n, _)| n == "main.rs"));
+
+        // Paths outside every binding do not exist.
+        for missing in ["third-party", "services/bar", "libs/common/missing.rs"] {
+            let err = lookup_path(&fs, missing).await.unwrap_err();
+            assert_eq!(io::Error::from(err).raw_os_error(), Some(libc::ENOENT));
+        }
+        assert!(!fs.is_opaque(req, services.attr.ino).await.unwrap());
+        assert!(fs.getattr(req, 99, None, 0).await.is_err());
+    }
+
+    #[test]
+    fn test_overlapping_targets_are_rejected() {
+        let layer = || Arc::new(MemUpperLayer::new(QuotaLimits::default())) as Arc<dyn Layer>;
+        let err = |bindings: Vec<(&str, Arc<dyn Layer>)>| {
+            CompositeLayer::new(
+                bindings
+                    .into_iter()
+                    .map(|(t, l)| (PathBuf::from(t), l))
+                    .collect(),
+            )
+            .err()
+            .map(|e| e.kind())
+        };
+        assert_eq!(err(vec![]), Some(io::ErrorKind::InvalidInput));
+        assert_eq!(
+            err(vec![("libs", layer()), ("libs/common", layer())]),
+            Some(io::ErrorKind::InvalidInput)
+        );
+        assert_eq!(
+            err(vec![("../x", layer())]),
+            Some(io::ErrorKind::InvalidInput)
+        );
+        assert_eq!(
+            err(vec![("/abs", layer())]),
+            Some(io::ErrorKind::InvalidInput)
+        );
+        assert_eq!(err(vec![("libs/a", layer()), ("libs/b", layer())]), None);
+        assert!(overlaps(Path::new("/libs"), Path::new("/libs/common")));
+        assert!(!overlaps(Path::new("/libs/co"), Path::new("/libs/common")));
+    }
+}
@@ -6,14 +6,27 @@ use libfuse_fs::{
 };
 use tokio::task::JoinHandle;
 
-use crate::server::mount_filesystem_with_antares_cache;
+use super::{
+    composite::CompositeLayer,
+    mem_upper::MemUpperLayer,
+    quota::{QuotaFs, UpperQuota},
+};
+use crate::server::{mount_filesystem_with_antares_cache, mount_read_only_filesystem};
 
-/// Antares union-fs wrapper: dicfuse lower + passthrough upper/CL.
+/// Antares union-fs wrapper: dicfuse lower + passthrough (or in-memory) upper + passthrough CLs.
 pub struct AntaresFuse {
     pub mountpoint: PathBuf,
-    pub upper_dir: PathBuf,
+    /// Writable layer on disk; `None` for read-only and in-memory mounts.
+    pub upper_dir: Option<PathBuf>,
+    /// Writable layer kept in RAM instead of `upper_dir`.
+    mem_upper: Option<Arc<MemUpperLayer>>,
     pub dic: Arc<crate::dicfuse::Dicfuse>,
-    pub cl_dir: Option<PathBuf>,
+    /// Dicfuse views served at mount-relative paths instead of `dic` (composite mounts).
+    pub bindings: Vec<(PathBuf, Arc<crate::dicfuse::Dicfuse>)>,
+    /// CL layers stacked above Dicfuse, bottom first; later CLs override earlier ones.
+    pub cl_dirs: Vec<PathBuf>,
+    /// Optional byte/inode quota enforced on the upper layer.
+    quota: Option<Arc<UpperQuota>>,
     /// Background task running the FUSE session.
     fuse_task: Option<JoinHandle<()>>,
 }
@@ -26,29 +39,127 @@ impl AntaresFuse {
         upper_dir: PathBuf,
         cl_dir: Option<PathBuf>,
     ) -> std::io::Result<Self> {
-        if let Some(cl) = &cl_dir {
+        Self::new_with_upper(mountpoint, dic, Some(upper_dir), cl_dir).await
+    }
+
+    /// Build a read-only view of Dicfuse plus the optional CL layer.
+    ///
+    /// No upper directory is created, and the session is mounted `ro`, so every
+    /// write fails with `EROFS`.
+    pub async fn new_read_only(
+        mountpoint: PathBuf,
+        dic: Arc<crate::dicfuse::Dicfuse>,
+        cl_dir: Option<PathBuf>,
+    ) -> std::io::Result<Self> {
+        Self::new_with_upper(mountpoint, dic, None, cl_dir).await
+    }
+
+    /// Build a view whose writes land in `upper` (RAM) instead of a directory.
+    ///
+    /// Nothing is created on disk apart from the mountpoint and the optional CL dir. The
+    /// layer is shared, so remounting with the same `upper` keeps its contents.
+    pub async fn new_in_memory(
+        mountpoint: PathBuf,
+        dic: Arc<crate::dicfuse::Dicfuse>,
+        upper: Arc<MemUpperLayer>,
+        cl_dir: Option<PathBuf>,
+    ) -> std::io::Result<Self> {
+        Ok(Self::new_with_upper(mountpoint, dic, None, cl_dir)
+            .await?
+            .with_memory_upper(upper))
+    }
+
+    /// Same as [`Self::new`], with `upper_dir: None` meaning a read-only mount.
+    pub async fn new_with_upper(
+        mountpoint: PathBuf,
+        dic: Arc<crate::dicfuse::Dicfuse>,
+        upper_dir: Option<PathBuf>,
+        cl_dir: Option<PathBuf>,
+    ) -> std::io::Result<Self> {
+        Self::new_with_layers(mountpoint, dic, upper_dir, cl_dir.into_iter().collect()).await
+    }
+
+    /// Same as [`Self::new_with_upper`], with an ordered stack of CL layers (bottom first).
+    pub async fn new_with_layers(
+        mountpoint: PathBuf,
+        dic: Arc<crate::dicfuse::Dicfuse>,
+        upper_dir: Option<PathBuf>,
+        cl_dirs: Vec<PathBuf>,
+    ) -> std::io::Result<Self> {
+        for cl in &cl_dirs {
             std::fs::create_dir_all(cl)?;
         }
-        std::fs::create_dir_all(&upper_dir)?;
+        if let Some(upper) = &upper_dir {
+            std::fs::create_dir_all(upper)?;
+        }
         std::fs::create_dir_all(&mountpoint)?;
 
-        Ok(Self {
+        Ok(Self::unmounted(mountpoint, dic, upper_dir, cl_dirs))
+    }
+
+    /// Describe layers without touching the disk, e.g. for a mount that is kept but not
+    /// served. Unmounting it is a no-op.
+    pub fn unmounted(
+        mountpoint: PathBuf,
+        dic: Arc<crate::dicfuse::Dicfuse>,
+        upper_dir: Option<PathBuf>,
+        cl_dirs: Vec<PathBuf>,
+    ) -> Self {
+        Self {
             mountpoint,
             upper_dir,
             dic,
-            cl_dir,
+            bindings: Vec::new(),
+            cl_dirs,
+            mem_upper: None,
+            quota: None,
             fuse_task: None,
-        })
+        }
+    }
+
+    /// Serve each Dicfuse view at its mount-relative path instead of `dic`; paths outside
+    /// every binding do not exist in the base.
+    pub fn with_bindings(mut self, bindings: Vec<(PathBuf, Arc<crate::dicfuse::Dicfuse>)>) -> Self {
+        self.bindings = bindings;
+        self
+    }
+
+    /// Use an in-memory upper layer. It takes precedence over `upper_dir`.
+    pub fn with_memory_upper(mut self, upper: impl Into<Option<Arc<MemUpperLayer>>>) -> Self {
+        self.mem_upper = upper.into();
+        self
+    }
+
+    /// Enforce `quota` on the upper layer for every subsequent mount.
+    pub fn with_quota(mut self, quota: impl Into<Option<Arc<UpperQuota>>>) -> Self {
+        self.quota = quota.into();
+        self
+    }
+
+    /// Whether this mount has no upper layer.
+    pub fn is_read_only(&self) -> bool {
+        self.upper_dir.is_none() && self.mem_upper.is_none()
+    }
+
+    /// The in-memory upper layer, if this mount uses one.
+    pub fn memory_upper(&self) -> Option<&Arc<MemUpperLayer>> {
+        self.mem_upper.as_ref()
+    }
+
+    /// Quota counters for the upper layer, if one is enforced.
+    pub fn quota(&self) -> Option<&Arc<UpperQuota>> {
+        self.quota.as_ref()
     }
 
     /// Compose the union filesystem instance.
     pub async fn build_overlay(&self) -> std::io::Result<OverlayFs> {
-        // Build lower layers:
-        // - Optional CL dir sits above Dicfuse to override base files for the CL view.
-        // - Dicfuse remains the base read-only monorepo layer.
+        // Build lower layers (highest priority first):
+        // - CL dirs sit above Dicfuse to override base files; the last CL in the stack wins.
+        // - Dicfuse remains the base read-only monorepo layer; composite mounts serve their
+        //   bound views side by side instead.
         let mut lower_layers: Vec<Arc<dyn Layer>> = Vec::new();
 
-        if let Some(cl_dir) = &self.cl_dir {
+        for cl_dir in self.cl_dirs.iter().rev() {
             let cl_layer = new_passthroughfs_layer(PassthroughArgs {
                 root_dir: cl_dir,
                 mapping: None::<String>,
@@ -57,16 +168,30 @@ impl AntaresFuse {
             lower_layers.push(Arc::new(cl_layer) as Arc<dyn Layer>);
         }
 
-        lower_layers.push(self.dic.clone() as Arc<dyn Layer>);
+        if self.bindings.is_empty() {
+            lower_layers.push(self.dic.clone() as Arc<dyn Layer>);
+        } else {
+            let bound = self
+                .bindings
+                .iter()
+                .map(|(target, dic)| (target.clone(), dic.clone() as Arc<dyn Layer>))
+                .collect();
+            lower_layers.push(Arc::new(CompositeLayer::new(bound)?) as Arc<dyn Layer>);
+        }
 
-        // Upper layer mirrors upper_dir to keep writes separated from lower layers.
-        let upper_layer: Arc<dyn Layer> = Arc::new(
-            new_passthroughfs_layer(PassthroughArgs {
-                root_dir: &self.upper_dir,
-                mapping: None::<String>,
-            })
-            .await?,
-        );
+        // Upper layer mirrors upper_dir (or RAM) to keep writes separated from lower layers.
+        // Read-only mounts have none; the overlay then answers writes with EROFS.
+        let upper_layer: Option<Arc<dyn Layer>> = match (&self.mem_upper, &self.upper_dir) {
+            (Some(mem), _) => Some(mem.clone() as Arc<dyn Layer>),
+            (None, Some(upper_dir)) => Some(Arc::new(
+                new_passthroughfs_layer(PassthroughArgs {
+                    root_dir: upper_dir,
+                    mapping: None::<String>,
+                })
+                .await?,
+            )),
+            (None, None) => None,
+        };
 
         // passthrough Upper  - readwrite file system over upper dir
         // passthrough CL  - readwrite file system over upper dir
@@ -78,7 +203,7 @@ impl AntaresFuse {
             ..Default::default()
         };
 
-        OverlayFs::new(Some(upper_layer), lower_layers, cfg, 1)
+        OverlayFs::new(upper_layer, lower_layers, cfg, 1)
     }
 
     /// Mount the composed unionfs into the provided mountpoint, spawning a background task to run the FUSE session.
@@ -93,9 +218,21 @@ impl AntaresFuse {
         std::fs::metadata(&self.mountpoint)?;
 
         let overlay = self.build_overlay().await?;
-        let logfs = LoggingFileSystem::new(overlay);
-        let handle =
-            mount_filesystem_with_antares_cache(logfs, self.mountpoint.as_os_str(), true).await;
+        // Unlimited mounts skip the wrapper; their usage is only refreshed by rescans.
+        let handle = match self.quota.as_ref().filter(|q| !q.limits().is_unlimited()) {
+            _ if self.is_read_only() => {
+                let logfs = LoggingFileSystem::new(overlay);
+                mount_read_only_filesystem(logfs, self.mountpoint.as_os_str()).await
+            }
+            Some(quota) => {
+                let logfs = LoggingFileSystem::new(QuotaFs::new(overlay, quota.clone()));
+                mount_filesystem_with_antares_cache(logfs, self.mountpoint.as_os_str(), true).await
+            }
+            None => {
+                let logfs = LoggingFileSystem::new(overlay);
+                mount_filesystem_with_antares_cache(logfs, self.mountpoint.as_os_str(), true).await
+            }
+        };
 
         // Spawn background task to run the FUSE session
         let fuse_task = tokio::spawn(async move {
@@ -112,6 +249,14 @@ impl AntaresFuse {
         Ok(())
     }
 
+    /// Whether the FUSE session was started and has since exited without [`Self::unmount`],
+    /// e.g. because the kernel connection was aborted.
+    pub fn session_ended(&self) -> bool {
+        self.fuse_task
+            .as_ref()
+            .is_some_and(|task| task.is_finished())
+    }
+
     /// Unmount the FUSE session if mounted.
     ///
     /// Uses lazy unmount (`fusermount -uz`) to detach the filesystem even if
@@ -228,512 +373,32 @@ impl AntaresFuse {
 
 #[cfg(test)]
 mod tests {
-    use std::{
-        collections::HashMap,
-        ffi::{OsStr, OsString},
-        num::NonZeroU32,
-        path::PathBuf,
-        sync::atomic::{AtomicU64, Ordering},
-    };
+    use std::{ffi::OsStr, path::PathBuf};
 
-    use async_trait::async_trait;
-    use bytes::Bytes;
-    use libfuse_fs::{
-        context::OperationContext,
-        unionfs::{config::Config as UnionConfig, layer::Layer, OverlayFs},
-    };
-    use rfuse3::{
-        raw::{
-            reply::{
-                DirectoryEntry, FileAttr, ReplyAttr, ReplyCreated, ReplyData, ReplyDirectory,
-                ReplyEntry, ReplyInit, ReplyOpen, ReplyWrite, ReplyXAttr,
-            },
-            Filesystem, Request,
-        },
-        FileType, Inode, Result as FuseResult, Timestamp,
-    };
+    use libfuse_fs::unionfs::{config::Config as UnionConfig, layer::Layer, OverlayFs};
+    use rfuse3::raw::{Filesystem, Request};
     use serial_test::serial;
     use tokio::time::{sleep, Duration};
     use uuid::Uuid;
 
     use super::AntaresFuse;
-    use crate::{dicfuse::Dicfuse, util::config};
-
-    #[derive(Debug, Clone)]
-    struct MemNode {
-        inode: u64,
-        parent: u64,
-        kind: FileType,
-        perm: u16,
-        uid: u32,
-        gid: u32,
-        data: Vec<u8>,
-    }
-
-    #[derive(Debug, Default)]
-    struct MemState {
-        nodes: HashMap<u64, MemNode>,
-        children: HashMap<u64, HashMap<OsString, u64>>,
-    }
-
-    /// A tiny in-memory upper layer used to test OverlayFs copy-up semantics without requiring
-    /// passthroughfs (open_by_handle_at) or an actual kernel mount.
-    #[derive(Debug)]
-    struct MemUpperLayer {
-        next_inode: AtomicU64,
-        state: tokio::sync::RwLock<MemState>,
-    }
-
-    impl MemUpperLayer {
-        fn new() -> Self {
-            let uid = unsafe { libc::getuid() } as u32;
-            let gid = unsafe { libc::getgid() } as u32;
-            let mut st = MemState::default();
-            st.nodes.insert(
-                1,
-                MemNode {
-                    inode: 1,
-                    parent: 0,
-                    kind: FileType::Directory,
-                    perm: 0o755,
-                    uid,
-                    gid,
-                    data: Vec::new(),
-                },
-            );
-            Self {
-                next_inode: AtomicU64::new(1),
-                state: tokio::sync::RwLock::new(st),
-            }
-        }
-
-        fn now_ts() -> Timestamp {
-            Timestamp::from(std::time::SystemTime::now())
-        }
-
-        fn file_attr(node: &MemNode) -> FileAttr {
-            let ts = Self::now_ts();
-            FileAttr {
-                ino: node.inode,
-                size: node.data.len() as u64,
-                blocks: 0,
-                atime: ts,
-                mtime: ts,
-                ctime: ts,
-                kind: node.kind,
-                perm: node.perm,
-                nlink: if node.kind == FileType::Directory {
-                    2
-                } else {
-                    1
-                },
-                uid: node.uid,
-                gid: node.gid,
-                rdev: 0,
-                blksize: 4096,
-            }
-        }
-
-        async fn create_child(
-            &self,
-            parent: u64,
-            name: &OsStr,
-            kind: FileType,
-            perm: u16,
-        ) -> std::io::Result<u64> {
-            let mut st = self.state.write().await;
-            let parent_node = st
-                .nodes
-                .get(&parent)
-                .ok_or_else(|| std::io::Error::from_raw_os_error(libc::ENOENT))?;
-            if parent_node.kind != FileType::Directory {
-                return Err(std::io::Error::from_raw_os_error(libc::ENOTDIR));
-            }
-
-            let existing = st.children.get(&parent).and_then(|m| m.get(name).copied());
-            if let Some(inode) = existing {
-                return Ok(inode);
-            }
-
-            let inode = self.next_inode.fetch_add(1, Ordering::Relaxed) + 1;
-            let uid = unsafe { libc::getuid() } as u32;
-            let gid = unsafe { libc::getgid() } as u32;
-            st.nodes.insert(
-                inode,
-                MemNode {
-                    inode,
-                    parent,
-                    kind,
-                    perm,
-                    uid,
-                    gid,
-                    data: Vec::new(),
-                },
-            );
-            st.children
-                .entry(parent)
-                .or_default()
-                .insert(name.to_os_string(), inode);
-            Ok(inode)
-        }
-
-        async fn get_child_inode(&self, parent: u64, name: &OsStr) -> Option<u64> {
-            let st = self.state.read().await;
-            st.children.get(&parent).and_then(|m| m.get(name).copied())
-        }
-
-        async fn read_file_by_name(&self, name: &str) -> Option<Vec<u8>> {
-            let st = self.state.read().await;
-            let ino = st
-                .children
-                .get(&1)
-                .and_then(|m| m.get(OsStr::new(name)))
-                .copied()?;
-            st.nodes.get(&ino).map(|n| n.data.clone())
-        }
-    }
-
-    impl Filesystem for MemUpperLayer {
-        async fn init(&self, _req: Request) -> FuseResult<ReplyInit> {
-            Ok(ReplyInit {
-                max_write: NonZeroU32::new(128 * 1024).unwrap(),
-            })
-        }
-
-        async fn destroy(&self, _req: Request) {}
-
-        async fn lookup(
-            &self,
-            _req: Request,
-            parent: Inode,
-            name: &OsStr,
-        ) -> FuseResult<ReplyEntry> {
-            let inode = self
-                .get_child_inode(parent, name)
-                .await
-                .ok_or_else(|| std::io::Error::from_raw_os_error(libc::ENOENT))?;
-            let st = self.state.read().await;
-            let node = st
-                .nodes
-                .get(&inode)
-                .ok_or_else(|| std::io::Error::from_raw_os_error(libc::ENOENT))?;
-            Ok(ReplyEntry {
-                ttl: Duration::from_secs(1),
-                attr: Self::file_attr(node),
-                generation: 0,
-            })
-        }
-
-        async fn getattr(
-            &self,
-            _req: Request,
-            inode: Inode,
-            _fh: Option<u64>,
-            _flags: u32,
-        ) -> FuseResult<ReplyAttr> {
-            let st = self.state.read().await;
-            let node = st
-                .nodes
-                .get(&inode)
-                .ok_or_else(|| std::io::Error::from_raw_os_error(libc::ENOENT))?;
-            Ok(ReplyAttr {
-                ttl: Duration::from_secs(1),
-                attr: Self::file_attr(node),
-            })
-        }
-
-        async fn setattr(
-            &self,
-            _req: Request,
-            inode: Inode,
-            _fh: Option<u64>,
-            _set_attr: rfuse3::SetAttr,
-        ) -> FuseResult<ReplyAttr> {
-            // Minimal: accept setattr and return current attrs. Overlay copy-up uses this to
-            // preserve ownership/mode; our in-memory layer does not model these changes yet.
-            self.getattr(_req, inode, _fh, 0).await
-        }
-
-        async fn open(&self, _req: Request, inode: Inode, _flags: u32) -> FuseResult<ReplyOpen> {
-            // Use inode as the handle.
-            Ok(ReplyOpen {
-                fh: inode,
-                flags: 0,
-            })
-        }
-
-        async fn read(
-            &self,
-            _req: Request,
-            inode: Inode,
-            _fh: u64,
-            offset: u64,
-            size: u32,
-        ) -> FuseResult<ReplyData> {
-            let st = self.state.read().await;
-            let node = st
-                .nodes
-                .get(&inode)
-                .ok_or_else(|| std::io::Error::from_raw_os_error(libc::ENOENT))?;
-            let off = offset as usize;
-            let end = (off + size as usize).min(node.data.len());
-            let slice = if off >= node.data.len() {
-                &[]
-            } else {
-                &node.data[off..end]
-            };
-            Ok(ReplyData {
-                data: Bytes::copy_from_slice(slice),
-            })
-        }
-
-        #[allow(clippy::too_many_arguments)]
-        async fn write(
-            &self,
-            _req: Request,
-            inode: Inode,
-            _fh: u64,
-            offset: u64,
-            data: &[u8],
-            _write_flags: u32,
-            _flags: u32,
-        ) -> FuseResult<ReplyWrite> {
-            let mut st = self.state.write().await;
-            let node = st
-                .nodes
-                .get_mut(&inode)
-                .ok_or_else(|| std::io::Error::from_raw_os_error(libc::ENOENT))?;
-            if node.kind == FileType::Directory {
-                return Err(std::io::Error::from_raw_os_error(libc::EISDIR).into());
-            }
-            let off = offset as usize;
-            let needed = off + data.len();
-            if node.data.len() < needed {
-                node.data.resize(needed, 0);
-            }
-            node.data[off..off + data.len()].copy_from_slice(data);
-            Ok(ReplyWrite {
-                written: data.len() as u32,
-            })
-        }
-
-        async fn setxattr(
-            &self,
-            _req: Request,
-            _inode: Inode,
-            _name: &OsStr,
-            _value: &[u8],
-            _flags: u32,
-            _position: u32,
-        ) -> FuseResult<()> {
-            // No-op xattr support for overlay bookkeeping.
-            Ok(())
-        }
-
-        async fn getxattr(
-            &self,
-            _req: Request,
-            _inode: Inode,
-            _name: &OsStr,
-            _size: u32,
-        ) -> FuseResult<ReplyXAttr> {
-            Err(std::io::Error::from_raw_os_error(libc::ENODATA).into())
-        }
-
-        async fn listxattr(
-            &self,
-            _req: Request,
-            _inode: Inode,
-            size: u32,
-        ) -> FuseResult<ReplyXAttr> {
-            if size == 0 {
-                Ok(ReplyXAttr::Size(0))
-            } else {
-                Ok(ReplyXAttr::Data(Bytes::new()))
-            }
-        }
-
-        async fn removexattr(&self, _req: Request, _inode: Inode, _name: &OsStr) -> FuseResult<()> {
-            Ok(())
-        }
-
-        async fn readdir<'a>(
-            &'a self,
-            _req: Request,
-            parent: Inode,
-            _fh: u64,
-            offset: i64,
-        ) -> FuseResult<
-            ReplyDirectory<impl futures::Stream<Item = FuseResult<DirectoryEntry>> + Send + 'a>,
-        > {
-            use futures::stream::iter;
-
-            let st = self.state.read().await;
-            let parent_node = st
-                .nodes
-                .get(&parent)
-                .ok_or_else(|| std::io::Error::from_raw_os_error(libc::ENOENT))?;
-            if parent_node.kind != FileType::Directory {
-                return Err(std::io::Error::from_raw_os_error(libc::ENOTDIR).into());
-            }
-            let parent_parent_inode = if parent == 1 { 1 } else { parent_node.parent };
-
-            let mut out: Vec<std::result::Result<DirectoryEntry, rfuse3::Errno>> = Vec::new();
-
-            // offset 0: ".", offset 1: "..", offset 2+: children
-            if offset < 1 {
-                out.push(Ok(DirectoryEntry {
-                    inode: parent,
-                    kind: FileType::Directory,
-                    name: ".".into(),
-                    offset: 1,
-                }));
-            }
-            if offset < 2 {
-                out.push(Ok(DirectoryEntry {
-                    inode: parent_parent_inode,
-                    kind: FileType::Directory,
-                    name: "..".into(),
-                    offset: 2,
-                }));
-            }
-
-            if let Some(children) = st.children.get(&parent) {
-                for (idx, (name, inode)) in children.iter().enumerate() {
-                    let entry_offset = (idx + 2) as i64;
-                    if entry_offset > offset {
-                        let kind = st
-                            .nodes
-                            .get(inode)
-                            .map(|n| n.kind)
-                            .unwrap_or(FileType::RegularFile);
-                        out.push(Ok(DirectoryEntry {
-                            inode: *inode,
-                            kind,
-                            name: name.clone(),
-                            offset: entry_offset + 1,
-                        }));
-                    }
-                }
-            }
-
-            Ok(ReplyDirectory {
-                entries: iter(out.into_iter()),
-            })
-        }
-
-        async fn releasedir(
-            &self,
-            _req: Request,
-            _inode: Inode,
-            _fh: u64,
-            _flags: u32,
-        ) -> FuseResult<()> {
-            Ok(())
-        }
-
-        async fn getlk(
-            &self,
-            _req: Request,
-            _inode: Inode,
-            _fh: u64,
-            _lock_owner: u64,
-            start: u64,
-            end: u64,
-            _type: u32,
-            _pid: u32,
-        ) -> FuseResult<rfuse3::raw::reply::ReplyLock> {
-            // In-memory test layer: no locks held, report F_UNLCK.
-            Ok(rfuse3::raw::reply::ReplyLock {
-                start,
-                end,
-                r#type: libc::F_UNLCK as u32,
-                pid: 0,
-            })
-        }
-
-        async fn setlk(
-            &self,
-            _req: Request,
-            _inode: Inode,
-            _fh: u64,
-            _lock_owner: u64,
-            _start: u64,
-            _end: u64,
-            _type: u32,
-            _pid: u32,
-            _block: bool,
-        ) -> FuseResult<()> {
-            // In-memory test layer: accept lock requests (no real enforcement).
-            Ok(())
-        }
-    }
-
-    #[async_trait]
-    impl Layer for MemUpperLayer {
-        fn root_inode(&self) -> Inode {
-            1
-        }
-
-        async fn create_with_context(
-            &self,
-            _ctx: OperationContext,
-            parent: Inode,
-            name: &OsStr,
-            _mode: u32,
-            _flags: u32,
-        ) -> FuseResult<ReplyCreated> {
-            let inode = self
-                .create_child(parent, name, FileType::RegularFile, 0o644)
-                .await
-                .map_err(rfuse3::Errno::from)?;
-            let st = self.state.read().await;
-            let node = st
-                .nodes
-                .get(&inode)
-                .ok_or_else(|| std::io::Error::from_raw_os_error(libc::ENOENT))?;
-            Ok(ReplyCreated {
-                ttl: Duration::from_secs(1),
-                attr: Self::file_attr(node),
-                generation: 0,
-                fh: inode,
-                flags: 0,
-            })
-        }
-
-        async fn mkdir_with_context(
-            &self,
-            _ctx: OperationContext,
-            parent: Inode,
-            name: &OsStr,
-            _mode: u32,
-            _umask: u32,
-        ) -> FuseResult<ReplyEntry> {
-            let inode = self
-                .create_child(parent, name, FileType::Directory, 0o755)
-                .await
-                .map_err(rfuse3::Errno::from)?;
-            let st = self.state.read().await;
-            let node = st
-                .nodes
-                .get(&inode)
-                .ok_or_else(|| std::io::Error::from_raw_os_error(libc::ENOENT))?;
-            Ok(ReplyEntry {
-                ttl: Duration::from_secs(1),
-                attr: Self::file_attr(node),
-                generation: 0,
-            })
-        }
+    use crate::{
+        antares::{mem_upper::MemUpperLayer, quota::QuotaLimits},
+        dicfuse::Dicfuse,
+        util::config,
+    };
 
-        async fn symlink_with_context(
-            &self,
-            _ctx: OperationContext,
-            _parent: Inode,
-            _name: &OsStr,
-            _link: &OsStr,
-        ) -> FuseResult<ReplyEntry> {
-            Err(std::io::Error::from_raw_os_error(libc::ENOSYS).into())
-        }
+    /// Read `name` from the root of an in-memory upper through its `Filesystem` interface.
+    async fn read_upper_file(upper: &MemUpperLayer, name: &str) -> Option<Vec<u8>> {
+        let entry = upper
+            .lookup(Request::default(), 1, OsStr::new(name))
+            .await
+            .ok()?;
+        let data = upper
+            .read(Request::default(), entry.attr.ino, 0, 0, u32::MAX)
+            .await
+            .ok()?;
+        Some(data.data.to_vec())
     }
 
     /// No actual mount required: validate copy-up behavior via `OverlayFs`'s Filesystem interface.
@@ -752,7 +417,7 @@ mod tests {
         dic.store.load_db().await.unwrap();
 
         // Upper: in-memory writable layer.
-        let upper = std::sync::Arc::new(MemUpperLayer::new());
+        let upper = std::sync::Arc::new(MemUpperLayer::new(QuotaLimits::default()));
 
         let cfg = UnionConfig {
             mountpoint: PathBuf::from(format!("/tmp/scorpio_overlay_unit_{test_id}")),
@@ -810,7 +475,7 @@ mod tests {
 
         // Verify upper contains the file and lower store did not change.
         assert_eq!(
-            upper.read_file_by_name("hello.txt").await.unwrap(),
+            read_upper_file(&upper, "hello.txt").await.unwrap(),
             b"upper"
         );
         assert_eq!(dic.store.get_file_content(2).unwrap().to_vec(), b"lower");
@@ -848,8 +513,8 @@ mod tests {
         }
         let _ = std::fs::write(&marker_path, b"ok\n");
 
-        let upper1 = std::sync::Arc::new(MemUpperLayer::new());
-        let upper2 = std::sync::Arc::new(MemUpperLayer::new());
+        let upper1 = std::sync::Arc::new(MemUpperLayer::new(QuotaLimits::default()));
+        let upper2 = std::sync::Arc::new(MemUpperLayer::new(QuotaLimits::default()));
 
         let cfg1 = UnionConfig {
             mountpoint: PathBuf::from(format!("/tmp/scorpio_overlay_unit2_a_{test_id}")),
@@ -958,10 +623,10 @@ mod tests {
         assert_eq!(d2.data.as_ref(), b"lower");
 
         assert_eq!(
-            upper1.read_file_by_name("hello.txt").await.unwrap(),
+            read_upper_file(&upper1, "hello.txt").await.unwrap(),
             b"upper1"
         );
-        assert!(upper2.read_file_by_name("hello.txt").await.is_none());
+        assert!(read_upper_file(&upper2, "hello.txt").await.is_none());
 
         // Lower store content must remain unchanged.
         assert_eq!(dic.store.get_file_content(2).unwrap().to_vec(), b"lower");
@@ -0,0 +1,361 @@
+//! Garbage collection of Antares state nothing refers to any more.
+//!
+//! Crashes and skipped recoveries leave upper, CL and mount directories without a state
+//! entry, FUSE mounts whose serving process is gone, and per-base_path Dicfuse stores no
+//! mount uses. This module finds them on disk; the daemon decides what is still live
+//! (see `AntaresServiceImpl::collect_garbage`).
+//!
+//! Everything here errs on the side of keeping: a directory survives when a live path or
+//! a mount point lies inside it, when anything in it changed within the retention window,
+//! or when it cannot be inspected.
+
+use std::{
+    collections::HashSet,
+    fs, io,
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// What a collected path was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GcKind {
+    /// Directory under `antares_upper_root`.
+    Upper,
+    /// Directory under `antares_cl_root`.
+    Cl,
+    /// Directory under `antares_mount_root`.
+    Mount,
+    /// FUSE mount under `antares_mount_root` whose session is gone (`ENOTCONN`).
+    StaleMount,
+    /// Cached in-memory Dicfuse view no mount uses; the path is its store directory.
+    DicfuseView,
+    /// On-disk Dicfuse store no process has open.
+    DicfuseStore,
+}
+
+impl GcKind {
+    /// Serialized name, e.g. `stale_mount`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GcKind::Upper => "upper",
+            GcKind::Cl => "cl",
+            GcKind::Mount => "mount",
+            GcKind::StaleMount => "stale_mount",
+            GcKind::DicfuseView => "dicfuse_view",
+            GcKind::DicfuseStore => "dicfuse_store",
+        }
+    }
+}
+
+/// One path removed by a pass, or that a dry run would remove.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GcEntry {
+    pub kind: GcKind,
+    pub path: String,
+}
+
+/// Live state the directory scan must not touch.
+#[derive(Debug, Clone)]
+pub struct GcScan {
+    /// Roots to scan, each with the kind of the directories below it.
+    pub roots: Vec<(GcKind, PathBuf)>,
+    /// Paths in use (layers and mountpoints of live mounts, configured roots, state
+    /// file, ...); directories containing one are kept.
+    pub referenced: Vec<PathBuf>,
+    /// Ids of live and provisioning mounts; generated names starting with one are kept.
+    pub live_ids: HashSet<String>,
+    /// Current mount points; directories containing one are kept.
+    pub mount_points: Vec<PathBuf>,
+    /// Anything modified at or after this instant is kept.
+    pub cutoff: SystemTime,
+}
+
+impl GcScan {
+    /// Unreferenced directories directly under the roots; a root listed twice is
+    /// scanned once.
+    pub fn orphaned_dirs(&self) -> Vec<GcEntry> {
+        let mut seen = HashSet::new();
+        let mut orphans = Vec::new();
+        for (kind, root) in &self.roots {
+            if !seen.insert(root.clone()) {
+                continue;
+            }
+            let Ok(entries) = fs::read_dir(root) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+                if is_dir && !self.keeps(&path) {
+                    orphans.push(GcEntry {
+                        kind: *kind,
+                        path: path.to_string_lossy().into_owned(),
+                    });
+                }
+            }
+        }
+        orphans
+    }
+
+    fn keeps(&self, path: &Path) -> bool {
+        self.live_ids.contains(&generated_id(path))
+            || self.referenced.iter().any(|r| r.starts_with(path))
+            || self.mount_points.iter().any(|m| m.starts_with(path))
+            || modified_since(path, self.cutoff)
+    }
+}
+
+/// The mount id a generated layer or mount directory is named after: `{mount_id}`,
+/// `{mount_id}.{n}` for stacked CL layers, and staging siblings.
+pub fn generated_id(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    name.split('.').next().unwrap_or_default().to_string()
+}
+
+/// Whether anything at or below `path` was modified at or after `cutoff`. Symlinks are
+/// not followed; entries that cannot be inspected count as modified.
+pub fn modified_since(path: &Path, cutoff: SystemTime) -> bool {
+    let recent = |meta: &fs::Metadata| meta.modified().map_or(true, |m| m >= cutoff);
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        match fs::symlink_metadata(&dir) {
+            Ok(meta) if recent(&meta) => return true,
+            Ok(meta) if !meta.is_dir() => continue,
+            Ok(_) => {}
+            Err(_) => return true,
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            return true;
+        };
+        for entry in entries {
+            let Ok(entry) = entry else {
+                return true;
+            };
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => pending.push(entry.path()),
+                Ok(_) => match entry.metadata() {
+                    Ok(meta) if !recent(&meta) => {}
+                    _ => return true,
+                },
+                Err(_) => return true,
+            }
+        }
+    }
+    false
+}
+
+/// A line of `/proc/self/mountinfo`: mount point and filesystem type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountInfo {
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+}
+
+impl MountInfo {
+    pub fn is_fuse(&self) -> bool {
+        self.fs_type == "fuse" || self.fs_type.starts_with("fuse.")
+    }
+}
+
+/// Mounts visible to this process.
+pub fn mounts() -> io::Result<Vec<MountInfo>> {
+    Ok(parse_mountinfo(&fs::read_to_string(
+        "/proc/self/mountinfo",
+    )?))
+}
+
+/// Parse `mountinfo(5)`: field 5 is the mount point, the field after `-` the type.
+pub fn parse_mountinfo(content: &str) -> Vec<MountInfo> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let mount_point = fields.get(4)?;
+            let separator = fields.iter().position(|f| *f == "-")?;
+            let fs_type = fields.get(separator + 1)?;
+            Some(MountInfo {
+                mount_point: PathBuf::from(unescape_mountinfo(mount_point)),
+                fs_type: fs_type.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Undo the `\ooo` octal escapes mountinfo uses for space, tab, newline and backslash.
+fn unescape_mountinfo(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 4).filter(|_| bytes[i] == b'\\');
+        match escaped.and_then(|o| u8::from_str_radix(std::str::from_utf8(o).ok()?, 8).ok()) {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Per-base_path and tuned Dicfuse store directories under `store_root`.
+pub fn dicfuse_store_dirs(store_root: &Path) -> Vec<PathBuf> {
+    let subdirs = |dir: PathBuf| -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                    .map(|e| e.path())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let base = store_root.join("dicfuse");
+    let tuned = base.join("tuned");
+    let mut dirs: Vec<PathBuf> = subdirs(base).into_iter().filter(|d| *d != tuned).collect();
+    dirs.extend(subdirs(tuned));
+    dirs
+}
+
+/// Whether any process holds one of the sled DBs of the Dicfuse store at `dir` open.
+///
+/// sled takes an exclusive `flock` on each DB's `db` file while it is open; failing to
+/// take it here means the store is in use, by this process or another one.
+pub fn dicfuse_store_in_use(dir: &Path) -> bool {
+    ["path.db", "content.db", "size.db"].iter().any(|db| {
+        let file = match fs::File::open(dir.join(db).join("db")) {
+            Ok(file) => file,
+            Err(e) => return e.kind() != io::ErrorKind::NotFound,
+        };
+        // The lock is released when `file` is closed.
+        unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) != 0 }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn scan(root: &Path, cutoff: SystemTime) -> GcScan {
+        GcScan {
+            roots: vec![(GcKind::Upper, root.to_path_buf())],
+            referenced: Vec::new(),
+            live_ids: HashSet::new(),
+            mount_points: Vec::new(),
+            cutoff,
+        }
+    }
+
+    fn orphan_paths(scan: &GcScan) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = scan
+            .orphaned_dirs()
+            .into_iter()
+            .map(|e| PathBuf::from(e.path))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_orphaned_dirs_skip_live_and_referenced() {
+        let root = tempfile::tempdir().unwrap();
+        for name in ["live", "live.1", "orphan", "custom", "mounted", "nested"] {
+            fs::create_dir_all(root.path().join(name).join("sub")).unwrap();
+        }
+        fs::write(root.path().join("file"), b"x").unwrap();
+
+        let mut scan = scan(root.path(), SystemTime::now() + Duration::from_secs(60));
+        scan.live_ids.insert("live".to_string());
+        scan.referenced.push(root.path().join("custom"));
+        scan.referenced.push(root.path().join("nested/sub/deeper"));
+        scan.mount_points.push(root.path().join("mounted/sub"));
+
+        assert_eq!(orphan_paths(&scan), vec![root.path().join("orphan")]);
+    }
+
+    #[test]
+    fn test_orphaned_dirs_respect_retention() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("fresh/sub")).unwrap();
+
+        let past = SystemTime::now() - Duration::from_secs(3600);
+        assert!(orphan_paths(&scan(root.path(), past)).is_empty());
+        let future = SystemTime::now() + Duration::from_secs(60);
+        assert_eq!(
+            orphan_paths(&scan(root.path(), future)),
+            vec![root.path().join("fresh")]
+        );
+    }
+
+    #[test]
+    fn test_modified_since_sees_nested_changes() {
+        let root = tempfile::tempdir().unwrap();
+        let file = root.path().join("a/b/c.txt");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, b"x").unwrap();
+        let written = fs::metadata(&file).unwrap().modified().unwrap();
+
+        assert!(modified_since(root.path(), written));
+        assert!(!modified_since(
+            root.path(),
+            written + Duration::from_secs(60)
+        ));
+        assert!(modified_since(&root.path().join("missing"), written));
+    }
+
+    #[test]
+    fn test_parse_mountinfo() {
+        let content = "\
+36 35 98:0 / /mnt1 rw,noatime master:1 - ext3 /dev/root rw,errors=continue
+52 36 0:45 / /tmp/antares/mnt/job\\0401 rw,nosuid,nodev shared:30 - fuse /dev/fuse rw,user_id=0
+53 36 0:46 / /tmp/other rw - fuse.sshfs host: rw";
+        let mounts = parse_mountinfo(content);
+        assert_eq!(mounts.len(), 3);
+        assert_eq!(
+            mounts[1].mount_point,
+            PathBuf::from("/tmp/antares/mnt/job 1")
+        );
+        assert!(!mounts[0].is_fuse());
+        assert!(mounts[1].is_fuse());
+        assert!(mounts[2].is_fuse());
+    }
+
+    #[test]
+    fn test_dicfuse_store_dirs_and_locks() {
+        let root = tempfile::tempdir().unwrap();
+        let plain = root.path().join("dicfuse/0123456789abcdef");
+        let tuned = root.path().join("dicfuse/tuned/0123456789abcdef-ttl5");
+        fs::create_dir_all(plain.join("path.db")).unwrap();
+        fs::create_dir_all(&tuned).unwrap();
+        fs::write(plain.join("path.db/db"), b"").unwrap();
+
+        let mut dirs = dicfuse_store_dirs(root.path());
+        dirs.sort();
+        assert_eq!(dirs, vec![plain.clone(), tuned.clone()]);
+
+        assert!(!dicfuse_store_in_use(&plain));
+        let held = fs::File::open(plain.join("path.db/db")).unwrap();
+        assert_eq!(
+            unsafe { libc::flock(held.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) },
+            0
+        );
+        assert!(dicfuse_store_in_use(&plain));
+        drop(held);
+        assert!(!dicfuse_store_in_use(&plain));
+        assert!(!dicfuse_store_in_use(&tuned));
+    }
+}
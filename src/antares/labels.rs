@@ -0,0 +1,198 @@
+//! Free-form labels on mounts and the selectors that match them.
+//!
+//! Labels are `key=value` pairs set when a mount is created (pipeline, team, target, ...).
+//! Keys and values use `A-Z a-z 0-9 - _ . /`, start with a letter or digit and are at most
+//! 63 characters long; values may also be empty.
+//!
+//! A [`LabelSelector`] is a comma-separated list of requirements, all of which must hold:
+//!
+//! | requirement | matches when |
+//! | --- | --- |
+//! | `key=value` (or `key==value`) | `key` is set to `value` |
+//! | `key!=value` | `key` is unset or set to another value |
+//! | `key` | `key` is set |
+//! | `!key` | `key` is unset |
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// Labels of one mount, sorted by key.
+pub type Labels = BTreeMap<String, String>;
+
+/// Most labels a single mount may carry.
+pub const MAX_LABELS: usize = 64;
+const MAX_LEN: usize = 63;
+
+fn valid_token(token: &str) -> bool {
+    token.len() <= MAX_LEN
+        && token
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+        && token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+}
+
+/// Check the keys, values and number of `labels`.
+pub fn validate(labels: &Labels) -> Result<(), String> {
+    if labels.len() > MAX_LABELS {
+        return Err(format!(
+            "a mount carries at most {MAX_LABELS} labels, got {}",
+            labels.len()
+        ));
+    }
+    for (key, value) in labels {
+        if !valid_token(key) {
+            return Err(format!("invalid label key {key:?}"));
+        }
+        if !value.is_empty() && !valid_token(value) {
+            return Err(format!("invalid value {value:?} for label {key}"));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Requirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    Exists(String),
+    NotExists(String),
+}
+
+impl Requirement {
+    fn matches(&self, labels: &Labels) -> bool {
+        match self {
+            Self::Equals(key, value) => labels.get(key) == Some(value),
+            Self::NotEquals(key, value) => labels.get(key) != Some(value),
+            Self::Exists(key) => labels.contains_key(key),
+            Self::NotExists(key) => !labels.contains_key(key),
+        }
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Equals(key, value) => write!(f, "{key}={value}"),
+            Self::NotEquals(key, value) => write!(f, "{key}!={value}"),
+            Self::Exists(key) => write!(f, "{key}"),
+            Self::NotExists(key) => write!(f, "!{key}"),
+        }
+    }
+}
+
+/// Conjunction of label requirements; the empty selector matches every mount.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSelector {
+    requirements: Vec<Requirement>,
+}
+
+impl LabelSelector {
+    pub fn is_empty(&self) -> bool {
+        self.requirements.is_empty()
+    }
+
+    pub fn matches(&self, labels: &Labels) -> bool {
+        self.requirements.iter().all(|r| r.matches(labels))
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut requirements = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let requirement = if let Some((key, value)) = part.split_once("!=") {
+                Requirement::NotEquals(key.trim().into(), value.trim().into())
+            } else if let Some((key, value)) = part.split_once('=') {
+                let value = value.strip_prefix('=').unwrap_or(value);
+                Requirement::Equals(key.trim().into(), value.trim().into())
+            } else if let Some(key) = part.strip_prefix('!') {
+                Requirement::NotExists(key.trim().into())
+            } else {
+                Requirement::Exists(part.into())
+            };
+            let (key, value) = match &requirement {
+                Requirement::Equals(key, value) | Requirement::NotEquals(key, value) => {
+                    (key, value.as_str())
+                }
+                Requirement::Exists(key) | Requirement::NotExists(key) => (key, ""),
+            };
+            if !valid_token(key) || !(value.is_empty() || valid_token(value)) {
+                return Err(format!("invalid label selector requirement {part:?}"));
+            }
+            requirements.push(requirement);
+        }
+        Ok(Self { requirements })
+    }
+}
+
+impl fmt::Display for LabelSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, requirement) in self.requirements.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{requirement}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> Labels {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_labels() {
+        assert!(validate(&labels(&[("pipeline", "1234"), ("team/infra", "")])).is_ok());
+        assert!(validate(&labels(&[("", "x")])).is_err());
+        assert!(validate(&labels(&[("-team", "x")])).is_err());
+        assert!(validate(&labels(&[("team", "a,b")])).is_err());
+        assert!(validate(&labels(&[("team", &"x".repeat(64))])).is_err());
+        let many: Labels = (0..=MAX_LABELS)
+            .map(|i| (format!("k{i}"), String::new()))
+            .collect();
+        assert!(validate(&many).is_err());
+    }
+
+    #[test]
+    fn test_selector_matches() {
+        let mount = labels(&[("pipeline", "1234"), ("team", "infra")]);
+        for (selector, expected) in [
+            ("", true),
+            ("pipeline=1234", true),
+            ("pipeline==1234, team=infra", true),
+            ("pipeline=99", false),
+            ("pipeline!=99", true),
+            ("target!=x", true),
+            ("team", true),
+            ("target", false),
+            ("!target", true),
+            ("!team", false),
+            ("pipeline=1234,!team", false),
+        ] {
+            let parsed: LabelSelector = selector.parse().unwrap();
+            assert_eq!(parsed.matches(&mount), expected, "{selector}");
+        }
+        assert_eq!(
+            "a=1, b!=2,c,!d"
+                .parse::<LabelSelector>()
+                .unwrap()
+                .to_string(),
+            "a=1,b!=2,c,!d"
+        );
+        for invalid in ["=x", "a=b=c", "!", "a b", "a!=b!=c"] {
+            assert!(invalid.parse::<LabelSelector>().is_err(), "{invalid}");
+        }
+    }
+}
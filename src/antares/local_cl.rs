@@ -0,0 +1,1348 @@
+//! CL layers built from a local patch or archive instead of the Mega CL API.
+//!
+//! A local CL is given as a unified diff or git-format patch, a tarball of changed files
+//! and/or a list of deleted paths, all using monorepo paths like a CL files-list. It is
+//! resolved against the mount's Dicfuse base into a [`LocalLayer`]: archive entries
+//! first, then the patches, then the deletions. A patch that does not apply cleanly is
+//! rejected as a whole with an [`ApplyReport`] naming every failing file and hunk.
+//!
+//! Hunks must match the base exactly, but may have moved by any number of lines (no
+//! fuzz). A patch may hold several commits touching the same file; each sees the result
+//! of the previous ones.
+//!
+//! The resolved layer becomes an ordinary [`ClManifest`] whose contents go into the shared
+//! [`BlobCache`] under their git blob ids, so it is built, rebuilt and collected exactly
+//! like a layer fetched from Mega.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use super::{
+    blob_cache::BlobCache,
+    cl_manifest::{self, ClManifest, ClManifestEntry, MODE_EXECUTABLE, MODE_SYMLINK},
+};
+use crate::dicfuse::{
+    store::{PathLookupStatus, EMPTY_BLOB_OID},
+    Dicfuse,
+};
+
+/// Git mode of a regular, non-executable file.
+pub const MODE_REGULAR: u32 = 0o100644;
+
+const NULL_PATH: &str = "/dev/null";
+const TAR_BLOCK: usize = 512;
+
+/// Contents and git mode of one file; the contents of a symlink are its target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalFile {
+    pub data: Vec<u8>,
+    pub mode: u32,
+}
+
+/// A patch that could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("malformed patch at line {line}: {message}")]
+pub struct PatchError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl PatchError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// Every problem found while applying a local CL. Nothing is built if there is one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Error)]
+#[error("local CL does not apply: {}", .problems.join("; "))]
+pub struct ApplyReport {
+    pub problems: Vec<String>,
+}
+
+/// The changes one patch makes to one file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilePatch {
+    /// Path before the change; `None` for a created file.
+    pub old_path: Option<String>,
+    /// Path after the change; `None` for a deleted file.
+    pub new_path: Option<String>,
+    /// Git mode before the change, when the patch states it.
+    pub old_mode: Option<u32>,
+    /// Git mode after the change, when the patch states it.
+    pub new_mode: Option<u32>,
+    /// The old path stays in place (`copy from` / `copy to`).
+    pub copy: bool,
+    /// The patch changes binary contents, which cannot be applied.
+    pub binary: bool,
+    hunks: Vec<Hunk>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Hunk {
+    /// The `@@ ... @@` line, for reports.
+    header: String,
+    old_start: usize,
+    old_lines: usize,
+    new_start: usize,
+    new_lines: usize,
+    lines: Vec<HunkLine>,
+}
+
+/// One line of a hunk, including its line terminator unless the file lacks one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+impl HunkLine {
+    fn text_mut(&mut self) -> &mut String {
+        match self {
+            HunkLine::Context(t) | HunkLine::Remove(t) | HunkLine::Add(t) => t,
+        }
+    }
+}
+
+impl Hunk {
+    fn old_side(&self) -> Vec<&[u8]> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(t) | HunkLine::Remove(t) => Some(t.as_bytes()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    fn new_side(&self) -> impl Iterator<Item = &[u8]> {
+        self.lines.iter().filter_map(|l| match l {
+            HunkLine::Context(t) | HunkLine::Add(t) => Some(t.as_bytes()),
+            HunkLine::Remove(_) => None,
+        })
+    }
+}
+
+impl FilePatch {
+    /// Path used to refer to this file in reports.
+    pub fn display_path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or("<unknown>")
+    }
+
+    /// Apply the hunks to `base`, or describe every hunk that does not match it.
+    pub fn apply(&self, base: &[u8]) -> Result<Vec<u8>, Vec<String>> {
+        let lines: Vec<&[u8]> = base.split_inclusive(|b| *b == b'\n').collect();
+        let mut out = Vec::with_capacity(base.len());
+        let mut failures = Vec::new();
+        // Next base line not yet copied, and how far the hunks have moved so far.
+        let mut cursor = 0;
+        let mut offset = 0isize;
+        for (n, hunk) in self.hunks.iter().enumerate() {
+            let old = hunk.old_side();
+            // A hunk without old lines inserts after `old_start` instead of at it.
+            let stated = if hunk.old_lines == 0 {
+                hunk.old_start
+            } else {
+                hunk.old_start.saturating_sub(1)
+            } as isize;
+            match find_hunk(&lines, &old, cursor, stated + offset) {
+                Some(pos) => {
+                    lines[cursor..pos]
+                        .iter()
+                        .for_each(|l| out.extend_from_slice(l));
+                    hunk.new_side().for_each(|l| out.extend_from_slice(l));
+                    cursor = pos + old.len();
+                    offset = pos as isize - stated;
+                }
+                None => failures.push(format!(
+                    "hunk #{} ({}) does not match the base",
+                    n + 1,
+                    hunk.header
+                )),
+            }
+        }
+        if !failures.is_empty() {
+            return Err(failures);
+        }
+        lines[cursor..]
+            .iter()
+            .for_each(|l| out.extend_from_slice(l));
+        Ok(out)
+    }
+}
+
+/// First position at or after `min` where `old` matches `lines`, nearest to `expected`.
+fn find_hunk(lines: &[&[u8]], old: &[&[u8]], min: usize, expected: isize) -> Option<usize> {
+    let max = lines.len().checked_sub(old.len())?;
+    if min > max {
+        return None;
+    }
+    let expected = expected.clamp(min as isize, max as isize) as usize;
+    let matches = |pos: usize| lines[pos..pos + old.len()] == *old;
+    for delta in 0..=(max - min) {
+        if expected + delta <= max && matches(expected + delta) {
+            return Some(expected + delta);
+        }
+        if delta > 0 && expected >= min + delta && matches(expected - delta) {
+            return Some(expected - delta);
+        }
+    }
+    None
+}
+
+/// Parse a unified diff or git-format patch (including `git format-patch` mails).
+///
+/// `strip` removes that many leading path components like `patch -p`. Without it git
+/// diffs drop their `a/` / `b/` prefixes, and plain diffs drop them only when both sides
+/// carry them.
+pub fn parse_patch(text: &str, strip: Option<usize>) -> Result<Vec<FilePatch>, PatchError> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut patches = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let parsed = if line.starts_with("diff --git ") {
+            Some(parse_git_file(&lines, i, strip)?)
+        } else if line.starts_with("--- ")
+            && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ "))
+        {
+            Some(parse_unified_file(&lines, i, strip)?)
+        } else {
+            None
+        };
+        match parsed {
+            Some((patch, next)) => {
+                patches.push(patch);
+                i = next;
+            }
+            None => i += 1,
+        }
+    }
+    if patches.is_empty() {
+        return Err(PatchError::new(lines.len(), "no file changes found"));
+    }
+    Ok(patches)
+}
+
+fn parse_git_file(
+    lines: &[&str],
+    start: usize,
+    strip: Option<usize>,
+) -> Result<(FilePatch, usize), PatchError> {
+    let p = strip.unwrap_or(1);
+    let header = trim_eol(&lines[start]["diff --git ".len()..]);
+    let mut patch = FilePatch::default();
+    if let Some((a, b)) = split_git_header(header) {
+        patch.old_path = Some(strip_path(&a, p, start + 1)?);
+        patch.new_path = Some(strip_path(&b, p, start + 1)?);
+    }
+    let (mut created, mut deleted) = (false, false);
+    let mut i = start + 1;
+    while i < lines.len() {
+        let line = trim_eol(lines[i]);
+        let lineno = i + 1;
+        if line.starts_with("diff --git ") {
+            break;
+        } else if let Some(m) = line.strip_prefix("old mode ") {
+            patch.old_mode = Some(parse_mode(m, lineno)?);
+        } else if let Some(m) = line.strip_prefix("new mode ") {
+            patch.new_mode = Some(parse_mode(m, lineno)?);
+        } else if let Some(m) = line.strip_prefix("deleted file mode ") {
+            patch.old_mode = Some(parse_mode(m, lineno)?);
+            deleted = true;
+        } else if let Some(m) = line.strip_prefix("new file mode ") {
+            patch.new_mode = Some(parse_mode(m, lineno)?);
+            created = true;
+        } else if let Some(path) = line
+            .strip_prefix("rename from ")
+            .or_else(|| line.strip_prefix("copy from "))
+        {
+            // Rename and copy lines carry no `a/` / `b/` prefix.
+            let path = path_field(path).unwrap_or_default();
+            patch.old_path = Some(strip_path(&path, p.saturating_sub(1), lineno)?);
+            patch.copy = line.starts_with("copy");
+        } else if let Some(path) = line
+            .strip_prefix("rename to ")
+            .or_else(|| line.strip_prefix("copy to "))
+        {
+            let path = path_field(path).unwrap_or_default();
+            patch.new_path = Some(strip_path(&path, p.saturating_sub(1), lineno)?);
+        } else if line.starts_with("index ")
+            || line.starts_with("similarity index ")
+            || line.starts_with("dissimilarity index ")
+        {
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            patch.binary = true;
+        } else if line.starts_with("--- ")
+            && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ "))
+        {
+            if let Some(path) = path_field(&line[4..]) {
+                patch.old_path = Some(strip_path(&path, p, lineno)?);
+            }
+            if let Some(path) = path_field(&lines[i + 1][4..]) {
+                patch.new_path = Some(strip_path(&path, p, lineno + 1)?);
+            }
+            i += 2;
+            while lines.get(i).is_some_and(|l| l.starts_with("@@ ")) {
+                let (hunk, next) = parse_hunk(lines, i)?;
+                patch.hunks.push(hunk);
+                i = next;
+            }
+            break;
+        } else if !patch.binary {
+            // End of this file's section (mail signature, next commit, ...).
+            break;
+        }
+        i += 1;
+    }
+    if patch.old_path.is_none() && patch.new_path.is_none() {
+        return Err(PatchError::new(
+            start + 1,
+            "cannot tell which file is patched",
+        ));
+    }
+    if created {
+        patch.old_path = None;
+    }
+    if deleted {
+        patch.new_path = None;
+    }
+    Ok((patch, i))
+}
+
+fn parse_unified_file(
+    lines: &[&str],
+    start: usize,
+    strip: Option<usize>,
+) -> Result<(FilePatch, usize), PatchError> {
+    let old = path_field(&lines[start][4..]);
+    let new = path_field(&lines[start + 1][4..]);
+    if old.is_none() && new.is_none() {
+        return Err(PatchError::new(start + 1, "both sides are /dev/null"));
+    }
+    let p = strip.unwrap_or_else(|| {
+        let a = old.as_deref().is_none_or(|p| p.starts_with("a/"));
+        let b = new.as_deref().is_none_or(|p| p.starts_with("b/"));
+        usize::from(a && b)
+    });
+    let mut patch = FilePatch {
+        old_path: old
+            .map(|path| strip_path(&path, p, start + 1))
+            .transpose()?,
+        new_path: new
+            .map(|path| strip_path(&path, p, start + 2))
+            .transpose()?,
+        ..FilePatch::default()
+    };
+
+    let mut i = start + 2;
+    while lines.get(i).is_some_and(|l| l.starts_with("@@ ")) {
+        let (hunk, next) = parse_hunk(lines, i)?;
+        patch.hunks.push(hunk);
+        i = next;
+    }
+    if patch.hunks.is_empty() {
+        return Err(PatchError::new(start + 3, "expected a hunk"));
+    }
+    // `diff -N` marks a missing side with an empty range instead of /dev/null.
+    if let [hunk] = patch.hunks.as_slice() {
+        if hunk.old_start == 0 && hunk.old_lines == 0 {
+            patch.old_path = None;
+        }
+        if hunk.new_start == 0 && hunk.new_lines == 0 {
+            patch.new_path = None;
+        }
+    }
+    Ok((patch, i))
+}
+
+fn parse_hunk(lines: &[&str], start: usize) -> Result<(Hunk, usize), PatchError> {
+    let header = trim_eol(lines[start]);
+    let (old_start, old_lines, new_start, new_lines) = parse_hunk_header(header)
+        .ok_or_else(|| PatchError::new(start + 1, format!("bad hunk header {header:?}")))?;
+    let mut hunk = Hunk {
+        header: header
+            .split_once(" @@")
+            .map_or(header, |(h, _)| h)
+            .to_string()
+            + " @@",
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        lines: Vec::new(),
+    };
+    let (mut old_left, mut new_left) = (old_lines, new_lines);
+    let mut i = start + 1;
+    while old_left > 0 || new_left > 0 {
+        let lineno = i + 1;
+        let Some(raw) = lines.get(i) else {
+            return Err(PatchError::new(lineno, "patch ends inside a hunk"));
+        };
+        let mut text = raw.get(1..).unwrap_or_default().to_string();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        let too_long = || PatchError::new(lineno, "hunk is longer than its header says");
+        match raw.as_bytes()[0] {
+            b' ' => {
+                old_left = old_left.checked_sub(1).ok_or_else(too_long)?;
+                new_left = new_left.checked_sub(1).ok_or_else(too_long)?;
+                hunk.lines.push(HunkLine::Context(text));
+            }
+            // Some tools drop the space of empty context lines.
+            b'\n' | b'\r' => {
+                old_left = old_left.checked_sub(1).ok_or_else(too_long)?;
+                new_left = new_left.checked_sub(1).ok_or_else(too_long)?;
+                hunk.lines.push(HunkLine::Context(raw.to_string()));
+            }
+            b'-' => {
+                old_left = old_left.checked_sub(1).ok_or_else(too_long)?;
+                hunk.lines.push(HunkLine::Remove(text));
+            }
+            b'+' => {
+                new_left = new_left.checked_sub(1).ok_or_else(too_long)?;
+                hunk.lines.push(HunkLine::Add(text));
+            }
+            b'\\' => no_newline(&mut hunk, lineno)?,
+            _ => {
+                return Err(PatchError::new(
+                    lineno,
+                    format!("unexpected line in hunk: {:?}", trim_eol(raw)),
+                ))
+            }
+        }
+        i += 1;
+    }
+    if lines.get(i).is_some_and(|l| l.starts_with('\\')) {
+        no_newline(&mut hunk, i + 1)?;
+        i += 1;
+    }
+    Ok((hunk, i))
+}
+
+/// Handle `\ No newline at end of file` after the last line of `hunk`.
+fn no_newline(hunk: &mut Hunk, lineno: usize) -> Result<(), PatchError> {
+    let last = hunk
+        .lines
+        .last_mut()
+        .ok_or_else(|| PatchError::new(lineno, "no-newline marker without a line"))?;
+    let text = last.text_mut();
+    if text.ends_with('\n') {
+        text.pop();
+    }
+    Ok(())
+}
+
+/// `(old_start, old_lines, new_start, new_lines)` of a `@@ -a,b +c,d @@` line.
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let (ranges, _) = line.strip_prefix("@@ -")?.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let range = |r: &str| -> Option<(usize, usize)> {
+        match r.split_once(',') {
+            Some((s, c)) => Some((s.parse().ok()?, c.parse().ok()?)),
+            None => Some((r.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_lines) = range(old)?;
+    let (new_start, new_lines) = range(new)?;
+    Some((old_start, old_lines, new_start, new_lines))
+}
+
+fn parse_mode(raw: &str, line: usize) -> Result<u32, PatchError> {
+    u32::from_str_radix(raw.trim(), 8)
+        .map_err(|_| PatchError::new(line, format!("bad file mode {raw:?}")))
+}
+
+fn trim_eol(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// Path of a `---` / `+++` / rename line, or `None` for `/dev/null`.
+fn path_field(raw: &str) -> Option<String> {
+    let raw = trim_eol(raw);
+    let path = if raw.starts_with('"') {
+        unquote(raw)?.0
+    } else {
+        // Plain diffs append a tab and a timestamp.
+        raw.split('\t').next().unwrap_or_default().to_string()
+    };
+    (path != NULL_PATH).then_some(path)
+}
+
+/// Split the `a/... b/...` part of a `diff --git` line.
+fn split_git_header(rest: &str) -> Option<(String, String)> {
+    if rest.starts_with('"') {
+        let (a, tail) = unquote(rest)?;
+        let tail = tail.strip_prefix(' ')?;
+        let b = match tail.starts_with('"') {
+            true => unquote(tail)?.0,
+            false => tail.to_string(),
+        };
+        return Some((a, b));
+    }
+    if let Some(pos) = rest.find(" \"") {
+        return Some((rest[..pos].to_string(), unquote(&rest[pos + 1..])?.0));
+    }
+    // Without a rename both sides name the same file, which may contain spaces.
+    let half = rest.len() / 2;
+    if rest.len() % 2 == 1 && rest.is_char_boundary(half) && rest.as_bytes()[half] == b' ' {
+        let (a, b) = (&rest[..half], &rest[half + 1..]);
+        if a.split_once('/').map(|(_, p)| p) == b.split_once('/').map(|(_, p)| p) {
+            return Some((a.to_string(), b.to_string()));
+        }
+    }
+    rest.split_once(' ')
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+}
+
+/// Decode a C-style quoted path as written by git; returns it and the text after it.
+fn unquote(s: &str) -> Option<(String, &str)> {
+    let bytes = s.as_bytes();
+    if bytes.first() != Some(&b'"') {
+        return None;
+    }
+    let mut out = Vec::new();
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => return Some((String::from_utf8(out).ok()?, &s[i + 1..])),
+            b'\\' => {
+                i += 1;
+                let c = *bytes.get(i)?;
+                if (b'0'..=b'7').contains(&c) {
+                    let digits = s.get(i..i + 3)?;
+                    out.push(u8::from_str_radix(digits, 8).ok()?);
+                    i += 3;
+                    continue;
+                }
+                out.push(match c {
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b'f' => 0x0c,
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'v' => 0x0b,
+                    other => other,
+                });
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    None
+}
+
+fn strip_path(path: &str, n: usize, line: usize) -> Result<String, PatchError> {
+    let mut rest = path;
+    for _ in 0..n {
+        rest = rest.split_once('/').map(|(_, r)| r).ok_or_else(|| {
+            PatchError::new(
+                line,
+                format!("cannot strip {n} leading components from {path:?}"),
+            )
+        })?;
+    }
+    Ok(rest.trim_start_matches('/').to_string())
+}
+
+/// Read the files and symlinks of a tarball (plain or gzip-compressed).
+///
+/// Directories are skipped since they are created along with their contents. Hardlinks,
+/// devices and FIFOs are rejected, and so are entries below a symlink of the archive and
+/// symlinks pointing outside it (see [`link_problems`]).
+pub fn read_archive(data: &[u8]) -> io::Result<Vec<(String, LocalFile)>> {
+    let data: Cow<[u8]> = if data.starts_with(&[0x1f, 0x8b]) {
+        let mut out = Vec::new();
+        flate2::read::GzDecoder::new(data).read_to_end(&mut out)?;
+        Cow::Owned(out)
+    } else {
+        Cow::Borrowed(data)
+    };
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let mut entries = Vec::new();
+    let (mut long_name, mut long_link) = (None, None);
+    let mut pos = 0;
+    while pos + TAR_BLOCK <= data.len() {
+        let header = &data[pos..pos + TAR_BLOCK];
+        if header.iter().all(|b| *b == 0) {
+            break;
+        }
+        let checksum: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, b)| if (148..156).contains(&i) { b' ' } else { *b } as u64)
+            .sum();
+        if tar_number(&header[148..156])? != checksum {
+            return Err(invalid(format!("bad tar header checksum at offset {pos}")));
+        }
+        let size = usize::try_from(tar_number(&header[124..136])?)
+            .map_err(|_| invalid(format!("tar entry at offset {pos} is too large")))?;
+        let body_start = pos + TAR_BLOCK;
+        let body = body_start
+            .checked_add(size)
+            .and_then(|end| data.get(body_start..end))
+            .ok_or_else(|| invalid(format!("tar entry at offset {pos} is truncated")))?;
+        pos = body_start + size.div_ceil(TAR_BLOCK) * TAR_BLOCK;
+
+        let typeflag = header[156];
+        match typeflag {
+            b'x' => {
+                for (key, value) in pax_records(body)? {
+                    match key.as_str() {
+                        "path" => long_name = Some(value),
+                        "linkpath" => long_link = Some(value),
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            b'g' => continue,
+            b'L' => {
+                long_name = Some(tar_string(body)?);
+                continue;
+            }
+            b'K' => {
+                long_link = Some(tar_string(body)?);
+                continue;
+            }
+            _ => {}
+        }
+
+        let name = match long_name.take() {
+            Some(name) => name,
+            None => {
+                let name = tar_string(&header[..100])?;
+                let prefix = match &header[257..262] == b"ustar" {
+                    true => tar_string(&header[345..500])?,
+                    false => String::new(),
+                };
+                match prefix.is_empty() {
+                    true => name,
+                    false => format!("{prefix}/{name}"),
+                }
+            }
+        };
+        let link = match long_link.take() {
+            Some(link) => link,
+            None => tar_string(&header[157..257])?,
+        };
+        let is_dir = typeflag == b'5' || (matches!(typeflag, 0 | b'0') && name.ends_with('/'));
+        let path = name.trim_start_matches("./").trim_matches('/');
+        if is_dir || path.is_empty() || path == "." {
+            continue;
+        }
+        if path.split('/').any(|c| c == "..") {
+            return Err(invalid(format!("tar entry {name:?} escapes the archive")));
+        }
+        let file = match typeflag {
+            0 | b'0' | b'7' => {
+                let mode = tar_number(&header[100..108])?;
+                LocalFile {
+                    data: body.to_vec(),
+                    mode: if mode & 0o111 != 0 {
+                        MODE_EXECUTABLE
+                    } else {
+                        MODE_REGULAR
+                    },
+                }
+            }
+            b'2' => LocalFile {
+                data: link.into_bytes(),
+                mode: MODE_SYMLINK,
+            },
+            other => {
+                return Err(invalid(format!(
+                    "unsupported tar entry type {:?} for {name:?}",
+                    other as char
+                )))
+            }
+        };
+        entries.push((path.to_string(), file));
+    }
+    let problems = link_problems(entries.iter().map(|(p, f)| (p.as_str(), f)), "the archive");
+    match problems.is_empty() {
+        true => Ok(entries),
+        false => Err(invalid(problems.join("; "))),
+    }
+}
+
+/// Symlinks a tree of files may not hold: entries whose parent is one of its symlinks,
+/// and symlinks that are absolute or climb out of `root`.
+///
+/// Targets are checked as paths only, so a link to another link inside the tree passes.
+fn link_problems<'a>(
+    files: impl IntoIterator<Item = (&'a str, &'a LocalFile)>,
+    root: &str,
+) -> Vec<String> {
+    let files: Vec<_> = files.into_iter().collect();
+    let is_link = |file: &LocalFile| file.mode & 0o170000 == MODE_SYMLINK;
+    let links: BTreeSet<&str> = files
+        .iter()
+        .filter(|(_, f)| is_link(f))
+        .map(|(p, _)| *p)
+        .collect();
+    let mut problems = Vec::new();
+    for (path, file) in &files {
+        let mut parents = path.match_indices('/').map(|(i, _)| &path[..i]);
+        if let Some(link) = parents.find(|p| links.contains(p)) {
+            problems.push(format!("{path} is below the symlink {link}"));
+        }
+        if is_link(file) && !link_stays_inside(path, &String::from_utf8_lossy(&file.data)) {
+            problems.push(format!(
+                "symlink {path} points outside {root}: {:?}",
+                String::from_utf8_lossy(&file.data)
+            ));
+        }
+    }
+    problems
+}
+
+/// Whether the relative symlink `target`, stored at `path`, resolves inside the tree.
+fn link_stays_inside(path: &str, target: &str) -> bool {
+    if target.is_empty() || target.starts_with('/') {
+        return false;
+    }
+    let mut depth = path.matches('/').count();
+    for component in target.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => match depth.checked_sub(1) {
+                Some(up) => depth = up,
+                None => return false,
+            },
+            _ => depth += 1,
+        }
+    }
+    true
+}
+
+/// Numeric tar header field: octal text, or base-256 when the high bit is set.
+fn tar_number(field: &[u8]) -> io::Result<u64> {
+    if field.first().is_some_and(|b| b & 0x80 != 0) {
+        return Ok(field[1..]
+            .iter()
+            .fold(u64::from(field[0] & 0x7f), |n, b| (n << 8) | u64::from(*b)));
+    }
+    let text = std::str::from_utf8(field).unwrap_or_default();
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("bad tar number {text:?}"),
+        )
+    })
+}
+
+fn tar_string(field: &[u8]) -> io::Result<String> {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    String::from_utf8(field[..end].to_vec())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "tar path is not UTF-8"))
+}
+
+/// `key=value` records of a pax extended header.
+fn pax_records(mut body: &[u8]) -> io::Result<Vec<(String, String)>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "bad pax header");
+    let mut records = Vec::new();
+    while !body.is_empty() && body[0] != 0 {
+        let space = body.iter().position(|b| *b == b' ').ok_or_else(invalid)?;
+        let len: usize = std::str::from_utf8(&body[..space])
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .filter(|len| *len > space + 1 && *len <= body.len())
+            .ok_or_else(invalid)?;
+        let record = std::str::from_utf8(&body[space + 1..len - 1]).map_err(|_| invalid())?;
+        let (key, value) = record.split_once('=').ok_or_else(invalid)?;
+        records.push((key.to_string(), value.to_string()));
+        body = &body[len..];
+    }
+    Ok(records)
+}
+
+/// Git blob id of `data`, the key local CL contents are cached under.
+pub fn git_blob_oid(data: &[u8]) -> String {
+    let mut ctx = ring::digest::Context::new(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY);
+    ctx.update(format!("blob {}\0", data.len()).as_bytes());
+    ctx.update(data);
+    hex::encode(ctx.finish())
+}
+
+/// Read access to the tree a local CL is applied against.
+#[async_trait]
+pub trait BaseTree: Sync {
+    /// The file at `path` (relative to the mount root), or `None` if there is none.
+    async fn read(&self, path: &str) -> io::Result<Option<LocalFile>>;
+}
+
+/// Bases of a composite mount, each serving the paths under its mount-relative target.
+#[async_trait]
+impl<B: BaseTree + Send> BaseTree for Vec<(PathBuf, Arc<B>)> {
+    async fn read(&self, path: &str) -> io::Result<Option<LocalFile>> {
+        for (target, base) in self {
+            if let Ok(rel) = Path::new(path).strip_prefix(target) {
+                return base.read(&rel.to_string_lossy()).await;
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl BaseTree for Dicfuse {
+    async fn read(&self, path: &str) -> io::Result<Option<LocalFile>> {
+        let user_path = format!("/{path}");
+        // Load the parents of `path` one directory listing at a time.
+        let inode = loop {
+            match self.store.lookup_path_status(&user_path).await? {
+                PathLookupStatus::Found(inode) => break inode,
+                PathLookupStatus::NotFound => return Ok(None),
+                PathLookupStatus::ParentNotLoaded { parent_path } => {
+                    let parent = self.store.get_inode_from_path(&parent_path).await?;
+                    self.store.ensure_dir_loaded(parent).await?;
+                    if matches!(
+                        self.store.lookup_path_status(&user_path).await?,
+                        PathLookupStatus::ParentNotLoaded { parent_path: p } if p == parent_path
+                    ) {
+                        return Ok(None);
+                    }
+                }
+            }
+        };
+        let item = self.store.get_inode(inode).await?;
+        if item.is_dir() {
+            return Err(io::Error::other(format!("{path} is a directory")));
+        }
+        // Copy out of the content cache first; its guard must not live across an await.
+        let cached = self.store.get_file_content(inode).map(|c| c.to_vec());
+        let data = match cached {
+            Some(content) => content,
+            None if item.hash.is_empty() || item.hash == EMPTY_BLOB_OID => Vec::new(),
+            None => match self.store.get_persisted_file_content(inode) {
+                Ok(content) => content,
+                Err(_) => {
+                    self.store.fetch_file_content(inode, &item.hash).await?;
+                    self.store.get_persisted_file_content(inode)?
+                }
+            },
+        };
+        let mode = match self.store.is_executable(inode) {
+            true => MODE_EXECUTABLE,
+            false => MODE_REGULAR,
+        };
+        Ok(Some(LocalFile { data, mode }))
+    }
+}
+
+/// The parts of a local CL request, with monorepo paths.
+#[derive(Debug, Clone, Default)]
+pub struct LocalCl {
+    pub archive: Vec<(String, LocalFile)>,
+    pub patches: Vec<FilePatch>,
+    pub deleted: Vec<String>,
+}
+
+/// Final state of every path a local CL touches, relative to the mount root.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalLayer {
+    /// `None` for deleted paths.
+    pub files: BTreeMap<String, Option<LocalFile>>,
+    /// Paths the patches create.
+    created: BTreeSet<String>,
+}
+
+impl LocalLayer {
+    async fn current(&self, path: &str, base: &impl BaseTree) -> io::Result<Option<LocalFile>> {
+        match self.files.get(path) {
+            Some(file) => Ok(file.clone()),
+            None => base.read(path).await,
+        }
+    }
+
+    /// Put the file contents into `cache` and describe the layer as the manifest of `cl`.
+    pub fn into_manifest(self, cl: &str, cache: &BlobCache) -> io::Result<ClManifest> {
+        let mut manifest = ClManifest {
+            cl: cl.to_string(),
+            ..ClManifest::default()
+        };
+        for (path, file) in self.files {
+            let entry = match file {
+                Some(file) => {
+                    let oid = git_blob_oid(&file.data);
+                    cache.insert(&oid, &file.data)?;
+                    ClManifestEntry {
+                        sha: oid,
+                        action: match self.created.contains(&path) {
+                            true => "new",
+                            false => "modified",
+                        }
+                        .to_string(),
+                        mode: cl_manifest::normalize_mode(file.mode),
+                    }
+                }
+                None => ClManifestEntry {
+                    action: "deleted".to_string(),
+                    ..ClManifestEntry::default()
+                },
+            };
+            manifest.files.insert(path, entry);
+        }
+        manifest.resolve_deleted_dirs();
+        Ok(manifest)
+    }
+}
+
+impl LocalCl {
+    /// Apply the archive, the patches and the deletions, in that order, on top of `base`.
+    ///
+    /// `relocate` maps a monorepo path to one relative to the mount root; paths outside
+    /// the mount are skipped, like those of a CL files-list.
+    pub async fn resolve(
+        &self,
+        base: &impl BaseTree,
+        relocate: impl Fn(&str) -> Option<String>,
+    ) -> Result<LocalLayer, ApplyReport> {
+        let mut layer = LocalLayer::default();
+        let mut problems = Vec::new();
+        for (path, file) in &self.archive {
+            if let Some(rel) = relocate(path) {
+                layer.files.insert(rel, Some(file.clone()));
+            }
+        }
+        for patch in &self.patches {
+            if let Err(problem) = Self::apply_patch(&mut layer, patch, base, &relocate).await {
+                problems.extend(
+                    problem
+                        .into_iter()
+                        .map(|p| format!("{}: {}", patch.display_path(), p)),
+                );
+            }
+        }
+        for path in &self.deleted {
+            if let Some(rel) = relocate(path) {
+                let prefix = format!("{rel}/");
+                layer.files.retain(|p, _| !p.starts_with(&prefix));
+                layer.files.insert(rel, None);
+            }
+        }
+        problems.extend(link_problems(
+            layer
+                .files
+                .iter()
+                .filter_map(|(p, f)| f.as_ref().map(|f| (p.as_str(), f))),
+            "the mount",
+        ));
+        match problems.is_empty() {
+            true => Ok(layer),
+            false => Err(ApplyReport { problems }),
+        }
+    }
+
+    async fn apply_patch(
+        layer: &mut LocalLayer,
+        patch: &FilePatch,
+        base: &impl BaseTree,
+        relocate: &impl Fn(&str) -> Option<String>,
+    ) -> Result<(), Vec<String>> {
+        let old = patch.old_path.as_deref().map(relocate);
+        let new = patch.new_path.as_deref().map(relocate);
+        let (old, new) = match (old, new) {
+            (None | Some(None), None | Some(None)) => return Ok(()),
+            (Some(None), Some(Some(_))) => {
+                return Err(vec!["renamed or copied from outside the mount".into()])
+            }
+            (old, new) => (old.flatten(), new.flatten()),
+        };
+        if let Some(mode) = patch.new_mode {
+            if !matches!(mode & 0o170000, 0o100000 | 0o120000) {
+                return Err(vec![format!("unsupported file mode {mode:o}")]);
+            }
+        }
+        let read_err = |e: io::Error| vec![format!("cannot read the base: {e}")];
+
+        let current = match &old {
+            Some(old) => match layer.current(old, base).await.map_err(read_err)? {
+                Some(file) => Some(file),
+                None if patch.old_path == patch.new_path => {
+                    return Err(vec!["not found in the base".into()])
+                }
+                None => return Err(vec![format!("source {old} not found in the base")]),
+            },
+            None => None,
+        };
+        if let (None, Some(new)) = (&old, &new) {
+            if layer.current(new, base).await.map_err(read_err)?.is_some() {
+                return Err(vec!["already exists in the base".into()]);
+            }
+        }
+        if patch.binary {
+            return Err(vec![
+                "binary patches are not supported; send the file in an archive".into(),
+            ]);
+        }
+        let empty = Vec::new();
+        let data = patch.apply(current.as_ref().map_or(&empty, |f| &f.data))?;
+
+        if patch.new_path.is_none() && !data.is_empty() {
+            return Err(vec!["deletion does not remove the whole file".into()]);
+        }
+        if let Some(old) = &old {
+            if !patch.copy && old.as_str() != new.as_deref().unwrap_or_default() {
+                layer.files.insert(old.clone(), None);
+                layer.created.remove(old);
+            }
+        }
+        if let Some(new) = new {
+            let mode = patch
+                .new_mode
+                .or(current.map(|f| f.mode))
+                .unwrap_or(MODE_REGULAR);
+            if old.is_none() {
+                layer.created.insert(new.clone());
+            }
+            layer.files.insert(new, Some(LocalFile { data, mode }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    struct MapBase(HashMap<&'static str, &'static str>);
+
+    #[async_trait]
+    impl BaseTree for MapBase {
+        async fn read(&self, path: &str) -> io::Result<Option<LocalFile>> {
+            Ok(self.0.get(path).map(|data| LocalFile {
+                data: data.as_bytes().to_vec(),
+                mode: if path.ends_with(".sh") {
+                    MODE_EXECUTABLE
+                } else {
+                    MODE_REGULAR
+                },
+            }))
+        }
+    }
+
+    fn relocate(path: &str) -> Option<String> {
+        path.strip_prefix("repo/").map(str::to_string)
+    }
+
+    fn tar_entry(out: &mut Vec<u8>, name: &str, typeflag: u8, mode: u32, link: &str, body: &[u8]) {
+        let mut header = [0u8; TAR_BLOCK];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(format!("{mode:07o}").as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", body.len()).as_bytes());
+        header[156] = typeflag;
+        header[157..157 + link.len()].copy_from_slice(link.as_bytes());
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|b| *b as u32).sum();
+        header[148..155].copy_from_slice(format!("{sum:06o}\0").as_bytes());
+        out.extend_from_slice(&header);
+        out.extend_from_slice(body);
+        out.resize(out.len().div_ceil(TAR_BLOCK) * TAR_BLOCK, 0);
+    }
+
+    #[test]
+    fn test_parse_git_patch() {
+        let patch = r#"From 1234 Mon Sep 17 00:00:00 2001
+Subject: [PATCH] tweak
+
+---
+ a.txt | 2 +-
+
+diff --git a/repo/a.txt b/repo/a.txt
+index 1111111..2222222 100644
+--- a/repo/a.txt
++++ b/repo/a.txt
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+diff --git a/repo/run.sh b/repo/run.sh
+old mode 100644
+new mode 100755
+diff --git a/repo/old name.txt b/repo/new.txt
+similarity index 100%
+rename from repo/old name.txt
+rename to repo/new.txt
+diff --git a/repo/gone.txt b/repo/gone.txt
+deleted file mode 100644
+--- a/repo/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+diff --git a/repo/img.png b/repo/img.png
+new file mode 100644
+index 0000000..3333333
+Binary files /dev/null and b/repo/img.png differ
+-- 
+2.43.0
+"#;
+        let files = parse_patch(patch, None).unwrap();
+        assert_eq!(files.len(), 5);
+        assert_eq!(files[0].old_path.as_deref(), Some("repo/a.txt"));
+        assert_eq!(files[0].hunks.len(), 1);
+        assert_eq!(files[1].new_mode, Some(0o100755));
+        assert!(files[1].hunks.is_empty());
+        assert_eq!(files[2].old_path.as_deref(), Some("repo/old name.txt"));
+        assert_eq!(files[2].new_path.as_deref(), Some("repo/new.txt"));
+        assert_eq!(files[3].new_path, None);
+        assert_eq!(files[4].old_path, None);
+        assert!(files[4].binary);
+
+        let plain = "--- repo/x.c\t2024-01-01 00:00:00\n+++ repo/x.c\t2024-01-02 00:00:00\n@@ -1 +1 @@\n-a\n+b\n";
+        let files = parse_patch(plain, None).unwrap();
+        assert_eq!(files[0].old_path.as_deref(), Some("repo/x.c"));
+        let files = parse_patch(plain, Some(1)).unwrap();
+        assert_eq!(files[0].new_path.as_deref(), Some("x.c"));
+
+        assert_eq!(parse_patch("just text\n", None).unwrap_err().line, 1);
+        let err = parse_patch("--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n a\n", None).unwrap_err();
+        assert_eq!(err.message, "patch ends inside a hunk");
+    }
+
+    #[test]
+    fn test_apply_with_offset_and_missing_newline() {
+        let patch = "--- a/f\n+++ b/f\n@@ -2,2 +2,2 @@\n b\n-c\n+C\n\\ No newline at end of file\n";
+        let files = parse_patch(patch, None).unwrap();
+        // The hunk matches two lines further down than it says.
+        assert_eq!(
+            files[0].apply(b"x\ny\na\nb\nc\n").unwrap(),
+            b"x\ny\na\nb\nC"
+        );
+        let failures = files[0].apply(b"a\nb\nd\n").unwrap_err();
+        assert_eq!(
+            failures,
+            vec!["hunk #1 (@@ -2,2 +2,2 @@) does not match the base".to_string()]
+        );
+
+        let insert = "--- a/f\n+++ b/f\n@@ -0,0 +1 @@\n+new\n";
+        assert!(parse_patch(insert, None).unwrap()[0].old_path.is_none());
+        let append = "--- a/f\n+++ b/f\n@@ -1,0 +2 @@\n+two\n";
+        assert_eq!(
+            parse_patch(append, None).unwrap()[0]
+                .apply(b"one\n")
+                .unwrap(),
+            b"one\ntwo\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_reports_every_problem() {
+        let base = MapBase(HashMap::from([
+            ("a.txt", "one\ntwo\nthree\n"),
+            ("run.sh", "echo hi\n"),
+            ("old.txt", "moved\n"),
+        ]));
+        let patch = r#"diff --git a/repo/a.txt b/repo/a.txt
+--- a/repo/a.txt
++++ b/repo/a.txt
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+diff --git a/repo/run.sh b/repo/run.sh
+--- a/repo/run.sh
++++ b/repo/run.sh
+@@ -1 +1 @@
+-echo hi
++echo bye
+diff --git a/repo/old.txt b/repo/new.txt
+rename from repo/old.txt
+rename to repo/new.txt
+diff --git a/repo/fresh.txt b/repo/fresh.txt
+new file mode 100644
+--- /dev/null
++++ b/repo/fresh.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/other/x b/other/x
+--- a/other/x
++++ b/other/x
+@@ -1 +1 @@
+-x
++y
+"#;
+        let local = LocalCl {
+            archive: vec![(
+                "repo/dir/tool".to_string(),
+                LocalFile {
+                    data: b"bin".to_vec(),
+                    mode: MODE_EXECUTABLE,
+                },
+            )],
+            patches: parse_patch(patch, None).unwrap(),
+            deleted: vec!["repo/dir".to_string(), "repo/b.txt".to_string()],
+        };
+        let layer = local.resolve(&base, relocate).await.unwrap();
+        let file = |p: &str| layer.files[p].clone();
+        assert_eq!(file("a.txt").unwrap().data, b"one\nTWO\nthree\n");
+        assert_eq!(file("run.sh").unwrap().mode, MODE_EXECUTABLE);
+        assert_eq!(file("old.txt"), None);
+        assert_eq!(file("new.txt").unwrap().data, b"moved\n");
+        assert_eq!(file("fresh.txt").unwrap().data, b"hello\n");
+        assert_eq!(file("dir"), None);
+        assert!(!layer.files.contains_key("dir/tool"));
+        assert!(!layer.files.contains_key("x"));
+
+        let broken = r#"--- a/repo/a.txt
++++ b/repo/a.txt
+@@ -1 +1 @@
+-zero
++0
+--- a/repo/missing.txt
++++ b/repo/missing.txt
+@@ -1 +1 @@
+-a
++b
+--- /dev/null
++++ b/repo/run.sh
+@@ -0,0 +1 @@
++dup
+"#;
+        let local = LocalCl {
+            patches: parse_patch(broken, None).unwrap(),
+            ..LocalCl::default()
+        };
+        let report = local.resolve(&base, relocate).await.unwrap_err();
+        assert_eq!(
+            report.problems,
+            vec![
+                "repo/a.txt: hunk #1 (@@ -1 +1 @@) does not match the base".to_string(),
+                "repo/missing.txt: not found in the base".to_string(),
+                "repo/run.sh: already exists in the base".to_string(),
+            ]
+        );
+
+        // Composite mounts look each path up in the base bound at its target.
+        let composite = vec![(PathBuf::from("svc"), Arc::new(base))];
+        let local = LocalCl {
+            patches: parse_patch(&patch.replace("repo/", "repo/svc/"), None).unwrap(),
+            ..LocalCl::default()
+        };
+        let layer = local.resolve(&composite, relocate).await.unwrap();
+        assert_eq!(
+            layer.files["svc/a.txt"].as_ref().unwrap().data,
+            b"one\nTWO\nthree\n"
+        );
+        let local = LocalCl {
+            patches: parse_patch(&broken.replace("repo/a.txt", "repo/lib/a.txt"), None).unwrap(),
+            ..LocalCl::default()
+        };
+        let report = local.resolve(&composite, relocate).await.unwrap_err();
+        assert_eq!(
+            report.problems[0],
+            "repo/lib/a.txt: not found in the base".to_string()
+        );
+    }
+
+    #[test]
+    fn test_read_archive_and_build_manifest() {
+        let mut tar = Vec::new();
+        tar_entry(&mut tar, "./repo/", b'5', 0o755, "", b"");
+        tar_entry(&mut tar, "./repo/hello.txt", b'0', 0o644, "", b"hello\n");
+        tar_entry(&mut tar, "./repo/run.sh", b'0', 0o755, "", b"#!/bin/sh\n");
+        tar_entry(&mut tar, "./repo/link", b'2', 0o777, "hello.txt", b"");
+        tar.extend_from_slice(&[0; 2 * TAR_BLOCK]);
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        io::Write::write_all(&mut gz, &tar).unwrap();
+        let entries = read_archive(&gz.finish().unwrap()).unwrap();
+        assert_eq!(entries, read_archive(&tar).unwrap());
+        let paths: Vec<&str> = entries.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, ["repo/hello.txt", "repo/run.sh", "repo/link"]);
+        assert_eq!(entries[1].1.mode, MODE_EXECUTABLE);
+        assert_eq!(entries[2].1.data, b"hello.txt");
+
+        let mut evil = Vec::new();
+        tar_entry(&mut evil, "../etc/passwd", b'0', 0o644, "", b"x");
+        assert!(read_archive(&evil).is_err());
+        let mut up = Vec::new();
+        tar_entry(&mut up, "./repo/up", b'2', 0o777, "../../outside", b"");
+        assert!(read_archive(&up).is_err());
+
+        assert_eq!(git_blob_oid(b""), EMPTY_BLOB_OID);
+        let tmp = tempdir().unwrap();
+        let cache = BlobCache::new(tmp.path());
+        let layer = LocalLayer {
+            files: entries
+                .into_iter()
+                .map(|(p, f)| (relocate(&p).unwrap(), Some(f)))
+                .collect(),
+            ..LocalLayer::default()
+        };
+        let manifest = layer.into_manifest("local", &cache).unwrap();
+        let hello = &manifest.files["hello.txt"];
+        assert_eq!(hello.sha, "ce013625030ba8dba906f756967f9e9ca394464a");
+        assert_eq!(hello.mode, None);
+        assert_eq!(manifest.files["link"].mode, Some(MODE_SYMLINK));
+        assert_eq!(cache.read(&hello.sha).unwrap().unwrap(), b"hello\n");
+    }
+
+    #[tokio::test]
+    async fn test_entries_below_or_through_symlinks_are_rejected() {
+        let mut tar = Vec::new();
+        tar_entry(&mut tar, "evil", b'2', 0o777, "/tmp/x", b"");
+        tar_entry(&mut tar, "evil/f", b'0', 0o644, "", b"pwned\n");
+        tar.extend_from_slice(&[0; 2 * TAR_BLOCK]);
+        let err = read_archive(&tar).unwrap_err().to_string();
+        assert!(err.contains("evil/f is below the symlink evil"), "{err}");
+        assert!(
+            err.contains("symlink evil points outside the archive"),
+            "{err}"
+        );
+
+        let link = |target: &str| LocalFile {
+            data: target.as_bytes().to_vec(),
+            mode: MODE_SYMLINK,
+        };
+        assert!(link_problems([("a/b", &link("../c"))], "the archive").is_empty());
+        assert_eq!(
+            link_problems([("a/b", &link("../../c"))], "the archive").len(),
+            1
+        );
+
+        // A patch creating a file inside a symlink the archive added.
+        let local = LocalCl {
+            archive: vec![("repo/lib".to_string(), link("src"))],
+            patches: parse_patch("--- /dev/null\n+++ b/repo/lib/f\n@@ -0,0 +1 @@\n+x\n", None)
+                .unwrap(),
+            ..LocalCl::default()
+        };
+        let report = local
+            .resolve(&MapBase(HashMap::new()), relocate)
+            .await
+            .unwrap_err();
+        assert_eq!(report.problems, ["lib/f is below the symlink lib"]);
+    }
+}
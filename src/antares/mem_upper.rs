@@ -0,0 +1,1581 @@
+//! In-memory upper layer for Antares overlays.
+//!
+//! [`MemUpperLayer`] keeps every copied-up or newly created node in RAM, so short-lived
+//! jobs get a writable view without any disk I/O, and nothing is left to clean up after
+//! unmount. Overlay bookkeeping works exactly as on a passthrough upper: whiteouts are
+//! 0/0 character devices and opaque directories carry the usual xattrs.
+//!
+//! The layer enforces its own [`QuotaLimits`]. Growing past `max_bytes` fails with
+//! `ENOSPC`, and creating a node past `max_inodes` fails with `EDQUOT`. Usage is tracked
+//! exactly, so unlike [`super::quota::UpperQuota`] no rescans are needed.
+//!
+//! File contents are copy-on-write (`Arc<Vec<u8>>`). [`MemUpperLayer::fork`] and
+//! [`MemUpperLayer::export_to`] therefore only copy metadata while holding the lock; data
+//! is shared until one side writes. [`MemUpperLayer::load_from`] replaces the whole tree
+//! from a directory on disk, which is how checkpoints are restored.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::{OsStr, OsString},
+    fs, io,
+    os::unix::{
+        ffi::{OsStrExt, OsStringExt},
+        fs::{FileTypeExt, MetadataExt, PermissionsExt},
+    },
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use libfuse_fs::{context::OperationContext, unionfs::layer::Layer};
+use rfuse3::{
+    raw::{prelude::*, reply::DirectoryEntry},
+    Inode, Result, Timestamp,
+};
+
+use super::{
+    quota::{QuotaLimits, UpperUsage},
+    snapshot::{self, CloneStats},
+};
+
+const ROOT_INODE: Inode = 1;
+const TTL: Duration = Duration::from_secs(1);
+const BLOCK_SIZE: u32 = 4096;
+/// Size reported by `statfs` when no byte limit is set.
+const UNLIMITED_BLOCKS: u64 = u64::MAX / BLOCK_SIZE as u64;
+
+#[derive(Debug, Clone)]
+struct MemNode {
+    kind: FileType,
+    perm: u16,
+    uid: u32,
+    gid: u32,
+    rdev: u32,
+    nlink: u32,
+    /// Parent directory; only meaningful for directories (`..` in readdir).
+    parent: Inode,
+    /// File contents, or the target of a symlink.
+    data: Arc<Vec<u8>>,
+    /// Directory entries, sorted so readdir offsets stay stable.
+    children: BTreeMap<OsString, Inode>,
+    xattrs: BTreeMap<OsString, Vec<u8>>,
+    /// Open handles; an unlinked file is kept until the last one is released.
+    open_count: u32,
+    atime: Timestamp,
+    mtime: Timestamp,
+    ctime: Timestamp,
+}
+
+impl MemNode {
+    fn new(kind: FileType, perm: u16, uid: u32, gid: u32, parent: Inode) -> Self {
+        let now = Timestamp::from(SystemTime::now());
+        Self {
+            kind,
+            perm: perm & 0o7777,
+            uid,
+            gid,
+            rdev: 0,
+            nlink: if kind == FileType::Directory { 2 } else { 1 },
+            parent,
+            data: Arc::new(Vec::new()),
+            children: BTreeMap::new(),
+            xattrs: BTreeMap::new(),
+            open_count: 0,
+            atime: now,
+            mtime: now,
+            ctime: now,
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        self.kind == FileType::Directory
+    }
+
+    /// Bytes counted against the quota: the contents of regular files.
+    fn charged_bytes(&self) -> u64 {
+        if self.kind == FileType::RegularFile {
+            self.data.len() as u64
+        } else {
+            0
+        }
+    }
+
+    fn attr(&self, ino: Inode) -> FileAttr {
+        let size = match self.kind {
+            FileType::Directory => BLOCK_SIZE as u64,
+            _ => self.data.len() as u64,
+        };
+        FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(512),
+            atime: self.atime,
+            mtime: self.mtime,
+            ctime: self.ctime,
+            kind: self.kind,
+            perm: self.perm,
+            nlink: self.nlink,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: self.rdev,
+            blksize: BLOCK_SIZE,
+        }
+    }
+
+    fn touch(&mut self) {
+        let now = Timestamp::from(SystemTime::now());
+        self.mtime = now;
+        self.ctime = now;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MemState {
+    nodes: HashMap<Inode, MemNode>,
+    next_inode: Inode,
+    /// Sum of [`MemNode::charged_bytes`] over all nodes, including unlinked-but-open ones.
+    bytes: u64,
+}
+
+impl MemState {
+    fn new(next_inode: Inode) -> Self {
+        // SAFETY: getuid/getgid cannot fail.
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            ROOT_INODE,
+            MemNode::new(FileType::Directory, 0o755, uid, gid, ROOT_INODE),
+        );
+        Self {
+            nodes,
+            next_inode: next_inode.max(ROOT_INODE + 1),
+            bytes: 0,
+        }
+    }
+
+    /// Nodes below the root, matching [`snapshot::tree_usage`] for disk uppers.
+    fn inodes(&self) -> u64 {
+        self.nodes.len() as u64 - 1
+    }
+
+    fn node(&self, ino: Inode) -> Result<&MemNode> {
+        self.nodes.get(&ino).ok_or_else(|| libc::ENOENT.into())
+    }
+
+    fn node_mut(&mut self, ino: Inode) -> Result<&mut MemNode> {
+        self.nodes.get_mut(&ino).ok_or_else(|| libc::ENOENT.into())
+    }
+
+    fn dir(&self, ino: Inode) -> Result<&MemNode> {
+        let node = self.node(ino)?;
+        if !node.is_dir() {
+            return Err(libc::ENOTDIR.into());
+        }
+        Ok(node)
+    }
+
+    fn regular_file(&self, ino: Inode) -> Result<&MemNode> {
+        let node = self.node(ino)?;
+        match node.kind {
+            FileType::RegularFile => Ok(node),
+            FileType::Directory => Err(libc::EISDIR.into()),
+            _ => Err(libc::EINVAL.into()),
+        }
+    }
+
+    fn child(&self, parent: Inode, name: &OsStr) -> Result<Option<Inode>> {
+        Ok(self.dir(parent)?.children.get(name).copied())
+    }
+
+    fn check_bytes(&self, limits: &QuotaLimits, growth: u64) -> Result<()> {
+        match limits.max_bytes {
+            Some(max) if self.bytes.saturating_add(growth) > max => Err(libc::ENOSPC.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Insert a new node under `parent`. Fails if the name is taken or the inode limit is hit.
+    fn insert(
+        &mut self,
+        limits: &QuotaLimits,
+        parent: Inode,
+        name: &OsStr,
+        mut node: MemNode,
+    ) -> Result<Inode> {
+        if self.child(parent, name)?.is_some() {
+            return Err(libc::EEXIST.into());
+        }
+        if let Some(max) = limits.max_inodes {
+            if self.inodes() >= max {
+                return Err(libc::EDQUOT.into());
+            }
+        }
+        let bytes = node.charged_bytes();
+        self.check_bytes(limits, bytes)?;
+
+        let ino = self.next_inode;
+        self.next_inode += 1;
+        node.parent = parent;
+        let is_dir = node.is_dir();
+        self.nodes.insert(ino, node);
+        self.bytes += bytes;
+
+        let parent_node = self.node_mut(parent)?;
+        parent_node.children.insert(name.to_os_string(), ino);
+        if is_dir {
+            parent_node.nlink += 1;
+        }
+        parent_node.touch();
+        Ok(ino)
+    }
+
+    /// Remove the directory entry `name` from `parent` and drop one link of its node.
+    fn detach(&mut self, parent: Inode, name: &OsStr) -> Result<Inode> {
+        let parent_node = self.node_mut(parent)?;
+        let ino = parent_node
+            .children
+            .remove(name)
+            .ok_or_else(|| rfuse3::Errno::from(libc::ENOENT))?;
+        parent_node.touch();
+        let is_dir = self.node(ino)?.is_dir();
+        if is_dir {
+            self.node_mut(parent)?.nlink -= 1;
+            // A directory's own `.` link goes away together with its entry.
+            self.node_mut(ino)?.nlink = 0;
+            self.release_if_unused(ino);
+        } else {
+            let node = self.node_mut(ino)?;
+            node.nlink = node.nlink.saturating_sub(1);
+            node.ctime = Timestamp::from(SystemTime::now());
+            self.release_if_unused(ino);
+        }
+        Ok(ino)
+    }
+
+    /// Free a node once it has neither links nor open handles.
+    fn release_if_unused(&mut self, ino: Inode) {
+        let unused = self
+            .nodes
+            .get(&ino)
+            .is_some_and(|n| n.nlink == 0 && n.open_count == 0);
+        if unused && ino != ROOT_INODE {
+            if let Some(node) = self.nodes.remove(&ino) {
+                self.bytes -= node.charged_bytes();
+            }
+        }
+    }
+
+    /// Replace a regular file's contents, charging the size difference against the limit.
+    fn resize(&mut self, limits: &QuotaLimits, ino: Inode, new_len: u64) -> Result<()> {
+        let node = self.regular_file(ino)?;
+        let old_len = node.data.len() as u64;
+        if new_len > old_len {
+            self.check_bytes(limits, new_len - old_len)?;
+        }
+        let node = self.node_mut(ino)?;
+        Arc::make_mut(&mut node.data).resize(new_len as usize, 0);
+        node.touch();
+        self.bytes = self.bytes + new_len - old_len;
+        Ok(())
+    }
+
+    fn write_at(
+        &mut self,
+        limits: &QuotaLimits,
+        ino: Inode,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<()> {
+        let node = self.regular_file(ino)?;
+        let end = offset + data.len() as u64;
+        let old_len = node.data.len() as u64;
+        if end > old_len {
+            self.check_bytes(limits, end - old_len)?;
+        }
+        let node = self.node_mut(ino)?;
+        let buf = Arc::make_mut(&mut node.data);
+        if end as usize > buf.len() {
+            buf.resize(end as usize, 0);
+        }
+        buf[offset as usize..end as usize].copy_from_slice(data);
+        node.touch();
+        self.bytes += end.saturating_sub(old_len);
+        Ok(())
+    }
+
+    /// Whether `ancestor` is `ino` or one of its parent directories.
+    fn is_ancestor(&self, ancestor: Inode, mut ino: Inode) -> bool {
+        loop {
+            if ino == ancestor {
+                return true;
+            }
+            match self.nodes.get(&ino) {
+                Some(node) if ino != ROOT_INODE => ino = node.parent,
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// RAM-backed writable layer for [`libfuse_fs::unionfs::OverlayFs`].
+#[derive(Debug)]
+pub struct MemUpperLayer {
+    limits: QuotaLimits,
+    state: RwLock<MemState>,
+}
+
+impl MemUpperLayer {
+    /// Create an empty layer bounded by `limits`.
+    pub fn new(limits: QuotaLimits) -> Self {
+        Self {
+            limits,
+            state: RwLock::new(MemState::new(ROOT_INODE + 1)),
+        }
+    }
+
+    pub fn limits(&self) -> QuotaLimits {
+        self.limits
+    }
+
+    /// Exact current consumption together with the limits.
+    pub fn usage(&self) -> UpperUsage {
+        let st = self.state();
+        UpperUsage {
+            used_bytes: st.bytes,
+            used_inodes: st.inodes(),
+            max_bytes: self.limits.max_bytes,
+            max_inodes: self.limits.max_inodes,
+        }
+    }
+
+    /// An independent copy of this layer. File contents are shared until either side writes.
+    pub fn fork(&self) -> Self {
+        Self {
+            limits: self.limits,
+            state: RwLock::new(self.state().clone()),
+        }
+    }
+
+    /// Write the layer's contents into `dst` as a regular upper directory.
+    ///
+    /// `dst` is created if missing and must be empty otherwise. Whiteouts, symlinks,
+    /// hard links, modes and xattrs are reproduced; ownership is not. Blocking I/O: call it
+    /// from `spawn_blocking` in async contexts.
+    pub fn export_to(&self, dst: &Path) -> io::Result<CloneStats> {
+        // Only metadata is copied under the lock; file contents are reference-counted.
+        let st = self.state().clone();
+
+        if dst.exists() && fs::read_dir(dst)?.next().is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("export destination {} is not empty", dst.display()),
+            ));
+        }
+        fs::create_dir_all(dst)?;
+
+        let mut stats = CloneStats::default();
+        let mut linked: HashMap<Inode, PathBuf> = HashMap::new();
+        // Directory modes are applied last so read-only directories can still be filled.
+        let mut dir_modes: Vec<(PathBuf, u16)> = Vec::new();
+        let mut stack = vec![(ROOT_INODE, dst.to_path_buf())];
+        while let Some((dir_ino, dir_path)) = stack.pop() {
+            let dir = st.node(dir_ino).map_err(io::Error::from)?;
+            for (name, &ino) in &dir.children {
+                let node = st.node(ino).map_err(io::Error::from)?;
+                let path = dir_path.join(name);
+                if node.nlink > 1 {
+                    if let Some(existing) = linked.get(&ino) {
+                        fs::hard_link(existing, &path)?;
+                        continue;
+                    }
+                    linked.insert(ino, path.clone());
+                }
+                match node.kind {
+                    FileType::Directory => {
+                        fs::create_dir(&path)?;
+                        dir_modes.push((path.clone(), node.perm));
+                        stack.push((ino, path.clone()));
+                        stats.dirs += 1;
+                    }
+                    FileType::Symlink => {
+                        std::os::unix::fs::symlink(OsStr::from_bytes(&node.data), &path)?;
+                        stats.symlinks += 1;
+                    }
+                    FileType::RegularFile => {
+                        fs::write(&path, node.data.as_slice())?;
+                        stats.files += 1;
+                        stats.bytes += node.data.len() as u64;
+                    }
+                    FileType::CharDevice | FileType::BlockDevice | FileType::NamedPipe => {
+                        let mode = node.kind.const_into_mode_t() | node.perm as u32;
+                        snapshot::mknod(&path, mode, node.rdev as u64)?;
+                        stats.special += 1;
+                    }
+                    FileType::Socket => {
+                        tracing::debug!(path = ?path, "mem upper export: skipping socket");
+                        continue;
+                    }
+                }
+                if !matches!(node.kind, FileType::Directory | FileType::Symlink) {
+                    fs::set_permissions(&path, fs::Permissions::from_mode(node.perm as u32))?;
+                }
+                snapshot::write_xattrs(&path, &xattr_list(node));
+            }
+        }
+        for (path, perm) in dir_modes.into_iter().rev() {
+            fs::set_permissions(&path, fs::Permissions::from_mode(perm as u32))?;
+        }
+        let root = st.node(ROOT_INODE).map_err(io::Error::from)?;
+        fs::set_permissions(dst, fs::Permissions::from_mode(root.perm as u32))?;
+        snapshot::write_xattrs(dst, &xattr_list(root));
+        Ok(stats)
+    }
+
+    /// Replace the layer's contents with the directory tree at `src`.
+    ///
+    /// The new tree is built aside and swapped in atomically; if it does not fit the limits
+    /// the call fails with `ENOSPC`/`EDQUOT` and the current contents are kept. The overlay
+    /// on top must be rebuilt afterwards, since every inode number changes.
+    pub fn load_from(&self, src: &Path) -> io::Result<CloneStats> {
+        let first_inode = self.state().next_inode;
+        let mut st = MemState::new(first_inode);
+        let mut stats = CloneStats::default();
+
+        let root_meta = fs::symlink_metadata(src)?;
+        if !root_meta.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("load source {} is not a directory", src.display()),
+            ));
+        }
+        let root = st.node_mut(ROOT_INODE).map_err(io::Error::from)?;
+        root.perm = (root_meta.mode() & 0o7777) as u16;
+        root.xattrs = xattr_map(src);
+
+        let mut linked: HashMap<(u64, u64), Inode> = HashMap::new();
+        let mut stack = vec![(ROOT_INODE, src.to_path_buf())];
+        while let Some((dir_ino, dir_path)) = stack.pop() {
+            for entry in fs::read_dir(&dir_path)? {
+                let entry = entry?;
+                let path = entry.path();
+                let name = entry.file_name();
+                let meta = fs::symlink_metadata(&path)?;
+                let file_type = meta.file_type();
+
+                if !file_type.is_dir() && meta.nlink() > 1 {
+                    if let Some(&ino) = linked.get(&(meta.dev(), meta.ino())) {
+                        st.node_mut(dir_ino)?.children.insert(name, ino);
+                        st.node_mut(ino)?.nlink += 1;
+                        continue;
+                    }
+                }
+
+                let kind = if file_type.is_dir() {
+                    FileType::Directory
+                } else if file_type.is_symlink() {
+                    FileType::Symlink
+                } else if file_type.is_file() {
+                    FileType::RegularFile
+                } else if file_type.is_char_device() {
+                    FileType::CharDevice
+                } else if file_type.is_block_device() {
+                    FileType::BlockDevice
+                } else if file_type.is_fifo() {
+                    FileType::NamedPipe
+                } else {
+                    tracing::debug!(path = ?path, "mem upper load: skipping socket");
+                    continue;
+                };
+
+                let mut node = MemNode::new(
+                    kind,
+                    (meta.mode() & 0o7777) as u16,
+                    meta.uid(),
+                    meta.gid(),
+                    dir_ino,
+                );
+                node.rdev = meta.rdev() as u32;
+                node.xattrs = xattr_map(&path);
+                match kind {
+                    FileType::RegularFile => {
+                        node.data = Arc::new(fs::read(&path)?);
+                        stats.files += 1;
+                        stats.bytes += node.data.len() as u64;
+                    }
+                    FileType::Symlink => {
+                        node.data = Arc::new(fs::read_link(&path)?.into_os_string().into_vec());
+                        stats.symlinks += 1;
+                    }
+                    FileType::Directory => stats.dirs += 1,
+                    _ => stats.special += 1,
+                }
+                let ino = st.insert(&self.limits, dir_ino, &name, node)?;
+                if kind == FileType::Directory {
+                    stack.push((ino, path));
+                } else if meta.nlink() > 1 {
+                    linked.insert((meta.dev(), meta.ino()), ino);
+                }
+            }
+        }
+
+        *self.state_mut() = st;
+        Ok(stats)
+    }
+
+    fn state(&self) -> RwLockReadGuard<'_, MemState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn state_mut(&self) -> RwLockWriteGuard<'_, MemState> {
+        self.state.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn entry(st: &MemState, ino: Inode) -> Result<ReplyEntry> {
+        Ok(ReplyEntry {
+            ttl: TTL,
+            attr: st.node(ino)?.attr(ino),
+            generation: 0,
+        })
+    }
+
+    fn create_node(
+        &self,
+        parent: Inode,
+        name: &OsStr,
+        kind: FileType,
+        perm: u32,
+        uid: u32,
+        gid: u32,
+    ) -> Result<(Inode, ReplyEntry)> {
+        let mut st = self.state_mut();
+        let ino = st.insert(
+            &self.limits,
+            parent,
+            name,
+            MemNode::new(kind, perm as u16, uid, gid, parent),
+        )?;
+        let entry = Self::entry(&st, ino)?;
+        Ok((ino, entry))
+    }
+
+    fn do_create(
+        &self,
+        parent: Inode,
+        name: &OsStr,
+        mode: u32,
+        flags: u32,
+        uid: u32,
+        gid: u32,
+    ) -> Result<ReplyCreated> {
+        let mut st = self.state_mut();
+        let ino = match st.child(parent, name)? {
+            Some(_) if flags & libc::O_EXCL as u32 != 0 => return Err(libc::EEXIST.into()),
+            Some(ino) => {
+                if st.node(ino)?.is_dir() {
+                    return Err(libc::EISDIR.into());
+                }
+                if flags & libc::O_TRUNC as u32 != 0 {
+                    st.resize(&self.limits, ino, 0)?;
+                }
+                ino
+            }
+            None => st.insert(
+                &self.limits,
+                parent,
+                name,
+                MemNode::new(FileType::RegularFile, mode as u16, uid, gid, parent),
+            )?,
+        };
+        st.node_mut(ino)?.open_count += 1;
+        let attr = st.node(ino)?.attr(ino);
+        Ok(ReplyCreated {
+            ttl: TTL,
+            attr,
+            generation: 0,
+            fh: ino,
+            flags: 0,
+        })
+    }
+
+    fn do_symlink(
+        &self,
+        parent: Inode,
+        name: &OsStr,
+        link: &OsStr,
+        uid: u32,
+        gid: u32,
+    ) -> Result<ReplyEntry> {
+        let mut node = MemNode::new(FileType::Symlink, 0o777, uid, gid, parent);
+        node.data = Arc::new(link.as_bytes().to_vec());
+        let mut st = self.state_mut();
+        let ino = st.insert(&self.limits, parent, name, node)?;
+        Self::entry(&st, ino)
+    }
+}
+
+fn xattr_list(node: &MemNode) -> Vec<(Vec<u8>, Vec<u8>)> {
+    node.xattrs
+        .iter()
+        .map(|(k, v)| (k.as_bytes().to_vec(), v.clone()))
+        .collect()
+}
+
+fn xattr_map(path: &Path) -> BTreeMap<OsString, Vec<u8>> {
+    snapshot::read_xattrs(path)
+        .into_iter()
+        .map(|(k, v)| (OsString::from_vec(k), v))
+        .collect()
+}
+
+fn kind_from_mode(mode: u32) -> Result<FileType> {
+    Ok(match mode & libc::S_IFMT {
+        libc::S_IFREG | 0 => FileType::RegularFile,
+        libc::S_IFCHR => FileType::CharDevice,
+        libc::S_IFBLK => FileType::BlockDevice,
+        libc::S_IFIFO => FileType::NamedPipe,
+        libc::S_IFSOCK => FileType::Socket,
+        _ => return Err(libc::EINVAL.into()),
+    })
+}
+
+/// Answer an xattr query: the size if `size` is 0, `ERANGE` if it is too small.
+fn xattr_reply(data: Vec<u8>, size: u32) -> Result<ReplyXAttr> {
+    if size == 0 {
+        Ok(ReplyXAttr::Size(data.len() as u32))
+    } else if (size as usize) < data.len() {
+        Err(libc::ERANGE.into())
+    } else {
+        Ok(ReplyXAttr::Data(Bytes::from(data)))
+    }
+}
+
+impl Filesystem for MemUpperLayer {
+    async fn init(&self, _req: Request) -> Result<ReplyInit> {
+        Ok(ReplyInit::default())
+    }
+
+    async fn destroy(&self, _req: Request) {}
+
+    async fn lookup(&self, _req: Request, parent: Inode, name: &OsStr) -> Result<ReplyEntry> {
+        let st = self.state();
+        let ino = st
+            .child(parent, name)?
+            .ok_or_else(|| rfuse3::Errno::from(libc::ENOENT))?;
+        Self::entry(&st, ino)
+    }
+
+    async fn getattr(
+        &self,
+        _req: Request,
+        inode: Inode,
+        _fh: Option<u64>,
+        _flags: u32,
+    ) -> Result<ReplyAttr> {
+        let st = self.state();
+        Ok(ReplyAttr {
+            ttl: TTL,
+            attr: st.node(inode)?.attr(inode),
+        })
+    }
+
+    async fn setattr(
+        &self,
+        _req: Request,
+        inode: Inode,
+        _fh: Option<u64>,
+        set_attr: SetAttr,
+    ) -> Result<ReplyAttr> {
+        let mut st = self.state_mut();
+        if let Some(size) = set_attr.size {
+            st.resize(&self.limits, inode, size)?;
+        }
+        let node = st.node_mut(inode)?;
+        if let Some(mode) = set_attr.mode {
+            node.perm = (mode & 0o7777) as u16;
+        }
+        if let Some(uid) = set_attr.uid {
+            node.uid = uid;
+        }
+        if let Some(gid) = set_attr.gid {
+            node.gid = gid;
+        }
+        if let Some(atime) = set_attr.atime {
+            node.atime = atime;
+        }
+        if let Some(mtime) = set_attr.mtime {
+            node.mtime = mtime;
+        }
+        node.ctime = set_attr
+            .ctime
+            .unwrap_or_else(|| Timestamp::from(SystemTime::now()));
+        Ok(ReplyAttr {
+            ttl: TTL,
+            attr: node.attr(inode),
+        })
+    }
+
+    async fn readlink(&self, _req: Request, inode: Inode) -> Result<ReplyData> {
+        let st = self.state();
+        let node = st.node(inode)?;
+        if node.kind != FileType::Symlink {
+            return Err(libc::EINVAL.into());
+        }
+        Ok(ReplyData {
+            data: Bytes::copy_from_slice(&node.data),
+        })
+    }
+
+    async fn symlink(
+        &self,
+        req: Request,
+        parent: Inode,
+        name: &OsStr,
+        link: &OsStr,
+    ) -> Result<ReplyEntry> {
+        self.do_symlink(parent, name, link, req.uid, req.gid)
+    }
+
+    async fn mknod(
+        &self,
+        req: Request,
+        parent: Inode,
+        name: &OsStr,
+        mode: u32,
+        rdev: u32,
+    ) -> Result<ReplyEntry> {
+        let kind = kind_from_mode(mode)?;
+        let mut node = MemNode::new(kind, mode as u16, req.uid, req.gid, parent);
+        node.rdev = rdev;
+        let mut st = self.state_mut();
+        let ino = st.insert(&self.limits, parent, name, node)?;
+        Self::entry(&st, ino)
+    }
+
+    async fn mkdir(
+        &self,
+        req: Request,
+        parent: Inode,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+    ) -> Result<ReplyEntry> {
+        self.create_node(
+            parent,
+            name,
+            FileType::Directory,
+            mode & !umask,
+            req.uid,
+            req.gid,
+        )
+        .map(|(_, entry)| entry)
+    }
+
+    async fn unlink(&self, _req: Request, parent: Inode, name: &OsStr) -> Result<()> {
+        let mut st = self.state_mut();
+        let ino = st
+            .child(parent, name)?
+            .ok_or_else(|| rfuse3::Errno::from(libc::ENOENT))?;
+        if st.node(ino)?.is_dir() {
+            return Err(libc::EISDIR.into());
+        }
+        st.detach(parent, name)?;
+        Ok(())
+    }
+
+    async fn rmdir(&self, _req: Request, parent: Inode, name: &OsStr) -> Result<()> {
+        let mut st = self.state_mut();
+        let ino = st
+            .child(parent, name)?
+            .ok_or_else(|| rfuse3::Errno::from(libc::ENOENT))?;
+        let node = st.dir(ino)?;
+        if !node.children.is_empty() {
+            return Err(libc::ENOTEMPTY.into());
+        }
+        st.detach(parent, name)?;
+        Ok(())
+    }
+
+    async fn rename(
+        &self,
+        req: Request,
+        parent: Inode,
+        name: &OsStr,
+        new_parent: Inode,
+        new_name: &OsStr,
+    ) -> Result<()> {
+        self.rename2(req, parent, name, new_parent, new_name, 0)
+            .await
+    }
+
+    async fn rename2(
+        &self,
+        _req: Request,
+        parent: Inode,
+        name: &OsStr,
+        new_parent: Inode,
+        new_name: &OsStr,
+        flags: u32,
+    ) -> Result<()> {
+        if flags & !libc::RENAME_NOREPLACE != 0 {
+            // RENAME_EXCHANGE / RENAME_WHITEOUT are not needed by the overlay.
+            return Err(libc::EINVAL.into());
+        }
+        let mut st = self.state_mut();
+        let ino = st
+            .child(parent, name)?
+            .ok_or_else(|| rfuse3::Errno::from(libc::ENOENT))?;
+        let src_is_dir = st.node(ino)?.is_dir();
+        if src_is_dir && st.is_ancestor(ino, new_parent) {
+            return Err(libc::EINVAL.into());
+        }
+        if let Some(existing) = st.child(new_parent, new_name)? {
+            if existing == ino {
+                return Ok(());
+            }
+            if flags & libc::RENAME_NOREPLACE != 0 {
+                return Err(libc::EEXIST.into());
+            }
+            let dest = st.node(existing)?;
+            match (src_is_dir, dest.is_dir()) {
+                (true, false) => return Err(libc::ENOTDIR.into()),
+                (false, true) => return Err(libc::EISDIR.into()),
+                (true, true) if !dest.children.is_empty() => return Err(libc::ENOTEMPTY.into()),
+                _ => {}
+            }
+            st.detach(new_parent, new_name)?;
+        }
+
+        let old_parent = st.node_mut(parent)?;
+        old_parent.children.remove(name);
+        old_parent.touch();
+        if src_is_dir {
+            old_parent.nlink -= 1;
+        }
+        let target = st.node_mut(new_parent)?;
+        target.children.insert(new_name.to_os_string(), ino);
+        target.touch();
+        if src_is_dir {
+            target.nlink += 1;
+        }
+        let node = st.node_mut(ino)?;
+        node.parent = new_parent;
+        node.ctime = Timestamp::from(SystemTime::now());
+        Ok(())
+    }
+
+    async fn link(
+        &self,
+        _req: Request,
+        inode: Inode,
+        new_parent: Inode,
+        new_name: &OsStr,
+    ) -> Result<ReplyEntry> {
+        let mut st = self.state_mut();
+        if st.node(inode)?.is_dir() {
+            return Err(libc::EPERM.into());
+        }
+        if st.child(new_parent, new_name)?.is_some() {
+            return Err(libc::EEXIST.into());
+        }
+        let target = st.node_mut(new_parent)?;
+        target.children.insert(new_name.to_os_string(), inode);
+        target.touch();
+        let node = st.node_mut(inode)?;
+        node.nlink += 1;
+        node.ctime = Timestamp::from(SystemTime::now());
+        Self::entry(&st, inode)
+    }
+
+    async fn open(&self, _req: Request, inode: Inode, flags: u32) -> Result<ReplyOpen> {
+        let mut st = self.state_mut();
+        if flags & libc::O_TRUNC as u32 != 0 && st.node(inode)?.kind == FileType::RegularFile {
+            st.resize(&self.limits, inode, 0)?;
+        }
+        st.node_mut(inode)?.open_count += 1;
+        Ok(ReplyOpen {
+            fh: inode,
+            flags: 0,
+        })
+    }
+
+    async fn read(
+        &self,
+        _req: Request,
+        inode: Inode,
+        _fh: u64,
+        offset: u64,
+        size: u32,
+    ) -> Result<ReplyData> {
+        let st = self.state();
+        let node = st.node(inode)?;
+        if node.is_dir() {
+            return Err(libc::EISDIR.into());
+        }
+        let len = node.data.len() as u64;
+        let start = offset.min(len) as usize;
+        let end = offset.saturating_add(size as u64).min(len) as usize;
+        Ok(ReplyData {
+            data: Bytes::copy_from_slice(&node.data[start..end]),
+        })
+    }
+
+    async fn write(
+        &self,
+        _req: Request,
+        inode: Inode,
+        _fh: u64,
+        offset: u64,
+        data: &[u8],
+        _write_flags: u32,
+        _flags: u32,
+    ) -> Result<ReplyWrite> {
+        self.state_mut()
+            .write_at(&self.limits, inode, offset, data)?;
+        Ok(ReplyWrite {
+            written: data.len() as u32,
+        })
+    }
+
+    async fn statfs(&self, _req: Request, _inode: Inode) -> Result<ReplyStatFs> {
+        let st = self.state();
+        let blocks = self
+            .limits
+            .max_bytes
+            .map(|b| b.div_ceil(BLOCK_SIZE as u64))
+            .unwrap_or(UNLIMITED_BLOCKS);
+        let free = blocks.saturating_sub(st.bytes.div_ceil(BLOCK_SIZE as u64));
+        let files = self.limits.max_inodes.unwrap_or(u64::MAX);
+        Ok(ReplyStatFs {
+            blocks,
+            bfree: free,
+            bavail: free,
+            files,
+            ffree: files.saturating_sub(st.inodes()),
+            bsize: BLOCK_SIZE,
+            namelen: 255,
+            frsize: BLOCK_SIZE,
+        })
+    }
+
+    async fn release(
+        &self,
+        _req: Request,
+        inode: Inode,
+        _fh: u64,
+        _flags: u32,
+        _lock_owner: u64,
+        _flush: bool,
+    ) -> Result<()> {
+        let mut st = self.state_mut();
+        if let Some(node) = st.nodes.get_mut(&inode) {
+            node.open_count = node.open_count.saturating_sub(1);
+        }
+        st.release_if_unused(inode);
+        Ok(())
+    }
+
+    async fn fsync(&self, _req: Request, _inode: Inode, _fh: u64, _datasync: bool) -> Result<()> {
+        Ok(())
+    }
+
+    async fn setxattr(
+        &self,
+        _req: Request,
+        inode: Inode,
+        name: &OsStr,
+        value: &[u8],
+        flags: u32,
+        _position: u32,
+    ) -> Result<()> {
+        let mut st = self.state_mut();
+        let node = st.node_mut(inode)?;
+        let exists = node.xattrs.contains_key(name);
+        if flags & libc::XATTR_CREATE as u32 != 0 && exists {
+            return Err(libc::EEXIST.into());
+        }
+        if flags & libc::XATTR_REPLACE as u32 != 0 && !exists {
+            return Err(libc::ENODATA.into());
+        }
+        node.xattrs.insert(name.to_os_string(), value.to_vec());
+        node.ctime = Timestamp::from(SystemTime::now());
+        Ok(())
+    }
+
+    async fn getxattr(
+        &self,
+        _req: Request,
+        inode: Inode,
+        name: &OsStr,
+        size: u32,
+    ) -> Result<ReplyXAttr> {
+        let st = self.state();
+        let value = st
+            .node(inode)?
+            .xattrs
+            .get(name)
+            .cloned()
+            .ok_or_else(|| rfuse3::Errno::from(libc::ENODATA))?;
+        xattr_reply(value, size)
+    }
+
+    async fn listxattr(&self, _req: Request, inode: Inode, size: u32) -> Result<ReplyXAttr> {
+        let st = self.state();
+        let mut names = Vec::new();
+        for name in st.node(inode)?.xattrs.keys() {
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+        }
+        xattr_reply(names, size)
+    }
+
+    async fn removexattr(&self, _req: Request, inode: Inode, name: &OsStr) -> Result<()> {
+        let mut st = self.state_mut();
+        let node = st.node_mut(inode)?;
+        node.xattrs
+            .remove(name)
+            .ok_or_else(|| rfuse3::Errno::from(libc::ENODATA))?;
+        node.ctime = Timestamp::from(SystemTime::now());
+        Ok(())
+    }
+
+    async fn flush(&self, _req: Request, _inode: Inode, _fh: u64, _lock_owner: u64) -> Result<()> {
+        Ok(())
+    }
+
+    async fn opendir(&self, _req: Request, inode: Inode, _flags: u32) -> Result<ReplyOpen> {
+        self.state().dir(inode)?;
+        Ok(ReplyOpen {
+            fh: inode,
+            flags: 0,
+        })
+    }
+
+    async fn readdir<'a>(
+        &'a self,
+        _req: Request,
+        parent: Inode,
+        _fh: u64,
+        offset: i64,
+    ) -> Result<ReplyDirectory<impl futures::Stream<Item = Result<DirectoryEntry>> + Send + 'a>>
+    {
+        let st = self.state();
+        let dir = st.dir(parent)?;
+
+        // Offset 0 is ".", 1 is "..", 2.. are children in name order.
+        let mut out: Vec<Result<DirectoryEntry>> = Vec::new();
+        let dots = [(parent, "."), (dir.parent, "..")];
+        for (idx, (ino, name)) in dots.into_iter().enumerate() {
+            if (idx as i64) >= offset {
+                out.push(Ok(DirectoryEntry {
+                    inode: ino,
+                    kind: FileType::Directory,
+                    name: name.into(),
+                    offset: idx as i64 + 1,
+                }));
+            }
+        }
+        for (idx, (name, &ino)) in dir.children.iter().enumerate() {
+            let entry_offset = idx as i64 + 2;
+            if entry_offset < offset {
+                continue;
+            }
+            out.push(Ok(DirectoryEntry {
+                inode: ino,
+                kind: st
+                    .node(ino)
+                    .map(|n| n.kind)
+                    .unwrap_or(FileType::RegularFile),
+                name: name.clone(),
+                offset: entry_offset + 1,
+            }));
+        }
+        Ok(ReplyDirectory {
+            entries: futures::stream::iter(out),
+        })
+    }
+
+    async fn releasedir(&self, _req: Request, _inode: Inode, _fh: u64, _flags: u32) -> Result<()> {
+        Ok(())
+    }
+
+    async fn fsyncdir(
+        &self,
+        _req: Request,
+        _inode: Inode,
+        _fh: u64,
+        _datasync: bool,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn getlk(
+        &self,
+        _req: Request,
+        _inode: Inode,
+        _fh: u64,
+        _lock_owner: u64,
+        start: u64,
+        end: u64,
+        _type: u32,
+        _pid: u32,
+    ) -> Result<ReplyLock> {
+        // No locks are held on an in-memory layer; report F_UNLCK.
+        Ok(ReplyLock {
+            start,
+            end,
+            r#type: libc::F_UNLCK as u32,
+            pid: 0,
+        })
+    }
+
+    async fn setlk(
+        &self,
+        _req: Request,
+        _inode: Inode,
+        _fh: u64,
+        _lock_owner: u64,
+        _start: u64,
+        _end: u64,
+        _type: u32,
+        _pid: u32,
+        _block: bool,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn access(&self, _req: Request, inode: Inode, _mask: u32) -> Result<()> {
+        // Permission checks happen in the kernel against the attributes we report.
+        self.state().node(inode).map(|_| ())
+    }
+
+    async fn create(
+        &self,
+        req: Request,
+        parent: Inode,
+        name: &OsStr,
+        mode: u32,
+        flags: u32,
+    ) -> Result<ReplyCreated> {
+        self.do_create(parent, name, mode, flags, req.uid, req.gid)
+    }
+
+    async fn fallocate(
+        &self,
+        _req: Request,
+        inode: Inode,
+        _fh: u64,
+        offset: u64,
+        length: u64,
+        mode: u32,
+    ) -> Result<()> {
+        let mut st = self.state_mut();
+        let len = st.node(inode)?.data.len() as u64;
+        let end = offset.saturating_add(length);
+        let mode = mode as i32;
+        if mode == 0 {
+            if end > len {
+                st.resize(&self.limits, inode, end)?;
+            }
+            Ok(())
+        } else if mode == libc::FALLOC_FL_KEEP_SIZE {
+            Ok(())
+        } else if mode == libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE {
+            let node = st.node_mut(inode)?;
+            let (start, stop) = (offset.min(len) as usize, end.min(len) as usize);
+            Arc::make_mut(&mut node.data)[start..stop].fill(0);
+            node.touch();
+            Ok(())
+        } else {
+            Err(libc::EOPNOTSUPP.into())
+        }
+    }
+
+    async fn lseek(
+        &self,
+        _req: Request,
+        inode: Inode,
+        _fh: u64,
+        offset: u64,
+        whence: u32,
+    ) -> Result<ReplyLSeek> {
+        let len = self.state().node(inode)?.data.len() as u64;
+        let offset = match whence as i32 {
+            libc::SEEK_SET => offset,
+            libc::SEEK_END => len.saturating_add(offset),
+            // Files are never sparse in memory: all data, with the only hole at EOF.
+            libc::SEEK_DATA if offset < len => offset,
+            libc::SEEK_HOLE if offset < len => len,
+            libc::SEEK_DATA | libc::SEEK_HOLE => return Err(libc::ENXIO.into()),
+            _ => return Err(libc::EINVAL.into()),
+        };
+        Ok(ReplyLSeek { offset })
+    }
+
+    async fn copy_file_range(
+        &self,
+        _req: Request,
+        inode: Inode,
+        _fh_in: u64,
+        off_in: u64,
+        inode_out: Inode,
+        _fh_out: u64,
+        off_out: u64,
+        length: u64,
+        _flags: u64,
+    ) -> Result<ReplyCopyFileRange> {
+        let mut st = self.state_mut();
+        let src = st.node(inode)?;
+        if src.is_dir() {
+            return Err(libc::EISDIR.into());
+        }
+        let len = src.data.len() as u64;
+        let start = off_in.min(len) as usize;
+        let end = off_in.saturating_add(length).min(len) as usize;
+        let chunk = src.data[start..end].to_vec();
+        st.write_at(&self.limits, inode_out, off_out, &chunk)?;
+        Ok(ReplyCopyFileRange {
+            copied: chunk.len() as u64,
+        })
+    }
+}
+
+#[async_trait]
+impl Layer for MemUpperLayer {
+    fn root_inode(&self) -> Inode {
+        ROOT_INODE
+    }
+
+    async fn create_with_context(
+        &self,
+        ctx: OperationContext,
+        parent: Inode,
+        name: &OsStr,
+        mode: u32,
+        flags: u32,
+    ) -> Result<ReplyCreated> {
+        self.do_create(
+            parent,
+            name,
+            mode,
+            flags,
+            ctx.uid.unwrap_or(ctx.req.uid),
+            ctx.gid.unwrap_or(ctx.req.gid),
+        )
+    }
+
+    async fn mkdir_with_context(
+        &self,
+        ctx: OperationContext,
+        parent: Inode,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+    ) -> Result<ReplyEntry> {
+        self.create_node(
+            parent,
+            name,
+            FileType::Directory,
+            mode & !umask,
+            ctx.uid.unwrap_or(ctx.req.uid),
+            ctx.gid.unwrap_or(ctx.req.gid),
+        )
+        .map(|(_, entry)| entry)
+    }
+
+    async fn symlink_with_context(
+        &self,
+        ctx: OperationContext,
+        parent: Inode,
+        name: &OsStr,
+        link: &OsStr,
+    ) -> Result<ReplyEntry> {
+        self.do_symlink(
+            parent,
+            name,
+            link,
+            ctx.uid.unwrap_or(ctx.req.uid),
+            ctx.gid.unwrap_or(ctx.req.gid),
+        )
+    }
+
+    async fn getattr_with_mapping(
+        &self,
+        inode: Inode,
+        _handle: Option<u64>,
+        _mapping: bool,
+    ) -> io::Result<(libc::stat64, Duration)> {
+        let st = self.state();
+        let attr = st.node(inode).map_err(io::Error::from)?.attr(inode);
+
+        // SAFETY: stat64 is plain old data; zeroed() also covers platform padding fields.
+        let mut stat: libc::stat64 = unsafe { std::mem::zeroed() };
+        stat.st_ino = attr.ino;
+        stat.st_nlink = attr.nlink as _;
+        stat.st_mode = attr.kind.const_into_mode_t() | attr.perm as libc::mode_t;
+        stat.st_uid = attr.uid;
+        stat.st_gid = attr.gid;
+        stat.st_rdev = attr.rdev as _;
+        stat.st_size = attr.size as i64;
+        stat.st_blksize = attr.blksize as _;
+        stat.st_blocks = attr.blocks as _;
+        stat.st_atime = attr.atime.sec;
+        stat.st_atime_nsec = attr.atime.nsec.into();
+        stat.st_mtime = attr.mtime.sec;
+        stat.st_mtime_nsec = attr.mtime.nsec.into();
+        stat.st_ctime = attr.ctime.sec;
+        stat.st_ctime_nsec = attr.ctime.nsec.into();
+        Ok((stat, TTL))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn name(s: &str) -> &OsStr {
+        OsStr::new(s)
+    }
+
+    async fn write_file(layer: &MemUpperLayer, parent: Inode, file: &str, data: &[u8]) -> Inode {
+        let created = layer
+            .create(
+                Request::default(),
+                parent,
+                name(file),
+                libc::S_IFREG | 0o644,
+                libc::O_WRONLY as u32,
+            )
+            .await
+            .unwrap();
+        layer
+            .write(
+                Request::default(),
+                created.attr.ino,
+                created.fh,
+                0,
+                data,
+                0,
+                0,
+            )
+            .await
+            .unwrap();
+        layer
+            .release(
+                Request::default(),
+                created.attr.ino,
+                created.fh,
+                0,
+                0,
+                false,
+            )
+            .await
+            .unwrap();
+        created.attr.ino
+    }
+
+    async fn read_file(layer: &MemUpperLayer, parent: Inode, file: &str) -> Vec<u8> {
+        let entry = layer
+            .lookup(Request::default(), parent, name(file))
+            .await
+            .unwrap();
+        let data = layer
+            .read(Request::default(), entry.attr.ino, 0, 0, u32::MAX)
+            .await
+            .unwrap();
+        data.data.to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_limits_are_enforced_exactly() {
+        let layer = MemUpperLayer::new(QuotaLimits {
+            max_bytes: Some(10),
+            max_inodes: Some(2),
+        });
+        let ino = write_file(&layer, ROOT_INODE, "a", b"12345678").await;
+
+        let err = layer
+            .write(Request::default(), ino, 0, 8, b"abc", 0, 0)
+            .await
+            .unwrap_err();
+        assert_eq!(err, rfuse3::Errno::from(libc::ENOSPC));
+        assert_eq!(read_file(&layer, ROOT_INODE, "a").await, b"12345678");
+
+        layer
+            .mkdir(Request::default(), ROOT_INODE, name("d"), 0o755, 0)
+            .await
+            .unwrap();
+        let err = layer
+            .mkdir(Request::default(), ROOT_INODE, name("e"), 0o755, 0)
+            .await
+            .unwrap_err();
+        assert_eq!(err, rfuse3::Errno::from(libc::EDQUOT));
+
+        // Deleting frees both budgets immediately.
+        layer
+            .unlink(Request::default(), ROOT_INODE, name("a"))
+            .await
+            .unwrap();
+        let usage = layer.usage();
+        assert_eq!((usage.used_bytes, usage.used_inodes), (0, 1));
+        write_file(&layer, ROOT_INODE, "b", b"0123456789").await;
+    }
+
+    #[tokio::test]
+    async fn test_unlinked_open_file_is_freed_on_release() {
+        let layer = MemUpperLayer::new(QuotaLimits::default());
+        let ino = write_file(&layer, ROOT_INODE, "f", b"data").await;
+        let fh = layer
+            .open(Request::default(), ino, libc::O_RDONLY as u32)
+            .await
+            .unwrap()
+            .fh;
+        layer
+            .unlink(Request::default(), ROOT_INODE, name("f"))
+            .await
+            .unwrap();
+        assert_eq!(layer.usage().used_bytes, 4);
+        layer
+            .release(Request::default(), ino, fh, 0, 0, false)
+            .await
+            .unwrap();
+        assert_eq!(layer.usage().used_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_rename_moves_and_replaces() {
+        let layer = MemUpperLayer::new(QuotaLimits::default());
+        let dir = layer
+            .mkdir(Request::default(), ROOT_INODE, name("dir"), 0o755, 0)
+            .await
+            .unwrap()
+            .attr
+            .ino;
+        write_file(&layer, ROOT_INODE, "src", b"new").await;
+        write_file(&layer, dir, "dst", b"old-contents").await;
+
+        layer
+            .rename(
+                Request::default(),
+                ROOT_INODE,
+                name("src"),
+                dir,
+                name("dst"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(read_file(&layer, dir, "dst").await, b"new");
+        assert!(layer
+            .lookup(Request::default(), ROOT_INODE, name("src"))
+            .await
+            .is_err());
+        assert_eq!(layer.usage().used_bytes, 3);
+
+        // A directory cannot be moved into its own subtree.
+        let err = layer
+            .rename(
+                Request::default(),
+                ROOT_INODE,
+                name("dir"),
+                dir,
+                name("loop"),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err, rfuse3::Errno::from(libc::EINVAL));
+    }
+
+    #[tokio::test]
+    async fn test_fork_is_isolated() {
+        let layer = MemUpperLayer::new(QuotaLimits::default());
+        let ino = write_file(&layer, ROOT_INODE, "f", b"shared").await;
+        let fork = layer.fork();
+
+        layer
+            .write(Request::default(), ino, 0, 0, b"SOURCE", 0, 0)
+            .await
+            .unwrap();
+        assert_eq!(read_file(&fork, ROOT_INODE, "f").await, b"shared");
+        assert_eq!(read_file(&layer, ROOT_INODE, "f").await, b"SOURCE");
+        assert_eq!(fork.usage(), layer.usage());
+    }
+
+    #[tokio::test]
+    async fn test_export_and_load_roundtrip() {
+        let layer = MemUpperLayer::new(QuotaLimits::default());
+        let dir = layer
+            .mkdir(Request::default(), ROOT_INODE, name("dir"), 0o750, 0)
+            .await
+            .unwrap()
+            .attr
+            .ino;
+        write_file(&layer, dir, "file", b"payload").await;
+        layer
+            .symlink(
+                Request::default(),
+                ROOT_INODE,
+                name("link"),
+                name("dir/file"),
+            )
+            .await
+            .unwrap();
+        layer
+            .create_whiteout(Request::default(), ROOT_INODE, name("gone"))
+            .await
+            .unwrap();
+        layer.set_opaque(Request::default(), dir).await.unwrap();
+
+        let out = tempdir().unwrap();
+        let export = out.path().join("upper");
+        let stats = layer.export_to(&export).unwrap();
+        assert_eq!(stats.files, 1);
+        assert_eq!(stats.bytes, 7);
+        assert_eq!(fs::read(export.join("dir/file")).unwrap(), b"payload");
+        assert_eq!(
+            fs::read_link(export.join("link")).unwrap(),
+            Path::new("dir/file")
+        );
+
+        let restored = MemUpperLayer::new(QuotaLimits::default());
+        write_file(&restored, ROOT_INODE, "stale", b"x").await;
+        restored.load_from(&export).unwrap();
+        assert!(restored
+            .lookup(Request::default(), ROOT_INODE, name("stale"))
+            .await
+            .is_err());
+        let dir = restored
+            .lookup(Request::default(), ROOT_INODE, name("dir"))
+            .await
+            .unwrap();
+        assert_eq!(dir.attr.perm, 0o750);
+        assert_eq!(read_file(&restored, dir.attr.ino, "file").await, b"payload");
+        let gone = restored
+            .lookup(Request::default(), ROOT_INODE, name("gone"))
+            .await
+            .unwrap();
+        assert!(restored
+            .is_whiteout(Request::default(), gone.attr.ino)
+            .await
+            .unwrap());
+        assert_eq!(restored.usage(), layer.usage());
+    }
+
+    #[test]
+    fn test_load_rejects_tree_over_limits() {
+        let src = tempdir().unwrap();
+        fs::write(src.path().join("big"), vec![0u8; 64]).unwrap();
+
+        let layer = MemUpperLayer::new(QuotaLimits {
+            max_bytes: Some(32),
+            max_inodes: None,
+        });
+        let err = layer.load_from(src.path()).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOSPC));
+        assert_eq!(layer.usage().used_inodes, 0);
+    }
+}
@@ -8,8 +8,8 @@
 //! ## Key Components
 //!
 //! - [`AntaresPaths`]: Configuration for layer and state directories
-//! - [`AntaresConfig`]: Per-mount configuration (job_id, paths, etc.)
-//! - [`AntaresManager`]: Manages mount lifecycle (create, unmount, list)
+//! - [`AntaresManager`]: Embeds the mount engine of the Antares daemon
+//!   ([`AntaresServiceImpl`]) to create, unmount and list mounts in-process
 //!
 //! ## Layer Stack
 //!
@@ -25,6 +25,12 @@
 //! └─────────────────┘
 //! ```
 //!
+//! Read-only mounts (e.g. for indexers and linters) omit the upper layer entirely
+//! and are mounted `ro`, so writes fail with `EROFS`.
+//!
+//! Composite mounts replace the single Dicfuse view with several per-subtree views served
+//! side by side (see [`composite::CompositeLayer`]).
+//!
 //! ## Example
 //!
 //! ```rust,ignore
@@ -32,44 +38,53 @@
 //! use std::path::PathBuf;
 //!
 //! #[tokio::main]
-//! async fn main() -> std::io::Result<()> {
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let paths = AntaresPaths::from_global_config();
-//!     let manager = AntaresManager::new(paths).await;
-//!     
+//!     let manager = AntaresManager::new(paths).await?;
+//!
 //!     // Mount with auto-generated path (under configured mount_root)
-//!     let config = manager.mount_job("build-42", Some("cl-123")).await?;
-//!     println!("Mounted at: {}", config.mountpoint.display());
-//!     
+//!     let status = manager.mount_job("build-42", Some("cl-123")).await?;
+//!     println!("Mounted at: {}", status.mountpoint);
+//!
 //!     // Or mount to any custom directory
-//!     let custom_config = manager.mount_job_at(
-//!         "build-43",
-//!         PathBuf::from("/home/user/my-workspace"),
-//!         None,
-//!     ).await?;
-//!     
+//!     let custom = manager
+//!         .mount_job_at("build-43", PathBuf::from("/home/user/my-workspace"), None)
+//!         .await?;
+//!
 //!     // Later, unmount
 //!     manager.umount_job("build-42").await?;
 //!     manager.umount_job("build-43").await?;
 //!     Ok(())
 //! }
 //! ```
+//!
+//! The manager records its mounts in the same state file as the daemon, and only one
+//! instance may own a given `state_file`: its directory is locked, and a second manager or
+//! daemon on it fails to start (see [`state_store`]). To control embedded mounts over
+//! HTTP as well, serve
+//! [`AntaresManager::service`] with [`crate::daemon::antares::AntaresDaemon`].
 
+pub mod blob_cache;
+pub mod checkpoint;
+pub mod cl_manifest;
+pub mod cl_stack;
+pub mod composite;
 pub mod fuse;
+pub mod gc;
+pub mod labels;
+pub mod local_cl;
+pub mod mem_upper;
+pub mod quota;
+pub mod snapshot;
+pub mod state_store;
 
-use std::{
-    collections::HashMap,
-    fs::{self, File},
-    io::Write,
-    path::{Path, PathBuf},
-    sync::Arc,
-};
-
-use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
-use uuid::Uuid;
+use std::{path::PathBuf, sync::Arc};
 
 use crate::{
-    dicfuse::{Dicfuse, DicfuseManager},
+    daemon::antares::{
+        AntaresService, AntaresServiceImpl, CreateMountRequest, MountStatus, ServiceError,
+    },
+    dicfuse::Dicfuse,
     util::config,
 };
 
@@ -82,7 +97,8 @@ pub struct AntaresPaths {
     pub cl_root: PathBuf,
     /// Base directory for mountpoints returned to callers.
     pub mount_root: PathBuf,
-    /// Path to persist mount state as TOML.
+    /// Path to persist mount state as TOML. Its journal (`{state_file}.journal`) and the
+    /// lock held by the owning instance (`antares.lock`) live next to it.
     pub state_file: PathBuf,
 }
 
@@ -112,79 +128,83 @@ impl AntaresPaths {
     }
 }
 
-/// Persisted config for a mounted Antares job instance.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AntaresConfig {
-    pub job_id: String,
-    pub mountpoint: PathBuf,
-    pub upper_id: String,
-    pub upper_dir: PathBuf,
-    pub cl_dir: Option<PathBuf>,
-    pub cl_id: Option<String>,
-}
-
-#[derive(Debug, Default, Serialize, Deserialize)]
-struct AntaresState {
-    mounts: Vec<AntaresConfig>,
-}
-
-/// Manager responsible for creating and tracking Antares overlay instances.
-/// This scaffold currently wires directory creation and bookkeeping; the unionfs
-/// integration will be added once the layer stack is finalized.
+/// In-process access to the Antares mount engine.
+///
+/// Mounts are created by the same [`AntaresServiceImpl`] the daemon serves over HTTP and
+/// persisted in its state file, so library users, the CLI and the HTTP API all see the
+/// same mounts. Mounts live as long as the process that created them.
 pub struct AntaresManager {
-    dic: Arc<Dicfuse>,
-    paths: AntaresPaths,
-    instances: Arc<Mutex<HashMap<String, AntaresConfig>>>,
+    service: Arc<AntaresServiceImpl>,
 }
 
 impl AntaresManager {
-    /// Build an independent Antares manager with its own Dicfuse instance.
-    pub async fn new(paths: AntaresPaths) -> Self {
-        let dic = DicfuseManager::global().await;
-        let instances = Self::load_state(&paths.state_file).unwrap_or_default();
-        Self {
-            dic,
-            paths,
-            instances: Arc::new(Mutex::new(instances)),
-        }
+    /// Start a mount engine on `paths` and remount the mounts its state file records.
+    ///
+    /// Fails when another instance holds the directory of `paths.state_file`.
+    pub async fn new(paths: AntaresPaths) -> Result<Self, ServiceError> {
+        let service = AntaresServiceImpl::with_paths_and_recovery(None, paths).await?;
+        Ok(Self::from_service(Arc::new(service)))
+    }
+
+    /// Manage the mounts of an existing service, e.g. one also served by a daemon.
+    pub fn from_service(service: Arc<AntaresServiceImpl>) -> Self {
+        Self { service }
+    }
+
+    /// The underlying mount engine, for [`crate::daemon::antares::AntaresDaemon::new`]
+    /// or the full [`AntaresService`] API.
+    pub fn service(&self) -> Arc<AntaresServiceImpl> {
+        self.service.clone()
     }
 
-    /// Create directories and register a job instance with default mountpoint.
+    /// Create a mount and return its status once mounted.
     ///
-    /// The mountpoint will be created at `{mount_root}/{job_id}` using the
-    /// configured mount root directory.
+    /// Unlike HTTP requests, `request.mountpoint` may be any directory: it is not
+    /// checked against `antares_mountpoint_prefixes`.
+    pub async fn mount(
+        &self,
+        mut request: CreateMountRequest,
+    ) -> Result<MountStatus, ServiceError> {
+        request.unrestricted_mountpoint = true;
+        request.background = false;
+        let created = self.service.create_mount(request).await?;
+        self.service.describe_mount(created.mount_id).await
+    }
+
+    /// Mount the monorepo root for `job_id` at `{mount_root}/{mount_id}`.
+    ///
+    /// Creating the same job again returns the existing mount.
     ///
     /// # Arguments
     /// * `job_id` - Unique identifier for this job
-    /// * `cl_name` - Optional CL (changelist) layer name
+    /// * `cl` - Optional CL (changelist) to apply on top of the base tree
     ///
     /// # Example
     /// ```rust,ignore
-    /// let config = manager.mount_job("build-123", Some("cl-456")).await?;
-    /// // Mountpoint will be at: {mount_root}/build-123
+    /// let status = manager.mount_job("build-123", Some("cl-456")).await?;
     /// ```
     pub async fn mount_job(
         &self,
         job_id: &str,
-        cl_name: Option<&str>,
-    ) -> std::io::Result<AntaresConfig> {
-        let mountpoint = self.paths.mount_root.join(job_id);
-        self.mount_job_at(job_id, mountpoint, cl_name).await
+        cl: Option<&str>,
+    ) -> Result<MountStatus, ServiceError> {
+        self.mount(Self::job_request(job_id, None, cl, false)).await
     }
 
-    /// Create directories and register a job instance at a custom mountpoint.
+    /// Mount the monorepo root for `job_id` at a custom mountpoint.
     ///
-    /// Unlike [`mount_job`], this method allows specifying any directory as
-    /// the mountpoint, not limited to the configured mount root.
+    /// Unlike [`Self::mount_job`], this method allows specifying any directory as
+    /// the mountpoint, not limited to the configured mount root. It must be missing
+    /// or an empty directory.
     ///
     /// # Arguments
     /// * `job_id` - Unique identifier for this job
     /// * `mountpoint` - Custom path where the filesystem will be mounted
-    /// * `cl_name` - Optional CL (changelist) layer name
+    /// * `cl` - Optional CL (changelist) to apply on top of the base tree
     ///
     /// # Example
     /// ```rust,ignore
-    /// let config = manager.mount_job_at(
+    /// let status = manager.mount_job_at(
     ///     "build-123",
     ///     PathBuf::from("/home/user/my-build"),
     ///     None
@@ -194,148 +214,67 @@ impl AntaresManager {
         &self,
         job_id: &str,
         mountpoint: impl Into<PathBuf>,
-        cl_name: Option<&str>,
-    ) -> std::io::Result<AntaresConfig> {
-        let mountpoint = mountpoint.into();
-        let start = std::time::Instant::now();
-        tracing::info!(
-            "antares: mount_job_at start job_id={} mountpoint={} cl={:?}",
-            job_id,
-            mountpoint.display(),
-            cl_name
-        );
-
-        // Prepare per-job paths
-        let upper_id = Uuid::new_v4().to_string();
-        let upper_dir = self.paths.upper_root.join(&upper_id);
-        let (cl_id, cl_dir) = match cl_name {
-            Some(_) => {
-                let id = Uuid::new_v4().to_string();
-                (Some(id.clone()), Some(self.paths.cl_root.join(id)))
-            }
-            None => (None, None),
-        };
-
-        std::fs::create_dir_all(&upper_dir)?;
-        if let Some(cl) = &cl_dir {
-            std::fs::create_dir_all(cl)?;
-        }
-        std::fs::create_dir_all(&mountpoint)?;
-
-        let instance = AntaresConfig {
-            job_id: job_id.to_string(),
-            mountpoint,
-            upper_id,
-            upper_dir,
-            cl_dir,
-            cl_id,
-        };
+        cl: Option<&str>,
+    ) -> Result<MountStatus, ServiceError> {
+        let request = Self::job_request(job_id, Some(mountpoint.into()), cl, false);
+        self.mount(request).await
+    }
 
-        self.instances
-            .lock()
-            .await
-            .insert(job_id.to_string(), instance.clone());
+    /// Mount the monorepo root for `job_id` without an upper layer; writes fail with
+    /// `EROFS`.
+    pub async fn mount_job_read_only(
+        &self,
+        job_id: &str,
+        cl: Option<&str>,
+    ) -> Result<MountStatus, ServiceError> {
+        self.mount(Self::job_request(job_id, None, cl, true)).await
+    }
 
-        self.persist_state().await?;
+    /// Read-only variant of [`Self::mount_job_at`].
+    pub async fn mount_job_at_read_only(
+        &self,
+        job_id: &str,
+        mountpoint: impl Into<PathBuf>,
+        cl: Option<&str>,
+    ) -> Result<MountStatus, ServiceError> {
+        let request = Self::job_request(job_id, Some(mountpoint.into()), cl, true);
+        self.mount(request).await
+    }
 
-        tracing::info!(
-            "antares: mount_job done job_id={} mountpoint={} elapsed={:.2}s",
-            job_id,
-            instance.mountpoint.display(),
-            start.elapsed().as_secs_f64()
-        );
-        Ok(instance)
+    fn job_request(
+        job_id: &str,
+        mountpoint: Option<PathBuf>,
+        cl: Option<&str>,
+        read_only: bool,
+    ) -> CreateMountRequest {
+        CreateMountRequest {
+            job_id: Some(job_id.to_string()),
+            path: "/".to_string(),
+            cl: cl.map(str::to_string),
+            read_only,
+            mountpoint: mountpoint.map(|m| m.to_string_lossy().into_owned()),
+            ..Default::default()
+        }
     }
 
-    /// Unmount the FUSE filesystem and remove bookkeeping for a job.
+    /// Unmount the mount of `job_id`, whoever created it.
     ///
-    /// Attempts to unmount the filesystem using `fusermount -u`. If the filesystem
-    /// is not mounted (e.g., it was never mounted or already unmounted), the unmount
-    /// attempt will fail but the function will still remove the bookkeeping entry.
-    pub async fn umount_job(&self, job_id: &str) -> std::io::Result<Option<AntaresConfig>> {
-        use tracing::{info, warn};
-
-        // Lock and get the config, but do not remove yet
-        let mut instances = self.instances.lock().await;
-        let config = match instances.get(job_id) {
-            Some(cfg) => cfg.clone(),
-            None => return Ok(None),
-        };
-
-        // Attempt to unmount the FUSE mount
-        let mount_path = &config.mountpoint;
-        info!("Attempting to unmount FUSE mount at {:?}", mount_path);
-
-        let output = tokio::process::Command::new("fusermount")
-            .arg("-u")
-            .arg(mount_path)
-            .output()
-            .await?;
-
-        if !output.status.success() {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
-            // Check if the error is because the filesystem is not mounted
-            // In this case, we still proceed to remove bookkeeping
-            if error_msg.contains("not mounted") || error_msg.contains("Invalid argument") {
-                warn!(
-                    "Filesystem at {:?} is not mounted, removing bookkeeping only: {}",
-                    mount_path, error_msg
-                );
-            } else {
-                warn!(
-                    "fusermount -u failed with status {} for {:?}: {}",
-                    output.status, mount_path, error_msg
-                );
-                // For other errors, we still remove bookkeeping to avoid stale entries
-                // but log the warning
-            }
-        } else {
-            info!("Successfully unmounted {:?}", mount_path);
+    /// Returns `Ok(None)` when no mount belongs to the job.
+    pub async fn umount_job(&self, job_id: &str) -> Result<Option<MountStatus>, ServiceError> {
+        match self.service.delete_mount_by_job(job_id.to_string()).await {
+            Ok(status) => Ok(Some(status)),
+            Err(ServiceError::NotFoundTask(_)) => Ok(None),
+            Err(err) => Err(err),
         }
-
-        // Remove from bookkeeping and persist (even if unmount failed)
-        let removed = instances.remove(job_id);
-        drop(instances);
-        self.persist_state().await?;
-
-        Ok(removed)
     }
 
-    /// List all tracked instances.
-    pub async fn list(&self) -> Vec<AntaresConfig> {
-        self.instances.lock().await.values().cloned().collect()
+    /// List all mounts of the engine.
+    pub async fn list(&self) -> Result<Vec<MountStatus>, ServiceError> {
+        self.service.list_mounts().await
     }
 
     /// Access the underlying Dicfuse instance (read-only tree layer).
     pub fn dicfuse(&self) -> Arc<Dicfuse> {
-        self.dic.clone()
-    }
-
-    fn load_state(path: &Path) -> std::io::Result<HashMap<String, AntaresConfig>> {
-        if !path.exists() {
-            return Ok(HashMap::new());
-        }
-        let content = fs::read_to_string(path)?;
-        let state: AntaresState = toml::from_str(&content).map_err(|e| {
-            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("parse state: {e}"))
-        })?;
-        let mut map = HashMap::new();
-        for m in state.mounts {
-            map.insert(m.job_id.clone(), m);
-        }
-        Ok(map)
-    }
-
-    async fn persist_state(&self) -> std::io::Result<()> {
-        let mounts: Vec<AntaresConfig> = self.instances.lock().await.values().cloned().collect();
-        let state = AntaresState { mounts };
-        let data = toml::to_string_pretty(&state)
-            .map_err(|e| std::io::Error::other(format!("encode state: {e}")))?;
-        if let Some(parent) = self.paths.state_file.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        let mut f = File::create(&self.paths.state_file)?;
-        f.write_all(data.as_bytes())?;
-        Ok(())
+        self.service.dicfuse()
     }
 }
@@ -0,0 +1,839 @@
+//! Byte and inode quotas for Antares upper layers.
+//!
+//! [`QuotaFs`] wraps the composed overlay and checks every request that can grow the
+//! upper layer. Writers over the byte limit get `ENOSPC`; creating a node over the inode
+//! limit gets `EDQUOT`.
+//!
+//! Accounting is optimistic: counters are seeded by scanning the upper dir and grown in
+//! the write path. Shrinks (unlink, truncate of copied-up files, whiteouts) are credited
+//! by a rescan, which runs periodically in the background and synchronously before a
+//! request is rejected. A deletion since the last scan forces that rescan regardless of
+//! the interval, so a build that deletes its outputs is never denied on stale numbers.
+
+use std::{
+    ffi::OsStr,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use rfuse3::{
+    notify::Notify,
+    raw::{prelude::*, reply::DirectoryEntry},
+    Inode, Result,
+};
+use serde::{Deserialize, Serialize};
+
+use super::snapshot;
+
+/// Minimum time between two rescans of the same upper dir.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Quota limits for one upper layer. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct QuotaLimits {
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    #[serde(default)]
+    pub max_inodes: Option<u64>,
+}
+
+impl QuotaLimits {
+    /// Limits from the global `antares_upper_max_bytes` / `antares_upper_max_inodes` config.
+    pub fn from_global_config() -> Self {
+        let non_zero = |v: u64| (v > 0).then_some(v);
+        Self {
+            max_bytes: non_zero(crate::util::config::antares_upper_max_bytes()),
+            max_inodes: non_zero(crate::util::config::antares_upper_max_inodes()),
+        }
+    }
+
+    /// Limits for in-memory upper layers: bytes come from `antares_memory_upper_max_bytes`,
+    /// inodes from `antares_upper_max_inodes`.
+    pub fn memory_default() -> Self {
+        let max_bytes = crate::util::config::antares_memory_upper_max_bytes();
+        Self {
+            max_bytes: (max_bytes > 0).then_some(max_bytes),
+            ..Self::from_global_config()
+        }
+    }
+
+    /// Whether neither bytes nor inodes are limited.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_bytes.is_none() && self.max_inodes.is_none()
+    }
+
+    /// Apply per-field overrides on top of these limits. Overrides can only tighten a
+    /// limit: the smaller of the two wins.
+    pub fn with_overrides(self, overrides: &QuotaLimits) -> Self {
+        let pick = |base: Option<u64>, over: Option<u64>| match (base, over) {
+            (Some(base), Some(over)) => Some(base.min(over)),
+            (base, over) => over.or(base),
+        };
+        Self {
+            max_bytes: pick(self.max_bytes, overrides.max_bytes),
+            max_inodes: pick(self.max_inodes, overrides.max_inodes),
+        }
+    }
+}
+
+/// Current consumption of an upper layer together with its limits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UpperUsage {
+    pub used_bytes: u64,
+    pub used_inodes: u64,
+    pub max_bytes: Option<u64>,
+    pub max_inodes: Option<u64>,
+}
+
+/// Shared usage counters for one upper dir.
+#[derive(Debug)]
+pub struct UpperQuota {
+    upper_dir: PathBuf,
+    limits: QuotaLimits,
+    bytes: AtomicU64,
+    inodes: AtomicU64,
+    last_rescan_ms: AtomicU64,
+    rescan_running: AtomicBool,
+    /// Something was deleted since the last scan, so the counters may be too high.
+    freed: AtomicBool,
+}
+
+impl UpperQuota {
+    /// Create counters for `upper_dir` and seed them with a blocking scan.
+    pub fn new(upper_dir: impl Into<PathBuf>, limits: QuotaLimits) -> io::Result<Arc<Self>> {
+        let quota = Arc::new(Self {
+            upper_dir: upper_dir.into(),
+            limits,
+            bytes: AtomicU64::new(0),
+            inodes: AtomicU64::new(0),
+            last_rescan_ms: AtomicU64::new(0),
+            rescan_running: AtomicBool::new(false),
+            freed: AtomicBool::new(false),
+        });
+        quota.rescan()?;
+        Ok(quota)
+    }
+
+    pub fn upper_dir(&self) -> &Path {
+        &self.upper_dir
+    }
+
+    pub fn limits(&self) -> QuotaLimits {
+        self.limits
+    }
+
+    pub fn usage(&self) -> UpperUsage {
+        UpperUsage {
+            used_bytes: self.bytes.load(Ordering::Relaxed),
+            used_inodes: self.inodes.load(Ordering::Relaxed),
+            max_bytes: self.limits.max_bytes,
+            max_inodes: self.limits.max_inodes,
+        }
+    }
+
+    /// Recount the upper dir from disk. Blocking.
+    pub fn rescan(&self) -> io::Result<UpperUsage> {
+        // Deletions racing with the walk set it again and force the next rescan.
+        self.freed.store(false, Ordering::Relaxed);
+        let usage = if self.upper_dir.exists() {
+            snapshot::tree_usage(&self.upper_dir)?
+        } else {
+            snapshot::TreeUsage::default()
+        };
+        self.bytes.store(usage.bytes, Ordering::Relaxed);
+        self.inodes.store(usage.inodes, Ordering::Relaxed);
+        self.last_rescan_ms.store(epoch_ms(), Ordering::Relaxed);
+        Ok(self.usage())
+    }
+
+    fn rescan_due(&self) -> bool {
+        let last = self.last_rescan_ms.load(Ordering::Relaxed);
+        epoch_ms().saturating_sub(last) >= RESCAN_INTERVAL.as_millis() as u64
+    }
+
+    /// Rescan on a blocking thread if the last scan is older than [`RESCAN_INTERVAL`].
+    async fn rescan_if_due(self: &Arc<Self>) {
+        if !self.rescan_due() || self.rescan_running.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let quota = self.clone();
+        let result = tokio::task::spawn_blocking(move || quota.rescan()).await;
+        self.rescan_running.store(false, Ordering::Release);
+        if let Ok(Err(e)) = result {
+            tracing::warn!(upper = ?self.upper_dir, error = %e, "quota rescan failed");
+        }
+    }
+
+    /// Rescan before rejecting a request: always after a deletion, otherwise only if
+    /// one is due.
+    async fn rescan_before_reject(self: &Arc<Self>) {
+        if !self.freed.load(Ordering::Relaxed) {
+            self.rescan_if_due().await;
+            return;
+        }
+        let quota = self.clone();
+        if let Ok(Err(e)) = tokio::task::spawn_blocking(move || quota.rescan()).await {
+            tracing::warn!(upper = ?self.upper_dir, error = %e, "quota rescan failed");
+        }
+    }
+
+    /// Kick off a background rescan if one is due, without waiting for it.
+    fn spawn_rescan_if_due(self: &Arc<Self>) {
+        if self.rescan_due() {
+            let quota = self.clone();
+            tokio::spawn(async move { quota.rescan_if_due().await });
+        }
+    }
+
+    fn bytes_exceeded(&self, growth: u64) -> bool {
+        match self.limits.max_bytes {
+            Some(max) => self.bytes.load(Ordering::Relaxed).saturating_add(growth) > max,
+            None => false,
+        }
+    }
+
+    fn inodes_exceeded(&self) -> bool {
+        match self.limits.max_inodes {
+            Some(max) => self.inodes.load(Ordering::Relaxed) >= max,
+            None => false,
+        }
+    }
+
+    async fn check_bytes(self: &Arc<Self>, growth: u64) -> Result<()> {
+        if growth == 0 || !self.bytes_exceeded(growth) {
+            return Ok(());
+        }
+        // Counters only ever grow between scans; make sure we are not rejecting on stale data.
+        self.rescan_before_reject().await;
+        if self.bytes_exceeded(growth) {
+            tracing::debug!(upper = ?self.upper_dir, growth, "upper byte quota exceeded");
+            return Err(libc::ENOSPC.into());
+        }
+        Ok(())
+    }
+
+    async fn check_inode(self: &Arc<Self>) -> Result<()> {
+        if !self.inodes_exceeded() {
+            return Ok(());
+        }
+        self.rescan_before_reject().await;
+        if self.inodes_exceeded() {
+            tracing::debug!(upper = ?self.upper_dir, "upper inode quota exceeded");
+            return Err(libc::EDQUOT.into());
+        }
+        Ok(())
+    }
+
+    fn add_bytes(self: &Arc<Self>, n: u64) {
+        if n > 0 {
+            self.bytes.fetch_add(n, Ordering::Relaxed);
+            self.spawn_rescan_if_due();
+        }
+    }
+
+    fn sub_bytes(&self, n: u64) {
+        let _ = self
+            .bytes
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| {
+                Some(v.saturating_sub(n))
+            });
+    }
+
+    fn add_inode(self: &Arc<Self>) {
+        self.inodes.fetch_add(1, Ordering::Relaxed);
+        self.spawn_rescan_if_due();
+    }
+
+    /// Note a deletion; the next request over a limit rescans before it is rejected.
+    fn mark_freed(&self) {
+        self.freed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Filesystem wrapper enforcing an [`UpperQuota`] on everything that can grow the upper layer.
+pub struct QuotaFs<F: Filesystem> {
+    inner: F,
+    quota: Arc<UpperQuota>,
+}
+
+impl<F: Filesystem> QuotaFs<F> {
+    pub fn new(inner: F, quota: Arc<UpperQuota>) -> Self {
+        Self { inner, quota }
+    }
+
+    async fn size_of(&self, req: Request, inode: Inode, fh: Option<u64>) -> u64 {
+        match self.inner.getattr(req, inode, fh, 0).await {
+            Ok(reply) => reply.attr.size,
+            // Unknown size: assume the whole range is new, which errs on the safe side.
+            Err(_) => 0,
+        }
+    }
+}
+
+impl<F: Filesystem + Sync> Filesystem for QuotaFs<F> {
+    async fn init(&self, req: Request) -> Result<ReplyInit> {
+        self.inner.init(req).await
+    }
+
+    async fn destroy(&self, req: Request) {
+        self.inner.destroy(req).await
+    }
+
+    async fn lookup(&self, req: Request, parent: Inode, name: &OsStr) -> Result<ReplyEntry> {
+        self.inner.lookup(req, parent, name).await
+    }
+
+    async fn forget(&self, req: Request, inode: Inode, nlookup: u64) {
+        self.inner.forget(req, inode, nlookup).await
+    }
+
+    async fn getattr(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: Option<u64>,
+        flags: u32,
+    ) -> Result<ReplyAttr> {
+        self.inner.getattr(req, inode, fh, flags).await
+    }
+
+    async fn setattr(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: Option<u64>,
+        set_attr: SetAttr,
+    ) -> Result<ReplyAttr> {
+        let Some(new_size) = set_attr.size else {
+            return self.inner.setattr(req, inode, fh, set_attr).await;
+        };
+        let old_size = self.size_of(req, inode, fh).await;
+        self.quota
+            .check_bytes(new_size.saturating_sub(old_size))
+            .await?;
+        let reply = self.inner.setattr(req, inode, fh, set_attr).await?;
+        if new_size >= old_size {
+            self.quota.add_bytes(new_size - old_size);
+        } else {
+            self.quota.sub_bytes(old_size - new_size);
+        }
+        Ok(reply)
+    }
+
+    async fn readlink(&self, req: Request, inode: Inode) -> Result<ReplyData> {
+        self.inner.readlink(req, inode).await
+    }
+
+    async fn symlink(
+        &self,
+        req: Request,
+        parent: Inode,
+        name: &OsStr,
+        link: &OsStr,
+    ) -> Result<ReplyEntry> {
+        self.quota.check_inode().await?;
+        let reply = self.inner.symlink(req, parent, name, link).await?;
+        self.quota.add_inode();
+        Ok(reply)
+    }
+
+    async fn mknod(
+        &self,
+        req: Request,
+        parent: Inode,
+        name: &OsStr,
+        mode: u32,
+        rdev: u32,
+    ) -> Result<ReplyEntry> {
+        self.quota.check_inode().await?;
+        let reply = self.inner.mknod(req, parent, name, mode, rdev).await?;
+        self.quota.add_inode();
+        Ok(reply)
+    }
+
+    async fn mkdir(
+        &self,
+        req: Request,
+        parent: Inode,
+        name: &OsStr,
+        mode: u32,
+        umask: u32,
+    ) -> Result<ReplyEntry> {
+        self.quota.check_inode().await?;
+        let reply = self.inner.mkdir(req, parent, name, mode, umask).await?;
+        self.quota.add_inode();
+        Ok(reply)
+    }
+
+    async fn unlink(&self, req: Request, parent: Inode, name: &OsStr) -> Result<()> {
+        self.inner.unlink(req, parent, name).await?;
+        self.quota.mark_freed();
+        Ok(())
+    }
+
+    async fn rmdir(&self, req: Request, parent: Inode, name: &OsStr) -> Result<()> {
+        self.inner.rmdir(req, parent, name).await?;
+        self.quota.mark_freed();
+        Ok(())
+    }
+
+    async fn rename(
+        &self,
+        req: Request,
+        parent: Inode,
+        name: &OsStr,
+        new_parent: Inode,
+        new_name: &OsStr,
+    ) -> Result<()> {
+        // Renaming over an existing entry frees it.
+        self.inner
+            .rename(req, parent, name, new_parent, new_name)
+            .await?;
+        self.quota.mark_freed();
+        Ok(())
+    }
+
+    async fn link(
+        &self,
+        req: Request,
+        inode: Inode,
+        new_parent: Inode,
+        new_name: &OsStr,
+    ) -> Result<ReplyEntry> {
+        // A hard link may copy the target up first; count it like a new node.
+        self.quota.check_inode().await?;
+        let reply = self.inner.link(req, inode, new_parent, new_name).await?;
+        self.quota.add_inode();
+        Ok(reply)
+    }
+
+    async fn open(&self, req: Request, inode: Inode, flags: u32) -> Result<ReplyOpen> {
+        self.inner.open(req, inode, flags).await
+    }
+
+    async fn read(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: u64,
+        offset: u64,
+        size: u32,
+    ) -> Result<ReplyData> {
+        self.inner.read(req, inode, fh, offset, size).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn write(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: u64,
+        offset: u64,
+        data: &[u8],
+        write_flags: u32,
+        flags: u32,
+    ) -> Result<ReplyWrite> {
+        let old_size = self.size_of(req, inode, Some(fh)).await;
+        let end = offset.saturating_add(data.len() as u64);
+        self.quota.check_bytes(end.saturating_sub(old_size)).await?;
+        let reply = self
+            .inner
+            .write(req, inode, fh, offset, data, write_flags, flags)
+            .await?;
+        let written_end = offset.saturating_add(reply.written as u64);
+        self.quota.add_bytes(written_end.saturating_sub(old_size));
+        Ok(reply)
+    }
+
+    async fn statfs(&self, req: Request, inode: Inode) -> Result<ReplyStatFs> {
+        let mut reply = self.inner.statfs(req, inode).await?;
+        let usage = self.quota.usage();
+        // Report the quota as the filesystem size so `df` inside the mount is meaningful.
+        if let Some(max) = usage.max_bytes {
+            let bsize = u64::from(reply.bsize.max(1));
+            let free = max.saturating_sub(usage.used_bytes) / bsize;
+            reply.blocks = reply.blocks.min(max / bsize);
+            reply.bfree = reply.bfree.min(free);
+            reply.bavail = reply.bavail.min(free);
+        }
+        if let Some(max) = usage.max_inodes {
+            let free = max.saturating_sub(usage.used_inodes);
+            reply.files = reply.files.min(max);
+            reply.ffree = reply.ffree.min(free);
+        }
+        Ok(reply)
+    }
+
+    async fn release(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: u64,
+        flags: u32,
+        lock_owner: u64,
+        flush: bool,
+    ) -> Result<()> {
+        self.inner
+            .release(req, inode, fh, flags, lock_owner, flush)
+            .await
+    }
+
+    async fn fsync(&self, req: Request, inode: Inode, fh: u64, datasync: bool) -> Result<()> {
+        self.inner.fsync(req, inode, fh, datasync).await
+    }
+
+    async fn setxattr(
+        &self,
+        req: Request,
+        inode: Inode,
+        name: &OsStr,
+        value: &[u8],
+        flags: u32,
+        position: u32,
+    ) -> Result<()> {
+        self.inner
+            .setxattr(req, inode, name, value, flags, position)
+            .await
+    }
+
+    async fn getxattr(
+        &self,
+        req: Request,
+        inode: Inode,
+        name: &OsStr,
+        size: u32,
+    ) -> Result<ReplyXAttr> {
+        self.inner.getxattr(req, inode, name, size).await
+    }
+
+    async fn listxattr(&self, req: Request, inode: Inode, size: u32) -> Result<ReplyXAttr> {
+        self.inner.listxattr(req, inode, size).await
+    }
+
+    async fn removexattr(&self, req: Request, inode: Inode, name: &OsStr) -> Result<()> {
+        self.inner.removexattr(req, inode, name).await
+    }
+
+    async fn flush(&self, req: Request, inode: Inode, fh: u64, lock_owner: u64) -> Result<()> {
+        self.inner.flush(req, inode, fh, lock_owner).await
+    }
+
+    async fn opendir(&self, req: Request, inode: Inode, flags: u32) -> Result<ReplyOpen> {
+        self.inner.opendir(req, inode, flags).await
+    }
+
+    async fn readdir<'a>(
+        &'a self,
+        req: Request,
+        parent: Inode,
+        fh: u64,
+        offset: i64,
+    ) -> Result<ReplyDirectory<impl futures::Stream<Item = Result<DirectoryEntry>> + Send + 'a>>
+    {
+        self.inner.readdir(req, parent, fh, offset).await
+    }
+
+    async fn readdirplus<'a>(
+        &'a self,
+        req: Request,
+        parent: Inode,
+        fh: u64,
+        offset: u64,
+        lock_owner: u64,
+    ) -> Result<
+        ReplyDirectoryPlus<impl futures::Stream<Item = Result<DirectoryEntryPlus>> + Send + 'a>,
+    > {
+        self.inner
+            .readdirplus(req, parent, fh, offset, lock_owner)
+            .await
+    }
+
+    async fn releasedir(&self, req: Request, inode: Inode, fh: u64, flags: u32) -> Result<()> {
+        self.inner.releasedir(req, inode, fh, flags).await
+    }
+
+    async fn fsyncdir(&self, req: Request, inode: Inode, fh: u64, datasync: bool) -> Result<()> {
+        self.inner.fsyncdir(req, inode, fh, datasync).await
+    }
+
+    async fn access(&self, req: Request, inode: Inode, mask: u32) -> Result<()> {
+        self.inner.access(req, inode, mask).await
+    }
+
+    async fn create(
+        &self,
+        req: Request,
+        parent: Inode,
+        name: &OsStr,
+        mode: u32,
+        flags: u32,
+    ) -> Result<ReplyCreated> {
+        self.quota.check_inode().await?;
+        let reply = self.inner.create(req, parent, name, mode, flags).await?;
+        self.quota.add_inode();
+        Ok(reply)
+    }
+
+    async fn bmap(
+        &self,
+        req: Request,
+        inode: Inode,
+        blocksize: u32,
+        idx: u64,
+    ) -> Result<ReplyBmap> {
+        self.inner.bmap(req, inode, blocksize, idx).await
+    }
+
+    async fn batch_forget(&self, req: Request, inodes: &[(Inode, u64)]) {
+        self.inner.batch_forget(req, inodes).await
+    }
+
+    async fn fallocate(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: u64,
+        offset: u64,
+        length: u64,
+        mode: u32,
+    ) -> Result<()> {
+        let growth = if mode as i32 & libc::FALLOC_FL_PUNCH_HOLE != 0 {
+            0
+        } else {
+            let old_size = self.size_of(req, inode, Some(fh)).await;
+            offset.saturating_add(length).saturating_sub(old_size)
+        };
+        self.quota.check_bytes(growth).await?;
+        self.inner
+            .fallocate(req, inode, fh, offset, length, mode)
+            .await?;
+        self.quota.add_bytes(growth);
+        Ok(())
+    }
+
+    async fn rename2(
+        &self,
+        req: Request,
+        parent: Inode,
+        name: &OsStr,
+        new_parent: Inode,
+        new_name: &OsStr,
+        flags: u32,
+    ) -> Result<()> {
+        self.inner
+            .rename2(req, parent, name, new_parent, new_name, flags)
+            .await?;
+        self.quota.mark_freed();
+        Ok(())
+    }
+
+    async fn lseek(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: u64,
+        offset: u64,
+        whence: u32,
+    ) -> Result<ReplyLSeek> {
+        self.inner.lseek(req, inode, fh, offset, whence).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn copy_file_range(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh_in: u64,
+        off_in: u64,
+        inode_out: Inode,
+        fh_out: u64,
+        off_out: u64,
+        length: u64,
+        flags: u64,
+    ) -> Result<ReplyCopyFileRange> {
+        let old_size = self.size_of(req, inode_out, Some(fh_out)).await;
+        let end = off_out.saturating_add(length);
+        self.quota.check_bytes(end.saturating_sub(old_size)).await?;
+        let reply = self
+            .inner
+            .copy_file_range(
+                req, inode, fh_in, off_in, inode_out, fh_out, off_out, length, flags,
+            )
+            .await?;
+        let copied_end = off_out.saturating_add(reply.copied);
+        self.quota.add_bytes(copied_end.saturating_sub(old_size));
+        Ok(reply)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn getlk(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: u64,
+        lock_owner: u64,
+        start: u64,
+        end: u64,
+        r#type: u32,
+        pid: u32,
+    ) -> Result<ReplyLock> {
+        self.inner
+            .getlk(req, inode, fh, lock_owner, start, end, r#type, pid)
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn setlk(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: u64,
+        lock_owner: u64,
+        start: u64,
+        end: u64,
+        r#type: u32,
+        pid: u32,
+        block: bool,
+    ) -> Result<()> {
+        self.inner
+            .setlk(req, inode, fh, lock_owner, start, end, r#type, pid, block)
+            .await
+    }
+
+    async fn notify_reply(
+        &self,
+        req: Request,
+        inode: Inode,
+        offset: u64,
+        data: Bytes,
+    ) -> Result<()> {
+        self.inner.notify_reply(req, inode, offset, data).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn poll(
+        &self,
+        req: Request,
+        inode: Inode,
+        fh: u64,
+        kh: Option<u64>,
+        flags: u32,
+        events: u32,
+        notify: &Notify,
+    ) -> Result<ReplyPoll> {
+        self.inner
+            .poll(req, inode, fh, kh, flags, events, notify)
+            .await
+    }
+}
+
+fn epoch_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_limits_overrides() {
+        let base = QuotaLimits {
+            max_bytes: Some(100),
+            max_inodes: None,
+        };
+        let merged = base.with_overrides(&QuotaLimits {
+            max_bytes: Some(50),
+            max_inodes: Some(10),
+        });
+        assert_eq!(merged.max_bytes, Some(50));
+        assert_eq!(merged.max_inodes, Some(10));
+        assert_eq!(base.with_overrides(&QuotaLimits::default()), base);
+
+        // Overrides never raise or lift the configured limit.
+        let raised = base.with_overrides(&QuotaLimits {
+            max_bytes: Some(1000),
+            max_inodes: None,
+        });
+        assert_eq!(raised.max_bytes, Some(100));
+    }
+
+    #[tokio::test]
+    async fn test_quota_seeds_from_upper_and_enforces() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a"), vec![0u8; 60]).unwrap();
+        let quota = UpperQuota::new(
+            dir.path(),
+            QuotaLimits {
+                max_bytes: Some(100),
+                max_inodes: Some(2),
+            },
+        )
+        .unwrap();
+        assert_eq!(quota.usage().used_bytes, 60);
+        assert_eq!(quota.usage().used_inodes, 1);
+
+        assert!(quota.check_bytes(40).await.is_ok());
+        let err = quota.check_bytes(41).await.unwrap_err();
+        assert_eq!(err, rfuse3::Errno::from(libc::ENOSPC));
+
+        assert!(quota.check_inode().await.is_ok());
+        quota.add_inode();
+        let err = quota.check_inode().await.unwrap_err();
+        assert_eq!(err, rfuse3::Errno::from(libc::EDQUOT));
+    }
+
+    #[tokio::test]
+    async fn test_quota_rescan_credits_deletions_before_rejecting() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("big"), vec![0u8; 90]).unwrap();
+        let quota = UpperQuota::new(
+            dir.path(),
+            QuotaLimits {
+                max_bytes: Some(100),
+                max_inodes: None,
+            },
+        )
+        .unwrap();
+
+        fs::remove_file(dir.path().join("big")).unwrap();
+        // Pretend the last scan is old so the rejection path is allowed to rescan.
+        quota.last_rescan_ms.store(0, Ordering::Relaxed);
+        assert!(quota.check_bytes(50).await.is_ok());
+        assert_eq!(quota.usage().used_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_quota_deletion_forces_rescan_within_interval() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("big"), vec![0u8; 90]).unwrap();
+        let quota = UpperQuota::new(
+            dir.path(),
+            QuotaLimits {
+                max_bytes: Some(100),
+                max_inodes: Some(1),
+            },
+        )
+        .unwrap();
+
+        // The seed scan just ran, so only the recorded deletion allows a rescan.
+        fs::remove_file(dir.path().join("big")).unwrap();
+        quota.mark_freed();
+        assert!(quota.check_bytes(50).await.is_ok());
+        assert!(quota.check_inode().await.is_ok());
+        assert_eq!(quota.usage().used_bytes, 0);
+        assert_eq!(quota.usage().used_inodes, 0);
+    }
+}
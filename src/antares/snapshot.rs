@@ -0,0 +1,596 @@
+//! Directory copy helpers for Antares layers.
+//!
+//! Forking a mount needs a private copy of its upper (and CL) directory. Copies try the
+//! cheapest primitive the filesystem offers:
+//!
+//! 1. `FICLONE` reflink (btrfs, xfs, bcachefs): shares extents, O(metadata).
+//! 2. `copy_file_range(2)`: in-kernel copy, may still be offloaded by the filesystem.
+//! 3. Plain read/write copy for everything else.
+//!
+//! Overlay bookkeeping is preserved: whiteouts (0/0 character devices), symlinks,
+//! permission bits and extended attributes (e.g. opaque-dir markers) are copied as-is.
+
+use std::{
+    ffi::CString,
+    fs::{self, File},
+    io,
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{FileTypeExt, MetadataExt, PermissionsExt},
+        io::AsRawFd,
+    },
+    path::{Path, PathBuf},
+};
+
+/// `FICLONE` from `<linux/fs.h>`; not exported by every libc target.
+const FICLONE: libc::c_ulong = 0x4004_9409;
+
+/// Largest chunk handed to a single `copy_file_range` call.
+const COPY_CHUNK: usize = 64 * 1024 * 1024;
+
+/// How a regular file ended up being copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyMethod {
+    Reflink,
+    CopyFileRange,
+    ReadWrite,
+}
+
+/// Counters collected while cloning a directory tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CloneStats {
+    pub files: u64,
+    pub dirs: u64,
+    pub symlinks: u64,
+    /// Whiteouts, fifos and other special nodes.
+    pub special: u64,
+    pub bytes: u64,
+    /// Files that were shared via reflink instead of copied.
+    pub reflinked: u64,
+}
+
+impl CloneStats {
+    /// Fold another run's counters into this one.
+    pub fn merge(&mut self, other: &CloneStats) {
+        self.files += other.files;
+        self.dirs += other.dirs;
+        self.symlinks += other.symlinks;
+        self.special += other.special;
+        self.bytes += other.bytes;
+        self.reflinked += other.reflinked;
+    }
+}
+
+/// Recursively copy `src` into `dst`.
+///
+/// `dst` is created if missing and must be empty otherwise, so a half-finished clone is
+/// never mixed with unrelated content. Sockets are skipped; every other node type is
+/// reproduced. `src` may be written while it is copied: entries removed mid-walk are
+/// skipped, so the copy is best-effort rather than a point-in-time snapshot. This is
+/// blocking I/O: call it from `spawn_blocking` in async contexts.
+pub fn clone_tree(src: &Path, dst: &Path) -> io::Result<CloneStats> {
+    let src_meta = fs::symlink_metadata(src)?;
+    if !src_meta.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("clone source {} is not a directory", src.display()),
+        ));
+    }
+    if dst.exists() && fs::read_dir(dst)?.next().is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("clone destination {} is not empty", dst.display()),
+        ));
+    }
+    fs::create_dir_all(dst)?;
+
+    let mut stats = CloneStats::default();
+    let mut dirs = Vec::new();
+    let mut stack: Vec<(PathBuf, PathBuf)> = vec![(src.to_path_buf(), dst.to_path_buf())];
+    while let Some((from_dir, to_dir)) = stack.pop() {
+        let Some(entries) = vanished(fs::read_dir(&from_dir))? else {
+            continue;
+        };
+        for entry in entries {
+            let entry = entry?;
+            let from = entry.path();
+            let to = to_dir.join(entry.file_name());
+            let Some(meta) = vanished(fs::symlink_metadata(&from))? else {
+                continue;
+            };
+            let file_type = meta.file_type();
+
+            if file_type.is_dir() {
+                fs::create_dir(&to)?;
+                stack.push((from.clone(), to.clone()));
+                dirs.push((from, to, meta.mode()));
+                stats.dirs += 1;
+                continue;
+            } else if file_type.is_symlink() {
+                let Some(target) = vanished(fs::read_link(&from))? else {
+                    continue;
+                };
+                std::os::unix::fs::symlink(&target, &to)?;
+                stats.symlinks += 1;
+                copy_xattrs(&from, &to);
+                continue;
+            } else if file_type.is_file() {
+                let Some(method) = vanished(copy_file(&from, &to))? else {
+                    continue;
+                };
+                if method == CopyMethod::Reflink {
+                    stats.reflinked += 1;
+                }
+                stats.files += 1;
+                stats.bytes += meta.len();
+            } else if file_type.is_char_device() || file_type.is_block_device() {
+                mknod(&to, meta.mode(), meta.rdev())?;
+                stats.special += 1;
+            } else if file_type.is_fifo() {
+                mknod(&to, meta.mode(), 0)?;
+                stats.special += 1;
+            } else {
+                tracing::debug!(path = ?from, "clone_tree: skipping socket");
+                continue;
+            }
+
+            fs::set_permissions(&to, fs::Permissions::from_mode(meta.mode() & 0o7777))?;
+            copy_xattrs(&from, &to);
+        }
+    }
+
+    finish_dirs(&dirs)?;
+    // Mirror the root's own mode and xattrs last so a read-only root does not block the copy.
+    fs::set_permissions(dst, fs::Permissions::from_mode(src_meta.mode() & 0o7777))?;
+    copy_xattrs(src, dst);
+    Ok(stats)
+}
+
+/// `None` if the source node was removed while it was being copied.
+fn vanished<T>(result: io::Result<T>) -> io::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Give copied directories the xattrs and mode of their source, children before parents,
+/// so a read-only directory is only sealed once everything below it is in place.
+fn finish_dirs(dirs: &[(PathBuf, PathBuf, u32)]) -> io::Result<()> {
+    for (from, to, mode) in dirs.iter().rev() {
+        copy_xattrs(from, to);
+        fs::set_permissions(to, fs::Permissions::from_mode(mode & 0o7777))?;
+    }
+    Ok(())
+}
+
+/// Recreate the directory structure of `src` at `dst` and hardlink every other node.
+///
+/// Only suitable for trees that are never written in place (e.g. CL layers, whose files
+/// are replaced by unlink + create), since both trees share inodes. `dst` must not exist
+/// yet and must be on the same filesystem as `src`.
+pub fn link_tree(src: &Path, dst: &Path) -> io::Result<CloneStats> {
+    let src_meta = fs::symlink_metadata(src)?;
+    fs::create_dir(dst)?;
+
+    let mut stats = CloneStats::default();
+    let mut dirs = Vec::new();
+    let mut stack: Vec<(PathBuf, PathBuf)> = vec![(src.to_path_buf(), dst.to_path_buf())];
+    while let Some((from_dir, to_dir)) = stack.pop() {
+        for entry in fs::read_dir(&from_dir)? {
+            let entry = entry?;
+            let from = entry.path();
+            let to = to_dir.join(entry.file_name());
+            let meta = fs::symlink_metadata(&from)?;
+            let file_type = meta.file_type();
+
+            if file_type.is_dir() {
+                fs::create_dir(&to)?;
+                stack.push((from.clone(), to.clone()));
+                dirs.push((from, to, meta.mode()));
+                stats.dirs += 1;
+                continue;
+            }
+            // `link(2)` does not follow symlinks, so they are shared like any other node.
+            fs::hard_link(&from, &to)?;
+            if file_type.is_symlink() {
+                stats.symlinks += 1;
+            } else if file_type.is_file() {
+                stats.files += 1;
+                stats.bytes += meta.len();
+            } else {
+                stats.special += 1;
+            }
+        }
+    }
+
+    finish_dirs(&dirs)?;
+    fs::set_permissions(dst, fs::Permissions::from_mode(src_meta.mode() & 0o7777))?;
+    copy_xattrs(src, dst);
+    Ok(stats)
+}
+
+/// Apparent size and node count of a directory tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeUsage {
+    pub bytes: u64,
+    /// Number of nodes below the root (files, dirs, symlinks, whiteouts...).
+    pub inodes: u64,
+}
+
+/// Walk `root` and sum the apparent size of regular files. Symlinks are not followed.
+pub fn tree_usage(root: &Path) -> io::Result<TreeUsage> {
+    let mut usage = TreeUsage::default();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let meta = fs::symlink_metadata(entry.path())?;
+            usage.inodes += 1;
+            if meta.is_dir() {
+                stack.push(entry.path());
+            } else if meta.is_file() {
+                usage.bytes += meta.len();
+            }
+        }
+    }
+    Ok(usage)
+}
+
+/// Copy one regular file, preferring reflink and `copy_file_range` over a byte copy.
+pub fn copy_file(src: &Path, dst: &Path) -> io::Result<CopyMethod> {
+    let input = File::open(src)?;
+    let output = File::create(dst)?;
+
+    // SAFETY: both descriptors are open for the duration of the call.
+    let res = unsafe { libc::ioctl(output.as_raw_fd(), FICLONE as _, input.as_raw_fd()) };
+    if res == 0 {
+        return Ok(CopyMethod::Reflink);
+    }
+
+    let len = input.metadata()?.len();
+    let mut copied: u64 = 0;
+    while copied < len {
+        let chunk = ((len - copied) as usize).min(COPY_CHUNK);
+        // SAFETY: null offsets make the kernel use and advance both file positions.
+        let n = unsafe {
+            libc::copy_file_range(
+                input.as_raw_fd(),
+                std::ptr::null_mut(),
+                output.as_raw_fd(),
+                std::ptr::null_mut(),
+                chunk,
+                0,
+            )
+        };
+        if n < 0 {
+            let err = io::Error::last_os_error();
+            let unsupported = matches!(
+                err.raw_os_error(),
+                Some(libc::EXDEV)
+                    | Some(libc::ENOSYS)
+                    | Some(libc::EOPNOTSUPP)
+                    | Some(libc::EINVAL)
+                    | Some(libc::EPERM)
+            );
+            if copied == 0 && unsupported {
+                return read_write_copy(input, output);
+            }
+            return Err(err);
+        }
+        if n == 0 {
+            // Source shrank underneath us; keep what we have.
+            break;
+        }
+        copied += n as u64;
+    }
+    Ok(CopyMethod::CopyFileRange)
+}
+
+fn read_write_copy(mut input: File, mut output: File) -> io::Result<CopyMethod> {
+    io::copy(&mut input, &mut output)?;
+    Ok(CopyMethod::ReadWrite)
+}
+
+pub(crate) fn mknod(path: &Path, mode: u32, rdev: u64) -> io::Result<()> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    // SAFETY: c_path is a valid NUL-terminated path.
+    let res = unsafe { libc::mknod(c_path.as_ptr(), mode as libc::mode_t, rdev as libc::dev_t) };
+    if res != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Best-effort copy of all extended attributes from `src` to `dst` (without following symlinks).
+///
+/// Failures are logged and ignored: `trusted.*` needs CAP_SYS_ADMIN and some filesystems do
+/// not support user xattrs at all.
+fn copy_xattrs(src: &Path, dst: &Path) {
+    write_xattrs(dst, &read_xattrs(src));
+}
+
+/// Read every extended attribute of `path` (without following symlinks).
+///
+/// Attributes that cannot be read are skipped.
+pub(crate) fn read_xattrs(path: &Path) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return Vec::new();
+    };
+
+    // SAFETY: a null buffer with size 0 asks for the required list length.
+    let list_len = unsafe { libc::llistxattr(c_path.as_ptr(), std::ptr::null_mut(), 0) };
+    if list_len <= 0 {
+        return Vec::new();
+    }
+    let mut names = vec![0u8; list_len as usize];
+    // SAFETY: `names` has room for `list_len` bytes.
+    let list_len = unsafe {
+        libc::llistxattr(
+            c_path.as_ptr(),
+            names.as_mut_ptr() as *mut libc::c_char,
+            names.len(),
+        )
+    };
+    if list_len <= 0 {
+        return Vec::new();
+    }
+    names.truncate(list_len as usize);
+
+    let mut out = Vec::new();
+    for name in names.split(|b| *b == 0).filter(|n| !n.is_empty()) {
+        let Ok(c_name) = CString::new(name) else {
+            continue;
+        };
+        // SAFETY: size query with a null buffer.
+        let value_len =
+            unsafe { libc::lgetxattr(c_path.as_ptr(), c_name.as_ptr(), std::ptr::null_mut(), 0) };
+        if value_len < 0 {
+            continue;
+        }
+        let mut value = vec![0u8; value_len as usize];
+        // SAFETY: `value` has room for `value_len` bytes.
+        let value_len = unsafe {
+            libc::lgetxattr(
+                c_path.as_ptr(),
+                c_name.as_ptr(),
+                value.as_mut_ptr() as *mut libc::c_void,
+                value.len(),
+            )
+        };
+        if value_len < 0 {
+            continue;
+        }
+        value.truncate(value_len as usize);
+        out.push((name.to_vec(), value));
+    }
+    out
+}
+
+/// Best-effort write of extended attributes onto `path` (without following symlinks).
+pub(crate) fn write_xattrs(path: &Path, xattrs: &[(Vec<u8>, Vec<u8>)]) {
+    if xattrs.is_empty() {
+        return;
+    }
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return;
+    };
+    for (name, value) in xattrs {
+        let Ok(c_name) = CString::new(name.as_slice()) else {
+            continue;
+        };
+        // SAFETY: pointers and lengths describe live buffers.
+        let res = unsafe {
+            libc::lsetxattr(
+                c_path.as_ptr(),
+                c_name.as_ptr(),
+                value.as_ptr() as *const libc::c_void,
+                value.len(),
+                0,
+            )
+        };
+        if res != 0 {
+            tracing::debug!(
+                path = ?path,
+                xattr = %String::from_utf8_lossy(name),
+                error = %io::Error::last_os_error(),
+                "failed to write xattr"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_clone_tree_copies_files_dirs_and_symlinks() {
+        let root = tempdir().unwrap();
+        let src = root.path().join("src");
+        let dst = root.path().join("dst");
+        fs::create_dir_all(src.join("a/b")).unwrap();
+        fs::write(src.join("top.txt"), b"top").unwrap();
+        fs::write(src.join("a/b/deep.txt"), b"deep").unwrap();
+        fs::set_permissions(src.join("top.txt"), fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("a/b/deep.txt", src.join("link")).unwrap();
+
+        let stats = clone_tree(&src, &dst).unwrap();
+
+        assert_eq!(stats.files, 2);
+        assert_eq!(stats.dirs, 2);
+        assert_eq!(stats.symlinks, 1);
+        assert_eq!(stats.bytes, 7);
+        assert_eq!(fs::read(dst.join("top.txt")).unwrap(), b"top");
+        assert_eq!(fs::read(dst.join("a/b/deep.txt")).unwrap(), b"deep");
+        assert_eq!(
+            fs::read_link(dst.join("link")).unwrap(),
+            PathBuf::from("a/b/deep.txt")
+        );
+        let mode = fs::metadata(dst.join("top.txt"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn test_clone_tree_and_link_tree_keep_nested_read_only_dirs() {
+        let root = tempdir().unwrap();
+        let src = root.path().join("src");
+        fs::create_dir_all(src.join("ro/inner")).unwrap();
+        fs::write(src.join("ro/inner/f"), b"f").unwrap();
+        fs::write(src.join("ro/g"), b"g").unwrap();
+        let set_mode = |path: &Path, mode: u32| {
+            fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap()
+        };
+        set_mode(&src.join("ro/inner"), 0o500);
+        set_mode(&src.join("ro"), 0o555);
+
+        let cloned = root.path().join("cloned");
+        let linked = root.path().join("linked");
+        clone_tree(&src, &cloned).unwrap();
+        link_tree(&src, &linked).unwrap();
+
+        for dst in [&cloned, &linked] {
+            assert_eq!(fs::read(dst.join("ro/inner/f")).unwrap(), b"f");
+            assert_eq!(fs::read(dst.join("ro/g")).unwrap(), b"g");
+            let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&dst.join("ro")), 0o555);
+            assert_eq!(mode(&dst.join("ro/inner")), 0o500);
+        }
+
+        // Let the temp dir be removed when not running as root.
+        for base in [&src, &cloned, &linked] {
+            set_mode(&base.join("ro"), 0o755);
+            set_mode(&base.join("ro/inner"), 0o755);
+        }
+    }
+
+    #[test]
+    fn test_clone_tree_is_independent_of_source() {
+        let root = tempdir().unwrap();
+        let src = root.path().join("src");
+        let dst = root.path().join("dst");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("f"), b"before").unwrap();
+
+        clone_tree(&src, &dst).unwrap();
+        fs::write(src.join("f"), b"after").unwrap();
+        fs::write(dst.join("g"), b"new").unwrap();
+
+        assert_eq!(fs::read(dst.join("f")).unwrap(), b"before");
+        assert!(!src.join("g").exists());
+    }
+
+    #[test]
+    fn test_tree_usage_counts_bytes_and_nodes() {
+        let root = tempdir().unwrap();
+        fs::create_dir_all(root.path().join("d")).unwrap();
+        fs::write(root.path().join("d/f"), b"abc").unwrap();
+        fs::write(root.path().join("g"), b"de").unwrap();
+        std::os::unix::fs::symlink("g", root.path().join("l")).unwrap();
+
+        let usage = tree_usage(root.path()).unwrap();
+        assert_eq!(usage.bytes, 5);
+        assert_eq!(usage.inodes, 4);
+    }
+
+    #[test]
+    fn test_link_tree_shares_inodes() {
+        let tmp = tempdir().unwrap();
+        let src = tmp.path().join("src");
+        fs::create_dir_all(src.join("a/b")).unwrap();
+        fs::write(src.join("a/b/file"), b"hello").unwrap();
+        std::os::unix::fs::symlink("a/b/file", src.join("link")).unwrap();
+
+        let dst = tmp.path().join("dst");
+        let stats = link_tree(&src, &dst).unwrap();
+        assert_eq!((stats.files, stats.dirs, stats.symlinks), (1, 2, 1));
+        assert_eq!(
+            fs::metadata(dst.join("a/b/file")).unwrap().ino(),
+            fs::metadata(src.join("a/b/file")).unwrap().ino()
+        );
+        assert_eq!(
+            fs::read_link(dst.join("link")).unwrap(),
+            Path::new("a/b/file")
+        );
+
+        // Replacing a file in one tree leaves the other alone.
+        fs::remove_file(dst.join("a/b/file")).unwrap();
+        fs::write(dst.join("a/b/file"), b"bye").unwrap();
+        assert_eq!(fs::read(src.join("a/b/file")).unwrap(), b"hello");
+        assert!(link_tree(&src, &dst).is_err());
+    }
+
+    #[test]
+    fn test_clone_tree_rejects_non_empty_destination() {
+        let root = tempdir().unwrap();
+        let src = root.path().join("src");
+        let dst = root.path().join("dst");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(dst.join("stale"), b"x").unwrap();
+
+        let err = clone_tree(&src, &dst).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_clone_tree_preserves_whiteouts() {
+        if unsafe { libc::geteuid() } != 0 {
+            println!("Skipping: creating whiteouts requires CAP_MKNOD");
+            return;
+        }
+        let root = tempdir().unwrap();
+        let src = root.path().join("src");
+        let dst = root.path().join("dst");
+        fs::create_dir_all(&src).unwrap();
+        mknod(&src.join("gone"), libc::S_IFCHR, 0).unwrap();
+
+        let stats = clone_tree(&src, &dst).unwrap();
+
+        assert_eq!(stats.special, 1);
+        let meta = fs::symlink_metadata(dst.join("gone")).unwrap();
+        assert!(meta.file_type().is_char_device());
+        assert_eq!(meta.rdev(), 0);
+    }
+
+    #[test]
+    fn test_clone_tree_skips_entries_removed_mid_walk() {
+        let src = tempdir().unwrap();
+        for i in 0..50 {
+            let dir = src.path().join(format!("d{i}"));
+            fs::create_dir(&dir).unwrap();
+            fs::write(dir.join("f"), b"x").unwrap();
+        }
+        fs::write(src.path().join("kept"), b"kept").unwrap();
+
+        let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let churn = {
+            let (root, stop) = (src.path().to_path_buf(), stop.clone());
+            std::thread::spawn(move || {
+                while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+                    for i in 0..50 {
+                        let dir = root.join(format!("d{i}"));
+                        let _ = fs::remove_dir_all(&dir);
+                        let _ = fs::create_dir(&dir).and_then(|_| fs::write(dir.join("f"), b"x"));
+                    }
+                }
+            })
+        };
+        for _ in 0..20 {
+            let dst = tempdir().unwrap();
+            clone_tree(src.path(), &dst.path().join("copy")).unwrap();
+            assert_eq!(fs::read(dst.path().join("copy/kept")).unwrap(), b"kept");
+        }
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        churn.join().unwrap();
+    }
+}
@@ -0,0 +1,454 @@
+//! Crash-safe persistence of the Antares mount state.
+//!
+//! The state lives in two files next to each other:
+//! - the snapshot, `antares_state_file` (TOML), replaced only by write-to-temp, `fsync`,
+//!   `rename` and an `fsync` of the directory, so a crash leaves either the old or the new
+//!   snapshot behind;
+//! - the journal, `{state_file}.journal`, one JSON record per changed or removed mount,
+//!   appended and `fsync`ed after each change.
+//!
+//! Every snapshot carries a generation, and so does every journal record. Loading replays
+//! the records of the snapshot's generation in order and stops at the first one that does
+//! not decode (a write torn by a crash). Compaction writes a snapshot of the next
+//! generation and then empties the journal; records left behind by a crash in between
+//! belong to the old generation and are ignored.
+//!
+//! A [`StateLock`] on the state directory keeps a second process from opening the same
+//! state.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::daemon::antares::{PersistedMountState, PersistedState};
+
+/// Journal records after which the next write compacts into a new snapshot.
+const COMPACT_AFTER_RECORDS: usize = 256;
+
+/// Name of the lock file in the state directory.
+const LOCK_FILE: &str = "antares.lock";
+
+/// Exclusive `flock` on `{state_dir}/antares.lock`, held for the lifetime of the value.
+///
+/// The file records the pid of the holder, to name it when another process is refused.
+#[derive(Debug)]
+pub struct StateLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl StateLock {
+    /// Lock `state_dir`, creating it if needed. Fails with `WouldBlock` when another
+    /// process, or another open of the same directory in this one, holds the lock.
+    pub fn acquire(state_dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(state_dir)?;
+        let path = state_dir.join(LOCK_FILE);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::WouldBlock {
+                return Err(err);
+            }
+            let holder = fs::read_to_string(&path).unwrap_or_default();
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                format!(
+                    "state directory {} is in use by another Antares instance (pid {})",
+                    state_dir.display(),
+                    holder.split_whitespace().next().unwrap_or("unknown")
+                ),
+            ));
+        }
+        file.set_len(0)?;
+        writeln!(file, "{}", std::process::id())?;
+        Ok(Self { _file: file, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// One journal line.
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalRecord {
+    generation: u64,
+    #[serde(flatten)]
+    op: JournalOp,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalOp {
+    Put { mount: Box<PersistedMountState> },
+    Remove { mount_id: Uuid },
+}
+
+/// Snapshot plus journal of the mount state, owned by one process at a time.
+#[derive(Debug)]
+pub struct StateStore {
+    state_file: PathBuf,
+    journal_file: PathBuf,
+    /// Generation of the snapshot on disk.
+    generation: u64,
+    /// Each mount as last written, serialized, so a write only journals what changed.
+    written: HashMap<Uuid, String>,
+    /// Records in the journal since the last compaction.
+    journal_records: usize,
+    /// The journal may end in a partial record; compact before appending again.
+    needs_compaction: bool,
+    lock: StateLock,
+}
+
+impl StateStore {
+    /// Lock the directory of `state_file` and load the state, folding the journal into a
+    /// new snapshot.
+    ///
+    /// A snapshot that does not parse is moved aside to `{state_file}.corrupt` and the
+    /// state starts empty.
+    pub fn open(state_file: &Path) -> io::Result<Self> {
+        let state_dir = match state_file.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let lock = StateLock::acquire(state_dir)?;
+        let mut store = Self {
+            state_file: state_file.to_path_buf(),
+            journal_file: sibling(state_file, "journal"),
+            generation: 0,
+            written: HashMap::new(),
+            journal_records: 0,
+            needs_compaction: true,
+            lock,
+        };
+        let _ = fs::remove_file(sibling(state_file, "tmp"));
+
+        let (generation, state) = match store.read() {
+            Ok(loaded) => loaded,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                let aside = sibling(state_file, "corrupt");
+                tracing::error!(
+                    "Unreadable state file {:?} ({}); moving it to {:?} and starting empty",
+                    state_file,
+                    e,
+                    aside
+                );
+                fs::rename(state_file, &aside)?;
+                (0, PersistedState::default())
+            }
+            Err(e) => return Err(e),
+        };
+        store.generation = generation;
+        if let Err(e) = store.compact(&state) {
+            // Appending would extend a journal whose tail may be torn; retry on next write.
+            tracing::warn!("Failed to compact state journal: {}", e);
+            store.written = serialize_mounts(&state)?;
+        }
+        Ok(store)
+    }
+
+    /// The state on disk: the snapshot with its journal replayed.
+    pub fn load(&self) -> io::Result<PersistedState> {
+        self.read().map(|(_, state)| state)
+    }
+
+    /// Record `state`, journaling the mounts that changed since the last write, or
+    /// compacting into a new snapshot when the journal has grown long.
+    pub fn write(&mut self, state: &PersistedState) -> io::Result<()> {
+        let current = serialize_mounts(state)?;
+        let mut ops = Vec::new();
+        for mount in &state.mounts {
+            if self.written.get(&mount.mount_id) != current.get(&mount.mount_id) {
+                ops.push(JournalOp::Put {
+                    mount: Box::new(mount.clone()),
+                });
+            }
+        }
+        for mount_id in self.written.keys() {
+            if !current.contains_key(mount_id) {
+                ops.push(JournalOp::Remove {
+                    mount_id: *mount_id,
+                });
+            }
+        }
+        if ops.is_empty() {
+            return Ok(());
+        }
+        if self.needs_compaction || self.journal_records + ops.len() > COMPACT_AFTER_RECORDS {
+            return self.compact(state);
+        }
+
+        let mut lines = String::new();
+        for op in ops {
+            let record = JournalRecord {
+                generation: self.generation,
+                op,
+            };
+            lines.push_str(&serde_json::to_string(&record).map_err(io::Error::other)?);
+            lines.push('\n');
+        }
+        let appended = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.journal_file)
+            .and_then(|mut journal| {
+                journal.write_all(lines.as_bytes())?;
+                journal.sync_data()
+            });
+        if let Err(e) = appended {
+            self.needs_compaction = true;
+            return Err(e);
+        }
+        self.journal_records += lines.lines().count();
+        self.written = current;
+        Ok(())
+    }
+
+    /// Write `state` as the snapshot of the next generation, then empty the journal.
+    pub fn compact(&mut self, state: &PersistedState) -> io::Result<()> {
+        let generation = self.generation + 1;
+        let snapshot = PersistedState {
+            generation,
+            mounts: state.mounts.clone(),
+        };
+        let content = toml::to_string_pretty(&snapshot).map_err(io::Error::other)?;
+        write_atomically(&self.state_file, content.as_bytes())?;
+        self.generation = generation;
+
+        File::create(&self.journal_file)?.sync_all()?;
+        self.journal_records = 0;
+        self.needs_compaction = false;
+        self.written = serialize_mounts(state)?;
+        Ok(())
+    }
+
+    pub fn state_file(&self) -> &Path {
+        &self.state_file
+    }
+
+    pub fn journal_file(&self) -> &Path {
+        &self.journal_file
+    }
+
+    pub fn lock_file(&self) -> &Path {
+        self.lock.path()
+    }
+
+    fn read(&self) -> io::Result<(u64, PersistedState)> {
+        let mut state = match fs::read_to_string(&self.state_file) {
+            Ok(content) => toml::from_str::<PersistedState>(&content)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => PersistedState::default(),
+            Err(e) => return Err(e),
+        };
+        let journal = match File::open(&self.journal_file) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((state.generation, state)),
+            Err(e) => return Err(e),
+        };
+
+        let mut mounts: Vec<PersistedMountState> = std::mem::take(&mut state.mounts);
+        for (index, line) in BufReader::new(journal).lines().enumerate() {
+            let record = line
+                .ok()
+                .and_then(|line| serde_json::from_str::<JournalRecord>(&line).ok());
+            let Some(record) = record else {
+                tracing::warn!(
+                    "Ignoring state journal {:?} from line {} on: incomplete record",
+                    self.journal_file,
+                    index + 1
+                );
+                break;
+            };
+            if record.generation != state.generation {
+                continue;
+            }
+            match record.op {
+                JournalOp::Put { mount } => {
+                    match mounts.iter_mut().find(|m| m.mount_id == mount.mount_id) {
+                        Some(existing) => *existing = *mount,
+                        None => mounts.push(*mount),
+                    }
+                }
+                JournalOp::Remove { mount_id } => mounts.retain(|m| m.mount_id != mount_id),
+            }
+        }
+        state.mounts = mounts;
+        Ok((state.generation, state))
+    }
+}
+
+/// `{path}.{suffix}`, e.g. `state.toml.journal`.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn serialize_mounts(state: &PersistedState) -> io::Result<HashMap<Uuid, String>> {
+    state
+        .mounts
+        .iter()
+        .map(|m| {
+            Ok((
+                m.mount_id,
+                serde_json::to_string(m).map_err(io::Error::other)?,
+            ))
+        })
+        .collect()
+}
+
+/// Replace `path` with `content` so that a crash leaves either the old or the new file.
+pub fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+    let tmp = sibling(path, "tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp, path)?;
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => File::open(dir)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mount(path: &str) -> PersistedMountState {
+        toml::from_str(&format!(
+            "mount_id = \"{}\"\npath = \"{path}\"\nmountpoint = \"/mnt{path}\"\ncreated_at_epoch_ms = 1\n",
+            Uuid::new_v4()
+        ))
+        .unwrap()
+    }
+
+    fn paths(state: &PersistedState) -> Vec<String> {
+        let mut paths: Vec<String> = state.mounts.iter().map(|m| m.path.clone()).collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_journal_replays_changes_after_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("state.toml");
+        let mut store = StateStore::open(&state_file).unwrap();
+
+        let (a, b) = (mount("/a"), mount("/b"));
+        let mut state = PersistedState {
+            mounts: vec![a.clone(), b.clone()],
+            ..Default::default()
+        };
+        store.write(&state).unwrap();
+        state.mounts.retain(|m| m.mount_id != a.mount_id);
+        state.mounts[0].job_id = Some("job-b".to_string());
+        store.write(&state).unwrap();
+
+        // The snapshot still predates both writes; the journal holds them.
+        assert!(fs::read_to_string(&state_file)
+            .unwrap()
+            .contains("generation = 1"));
+        assert_eq!(store.journal_records, 4);
+        let loaded = store.load().unwrap();
+        assert_eq!(paths(&loaded), vec!["/b"]);
+        assert_eq!(loaded.mounts[0].job_id.as_deref(), Some("job-b"));
+
+        drop(store);
+        let reopened = StateStore::open(&state_file).unwrap();
+        assert_eq!(paths(&reopened.load().unwrap()), vec!["/b"]);
+        assert_eq!(fs::metadata(reopened.journal_file()).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_torn_and_stale_journal_records_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("state.toml");
+        let mut store = StateStore::open(&state_file).unwrap();
+        let kept = mount("/kept");
+        store
+            .write(&PersistedState {
+                mounts: vec![kept.clone()],
+                ..Default::default()
+            })
+            .unwrap();
+
+        let stale = JournalRecord {
+            generation: 0,
+            op: JournalOp::Remove {
+                mount_id: kept.mount_id,
+            },
+        };
+        let mut journal = OpenOptions::new()
+            .append(true)
+            .open(store.journal_file())
+            .unwrap();
+        writeln!(journal, "{}", serde_json::to_string(&stale).unwrap()).unwrap();
+        write!(journal, "{{\"generation\":1,\"op\":\"remo").unwrap();
+        let after_torn = JournalRecord {
+            generation: 1,
+            op: JournalOp::Remove {
+                mount_id: kept.mount_id,
+            },
+        };
+        writeln!(journal, "\n{}", serde_json::to_string(&after_torn).unwrap()).unwrap();
+
+        assert_eq!(paths(&store.load().unwrap()), vec!["/kept"]);
+    }
+
+    #[test]
+    fn test_compaction_and_corrupt_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("state.toml");
+        let mut store = StateStore::open(&state_file).unwrap();
+        let mut state = PersistedState::default();
+        for i in 0..=COMPACT_AFTER_RECORDS {
+            state.mounts.push(mount(&format!("/m{i}")));
+            store.write(&state).unwrap();
+        }
+        assert!(store.journal_records < COMPACT_AFTER_RECORDS);
+        assert!(store.generation > 1);
+        assert_eq!(
+            store.load().unwrap().mounts.len(),
+            COMPACT_AFTER_RECORDS + 1
+        );
+        drop(store);
+
+        fs::write(&state_file, "mounts = [").unwrap();
+        let store = StateStore::open(&state_file).unwrap();
+        assert!(store.load().unwrap().mounts.is_empty());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("state.toml.corrupt")).unwrap(),
+            "mounts = ["
+        );
+    }
+
+    #[test]
+    fn test_state_lock_is_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("nested/state.toml");
+        let store = StateStore::open(&state_file).unwrap();
+        assert_eq!(store.lock_file(), dir.path().join("nested/antares.lock"));
+
+        let err = StateStore::open(&state_file).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        assert!(err.to_string().contains(&std::process::id().to_string()));
+
+        drop(store);
+        StateStore::open(&state_file).unwrap();
+    }
+}
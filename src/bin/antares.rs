@@ -1,32 +1,52 @@
-use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use clap::{Parser, Subcommand};
-use reqwest::blocking::Client;
+use clap::{Args, Parser, Subcommand};
+use reqwest::{
+    blocking::{Client, RequestBuilder},
+    Method, StatusCode,
+};
 use scorpiofs::{
-    antares::{AntaresManager, AntaresPaths},
-    daemon::antares::{AntaresDaemon, AntaresServiceImpl},
+    antares::AntaresPaths,
+    daemon::{
+        antares::{
+            AntaresDaemon, AntaresServiceImpl, BuildClRequest, ErrorBody, GcReport, GcRequest,
+            HealthResponse, LocalClRequest, MountCollection, MountCreated, MountLifecycle,
+            MountReadyResponse, MountStatus, SupervisorConfig,
+        },
+        auth::{self, AuthPolicy},
+    },
     util::config,
 };
+use serde::{de::DeserializeOwned, Serialize};
+use uuid::Uuid;
 
-/// Antares build overlay manager (skeleton).
+/// Antares build overlay manager.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     /// Path to the configuration file (scorpio config).
     #[arg(long, default_value = "scorpio.toml")]
     config_path: String,
-    /// Root path to place per-job upper layers (overrides config when set).
+    /// Root path to place per-job upper layers (overrides config for `serve`).
     #[arg(long)]
     upper_root: Option<PathBuf>,
-    /// Root path to place per-job CL layers (overrides config when set).
+    /// Root path to place per-job CL layers (overrides config for `serve`).
     #[arg(long)]
     cl_root: Option<PathBuf>,
-    /// Root path for per-job mountpoints (overrides config when set).
+    /// Root path for per-job mountpoints (overrides config for `serve`).
     #[arg(long)]
     mount_root: Option<PathBuf>,
-    /// Path to persist mount state as TOML (overrides config when set).
+    /// Path to persist mount state as TOML (overrides config for `serve`).
     #[arg(long)]
     state_file: Option<PathBuf>,
+    /// Print responses and errors of the daemon client subcommands as JSON.
+    #[arg(long, global = true)]
+    json: bool,
 
     #[command(subcommand)]
     command: Commands,
@@ -34,26 +54,99 @@ struct Cli {
 
 #[derive(Subcommand, Debug)]
 enum Commands {
-    /// Mount a new Antares job instance.
+    /// Mount the monorepo root for a job through the daemon.
     Mount {
         /// Unique job identifier.
         job_id: String,
-        /// Optional CL layer name; when set, creates a CL passthrough layer placeholder.
+        /// Optional CL identifier applied on top of the base tree.
         #[arg(long)]
         cl: Option<String>,
+        /// Mount without an upper layer; writes fail with EROFS.
+        #[arg(long)]
+        read_only: bool,
+        #[command(flatten)]
+        daemon: DaemonArgs,
     },
-    /// Unmount a job instance.
+    /// Unmount the mount of a job through the daemon.
     Umount {
         /// Job identifier to remove.
         job_id: String,
+        #[command(flatten)]
+        daemon: DaemonArgs,
+    },
+    /// List the daemon's mounts.
+    List {
+        /// Label selector, e.g. `pipeline=1234,!keep`.
+        #[arg(long)]
+        selector: Option<String>,
+        /// Comma-separated lifecycle states, e.g. `Mounted,Ready`.
+        #[arg(long)]
+        state: Option<String>,
+        /// Monorepo path prefix.
+        #[arg(long)]
+        path_prefix: Option<String>,
+        #[command(flatten)]
+        daemon: DaemonArgs,
+    },
+    /// Show one mount.
+    Describe {
+        #[command(flatten)]
+        target: MountTarget,
+        #[command(flatten)]
+        daemon: DaemonArgs,
+    },
+    /// Unmount and remove one mount.
+    Delete {
+        #[command(flatten)]
+        target: MountTarget,
+        #[command(flatten)]
+        daemon: DaemonArgs,
+    },
+    /// Build or clear the CL layers of a mount.
+    Cl {
+        #[command(subcommand)]
+        command: ClCommand,
+    },
+    /// Wait until a mount is ready for heavy I/O.
+    WaitReady {
+        #[command(flatten)]
+        target: MountTarget,
+        /// Give up after this many seconds.
+        #[arg(long, default_value_t = 300)]
+        timeout: u64,
+        /// Delay between readiness probes, in milliseconds.
+        #[arg(long, default_value_t = 500)]
+        interval_ms: u64,
+        #[command(flatten)]
+        daemon: DaemonArgs,
+    },
+    /// Check the daemon's health.
+    Health {
+        #[command(flatten)]
+        daemon: DaemonArgs,
+    },
+    /// Remove directories, stale mounts and Dicfuse stores no mount refers to.
+    Gc {
+        /// Only report what would be removed.
+        #[arg(long)]
+        dry_run: bool,
+        /// Keep orphans modified within this many seconds (default: from config).
+        #[arg(long)]
+        retention_secs: Option<u64>,
+        #[command(flatten)]
+        daemon: DaemonArgs,
     },
-    /// List tracked instances.
-    List,
     /// Start HTTP daemon server.
     Serve {
         /// Address to bind to (e.g., "0.0.0.0:2726")
         #[arg(long, default_value = "0.0.0.0:2726")]
         bind: String,
+        /// Unix socket to listen on as well (default: `antares_socket` from config).
+        #[arg(long)]
+        socket: Option<PathBuf>,
+        /// Listen only on the unix socket.
+        #[arg(long)]
+        no_tcp: bool,
     },
     /// Mount via HTTP daemon (recommended for build systems to ensure unified behavior).
     HttpMount {
@@ -62,155 +155,643 @@ enum Commands {
         job_id: Option<String>,
         /// Monorepo path to mount (e.g., "/third-party/mega")
         path: String,
-        /// Optional CL identifier
+        /// Optional CL identifier; repeat to stack several CLs, later ones on top.
         #[arg(long)]
-        cl: Option<String>,
-        /// Daemon base URL (e.g., "http://127.0.0.1:2726")
-        #[arg(long, default_value = "http://127.0.0.1:2726")]
-        endpoint: String,
+        cl: Vec<String>,
+        /// Mount without an upper layer; writes fail with EROFS.
+        #[arg(long)]
+        read_only: bool,
+        /// Keep the upper layer in memory instead of on disk.
+        #[arg(long, conflicts_with = "read_only")]
+        memory_upper: bool,
+        #[command(flatten)]
+        daemon: DaemonArgs,
     },
 }
 
-#[tokio::main]
-async fn main() {
-    let cli = Cli::parse();
+#[derive(Subcommand, Debug)]
+enum ClCommand {
+    /// Replace the CL stack of a mount.
+    Build {
+        #[command(flatten)]
+        target: MountTarget,
+        /// CL identifier; repeat to stack several CLs, later ones on top.
+        #[arg(long, required_unless_present = "patch")]
+        cl: Vec<String>,
+        /// Build a single layer from a local patch file instead.
+        #[arg(long, conflicts_with = "cl")]
+        patch: Option<PathBuf>,
+        /// Name recorded for a local patch layer.
+        #[arg(long, requires = "patch")]
+        name: Option<String>,
+        #[command(flatten)]
+        daemon: DaemonArgs,
+    },
+    /// Remove all CL layers of a mount.
+    Clear {
+        #[command(flatten)]
+        target: MountTarget,
+        #[command(flatten)]
+        daemon: DaemonArgs,
+    },
+}
 
-    if let Err(e) = config::init_config(&cli.config_path) {
-        eprintln!("Failed to load config: {e}");
-        std::process::exit(1);
+/// The mount a subcommand acts on: by id, or by the job it was created for.
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+struct MountTarget {
+    /// Mount id.
+    mount_id: Option<Uuid>,
+    /// Job (or build) id of the mount.
+    #[arg(long)]
+    job: Option<String>,
+}
+
+impl MountTarget {
+    /// API path of the mount, `/mounts/{mount_id}` or `/mounts/by-job/{job}`.
+    fn path(&self) -> String {
+        match (&self.mount_id, &self.job) {
+            (Some(mount_id), _) => format!("/mounts/{mount_id}"),
+            (None, job) => format!("/mounts/by-job/{}", job.as_deref().unwrap_or_default()),
+        }
     }
+}
 
-    let mut paths = AntaresPaths::from_global_config();
-    if let Some(p) = cli.upper_root {
-        paths.upper_root = p;
+/// How to reach a running Antares daemon.
+#[derive(Args, Debug)]
+struct DaemonArgs {
+    /// Daemon base URL (e.g., "http://127.0.0.1:2726"); talks over the daemon's
+    /// unix socket when omitted.
+    #[arg(long)]
+    endpoint: Option<String>,
+    /// Unix socket of the daemon (default: `antares_socket` from config).
+    #[arg(long, conflicts_with = "endpoint")]
+    socket: Option<PathBuf>,
+    /// Bearer token for daemons that require auth; defaults to $ANTARES_TOKEN.
+    #[arg(long)]
+    token: Option<String>,
+}
+
+/// Exit codes of the daemon client subcommands; usage errors exit with 2.
+mod exit_code {
+    /// The daemon could not be reached or failed to handle the request.
+    pub const FAILURE: i32 = 1;
+    /// No such mount (`NOT_FOUND`).
+    pub const NOT_FOUND: i32 = 3;
+    /// The daemon rejected the request (`INVALID_REQUEST`, `BAD_PAYLOAD`).
+    pub const INVALID: i32 = 4;
+    /// Missing or insufficient credentials (`UNAUTHORIZED`, `FORBIDDEN`).
+    pub const DENIED: i32 = 5;
+    /// `wait-ready` timed out, or `health` reported the daemon degraded.
+    pub const NOT_READY: i32 = 6;
+}
+
+/// A failed client subcommand: the daemon's error body, or one made up locally.
+struct CliError {
+    exit_code: i32,
+    body: ErrorBody,
+}
+
+impl CliError {
+    fn new(exit_code: i32, code: &str, error: impl Into<String>) -> Self {
+        Self {
+            exit_code,
+            body: ErrorBody {
+                error: error.into(),
+                code: code.to_string(),
+            },
+        }
     }
-    if let Some(p) = cli.cl_root {
-        paths.cl_root = p;
+
+    /// Map an error response of the daemon, keeping its `ErrorBody` when there is one.
+    fn from_response(status: StatusCode, text: &str) -> Self {
+        let exit_code = match status {
+            StatusCode::NOT_FOUND => exit_code::NOT_FOUND,
+            StatusCode::BAD_REQUEST | StatusCode::CONFLICT | StatusCode::UNPROCESSABLE_ENTITY => {
+                exit_code::INVALID
+            }
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => exit_code::DENIED,
+            _ => exit_code::FAILURE,
+        };
+        let body = serde_json::from_str(text).unwrap_or_else(|_| ErrorBody {
+            error: format!("{status}: {text}"),
+            code: "HTTP_ERROR".to_string(),
+        });
+        Self { exit_code, body }
     }
-    if let Some(p) = cli.mount_root {
-        paths.mount_root = p;
+
+    /// Report the error (as JSON with `--json`) and exit with its code.
+    fn exit(self, json: bool) -> ! {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&self.body).unwrap());
+        } else {
+            eprintln!("error: {} ({})", self.body.error, self.body.code);
+        }
+        std::process::exit(self.exit_code)
     }
-    if let Some(p) = cli.state_file {
-        paths.state_file = p;
+}
+
+/// Blocking HTTP client for the daemon API.
+struct DaemonClient {
+    client: Client,
+    endpoint: String,
+    token: Option<String>,
+}
+
+impl DaemonClient {
+    fn new(args: DaemonArgs) -> Result<Self, CliError> {
+        let (client, endpoint) = match args.endpoint {
+            Some(endpoint) => (Client::new(), endpoint),
+            None => {
+                let socket = args
+                    .socket
+                    .unwrap_or_else(|| config::antares_socket().into());
+                let client = Client::builder().unix_socket(socket).build().map_err(|e| {
+                    CliError::new(
+                        exit_code::FAILURE,
+                        "CLIENT_ERROR",
+                        format!("failed to build http client: {e}"),
+                    )
+                })?;
+                // The host is ignored over a unix socket.
+                (client, "http://localhost".to_string())
+            }
+        };
+        Ok(Self {
+            client,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            token: args.token.or_else(|| std::env::var("ANTARES_TOKEN").ok()),
+        })
     }
 
-    match cli.command {
-        Commands::Mount { job_id, cl } => {
-            let manager = AntaresManager::new(paths.clone()).await;
-            match manager.mount_job(&job_id, cl.as_deref()).await {
-                Ok(instance) => {
-                    println!(
-                        "mounted job {} at {}",
-                        job_id,
-                        instance.mountpoint.display()
-                    );
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.request_with_query(method, path, &[])
+    }
+
+    fn request_with_query(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> RequestBuilder {
+        let mut url = format!("{}{}", self.endpoint, path);
+        if let Ok(mut parsed) = reqwest::Url::parse(&url) {
+            if !query.is_empty() {
+                parsed.query_pairs_mut().extend_pairs(query);
+                url = parsed.into();
+            }
+        }
+        let request = self.client.request(method, url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Send `request` and decode a successful JSON response.
+    fn call<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, CliError> {
+        let response = request.send().map_err(|e| {
+            CliError::new(
+                exit_code::FAILURE,
+                "CONNECTION_ERROR",
+                format!("request to {} failed: {e}", self.endpoint),
+            )
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().unwrap_or_default();
+            return Err(CliError::from_response(status, &text));
+        }
+        response.json().map_err(|e| {
+            CliError::new(
+                exit_code::FAILURE,
+                "BAD_RESPONSE",
+                format!("failed to parse response json: {e}"),
+            )
+        })
+    }
+
+    /// The id of the mount `target` names, looking jobs up on the daemon.
+    fn resolve(&self, target: &MountTarget) -> Result<Uuid, CliError> {
+        match target.mount_id {
+            Some(mount_id) => Ok(mount_id),
+            None => {
+                let status: MountStatus = self.call(self.request(Method::GET, &target.path()))?;
+                Ok(status.mount_id)
+            }
+        }
+    }
+
+    /// Poll the readiness probe of `mount_id` until it is ready, fails or `timeout` passes.
+    fn wait_ready(
+        &self,
+        mount_id: Uuid,
+        timeout: Duration,
+        interval: Duration,
+    ) -> Result<MountReadyResponse, CliError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let probe: MountReadyResponse =
+                self.call(self.request(Method::GET, &format!("/mounts/{mount_id}/ready")))?;
+            match &probe.state {
+                _ if probe.ready => return Ok(probe),
+                MountLifecycle::Failed { reason } => {
+                    return Err(CliError::new(
+                        exit_code::FAILURE,
+                        "MOUNT_FAILED",
+                        format!("mount {mount_id} failed: {reason}"),
+                    ));
                 }
-                Err(err) => {
-                    eprintln!("failed to mount job {}: {}", job_id, err);
-                    std::process::exit(1);
+                MountLifecycle::Unmounting | MountLifecycle::Unmounted => {
+                    return Err(CliError::new(
+                        exit_code::FAILURE,
+                        "MOUNT_GONE",
+                        format!("mount {mount_id} is {}", probe.state.name()),
+                    ));
                 }
+                _ => {}
+            }
+            if Instant::now() >= deadline {
+                return Err(CliError::new(
+                    exit_code::NOT_READY,
+                    "TIMEOUT",
+                    format!(
+                        "mount {mount_id} still {} after {}s",
+                        probe.state.name(),
+                        timeout.as_secs()
+                    ),
+                ));
             }
+            std::thread::sleep(interval.min(deadline.saturating_duration_since(Instant::now())));
+        }
+    }
+}
+
+/// Print `value` as JSON with `--json`, otherwise in the `human` form.
+fn print_output<T: Serialize>(json: bool, value: &T, human: impl FnOnce(&T)) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(value).unwrap());
+    } else {
+        human(value);
+    }
+}
+
+/// Run a subcommand that talks to the daemon.
+fn run_client(command: Commands, json: bool) -> Result<(), CliError> {
+    match command {
+        Commands::Mount {
+            job_id,
+            cl,
+            read_only,
+            daemon,
+        } => {
+            let daemon = DaemonClient::new(daemon)?;
+            let payload = serde_json::json!({
+                "job_id": job_id,
+                "path": "/",
+                "cl": cl,
+                "read_only": read_only,
+            });
+            let created: MountCreated =
+                daemon.call(daemon.request(Method::POST, "/mounts").json(&payload))?;
+            print_output(json, &created, |created| {
+                println!("mounted job {} at {}", job_id, created.mountpoint)
+            });
+        }
+        Commands::Umount { job_id, daemon } => {
+            let daemon = DaemonClient::new(daemon)?;
+            let request = daemon.request(Method::DELETE, &format!("/mounts/by-job/{job_id}"));
+            let status: MountStatus = daemon.call(request)?;
+            print_output(json, &status, |_| println!("unmounted job {}", job_id));
         }
-        Commands::Umount { job_id } => {
-            let manager = AntaresManager::new(paths.clone()).await;
-            match manager.umount_job(&job_id).await {
-                Ok(Some(_)) => println!("unmounted job {}", job_id),
-                Ok(None) => {
-                    eprintln!("job {} not found", job_id);
-                    std::process::exit(1);
+        Commands::List {
+            selector,
+            state,
+            path_prefix,
+            daemon,
+        } => {
+            let daemon = DaemonClient::new(daemon)?;
+            let query: Vec<(&str, &str)> = [
+                ("selector", selector.as_deref()),
+                ("state", state.as_deref()),
+                ("path_prefix", path_prefix.as_deref()),
+            ]
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, value?)))
+            .collect();
+            let collection: MountCollection =
+                daemon.call(daemon.request_with_query(Method::GET, "/mounts", &query))?;
+            print_output(json, &collection, |collection| {
+                if collection.mounts.is_empty() {
+                    println!("no active jobs");
                 }
-                Err(err) => {
-                    eprintln!("failed to unmount job {}: {}", job_id, err);
-                    std::process::exit(1);
+                for it in &collection.mounts {
+                    print_mount(it);
+                }
+            });
+        }
+        Commands::Describe { target, daemon } => {
+            let daemon = DaemonClient::new(daemon)?;
+            let status: MountStatus = daemon.call(daemon.request(Method::GET, &target.path()))?;
+            print_output(json, &status, print_status);
+        }
+        Commands::Delete { target, daemon } => {
+            let daemon = DaemonClient::new(daemon)?;
+            let status: MountStatus =
+                daemon.call(daemon.request(Method::DELETE, &target.path()))?;
+            print_output(json, &status, |status| {
+                println!("deleted mount {}", status.mount_id)
+            });
+        }
+        Commands::Cl {
+            command:
+                ClCommand::Build {
+                    target,
+                    cl,
+                    patch,
+                    name,
+                    daemon,
+                },
+        } => {
+            let daemon = DaemonClient::new(daemon)?;
+            let request = match patch {
+                Some(patch) => {
+                    let patch = std::fs::read_to_string(&patch).map_err(|e| {
+                        CliError::new(
+                            exit_code::INVALID,
+                            "CLIENT_ERROR",
+                            format!("failed to read {}: {e}", patch.display()),
+                        )
+                    })?;
+                    BuildClRequest {
+                        local: Some(LocalClRequest {
+                            name,
+                            patch: Some(patch),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }
                 }
+                None => BuildClRequest {
+                    cls: cl,
+                    ..Default::default()
+                },
+            };
+            let mount_id = daemon.resolve(&target)?;
+            let request = daemon
+                .request(Method::POST, &format!("/mounts/{mount_id}/cl"))
+                .json(&request);
+            let status: MountStatus = daemon.call(request)?;
+            print_output(json, &status, print_status);
+        }
+        Commands::Cl {
+            command: ClCommand::Clear { target, daemon },
+        } => {
+            let daemon = DaemonClient::new(daemon)?;
+            let mount_id = daemon.resolve(&target)?;
+            let request = daemon.request(Method::DELETE, &format!("/mounts/{mount_id}/cl"));
+            let status: MountStatus = daemon.call(request)?;
+            print_output(json, &status, print_status);
+        }
+        Commands::WaitReady {
+            target,
+            timeout,
+            interval_ms,
+            daemon,
+        } => {
+            let daemon = DaemonClient::new(daemon)?;
+            let mount_id = daemon.resolve(&target)?;
+            let probe = daemon.wait_ready(
+                mount_id,
+                Duration::from_secs(timeout),
+                Duration::from_millis(interval_ms),
+            )?;
+            print_output(json, &probe, |probe| {
+                println!("mount {} is {}", probe.mount_id, probe.state.name())
+            });
+        }
+        Commands::Health { daemon } => {
+            let daemon = DaemonClient::new(daemon)?;
+            let health: HealthResponse = daemon.call(daemon.request(Method::GET, "/health"))?;
+            print_output(json, &health, |health| {
+                println!(
+                    "status={} mounts={} failed={} uptime={}s",
+                    health.status, health.mount_count, health.failed_mounts, health.uptime_secs
+                )
+            });
+            if health.status != "healthy" {
+                std::process::exit(exit_code::NOT_READY);
             }
         }
-        Commands::List => {
-            let manager = AntaresManager::new(paths.clone()).await;
-            let items = manager.list().await;
-            if items.is_empty() {
-                println!("no active jobs");
-            } else {
-                for it in items {
-                    let cl = it
-                        .cl_dir
-                        .as_ref()
-                        .map(|p| p.display().to_string())
-                        .unwrap_or_else(|| "(none)".to_string());
+        Commands::Gc {
+            dry_run,
+            retention_secs,
+            daemon,
+        } => {
+            let daemon = DaemonClient::new(daemon)?;
+            let request = GcRequest {
+                dry_run,
+                retention_secs,
+            };
+            let report: GcReport =
+                daemon.call(daemon.request(Method::POST, "/admin/gc").json(&request))?;
+            print_output(json, &report, |report| {
+                let verb = if report.dry_run {
+                    "would remove"
+                } else {
+                    "removed"
+                };
+                for entry in &report.removed {
+                    println!("{verb} {} {}", entry.kind.name(), entry.path);
+                }
+                for failure in &report.failed {
                     println!(
-                        "job_id={} mount={} upper={} cl={}",
-                        it.job_id,
-                        it.mountpoint.display(),
-                        it.upper_dir.display(),
-                        cl
+                        "failed {} {}: {}",
+                        failure.entry.kind.name(),
+                        failure.entry.path,
+                        failure.error
                     );
                 }
-            }
-        }
-        Commands::Serve { bind } => {
-            // Initialize tracing for daemon mode
-            tracing_subscriber::fmt()
-                .with_env_filter(
-                    tracing_subscriber::EnvFilter::from_default_env()
-                        .add_directive("scorpio=info".parse().unwrap()),
-                )
-                .init();
-
-            let addr: SocketAddr = bind.parse().unwrap_or_else(|e| {
-                eprintln!("Invalid bind address '{}': {}", bind, e);
-                std::process::exit(1);
+                println!(
+                    "{} {} path(s), {} failure(s), retention {}s",
+                    verb,
+                    report.removed.len(),
+                    report.failed.len(),
+                    report.retention_secs
+                );
             });
-
-            // Create service with new Dicfuse instance
-            let service = Arc::new(AntaresServiceImpl::new(None).await);
-            let daemon = AntaresDaemon::new(service);
-
-            tracing::info!("Starting Antares daemon on {}", addr);
-
-            if let Err(e) = daemon.serve(addr).await {
-                tracing::error!("Daemon error: {}", e);
-                std::process::exit(1);
+            if !report.failed.is_empty() {
+                std::process::exit(exit_code::FAILURE);
             }
         }
         Commands::HttpMount {
             job_id,
             path,
             cl,
-            endpoint,
+            read_only,
+            memory_upper,
+            daemon,
         } => {
-            let client = Client::new();
-            let url = format!("{}/mounts", endpoint.trim_end_matches('/'));
+            let daemon = DaemonClient::new(daemon)?;
             let payload = serde_json::json!({
                 "job_id": job_id,
                 "path": path,
-                "cl": cl,
+                "cls": cl,
+                "read_only": read_only,
+                "upper": if memory_upper { "memory" } else { "disk" },
+            });
+            let created: MountCreated =
+                daemon.call(daemon.request(Method::POST, "/mounts").json(&payload))?;
+            print_output(json, &created, |created| {
+                println!(
+                    "mounted {} at {} (mount {})",
+                    path, created.mountpoint, created.mount_id
+                )
             });
+        }
+        Commands::Serve { .. } => unreachable!("serve runs the daemon itself"),
+    }
+    Ok(())
+}
 
-            let resp = client
-                .post(url)
-                .header("content-type", "application/json")
-                .json(&payload)
-                .send();
-
-            match resp {
-                Ok(r) if r.status().is_success() => {
-                    let created: serde_json::Value = r.json().unwrap_or_else(|e| {
-                        eprintln!("failed to parse response json: {e}");
-                        std::process::exit(1);
-                    });
-                    println!("{}", serde_json::to_string_pretty(&created).unwrap());
-                }
-                Ok(r) => {
-                    let status = r.status();
-                    let body = r.text().unwrap_or_default();
-                    eprintln!("http mount failed: status={} body={}", status, body);
-                    std::process::exit(1);
-                }
-                Err(e) => {
-                    eprintln!("http mount request failed: {}", e);
-                    std::process::exit(1);
-                }
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    if let Err(e) = config::init_config(&cli.config_path) {
+        eprintln!("Failed to load config: {e}");
+        std::process::exit(1);
+    }
+
+    let mut paths = AntaresPaths::from_global_config();
+    if let Some(p) = cli.upper_root {
+        paths.upper_root = p;
+    }
+    if let Some(p) = cli.cl_root {
+        paths.cl_root = p;
+    }
+    if let Some(p) = cli.mount_root {
+        paths.mount_root = p;
+    }
+    if let Some(p) = cli.state_file {
+        paths.state_file = p;
+    }
+
+    match cli.command {
+        Commands::Serve {
+            bind,
+            socket,
+            no_tcp,
+        } => serve(paths, bind, socket, no_tcp).await,
+        command => {
+            // The blocking HTTP client must not run on the async runtime's workers.
+            let json = cli.json;
+            let result = tokio::task::spawn_blocking(move || run_client(command, json))
+                .await
+                .expect("client task panicked");
+            if let Err(err) = result {
+                err.exit(json);
             }
         }
     }
 }
+
+/// Run the daemon on `paths` until shutdown.
+async fn serve(paths: AntaresPaths, bind: String, socket: Option<PathBuf>, no_tcp: bool) {
+    // Initialize tracing for daemon mode
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("scorpio=info".parse().unwrap()),
+        )
+        .init();
+
+    let addr: SocketAddr = bind.parse().unwrap_or_else(|e| {
+        eprintln!("Invalid bind address '{}': {}", bind, e);
+        std::process::exit(1);
+    });
+
+    let policy = AuthPolicy::from_global_config().unwrap_or_else(|e| {
+        eprintln!("Invalid HTTP auth configuration: {}", e);
+        std::process::exit(1);
+    });
+    let tls = auth::tls_from_global_config().unwrap_or_else(|e| {
+        eprintln!("Invalid HTTP TLS configuration: {}", e);
+        std::process::exit(1);
+    });
+
+    // Create service with new Dicfuse instance; recovery runs the startup GC once the
+    // persisted mounts are back.
+    let service = AntaresServiceImpl::with_paths_and_recovery(None, paths)
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to start Antares service: {}", e);
+            std::process::exit(1);
+        });
+    let service = Arc::new(service);
+    if let Some(supervisor) = SupervisorConfig::from_global_config() {
+        service.spawn_supervisor(supervisor);
+    }
+    let socket = socket.unwrap_or_else(|| config::antares_socket().into());
+    let mut daemon = AntaresDaemon::new(service)
+        .with_auth(Arc::new(policy))
+        .with_unix_socket(socket, config::http_socket_mode());
+    if let Some(tls) = tls {
+        daemon = daemon.with_tls(tls);
+    }
+
+    tracing::info!("Starting Antares daemon on {}", addr);
+
+    if let Err(e) = daemon.serve_on((!no_tcp).then_some(addr)).await {
+        tracing::error!("Daemon error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// One line per mount, for `list`.
+fn print_mount(it: &MountStatus) {
+    let cl = it.layers.cl.as_deref().unwrap_or("(none)");
+    let upper = match (&it.layers.upper, it.read_only) {
+        (Some(upper), _) => upper.as_str(),
+        (None, true) => "(read-only)",
+        (None, false) => "(memory)",
+    };
+    println!(
+        "job_id={} mount_id={} mount={} upper={} cl={}",
+        it.job_id.as_deref().unwrap_or("-"),
+        it.mount_id,
+        it.mountpoint,
+        upper,
+        cl
+    );
+}
+
+/// Multi-line summary of one mount, for `describe` and the CL commands.
+fn print_status(it: &MountStatus) {
+    println!("mount_id:   {}", it.mount_id);
+    println!("job_id:     {}", it.job_id.as_deref().unwrap_or("-"));
+    if it.bindings.is_empty() {
+        println!("path:       {}", it.path);
+    }
+    for binding in &it.bindings {
+        println!("binding:    {} -> {}", binding.source, binding.target);
+    }
+    match &it.state {
+        MountLifecycle::Failed { reason } => println!("state:      Failed ({reason})"),
+        state => println!("state:      {}", state.name()),
+    }
+    println!("mountpoint: {}", it.mountpoint);
+    println!("cls:        {}", it.cls.join(", "));
+    println!(
+        "upper:      {}",
+        it.layers.upper.as_deref().unwrap_or(if it.read_only {
+            "(read-only)"
+        } else {
+            "(memory)"
+        })
+    );
+    if !it.labels.is_empty() {
+        let labels: Vec<String> = it.labels.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        println!("labels:     {}", labels.join(","));
+    }
+}
@@ -4,14 +4,16 @@
 //! AntaresService implementations. Includes graceful shutdown with cleanup.
 
 use std::{
-    collections::{HashMap, VecDeque},
-    ffi::CString,
+    collections::{HashMap, HashSet, VecDeque},
+    convert::Infallible,
+    ffi::{CString, OsStr},
+    future::Future,
     net::SocketAddr,
-    os::unix::ffi::OsStrExt,
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt},
     path::{Component, Path, PathBuf},
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
-        Arc, Condvar, Mutex,
+        Arc, Condvar, Mutex, OnceLock,
     },
     thread,
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
@@ -19,31 +21,64 @@ use std::{
 
 use async_trait::async_trait;
 use axum::{
-    extract::{Path as AxumPath, State},
+    extract::{Path as AxumPath, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Response},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     routing::{delete, get, post},
-    Json, Router,
+    Extension, Json, Router,
 };
+use base64::Engine;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::{
     io::AsyncWriteExt,
-    sync::RwLock,
+    sync::{broadcast, Notify, RwLock},
     time::{sleep, timeout},
 };
+use tokio_rustls::rustls::ServerConfig;
 use uuid::Uuid;
 
 use crate::{
-    antares::fuse::AntaresFuse,
-    dicfuse::{Dicfuse, DicfuseManager},
+    antares::{
+        blob_cache::BlobCache,
+        checkpoint::{CheckpointInfo, CheckpointStore},
+        cl_manifest::{self, ClDiff, ClEntryKind, ClManifest, ClManifestEntry},
+        cl_stack::{self, ClConflict},
+        composite,
+        fuse::AntaresFuse,
+        gc::{self, GcEntry, GcKind, GcScan},
+        labels::{self, LabelSelector, Labels},
+        local_cl::{self, LocalCl},
+        mem_upper::MemUpperLayer,
+        quota::{QuotaLimits, UpperQuota, UpperUsage},
+        snapshot::{self, CloneStats},
+        state_store::StateStore,
+        AntaresPaths,
+    },
+    daemon::{
+        auth::{self, antares_scope, AuthGuard, AuthPolicy, Caller, UnixSocketListener},
+        webhook::{WebhookConfig, Webhooks},
+    },
+    dicfuse::{Dicfuse, DicfuseManager, DicfuseTuning},
+    util::config::DicfuseStatMode,
 };
 
 /// High-level HTTP daemon that exposes Antares orchestration capabilities.
 pub struct AntaresDaemon<S: AntaresService> {
     service: Arc<S>,
     shutdown_timeout: Duration,
+    auth: Arc<AuthPolicy>,
+    tls: Option<Arc<ServerConfig>>,
+    /// Unix socket path and permission bits.
+    unix_socket: Option<(PathBuf, u32)>,
+    /// Signalled by `POST /shutdown`.
+    shutdown: Arc<Notify>,
 }
 
 impl<S> AntaresDaemon<S>
@@ -55,6 +90,10 @@ where
         Self {
             service,
             shutdown_timeout: Duration::from_secs(10),
+            auth: Arc::new(AuthPolicy::open()),
+            tls: None,
+            unix_socket: None,
+            shutdown: Arc::new(Notify::new()),
         }
     }
 
@@ -64,56 +103,149 @@ where
         self
     }
 
+    /// Require credentials with the scope of each route (see [`antares_scope`]).
+    pub fn with_auth(mut self, policy: Arc<AuthPolicy>) -> Self {
+        self.auth = policy;
+        self
+    }
+
+    /// Serve HTTPS instead of plain HTTP.
+    pub fn with_tls(mut self, config: Arc<ServerConfig>) -> Self {
+        self.tls = Some(config);
+        self
+    }
+
+    /// Also listen on the unix socket `path`, created with permission bits `mode`.
+    /// Peers connecting through it are scoped by uid: root and the daemon's own euid get
+    /// `admin`, everyone else the policy's socket peer scope (`mount` by default).
+    pub fn with_unix_socket(mut self, path: impl Into<PathBuf>, mode: u32) -> Self {
+        self.unix_socket = Some((path.into(), mode));
+        self
+    }
+
+    /// Completes once an admin calls `POST /shutdown`.
+    pub fn shutdown_requested(&self) -> impl Future<Output = ()> + Send + 'static {
+        let shutdown = self.shutdown.clone();
+        async move { shutdown.notified().await }
+    }
+
     /// Produce an Axum router with all routes wired to their handlers.
     pub fn router(&self) -> Router {
-        Router::new()
+        let shutdown = self.shutdown.clone();
+        let router = Router::new()
             .route("/health", get(Self::healthcheck))
             .route("/mounts", post(Self::create_mount))
             .route("/mounts", get(Self::list_mounts))
+            .route("/mounts", delete(Self::delete_mounts))
             .route("/mounts/by-job/{job_id}", get(Self::describe_mount_by_job))
             .route("/mounts/by-job/{job_id}", delete(Self::delete_mount_by_job))
             .route("/mounts/{mount_id}", get(Self::describe_mount))
             .route("/mounts/{mount_id}", delete(Self::delete_mount))
             .route("/mounts/{mount_id}/cl", post(Self::build_cl))
             .route("/mounts/{mount_id}/cl", delete(Self::clear_cl))
+            .route("/mounts/{mount_id}/cl/progress", get(Self::cl_progress))
+            .route("/mounts/{mount_id}/fork", post(Self::fork_mount))
+            .route(
+                "/mounts/{mount_id}/checkpoints",
+                post(Self::create_checkpoint),
+            )
+            .route(
+                "/mounts/{mount_id}/checkpoints",
+                get(Self::list_checkpoints),
+            )
+            .route(
+                "/mounts/{mount_id}/checkpoints/{checkpoint_id}",
+                delete(Self::delete_checkpoint),
+            )
+            .route(
+                "/mounts/{mount_id}/checkpoints/{checkpoint_id}/restore",
+                post(Self::restore_checkpoint),
+            )
+            .route("/mounts/{mount_id}/usage", get(Self::mount_usage))
             .route("/mounts/{mount_id}/ready", get(Self::mount_ready))
-            .with_state(self.service.clone())
+            .route("/mounts/{mount_id}/events", get(Self::mount_events))
+            .route("/events", get(Self::events))
+            .route("/admin/gc", post(Self::collect_garbage))
+            .route(
+                "/shutdown",
+                post(move |caller: Option<Extension<Caller>>| async move {
+                    tracing::info!(
+                        caller = ?caller.map(|Extension(c)| c.name),
+                        "antares http: shutdown requested"
+                    );
+                    shutdown.notify_one();
+                    StatusCode::ACCEPTED
+                }),
+            )
+            .with_state(self.service.clone());
+        AuthGuard::new(self.auth.clone(), antares_scope).protect(router)
     }
 
     /// Run the HTTP server until it receives a shutdown signal.
     /// Note: For graceful shutdown with mount cleanup, use `AntaresDaemon<AntaresServiceImpl>`.
     pub async fn serve(self, bind_addr: SocketAddr) -> Result<(), ApiError> {
+        self.serve_on(Some(bind_addr)).await
+    }
+
+    /// Like [`serve`](Self::serve), but `None` listens only on the unix socket set by
+    /// [`with_unix_socket`](Self::with_unix_socket).
+    pub async fn serve_on(self, bind_addr: Option<SocketAddr>) -> Result<(), ApiError> {
         let router = self.router();
         let shutdown_timeout = self.shutdown_timeout;
         let service = self.service.clone();
 
-        let listener = tokio::net::TcpListener::bind(bind_addr)
-            .await
-            .map_err(|e| {
+        let mut listeners = auth::Listeners::default();
+        if let Some(bind_addr) = bind_addr {
+            let listener = tokio::net::TcpListener::bind(bind_addr)
+                .await
+                .map_err(|e| {
+                    ApiError::Service(ServiceError::Internal(format!(
+                        "failed to bind to {}: {}",
+                        bind_addr, e
+                    )))
+                })?;
+            tracing::info!("Antares daemon listening on {}", bind_addr);
+            if self.auth.is_open() && !bind_addr.ip().is_loopback() {
+                tracing::warn!(
+                    "Antares daemon on {} accepts unauthenticated requests; set http_auth_file to restrict it",
+                    bind_addr
+                );
+            }
+            listeners.tcp = Some(listener);
+        }
+        if let Some((path, mode)) = &self.unix_socket {
+            let listener = UnixSocketListener::bind(path, *mode).map_err(|e| {
                 ApiError::Service(ServiceError::Internal(format!(
                     "failed to bind to {}: {}",
-                    bind_addr, e
+                    path.display(),
+                    e
                 )))
             })?;
+            tracing::info!(
+                "Antares daemon listening on {} (mode {:o})",
+                path.display(),
+                mode
+            );
+            listeners.unix = Some(listener);
+        }
 
-        tracing::info!("Antares daemon listening on {}", bind_addr);
-
-        axum::serve(listener, router)
-            .with_graceful_shutdown(async move {
-                let _ = tokio::signal::ctrl_c().await;
-                tracing::info!("Received shutdown signal");
-                match timeout(shutdown_timeout, service.shutdown_cleanup()).await {
-                    Ok(Ok(())) => tracing::info!("Shutdown cleanup completed"),
-                    Ok(Err(e)) => tracing::warn!("Shutdown cleanup failed: {:?}", e),
-                    Err(_) => {
-                        tracing::warn!("Shutdown cleanup timed out after {:?}", shutdown_timeout)
-                    }
+        let shutdown_requested = self.shutdown_requested();
+        auth::serve(listeners, router, self.tls, async move {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = shutdown_requested => {}
+            }
+            tracing::info!("Received shutdown signal");
+            match timeout(shutdown_timeout, service.shutdown_cleanup()).await {
+                Ok(Ok(())) => tracing::info!("Shutdown cleanup completed"),
+                Ok(Err(e)) => tracing::warn!("Shutdown cleanup failed: {:?}", e),
+                Err(_) => {
+                    tracing::warn!("Shutdown cleanup timed out after {:?}", shutdown_timeout)
                 }
-            })
-            .await
-            .map_err(|e| {
-                ApiError::Service(ServiceError::Internal(format!("server error: {}", e)))
-            })?;
+            }
+        })
+        .await
+        .map_err(|e| ApiError::Service(ServiceError::Internal(format!("server error: {}", e))))?;
 
         Ok(())
     }
@@ -125,9 +257,11 @@ where
 
     async fn create_mount(
         State(service): State<Arc<S>>,
-        Json(request): Json<CreateMountRequest>,
-    ) -> Result<Json<MountCreated>, ApiError> {
+        caller: Option<Extension<Caller>>,
+        Json(mut request): Json<CreateMountRequest>,
+    ) -> Result<(StatusCode, Json<MountCreated>), ApiError> {
         let start = Instant::now();
+        request.created_by = caller.map(|Extension(c)| c.name);
         let job_id = request.job_id.clone();
         let build_id = request.build_id.clone();
         let path = request.path.clone();
@@ -137,6 +271,9 @@ where
             build_id = ?build_id,
             path = %path,
             cl = ?cl,
+            cls = ?request.cls,
+            background = request.background,
+            created_by = ?request.created_by,
             "antares http: create_mount request"
         );
 
@@ -155,12 +292,101 @@ where
             ),
         }
 
-        Ok(Json(created?))
+        let created = created?;
+        let code = if created.state == MountLifecycle::Provisioning {
+            StatusCode::ACCEPTED
+        } else {
+            StatusCode::OK
+        };
+        Ok((code, Json(created)))
+    }
+
+    async fn list_mounts(
+        State(service): State<Arc<S>>,
+        Query(query): Query<MountQuery>,
+    ) -> Result<Json<MountCollection>, ApiError> {
+        let filter = query.filter()?;
+        if query.limit == Some(0) {
+            return Err(ServiceError::InvalidRequest("limit must be at least 1".into()).into());
+        }
+        let mut mounts: Vec<MountStatus> = service
+            .list_mounts()
+            .await?
+            .into_iter()
+            .filter(|m| filter.matches(m))
+            .collect();
+        // Oldest first, so pages stay stable while new mounts are added.
+        mounts.sort_by_key(|m| (m.created_at_epoch_ms, m.mount_id));
+        let total = mounts.len();
+        let offset = query.offset.unwrap_or(0).min(total);
+        let end = match query.limit {
+            Some(limit) => offset.saturating_add(limit).min(total),
+            None => total,
+        };
+        let mounts = mounts.drain(offset..end).collect();
+        Ok(Json(MountCollection {
+            mounts,
+            total,
+            next_offset: (end < total).then_some(end),
+        }))
+    }
+
+    async fn delete_mounts(
+        State(service): State<Arc<S>>,
+        Query(query): Query<MountQuery>,
+    ) -> Result<Json<BulkDeleteResponse>, ApiError> {
+        let filter = query.filter()?;
+        if filter.is_empty() {
+            return Err(ServiceError::InvalidRequest(
+                "refusing to delete every mount; set selector, state or path_prefix".into(),
+            )
+            .into());
+        }
+        let start = Instant::now();
+        tracing::info!(filter = ?query, "antares http: delete_mounts request");
+        let result = service.delete_mounts(&filter).await;
+        match &result {
+            Ok(result) => tracing::info!(
+                deleted = result.deleted.len(),
+                failed = result.failed.len(),
+                elapsed_ms = start.elapsed().as_millis(),
+                "antares http: delete_mounts done"
+            ),
+            Err(err) => tracing::warn!(
+                elapsed_ms = start.elapsed().as_millis(),
+                error = %err,
+                "antares http: delete_mounts failed"
+            ),
+        }
+        Ok(Json(result?))
     }
 
-    async fn list_mounts(State(service): State<Arc<S>>) -> Result<Json<MountCollection>, ApiError> {
-        let mounts = service.list_mounts().await?;
-        Ok(Json(MountCollection { mounts }))
+    async fn collect_garbage(
+        State(service): State<Arc<S>>,
+        payload: Option<Json<GcRequest>>,
+    ) -> Result<Json<GcReport>, ApiError> {
+        let request = payload.map(|Json(r)| r).unwrap_or_default();
+        let start = Instant::now();
+        tracing::info!(
+            dry_run = request.dry_run,
+            retention_secs = ?request.retention_secs,
+            "antares http: collect_garbage start"
+        );
+        let result = service.collect_garbage(request).await;
+        match &result {
+            Ok(report) => tracing::info!(
+                elapsed_ms = start.elapsed().as_millis(),
+                removed = report.removed.len(),
+                failed = report.failed.len(),
+                "antares http: collect_garbage done"
+            ),
+            Err(err) => tracing::warn!(
+                elapsed_ms = start.elapsed().as_millis(),
+                error = %err,
+                "antares http: collect_garbage failed"
+            ),
+        }
+        Ok(Json(result?))
     }
 
     async fn describe_mount_by_job(
@@ -232,9 +458,27 @@ where
         Json(request): Json<BuildClRequest>,
     ) -> Result<Json<MountStatus>, ApiError> {
         let start = Instant::now();
-        let cl = request.cl;
-        tracing::info!(mount_id = %mount_id, cl = %cl, "antares http: build_cl request");
-        let status = service.build_cl(mount_id, cl).await;
+        let status = match request.local {
+            Some(local) => {
+                if request.cl.is_some() || !request.cls.is_empty() {
+                    return Err(ServiceError::InvalidRequest(
+                        "set either cl/cls or local, not both".into(),
+                    )
+                    .into());
+                }
+                tracing::info!(
+                    mount_id = %mount_id,
+                    name = ?local.name,
+                    "antares http: build_cl request (local)"
+                );
+                service.build_local_cl(mount_id, local).await
+            }
+            None => {
+                let cls = cl_stack_from(request.cl.as_deref(), &request.cls)?;
+                tracing::info!(mount_id = %mount_id, cls = ?cls, "antares http: build_cl request");
+                service.build_cl(mount_id, cls).await
+            }
+        };
         match &status {
             Ok(status) => tracing::info!(
                 mount_id = %status.mount_id,
@@ -276,6 +520,140 @@ where
         Ok(Json(status?))
     }
 
+    async fn fork_mount(
+        State(service): State<Arc<S>>,
+        AxumPath(mount_id): AxumPath<Uuid>,
+        caller: Option<Extension<Caller>>,
+        payload: Option<Json<ForkMountRequest>>,
+    ) -> Result<Json<MountCreated>, ApiError> {
+        let start = Instant::now();
+        let mut request = payload.map(|Json(r)| r).unwrap_or_default();
+        request.created_by = caller.map(|Extension(c)| c.name);
+        tracing::info!(
+            mount_id = %mount_id,
+            job_id = ?request.job_id,
+            created_by = ?request.created_by,
+            "antares http: fork_mount request"
+        );
+        let created = service.fork_mount(mount_id, request).await;
+        match &created {
+            Ok(created) => tracing::info!(
+                source_mount_id = %mount_id,
+                mount_id = %created.mount_id,
+                mountpoint = %created.mountpoint,
+                elapsed_ms = start.elapsed().as_millis(),
+                "antares http: fork_mount done"
+            ),
+            Err(err) => tracing::warn!(
+                mount_id = %mount_id,
+                elapsed_ms = start.elapsed().as_millis(),
+                error = %err,
+                "antares http: fork_mount failed"
+            ),
+        }
+        Ok(Json(created?))
+    }
+
+    async fn create_checkpoint(
+        State(service): State<Arc<S>>,
+        AxumPath(mount_id): AxumPath<Uuid>,
+        payload: Option<Json<CreateCheckpointRequest>>,
+    ) -> Result<Json<CheckpointInfo>, ApiError> {
+        let start = Instant::now();
+        let request = payload.map(|Json(r)| r).unwrap_or_default();
+        tracing::info!(
+            mount_id = %mount_id,
+            label = ?request.label,
+            "antares http: create_checkpoint request"
+        );
+        let info = service.create_checkpoint(mount_id, request).await;
+        match &info {
+            Ok(info) => tracing::info!(
+                mount_id = %mount_id,
+                checkpoint_id = %info.checkpoint_id,
+                size_bytes = info.size_bytes,
+                elapsed_ms = start.elapsed().as_millis(),
+                "antares http: create_checkpoint done"
+            ),
+            Err(err) => tracing::warn!(
+                mount_id = %mount_id,
+                elapsed_ms = start.elapsed().as_millis(),
+                error = %err,
+                "antares http: create_checkpoint failed"
+            ),
+        }
+        Ok(Json(info?))
+    }
+
+    async fn list_checkpoints(
+        State(service): State<Arc<S>>,
+        AxumPath(mount_id): AxumPath<Uuid>,
+    ) -> Result<Json<CheckpointCollection>, ApiError> {
+        let collection = service.list_checkpoints(mount_id).await?;
+        Ok(Json(collection))
+    }
+
+    async fn delete_checkpoint(
+        State(service): State<Arc<S>>,
+        AxumPath((mount_id, checkpoint_id)): AxumPath<(Uuid, Uuid)>,
+    ) -> Result<Json<CheckpointInfo>, ApiError> {
+        tracing::info!(
+            mount_id = %mount_id,
+            checkpoint_id = %checkpoint_id,
+            "antares http: delete_checkpoint request"
+        );
+        let info = service.delete_checkpoint(mount_id, checkpoint_id).await?;
+        Ok(Json(info))
+    }
+
+    async fn restore_checkpoint(
+        State(service): State<Arc<S>>,
+        AxumPath((mount_id, checkpoint_id)): AxumPath<(Uuid, Uuid)>,
+    ) -> Result<Json<MountStatus>, ApiError> {
+        let start = Instant::now();
+        tracing::info!(
+            mount_id = %mount_id,
+            checkpoint_id = %checkpoint_id,
+            "antares http: restore_checkpoint request"
+        );
+        let status = service.restore_checkpoint(mount_id, checkpoint_id).await;
+        match &status {
+            Ok(status) => tracing::info!(
+                mount_id = %status.mount_id,
+                checkpoint_id = %checkpoint_id,
+                state = ?status.state,
+                elapsed_ms = start.elapsed().as_millis(),
+                "antares http: restore_checkpoint done"
+            ),
+            Err(err) => tracing::warn!(
+                mount_id = %mount_id,
+                checkpoint_id = %checkpoint_id,
+                elapsed_ms = start.elapsed().as_millis(),
+                error = %err,
+                "antares http: restore_checkpoint failed"
+            ),
+        }
+        Ok(Json(status?))
+    }
+
+    /// Report a mount's upper-layer consumption against its quota.
+    async fn mount_usage(
+        State(service): State<Arc<S>>,
+        AxumPath(mount_id): AxumPath<Uuid>,
+    ) -> Result<Json<MountUsage>, ApiError> {
+        let usage = service.mount_usage(mount_id).await?;
+        Ok(Json(usage))
+    }
+
+    /// Report the progress of a mount's CL build.
+    async fn cl_progress(
+        State(service): State<Arc<S>>,
+        AxumPath(mount_id): AxumPath<Uuid>,
+    ) -> Result<Json<ClBuildProgress>, ApiError> {
+        let progress = service.cl_progress(mount_id).await?;
+        Ok(Json(progress))
+    }
+
     /// Check whether a mount is ready for heavy workloads.
     ///
     /// `ready=true` means Phase 1 (Dicfuse in-memory directory cache warmup)
@@ -292,6 +670,72 @@ where
         let resp = service.check_mount_ready(mount_id).await?;
         Ok(Json(resp))
     }
+
+    /// Stream the events of all mounts, starting with the current state of each.
+    async fn events(
+        State(service): State<Arc<S>>,
+    ) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+        // Subscribe first so that nothing between the snapshot and the stream is lost.
+        let events = service.subscribe_events();
+        let initial = service
+            .list_mounts()
+            .await?
+            .into_iter()
+            .map(MountStatus::into_event)
+            .collect();
+        Ok(Self::event_stream(initial, events, None))
+    }
+
+    /// Stream the events of one mount, starting with its current state. The stream ends
+    /// once the mount is removed.
+    async fn mount_events(
+        State(service): State<Arc<S>>,
+        AxumPath(mount_id): AxumPath<Uuid>,
+    ) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+        let events = service.subscribe_events();
+        let initial = service.describe_mount(mount_id).await?.into_event();
+        Ok(Self::event_stream(vec![initial], events, Some(mount_id)))
+    }
+
+    fn event_stream(
+        initial: Vec<MountEvent>,
+        events: broadcast::Receiver<MountEvent>,
+        mount_id: Option<Uuid>,
+    ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+        let live = futures::stream::unfold(Some(events), move |events| async move {
+            let mut events = events?;
+            loop {
+                match events.recv().await {
+                    Ok(event) if event.kind == MountEventKind::Shutdown => {
+                        return Some((sse_event(&event), None));
+                    }
+                    Ok(event) if mount_id.is_some_and(|id| id != event.mount_id) => continue,
+                    Ok(event) => {
+                        let last = mount_id.is_some() && event.kind == MountEventKind::Removed;
+                        return Some((sse_event(&event), (!last).then_some(events)));
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        // The subscriber should re-read the mounts it cares about.
+                        let lagged = Event::default().event("lagged").data(missed.to_string());
+                        return Some((lagged, Some(events)));
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        let stream = futures::stream::iter(initial.into_iter().map(|e| sse_event(&e)))
+            .chain(live)
+            .map(Ok);
+        Sse::new(stream).keep_alive(KeepAlive::default())
+    }
+}
+
+/// Encode `event` as an SSE event named after its kind.
+fn sse_event(event: &MountEvent) -> Event {
+    Event::default()
+        .event(event.kind.name())
+        .json_data(event)
+        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()))
 }
 
 /// Asynchronous service boundary that the HTTP layer depends on.
@@ -323,10 +767,80 @@ pub trait AntaresService: Send + Sync {
         let status = self.describe_mount_by_job(job_id.clone()).await?;
         self.delete_mount(status.mount_id).await
     }
-    /// Build or rebuild the CL layer for an existing mount
-    async fn build_cl(&self, mount_id: Uuid, cl_link: String) -> Result<MountStatus, ServiceError>;
-    /// Clear the CL layer for an existing mount
+
+    /// Delete every mount matching `filter`, one after the other.
+    ///
+    /// A mount that fails to unmount is reported and does not stop the others.
+    async fn delete_mounts(
+        &self,
+        filter: &MountFilter,
+    ) -> Result<BulkDeleteResponse, ServiceError> {
+        let mut response = BulkDeleteResponse::default();
+        let matching = self
+            .list_mounts()
+            .await?
+            .into_iter()
+            .filter(|m| filter.matches(m));
+        for status in matching {
+            match self.delete_mount(status.mount_id).await {
+                Ok(status) => response.deleted.push(status),
+                // Gone in the meantime, e.g. deleted by its job.
+                Err(ServiceError::NotFound(_)) => {}
+                Err(err) => response.failed.push(BulkDeleteFailure {
+                    mount_id: status.mount_id,
+                    error: err.to_string(),
+                }),
+            }
+        }
+        Ok(response)
+    }
+    /// Build or rebuild the CL layers for an existing mount, replacing its whole CL stack.
+    /// `cls` is applied bottom first; later CLs override earlier ones.
+    async fn build_cl(&self, mount_id: Uuid, cls: Vec<String>)
+        -> Result<MountStatus, ServiceError>;
+    /// Replace the CL stack of an existing mount with one layer built from a local patch
+    /// or archive, applied against the Dicfuse base.
+    async fn build_local_cl(
+        &self,
+        mount_id: Uuid,
+        request: LocalClRequest,
+    ) -> Result<MountStatus, ServiceError>;
+    /// Clear all CL layers of an existing mount
     async fn clear_cl(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError>;
+    /// Create a new mount whose upper (and CL) layer starts as a copy of an existing mount's.
+    ///
+    /// The source mount keeps running untouched; the fork shares the same Dicfuse base.
+    async fn fork_mount(
+        &self,
+        mount_id: Uuid,
+        request: ForkMountRequest,
+    ) -> Result<MountCreated, ServiceError>;
+
+    /// Snapshot a mount's upper layer. The mount is quiesced while the copy runs.
+    async fn create_checkpoint(
+        &self,
+        mount_id: Uuid,
+        request: CreateCheckpointRequest,
+    ) -> Result<CheckpointInfo, ServiceError>;
+    /// List a mount's checkpoints together with its checkpoint storage usage.
+    async fn list_checkpoints(&self, mount_id: Uuid) -> Result<CheckpointCollection, ServiceError>;
+    async fn delete_checkpoint(
+        &self,
+        mount_id: Uuid,
+        checkpoint_id: Uuid,
+    ) -> Result<CheckpointInfo, ServiceError>;
+    /// Roll a mount's upper layer back to a checkpoint. The CL layer is left as-is.
+    async fn restore_checkpoint(
+        &self,
+        mount_id: Uuid,
+        checkpoint_id: Uuid,
+    ) -> Result<MountStatus, ServiceError>;
+
+    /// Recount a mount's upper layer and return its usage and quota limits.
+    async fn mount_usage(&self, mount_id: Uuid) -> Result<MountUsage, ServiceError>;
+
+    /// Progress of the mount's current or most recent CL build.
+    async fn cl_progress(&self, mount_id: Uuid) -> Result<ClBuildProgress, ServiceError>;
 
     /// Check whether a mount is ready for heavy I/O workloads (e.g. buck2).
     ///
@@ -334,6 +848,16 @@ pub trait AntaresService: Send + Sync {
     /// Background kernel warmup (Phase 2) is intentionally non-blocking.
     async fn check_mount_ready(&self, mount_id: Uuid) -> Result<MountReadyResponse, ServiceError>;
 
+    /// Subscribe to the state transitions, preload progress and removals of all mounts.
+    ///
+    /// Only events sent after subscribing are received; pair with `describe_mount` or
+    /// `list_mounts` for the current state.
+    fn subscribe_events(&self) -> broadcast::Receiver<MountEvent>;
+
+    /// Remove layer directories, stale FUSE mounts and Dicfuse views and stores that no
+    /// live or provisioning mount refers to. With `dry_run`, only report them.
+    async fn collect_garbage(&self, request: GcRequest) -> Result<GcReport, ServiceError>;
+
     async fn health_info(&self) -> HealthResponse;
     async fn shutdown_cleanup(&self) -> Result<(), ServiceError>;
 }
@@ -346,10 +870,11 @@ pub trait AntaresService: Send + Sync {
 /// Paths are auto-generated using UUID-based naming under configured root directories:
 /// - `mountpoint`: `{antares_mount_root}/{uuid}` (e.g., `/var/lib/antares/mounts/550e8400-e29b-41d4-a716-446655440000`)
 /// - `upper_dir`: `{antares_upper_root}/{uuid}` (e.g., `/var/lib/antares/upper/550e8400-e29b-41d4-a716-446655440000`)
-/// - `cl_dir`: `{antares_cl_root}/{uuid}` (only if `cl` is provided)
+/// - CL layers: `{antares_cl_root}/{uuid}` for the first CL, `{antares_cl_root}/{uuid}.{n}`
+///   for the n-th one after it (only if `cl`/`cls` is provided)
 ///
 /// The UUID is generated per mount request, ensuring unique paths for each mount instance.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct CreateMountRequest {
     /// Optional build task identifier (job-level mount). When provided, Antares will treat
     /// mount creation as idempotent for the same task id.
@@ -361,60 +886,602 @@ pub struct CreateMountRequest {
     /// are provided, `job_id` takes precedence.
     #[serde(default)]
     pub build_id: Option<String>,
-    /// Monorepo path to mount (e.g., "/third-party/mega")
+    /// Monorepo path to mount (e.g., "/third-party/mega"). Mutually exclusive with `bindings`.
+    #[serde(default)]
     pub path: String,
+    /// Monorepo subtrees composed into one view, each served by its own Dicfuse view.
+    /// Paths outside every binding do not exist. Mutually exclusive with `path`.
+    #[serde(default)]
+    pub bindings: Vec<MountBinding>,
     /// Optional CL (changelist) identifier for the CL layer
     #[serde(default)]
     pub cl: Option<String>,
+    /// Ordered CL stack, applied bottom first; later CLs override earlier ones.
+    /// Mutually exclusive with `cl`.
+    #[serde(default)]
+    pub cls: Vec<String>,
+    /// Per-mount overrides of the global upper-layer quota; they can only tighten it.
+    #[serde(default)]
+    pub quota: Option<QuotaLimits>,
+    /// Per-mount overrides of the Dicfuse and deep-preload knobs.
+    #[serde(default)]
+    pub tuning: Option<MountTuning>,
+    /// Mount without an upper layer. Writes fail with `EROFS` and no `upper_dir` is created.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Where the upper layer lives. `memory` keeps writes in RAM, bounded by
+    /// `quota.max_bytes` (default `antares_memory_upper_max_bytes`).
+    #[serde(default)]
+    pub upper: UpperKind,
+    /// Return as soon as the request is validated, with the mount in `Provisioning`,
+    /// instead of waiting for Dicfuse and the CL layers. Progress and failures are
+    /// reported by describe and the event streams.
+    #[serde(default, rename = "async")]
+    pub background: bool,
+    /// http(s) URL notified with a signed POST on every state change of this mount,
+    /// in addition to the daemon-wide `antares_webhook_urls`.
+    #[serde(default)]
+    pub callback_url: Option<String>,
+    /// Free-form labels (pipeline, team, target, ...) for filtering and bulk deletion.
+    /// See [`labels`] for the allowed keys and values.
+    #[serde(default)]
+    pub labels: Labels,
+    /// Absolute directory to mount at instead of one generated under `antares_mount_root`.
+    /// It must lie under one of `antares_mountpoint_prefixes` and be missing or an empty
+    /// directory.
+    #[serde(default)]
+    pub mountpoint: Option<String>,
+    /// Accept any `mountpoint`, ignoring `antares_mountpoint_prefixes`. Set by in-process
+    /// callers such as [`crate::antares::AntaresManager`]; never read from the body.
+    #[serde(skip)]
+    pub unrestricted_mountpoint: bool,
+    /// Authenticated caller, filled in by the HTTP layer; never read from the body.
+    #[serde(skip)]
+    pub created_by: Option<String>,
 }
 
-/// Request payload for building/rebuilding a CL layer.
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct BuildClRequest {
-    /// CL (changelist) link identifier
-    pub cl: String,
+/// A monorepo subtree served at a path inside a composite mount.
+///
+/// Neither the sources nor the targets of one mount may contain one another.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct MountBinding {
+    /// Monorepo path to serve (e.g., "/libs/common").
+    pub source: String,
+    /// Where it appears, relative to the mount root (e.g., "libs/common"). Defaults to
+    /// the source path.
+    #[serde(default)]
+    pub target: String,
 }
 
-/// Response returned after mount creation succeeds.
-/// Only contains the essential information the caller needs.
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct MountCreated {
-    /// Unique identifier for this mount
-    pub mount_id: Uuid,
-    /// The actual filesystem path where the mount is accessible
-    pub mountpoint: String,
+impl MountBinding {
+    /// The whole mount serving `path`: how CL paths are placed in a plain mount.
+    fn whole(path: &str) -> Self {
+        Self {
+            source: path.to_string(),
+            target: String::new(),
+        }
+    }
 }
 
-/// Snapshot of a single mount's state.
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct MountStatus {
-    pub mount_id: Uuid,
-    /// Optional build task identifier (job/build id) associated with this mount.
-    #[serde(default)]
-    pub job_id: Option<String>,
-    /// The monorepo path being mounted
-    pub path: String,
-    /// Optional CL identifier
-    pub cl: Option<String>,
-    /// The actual filesystem mountpoint
-    pub mountpoint: String,
-    pub layers: MountLayers,
-    pub state: MountLifecycle,
-    pub created_at_epoch_ms: u64,
-    pub last_seen_epoch_ms: u64,
+/// Backing store of a writable mount's upper layer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpperKind {
+    /// Passthrough directory under `antares_upper_root`.
+    #[default]
+    Disk,
+    /// In-memory layer: no disk I/O, contents are dropped on unmount and not recovered
+    /// after a daemon restart.
+    Memory,
 }
 
-/// Convenience wrapper used by list endpoints.
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct MountCollection {
-    pub mounts: Vec<MountStatus>,
+/// Per-mount overrides of Dicfuse and background-warmup settings.
+///
+/// Every field is optional; unset fields fall back to `scorpio.toml` (Dicfuse knobs) or
+/// the `ANTARES_DEEP_PRELOAD_*` environment variables (warmup knobs). Mounts with the
+/// same path and the same Dicfuse overrides share one Dicfuse view.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MountTuning {
+    /// Overrides `antares_dicfuse_stat_mode` (`fast` | `accurate`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stat_mode: Option<DicfuseStatMode>,
+    /// Overrides `antares_load_dir_depth`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load_dir_depth: Option<usize>,
+    /// Overrides `antares_dicfuse_reply_ttl_secs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_ttl_secs: Option<u64>,
+    /// Overrides `antares_dicfuse_dir_sync_ttl_secs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir_sync_ttl_secs: Option<u64>,
+    /// Overrides `ANTARES_DEEP_PRELOAD_MODE`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deep_preload_mode: Option<DeepPreloadMode>,
+    /// Overrides `ANTARES_DEEP_PRELOAD_MAX_DEPTH` (capped at 64).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deep_preload_max_depth: Option<usize>,
+    /// Overrides `ANTARES_DEEP_PRELOAD_MAX_MS` (`0` = no budget, capped at 120000).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deep_preload_max_ms: Option<u64>,
+    /// Overrides `ANTARES_DEEP_PRELOAD_WORKERS` (clamped to 1..=64).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deep_preload_workers: Option<usize>,
 }
 
-/// Directory layout for a mount.
+impl MountTuning {
+    /// The subset of overrides that shapes the Dicfuse view.
+    pub fn dicfuse(&self) -> DicfuseTuning {
+        DicfuseTuning {
+            stat_mode: self.stat_mode,
+            load_dir_depth: self.load_dir_depth,
+            reply_ttl_secs: self.reply_ttl_secs,
+            dir_sync_ttl_secs: self.dir_sync_ttl_secs,
+        }
+    }
+
+    /// Deep-preload settings for this mount: environment defaults with overrides applied.
+    fn deep_preload(&self) -> DeepPreloadOptions {
+        let mut opts = DeepPreloadOptions::from_env();
+        if let Some(mode) = self.deep_preload_mode {
+            opts.mode = mode;
+        }
+        if let Some(depth) = self.deep_preload_max_depth {
+            opts.max_depth = depth.min(64);
+        }
+        if let Some(ms) = self.deep_preload_max_ms {
+            opts.max_duration = (ms > 0).then(|| Duration::from_millis(ms.min(120_000)));
+        }
+        if let Some(workers) = self.deep_preload_workers {
+            opts.workers = workers.clamp(1, 64);
+        }
+        opts
+    }
+}
+
+/// Request payload for building/rebuilding the CL layers. Replaces the whole stack.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct BuildClRequest {
+    /// CL (changelist) link identifier
+    #[serde(default)]
+    pub cl: Option<String>,
+    /// Ordered CL stack, applied bottom first. Mutually exclusive with `cl`.
+    #[serde(default)]
+    pub cls: Vec<String>,
+    /// Build a single layer from a local patch or archive instead of the Mega CL API.
+    /// Mutually exclusive with `cl` and `cls`.
+    #[serde(default)]
+    pub local: Option<LocalClRequest>,
+}
+
+/// Local source of a CL layer. Paths are monorepo paths, as in a CL files-list; those
+/// outside the mount are skipped.
+///
+/// The archive is unpacked first, then the patch is applied, then `deleted` is removed.
+/// At least one of `patch`, `archive` and `deleted` must be set.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct LocalClRequest {
+    /// Name recorded as the mount's CL stack (defaults to `local`).
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Unified diff or git-format patch; rejected unless every hunk applies to the base.
+    #[serde(default)]
+    pub patch: Option<String>,
+    /// Leading path components to strip from patch paths, like `patch -p`.
+    /// By default the `a/` / `b/` prefixes are dropped.
+    #[serde(default)]
+    pub strip: Option<usize>,
+    /// Base64-encoded tarball (optionally gzip-compressed) of changed files.
+    #[serde(default)]
+    pub archive: Option<String>,
+    /// Files or directories to delete.
+    #[serde(default)]
+    pub deleted: Vec<String>,
+}
+
+impl LocalClRequest {
+    /// Stack entry recorded for the layer.
+    fn cl_name(&self) -> Result<String, ServiceError> {
+        match self.name.as_deref().map(str::trim) {
+            None => Ok("local".to_string()),
+            Some("") => Err(ServiceError::InvalidRequest(
+                "local CL name cannot be empty".into(),
+            )),
+            Some(name) => Ok(name.to_string()),
+        }
+    }
+
+    /// Decode the archive and parse the patch.
+    fn parse(&self) -> Result<LocalCl, ServiceError> {
+        if self.patch.is_none() && self.archive.is_none() && self.deleted.is_empty() {
+            return Err(ServiceError::InvalidRequest(
+                "local CL needs a patch, an archive or deleted paths".into(),
+            ));
+        }
+        let archive = match &self.archive {
+            Some(encoded) => {
+                let data = base64::engine::general_purpose::STANDARD
+                    .decode(encoded.trim())
+                    .map_err(|e| {
+                        ServiceError::InvalidRequest(format!("archive is not valid base64: {}", e))
+                    })?;
+                local_cl::read_archive(&data).map_err(|e| {
+                    ServiceError::InvalidRequest(format!("failed to read archive: {}", e))
+                })?
+            }
+            None => Vec::new(),
+        };
+        let patches = match &self.patch {
+            Some(patch) => local_cl::parse_patch(patch, self.strip)
+                .map_err(|e| ServiceError::InvalidRequest(e.to_string()))?,
+            None => Vec::new(),
+        };
+        Ok(LocalCl {
+            archive,
+            patches,
+            deleted: self.deleted.clone(),
+        })
+    }
+}
+
+/// Resolve the `cl` / `cls` pair of a request into the CL stack, bottom first.
+fn cl_stack_from(cl: Option<&str>, cls: &[String]) -> Result<Vec<String>, ServiceError> {
+    if cl.is_some() && !cls.is_empty() {
+        return Err(ServiceError::InvalidRequest(
+            "set either cl or cls, not both".into(),
+        ));
+    }
+    let stack: Vec<String> = match cl {
+        Some(cl) => vec![cl.to_string()],
+        None => cls.to_vec(),
+    };
+    for (i, cl) in stack.iter().enumerate() {
+        if cl.trim().is_empty() {
+            return Err(ServiceError::InvalidRequest(
+                "cl link cannot be empty".to_string(),
+            ));
+        }
+        if stack[..i].contains(cl) {
+            return Err(ServiceError::InvalidRequest(format!(
+                "cl {} appears more than once in the stack",
+                cl
+            )));
+        }
+    }
+    Ok(stack)
+}
+
+/// Directory of the `index`-th CL layer (bottom first) of mount `id`.
+///
+/// The first layer keeps the single-CL location so older state files stay valid.
+fn cl_layer_dir(cl_root: &str, id: &str, index: usize) -> String {
+    match index {
+        0 => format!("{}/{}", cl_root, id),
+        n => format!("{}/{}.{}", cl_root, id, n),
+    }
+}
+
+/// Request payload for forking an existing mount.
+///
+/// The fork gets fresh UUID-based paths, a copy of the source's upper and CL
+/// directories, and the same monorepo path / CL identifier as the source.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ForkMountRequest {
+    /// Optional build task identifier for the new mount. Must not already be mounted.
+    #[serde(default)]
+    pub job_id: Option<String>,
+    /// Labels of the fork, set on top of the ones inherited from the source.
+    #[serde(default)]
+    pub labels: Labels,
+    /// Authenticated caller, filled in by the HTTP layer; never read from the body.
+    #[serde(skip)]
+    pub created_by: Option<String>,
+}
+
+/// Request payload for checkpointing a mount's upper layer.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CreateCheckpointRequest {
+    /// Optional label, e.g. the build step that just completed.
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Checkpoints of one mount plus its checkpoint storage accounting.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CheckpointCollection {
+    pub mount_id: Uuid,
+    /// Checkpoints ordered oldest first.
+    pub checkpoints: Vec<CheckpointInfo>,
+    /// Bytes currently held by this mount's checkpoints.
+    pub used_bytes: u64,
+    /// Per-mount checkpoint budget (`antares_checkpoint_max_bytes`).
+    pub limit_bytes: u64,
+}
+
+/// Upper-layer consumption of one mount, freshly counted.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MountUsage {
+    pub mount_id: Uuid,
+    #[serde(flatten)]
+    pub usage: UpperUsage,
+}
+
+/// Phase of a mount's most recent CL build.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClBuildState {
+    /// No CL build has run since the daemon started.
+    #[default]
+    Idle,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// Progress of a mount's most recent CL build.
+///
+/// Totals grow as each layer's files-list is fetched, so `files_total` is only final once
+/// the last layer has started.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClBuildProgress {
+    pub mount_id: Uuid,
+    pub state: ClBuildState,
+    /// CL stack being built, bottom first.
+    #[serde(default)]
+    pub cls: Vec<String>,
+    /// Index into `cls` of the layer currently being built.
+    #[serde(default)]
+    pub layer: usize,
+    /// Downloads and whiteouts needed so far; unchanged paths are not counted.
+    #[serde(default)]
+    pub files_total: u64,
+    #[serde(default)]
+    pub files_done: u64,
+    #[serde(default)]
+    pub bytes_done: u64,
+    /// Downloads that failed; any failure aborts the build.
+    #[serde(default)]
+    pub failures: u64,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub started_at_epoch_ms: u64,
+    #[serde(default)]
+    pub finished_at_epoch_ms: Option<u64>,
+}
+
+/// Response returned after mount creation succeeds.
+/// Only contains the essential information the caller needs.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MountCreated {
+    /// Unique identifier for this mount
+    pub mount_id: Uuid,
+    /// The actual filesystem path where the mount is accessible
+    pub mountpoint: String,
+    /// `Provisioning` for asynchronous creations still in progress, otherwise the
+    /// state of the (possibly pre-existing) mount.
+    pub state: MountLifecycle,
+}
+
+/// Snapshot of a single mount's state.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MountStatus {
+    pub mount_id: Uuid,
+    /// Optional build task identifier (job/build id) associated with this mount.
+    #[serde(default)]
+    pub job_id: Option<String>,
+    /// The monorepo path being mounted; empty for composite mounts.
+    pub path: String,
+    /// Subtrees composed into the mount, if it is a composite one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bindings: Vec<MountBinding>,
+    /// Optional CL identifier; the topmost CL when several are stacked.
+    pub cl: Option<String>,
+    /// Full CL stack, bottom first.
+    #[serde(default)]
+    pub cls: Vec<String>,
+    /// Paths supplied by more than one CL, with the CL each is served from.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cl_conflicts: Vec<ClConflict>,
+    /// The actual filesystem mountpoint
+    pub mountpoint: String,
+    /// Source mount this one was forked from, if any.
+    #[serde(default)]
+    pub forked_from: Option<Uuid>,
+    /// Whether the mount has no upper layer.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Backing store of the upper layer; `None` for read-only mounts.
+    #[serde(default)]
+    pub upper_kind: Option<UpperKind>,
+    /// Upper-layer consumption and quota as of the last accounting update.
+    #[serde(default)]
+    pub usage: Option<UpperUsage>,
+    /// Per-mount tuning overrides, if any were requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tuning: Option<MountTuning>,
+    /// Per-mount webhook, if one was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    /// Name of the authenticated caller that created the mount (absent when auth is off).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    /// Labels given at creation.
+    #[serde(default, skip_serializing_if = "Labels::is_empty")]
+    pub labels: Labels,
+    pub layers: MountLayers,
+    pub state: MountLifecycle,
+    pub created_at_epoch_ms: u64,
+    pub last_seen_epoch_ms: u64,
+}
+
+/// Convenience wrapper used by list endpoints.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MountCollection {
+    pub mounts: Vec<MountStatus>,
+    /// Number of mounts matching the filters, across all pages.
+    #[serde(default)]
+    pub total: usize,
+    /// `offset` of the next page; absent on the last one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+}
+
+/// Query parameters of `GET /mounts` and `DELETE /mounts`.
+///
+/// All filters must match. `DELETE /mounts` requires at least one filter and ignores
+/// the pagination parameters.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct MountQuery {
+    /// Label selector, e.g. `pipeline=1234,team!=infra,!keep` (see [`LabelSelector`]).
+    #[serde(default)]
+    pub selector: Option<String>,
+    /// Comma-separated lifecycle states, e.g. `Mounted,Ready`; case-insensitive.
+    #[serde(default)]
+    pub state: Option<String>,
+    /// Monorepo path prefix, compared by whole components. Composite mounts match
+    /// through any of their binding sources.
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    /// Page size, at least 1; every match when unset.
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Matches to skip, usually the `next_offset` of the previous page.
+    #[serde(default)]
+    pub offset: Option<usize>,
+}
+
+impl MountQuery {
+    /// Parse the filters, rejecting malformed selectors and unknown states.
+    pub fn filter(&self) -> Result<MountFilter, ServiceError> {
+        let selector = match self.selector.as_deref() {
+            Some(selector) => selector.parse().map_err(ServiceError::InvalidRequest)?,
+            None => LabelSelector::default(),
+        };
+        let mut states = Vec::new();
+        for state in self.state.iter().flat_map(|s| s.split(',')) {
+            let state = state.trim();
+            if state.is_empty() {
+                continue;
+            }
+            match MountLifecycle::NAMES
+                .iter()
+                .find(|name| name.eq_ignore_ascii_case(state))
+            {
+                Some(name) => states.push(*name),
+                None => {
+                    return Err(ServiceError::InvalidRequest(format!(
+                        "unknown mount state {:?}; expected one of {}",
+                        state,
+                        MountLifecycle::NAMES.join(", ")
+                    )))
+                }
+            }
+        }
+        let path_prefix = self
+            .path_prefix
+            .as_deref()
+            .map(AntaresServiceImpl::normalize_mount_path)
+            .filter(|p| !p.is_empty());
+        Ok(MountFilter {
+            selector,
+            states,
+            path_prefix,
+        })
+    }
+}
+
+/// Parsed filters of a [`MountQuery`].
+#[derive(Debug, Clone, Default)]
+pub struct MountFilter {
+    selector: LabelSelector,
+    states: Vec<&'static str>,
+    path_prefix: Option<String>,
+}
+
+impl MountFilter {
+    /// Whether the filter matches every mount.
+    pub fn is_empty(&self) -> bool {
+        self.selector.is_empty() && self.states.is_empty() && self.path_prefix.is_none()
+    }
+
+    pub fn matches(&self, mount: &MountStatus) -> bool {
+        if !self.selector.matches(&mount.labels) {
+            return false;
+        }
+        if !self.states.is_empty() && !self.states.contains(&mount.state.name()) {
+            return false;
+        }
+        match &self.path_prefix {
+            Some(prefix) if mount.bindings.is_empty() => Path::new(&mount.path).starts_with(prefix),
+            Some(prefix) => mount
+                .bindings
+                .iter()
+                .any(|b| Path::new(&b.source).starts_with(prefix)),
+            None => true,
+        }
+    }
+}
+
+/// Result of `DELETE /mounts`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct BulkDeleteResponse {
+    /// Final status of each removed mount.
+    pub deleted: Vec<MountStatus>,
+    /// Mounts that matched but could not be removed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<BulkDeleteFailure>,
+}
+
+/// A mount `DELETE /mounts` failed to remove.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BulkDeleteFailure {
+    pub mount_id: Uuid,
+    pub error: String,
+}
+
+/// Request payload of `POST /admin/gc`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GcRequest {
+    /// Report what would be removed without removing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Keep orphans modified within this many seconds; defaults to
+    /// `antares_gc_retention_secs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_secs: Option<u64>,
+}
+
+/// Result of `POST /admin/gc`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GcReport {
+    pub dry_run: bool,
+    /// Retention window the pass used.
+    pub retention_secs: u64,
+    /// Paths removed, or that would be removed in a dry run.
+    pub removed: Vec<GcEntry>,
+    /// Orphans that could not be removed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<GcFailure>,
+}
+
+/// An orphan `POST /admin/gc` failed to remove.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GcFailure {
+    #[serde(flatten)]
+    pub entry: GcEntry,
+    pub error: String,
+}
+
+/// Directory layout for a mount.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MountLayers {
-    pub upper: String,
+    /// Upper directory; `None` for read-only mounts.
+    pub upper: Option<String>,
+    /// Directory of the topmost CL layer.
     pub cl: Option<String>,
+    /// Directories of all CL layers, bottom first.
+    #[serde(default)]
+    pub cl_dirs: Vec<String>,
     pub dicfuse: String,
 }
 
@@ -437,6 +1504,32 @@ pub enum MountLifecycle {
     },
 }
 
+impl MountLifecycle {
+    /// Names of the states, as accepted by the `state` filter of `GET /mounts`.
+    pub const NAMES: [&'static str; 7] = [
+        "Provisioning",
+        "Mounted",
+        "Ready",
+        "Quiescing",
+        "Unmounting",
+        "Unmounted",
+        "Failed",
+    ];
+
+    /// The variant name, without the failure reason.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Provisioning => "Provisioning",
+            Self::Mounted => "Mounted",
+            Self::Ready => "Ready",
+            Self::Quiescing => "Quiescing",
+            Self::Unmounting => "Unmounting",
+            Self::Unmounted => "Unmounted",
+            Self::Failed { .. } => "Failed",
+        }
+    }
+}
+
 /// Response for the `/mounts/{mount_id}/ready` readiness probe.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MountReadyResponse {
@@ -448,6 +1541,97 @@ pub struct MountReadyResponse {
     pub state: MountLifecycle,
 }
 
+impl MountStatus {
+    /// The current state as an event, used to open a stream.
+    fn into_event(self) -> MountEvent {
+        MountEvent {
+            mount_id: self.mount_id,
+            epoch_ms: current_epoch_ms(),
+            kind: MountEventKind::State { state: self.state },
+        }
+    }
+}
+
+/// One change to a mount, as streamed by `/events` and `/mounts/{mount_id}/events`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MountEvent {
+    pub mount_id: Uuid,
+    pub epoch_ms: u64,
+    #[serde(flatten)]
+    pub kind: MountEventKind,
+}
+
+/// What happened to a mount; serialized with an `event` tag.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum MountEventKind {
+    /// The mount entered `state` (including `Failed`).
+    State { state: MountLifecycle },
+    /// The background kernel cache warmup started.
+    PreloadStarted,
+    /// The background kernel cache warmup finished or ran out of budget.
+    PreloadFinished {
+        entries_visited: usize,
+        metadata_touches: usize,
+        budget_exhausted: bool,
+        elapsed_ms: u64,
+    },
+    /// The background kernel cache warmup stopped on an error.
+    PreloadFailed { error: String },
+    /// The mount was deleted and is no longer tracked; ends a per-mount stream.
+    Removed,
+    /// The daemon is shutting down (sent with a nil `mount_id`); ends every stream.
+    Shutdown,
+}
+
+impl MountEventKind {
+    /// SSE event name, matching the `event` tag.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            MountEventKind::State { .. } => "state",
+            MountEventKind::PreloadStarted => "preload_started",
+            MountEventKind::PreloadFinished { .. } => "preload_finished",
+            MountEventKind::PreloadFailed { .. } => "preload_failed",
+            MountEventKind::Removed => "removed",
+            MountEventKind::Shutdown => "shutdown",
+        }
+    }
+}
+
+/// Broadcast channel carrying the events of every mount.
+///
+/// Slow subscribers that fall more than [`MountEvents::CAPACITY`] events behind skip
+/// ahead and are told how many they missed.
+#[derive(Debug, Clone)]
+pub struct MountEvents(broadcast::Sender<MountEvent>);
+
+impl MountEvents {
+    const CAPACITY: usize = 1024;
+
+    pub fn new() -> Self {
+        Self(broadcast::channel(Self::CAPACITY).0)
+    }
+
+    pub fn emit(&self, mount_id: Uuid, kind: MountEventKind) {
+        // No subscribers is not an error.
+        let _ = self.0.send(MountEvent {
+            mount_id,
+            epoch_ms: current_epoch_ms(),
+            kind,
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MountEvent> {
+        self.0.subscribe()
+    }
+}
+
+impl Default for MountEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Health check response payload.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct HealthResponse {
@@ -455,6 +1639,9 @@ pub struct HealthResponse {
     pub status: String,
     /// Current number of active mounts
     pub mount_count: usize,
+    /// Mounts in the `Failed` state; any makes the service "degraded"
+    #[serde(default)]
+    pub failed_mounts: usize,
     /// Service uptime in seconds
     pub uptime_secs: u64,
 }
@@ -477,6 +1664,10 @@ pub enum ServiceError {
     NotFound(Uuid),
     #[error("mount not found for task id: {0}")]
     NotFoundTask(String),
+    #[error("checkpoint not found: {0}")]
+    CheckpointNotFound(Uuid),
+    #[error("storage limit exceeded: {0}")]
+    LimitExceeded(String),
     #[error("failed to interact with fuse stack: {0}")]
     FuseFailure(String),
     #[error("unexpected error: {0}")]
@@ -510,6 +1701,16 @@ impl IntoResponse for ApiError {
                 "NOT_FOUND",
                 format!("mount for task {} not found", task),
             ),
+            ApiError::Service(ServiceError::CheckpointNotFound(id)) => (
+                StatusCode::NOT_FOUND,
+                "NOT_FOUND",
+                format!("checkpoint {} not found", id),
+            ),
+            ApiError::Service(ServiceError::LimitExceeded(msg)) => (
+                StatusCode::INSUFFICIENT_STORAGE,
+                "LIMIT_EXCEEDED",
+                msg.clone(),
+            ),
             ApiError::Service(ServiceError::FuseFailure(msg)) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, "FUSE_ERROR", msg.clone())
             }
@@ -544,22 +1745,42 @@ struct MountEntry {
     mount_id: Uuid,
     /// Optional build task identifier (job/build id) associated with this mount.
     job_id: Option<String>,
-    /// The monorepo path being mounted
+    /// The monorepo path being mounted; empty for composite mounts
     path: String,
-    /// Optional CL identifier
-    cl: Option<String>,
+    /// Subtrees of a composite mount, sorted by target (empty for plain mounts)
+    bindings: Vec<MountBinding>,
+    /// CL stack, bottom first (empty without CLs)
+    cls: Vec<String>,
     /// Auto-generated mountpoint path
     mountpoint: String,
-    /// Auto-generated upper directory
-    upper_dir: String,
-    /// Auto-generated CL directory (if cl is provided)
-    cl_dir: Option<String>,
+    /// Auto-generated upper directory; `None` for read-only mounts
+    upper_dir: Option<String>,
+    /// Auto-generated CL layer directories, parallel to `cls`
+    cl_dirs: Vec<String>,
+    /// Paths supplied by more than one CL, as of the last CL build.
+    cl_conflicts: Vec<ClConflict>,
+    /// Source mount this one was forked from, if any.
+    forked_from: Option<Uuid>,
+    /// Usage counters and limits for the upper layer (absent for read-only mounts).
+    quota: Option<Arc<UpperQuota>>,
+    /// RAM-backed upper layer; set instead of `upper_dir` and `quota`.
+    mem_upper: Option<Arc<MemUpperLayer>>,
+    /// Per-mount Dicfuse / deep-preload overrides.
+    tuning: Option<MountTuning>,
+    /// Webhook registered by the creator, if any.
+    callback_url: Option<String>,
+    /// Authenticated caller that created the mount.
+    created_by: Option<String>,
+    /// Labels given at creation (plus those inherited by a fork).
+    labels: Labels,
     fuse: AntaresFuse,
     state: MountLifecycle,
     created_at_epoch_ms: u64,
     last_seen_epoch_ms: u64,
     /// Signal for the background deep-preload task to stop early (e.g. on unmount).
     preload_cancel: Arc<AtomicBool>,
+    /// Where state transitions are announced.
+    events: MountEvents,
 }
 
 #[derive(Debug, Deserialize)]
@@ -569,299 +1790,169 @@ struct CommonResult<T> {
     err_message: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct ClFileEntry {
-    path: String,
-    sha: String,
-    action: String,
-}
+/// Live progress of one CL build, shared with its download workers.
+#[derive(Clone)]
+struct ClProgress(Arc<Mutex<ClBuildProgress>>);
 
-impl MountEntry {
-    /// Convert to public MountStatus for API responses.
-    fn to_status(&self) -> MountStatus {
-        MountStatus {
-            mount_id: self.mount_id,
-            job_id: self.job_id.clone(),
-            path: self.path.clone(),
-            cl: self.cl.clone(),
-            mountpoint: self.mountpoint.clone(),
-            layers: MountLayers {
-                upper: self.upper_dir.clone(),
-                cl: self.cl_dir.clone(),
-                dicfuse: "shared".to_string(),
-            },
-            state: self.state.clone(),
-            created_at_epoch_ms: self.created_at_epoch_ms,
-            last_seen_epoch_ms: self.last_seen_epoch_ms,
-        }
+impl ClProgress {
+    fn start(mount_id: Uuid, cls: &[String]) -> Self {
+        Self(Arc::new(Mutex::new(ClBuildProgress {
+            mount_id,
+            state: ClBuildState::Running,
+            cls: cls.to_vec(),
+            started_at_epoch_ms: current_epoch_ms(),
+            ..ClBuildProgress::default()
+        })))
     }
 
-    /// Update the last_seen timestamp.
-    fn update_last_seen(&mut self) {
-        self.last_seen_epoch_ms = current_epoch_ms();
+    fn update(&self, f: impl FnOnce(&mut ClBuildProgress)) {
+        let mut progress = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut progress);
     }
-}
 
-/// Get current time as milliseconds since UNIX epoch.
-fn current_epoch_ms() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64
-}
+    fn snapshot(&self) -> ClBuildProgress {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
 
-/// Type alias for path index: maps (monorepo_path, optional_cl) to mount_id.
-type PathIndex = Arc<RwLock<HashMap<(String, Option<String>), Uuid>>>;
-/// Type alias for job index: maps a build task id (job_id/build_id) to mount_id.
-type JobIndex = Arc<RwLock<HashMap<String, Uuid>>>;
+    fn is_running(&self) -> bool {
+        self.snapshot().state == ClBuildState::Running
+    }
 
-/// Persisted mount state for recovery across restarts.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PersistedMountState {
-    pub mount_id: Uuid,
-    #[serde(default)]
-    pub job_id: Option<String>,
-    pub path: String,
-    pub cl: Option<String>,
-    pub mountpoint: String,
-    pub upper_dir: String,
-    pub cl_dir: Option<String>,
-    pub created_at_epoch_ms: u64,
-}
-
-/// Persisted state file structure.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct PersistedState {
-    pub mounts: Vec<PersistedMountState>,
+    fn finish(&self, result: Result<(), &ServiceError>) {
+        self.update(|p| {
+            p.state = match result {
+                Ok(()) => ClBuildState::Succeeded,
+                Err(_) => ClBuildState::Failed,
+            };
+            p.error = result.err().map(|e| e.to_string());
+            p.finished_at_epoch_ms = Some(current_epoch_ms());
+        });
+    }
 }
 
-/// Concrete implementation of AntaresService.
-pub struct AntaresServiceImpl {
-    /// Shared Dicfuse instance for root path (read-only base layer).
-    dicfuse: Arc<Dicfuse>,
-    /// Cache of Dicfuse instances keyed by base_path for subdirectory mounts.
-    /// This avoids creating duplicate instances for the same path.
-    dicfuse_cache: Arc<RwLock<HashMap<String, Arc<Dicfuse>>>>,
-    /// Active mounts indexed by UUID.
-    mounts: Arc<RwLock<HashMap<Uuid, MountEntry>>>,
-    /// Fast lookup for (path, cl) -> mount_id to avoid linear scans.
-    path_index: PathIndex,
-    /// Fast lookup for (job_id/build_id) -> mount_id for task-granularity mounts.
-    job_index: JobIndex,
-    /// Service start time for uptime calculation.
-    start_time: Instant,
-    /// Path to the state file for persistence.
-    state_file: PathBuf,
+/// One entry of a CL files-list.
+#[derive(Debug, Deserialize)]
+struct ClFileEntry {
+    path: String,
+    #[serde(default)]
+    sha: String,
+    /// `new`, `modified`, `renamed` or `deleted`.
+    action: String,
+    /// Git mode as an octal string (`100644`, `100755`, `120000`, `040000`).
+    #[serde(default)]
+    mode: Option<String>,
+    /// Previous path of a `renamed` entry.
+    #[serde(default, alias = "from")]
+    old_path: Option<String>,
 }
 
-impl AntaresServiceImpl {
-    /// Create a new service instance.
-    ///
-    /// # Arguments
-    /// * `dicfuse` - Optional shared Dicfuse instance. If None, creates a new one.
-    ///
-    /// # Note
-    /// Requires config to be initialized via `config::init_config()` before calling.
-    pub async fn new(dicfuse: Option<Arc<Dicfuse>>) -> Self {
-        let dic = match dicfuse {
-            Some(d) => d,
-            None => DicfuseManager::global().await,
-        };
-        let state_file = PathBuf::from(crate::util::config::antares_state_file());
-        Self {
-            dicfuse: dic,
-            dicfuse_cache: Arc::new(RwLock::new(HashMap::new())),
-            mounts: Arc::new(RwLock::new(HashMap::new())),
-            path_index: Arc::new(RwLock::new(HashMap::new())),
-            job_index: Arc::new(RwLock::new(HashMap::new())),
-            start_time: Instant::now(),
-            state_file,
-        }
-    }
-
-    /// Create a new service instance and recover previous mounts if available.
-    ///
-    /// # Arguments
-    /// * `dicfuse` - Optional shared Dicfuse instance. If None, creates a new one.
-    ///
-    /// # Note
-    /// Requires config to be initialized via `config::init_config()` before calling.
-    pub async fn new_with_recovery(dicfuse: Option<Arc<Dicfuse>>) -> Self {
-        let instance = Self::new(dicfuse).await;
-        instance.recover_mounts().await;
-        instance
-    }
-
-    fn normalize_mount_path(path: &str) -> String {
-        let trimmed = path.trim();
-        if trimmed.is_empty() {
-            return String::new();
-        }
-        if trimmed == "/" {
-            return "/".to_string();
-        }
-        let mut normalized = if trimmed.starts_with('/') {
-            trimmed.to_string()
-        } else {
-            format!("/{trimmed}")
-        };
-        normalized = normalized.trim_end_matches('/').to_string();
-        if normalized.is_empty() {
-            "/".to_string()
-        } else {
-            normalized
-        }
-    }
-
-    fn normalize_abs_path(path: &str) -> String {
-        let trimmed = path.trim();
-        if trimmed.is_empty() {
-            return "/".to_string();
-        }
-        if trimmed == "/" {
-            return "/".to_string();
-        }
-        let mut normalized = if trimmed.starts_with('/') {
-            trimmed.to_string()
-        } else {
-            format!("/{trimmed}")
+/// Turn a CL files-list into the manifest of the layer it builds for a mount with `view`.
+///
+/// A rename whites out its old path unless the CL puts something else there, and deleted
+/// directories the CL fills again become opaque (see [`ClManifest::resolve_deleted_dirs`]).
+fn cl_manifest_from_files(
+    cl_link: &str,
+    view: &[MountBinding],
+    files: Vec<ClFileEntry>,
+) -> ClManifest {
+    let mut manifest = ClManifest {
+        cl: cl_link.to_string(),
+        ..ClManifest::default()
+    };
+    let mut renamed_from = Vec::new();
+    for file in files {
+        let rel_path = match AntaresServiceImpl::relative_path_in_view(&file.path, view) {
+            Some(p) => p.to_string_lossy().into_owned(),
+            None => continue,
         };
-        normalized = normalized.trim_end_matches('/').to_string();
-        if normalized.is_empty() {
-            "/".to_string()
-        } else {
-            normalized
-        }
-    }
-
-    fn relative_path_for_mount(entry_path: &str, mount_path: &str) -> Option<PathBuf> {
-        let entry = Self::normalize_abs_path(entry_path);
-        let mount = Self::normalize_abs_path(mount_path);
-        if mount == "/" {
-            let rel = entry.trim_start_matches('/');
-            if rel.is_empty() {
-                return None;
-            }
-            return Self::validated_relative_path(rel);
-        }
-
-        let prefix = format!("{}/", mount);
-        if !entry.starts_with(&prefix) {
-            return None;
-        }
-        let rel = entry[prefix.len()..].trim_start_matches('/');
-        if rel.is_empty() {
-            return None;
-        }
-        Self::validated_relative_path(rel)
-    }
-
-    fn validated_relative_path(rel: &str) -> Option<PathBuf> {
-        let rel_path = Path::new(rel);
-        let components = rel_path.components();
-        for component in components {
-            match component {
-                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
-                    return None;
+        match file.action.as_str() {
+            "new" | "modified" | "renamed" => {
+                let mode = match file.mode.as_deref() {
+                    None => None,
+                    Some(raw) => match u32::from_str_radix(raw.trim(), 8) {
+                        Ok(m) if matches!(m & 0o170000, 0o100000 | 0o120000) => {
+                            cl_manifest::normalize_mode(m)
+                        }
+                        _ => {
+                            tracing::warn!(
+                                "Unsupported CL mode '{}' for path {}, skipping",
+                                raw,
+                                file.path
+                            );
+                            continue;
+                        }
+                    },
+                };
+                if file.action == "renamed" {
+                    match file.old_path.as_deref() {
+                        Some(old) => renamed_from.extend(
+                            AntaresServiceImpl::relative_path_in_view(old, view)
+                                .map(|p| p.to_string_lossy().into_owned()),
+                        ),
+                        None => tracing::warn!(
+                            "CL rename of {} has no old path; the old path stays visible",
+                            file.path
+                        ),
+                    }
                 }
-                Component::CurDir | Component::Normal(_) => {}
+                manifest.files.insert(
+                    rel_path,
+                    ClManifestEntry {
+                        sha: file.sha,
+                        action: file.action,
+                        mode,
+                    },
+                );
+            }
+            "deleted" => {
+                manifest.files.insert(
+                    rel_path,
+                    ClManifestEntry {
+                        sha: file.sha,
+                        action: file.action,
+                        mode: None,
+                    },
+                );
+            }
+            other => {
+                tracing::warn!(
+                    "Unknown CL action '{}' for path {}, skipping",
+                    other,
+                    file.path
+                );
             }
         }
-        Some(rel_path.to_path_buf())
-    }
-
-    fn http_client() -> Result<Client, ServiceError> {
-        Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .map_err(|e| ServiceError::Internal(format!("failed to build http client: {}", e)))
     }
-
-    fn cl_quiesce_grace_duration() -> Duration {
-        const DEFAULT_MS: u64 = 150;
-        match std::env::var("ANTARES_CL_QUIESCE_GRACE_MS") {
-            Ok(raw) => match raw.trim().parse::<u64>() {
-                Ok(ms) => Duration::from_millis(ms.clamp(0, 3_000)),
-                Err(_) => {
-                    tracing::warn!(
-                        value = %raw,
-                        default_ms = DEFAULT_MS,
-                        "invalid ANTARES_CL_QUIESCE_GRACE_MS, using default"
-                    );
-                    Duration::from_millis(DEFAULT_MS)
-                }
-            },
-            Err(_) => Duration::from_millis(DEFAULT_MS),
-        }
+    for old in renamed_from {
+        manifest
+            .files
+            .entry(old)
+            .or_insert_with(|| ClManifestEntry {
+                action: "deleted".to_string(),
+                ..ClManifestEntry::default()
+            });
     }
+    manifest.resolve_deleted_dirs();
+    manifest
+}
 
-    /// Spawn a background deep-preload walk to warm FUSE kernel entry/attr caches.
-    ///
-    /// This is a **best-effort optimisation** — it does NOT block the mount from
-    /// being marked `Ready`.  By the time we call this, Dicfuse's internal
-    /// `load_dir_depth()` (Phase 1) has already populated the in-memory directory
-    /// cache.  Without this walk, FUSE `statx` calls still reach the daemon but
-    /// hit the Dicfuse memory cache (~1 ms each).  The walk pushes those entries
-    /// into the Linux kernel's FUSE cache so subsequent `statx` calls are served
-    /// at ~0 ms — a nice-to-have, not a prerequisite for correctness.
-    fn spawn_deep_preload_task(
-        &self,
-        mount_id: Uuid,
-        mountpoint: String,
-        cancel: Arc<AtomicBool>,
-        source: &'static str,
-    ) {
-        tokio::spawn(async move {
-            let start = Instant::now();
-            tracing::info!(
-                mount_id = %mount_id,
-                mountpoint = %mountpoint,
-                source = source,
-                "antares svc: starting background kernel cache warm (best-effort)"
-            );
-
-            let mp = mountpoint.clone();
-            let walk_result =
-                tokio::task::spawn_blocking(move || deep_preload_walk(&mp, &cancel)).await;
-
-            match walk_result {
-                Ok(Ok(stats)) => {
-                    tracing::info!(
-                        mount_id = %mount_id,
-                        source = source,
-                        entries_visited = stats.entries_visited,
-                        metadata_touches = stats.metadata_touches,
-                        budget_exhausted = stats.budget_exhausted,
-                        elapsed_ms = start.elapsed().as_millis(),
-                        "antares svc: kernel cache warm completed"
-                    );
-                }
-                Ok(Err(e)) => {
-                    tracing::warn!(
-                        mount_id = %mount_id,
-                        source = source,
-                        error = %e,
-                        elapsed_ms = start.elapsed().as_millis(),
-                        "antares svc: kernel cache warm finished with errors"
-                    );
-                }
-                Err(e) => {
-                    tracing::warn!(
-                        mount_id = %mount_id,
-                        source = source,
-                        error = %e,
-                        "antares svc: kernel cache warm task panicked"
-                    );
-                }
-            }
-        });
-    }
+/// Client for the Mega CL API that builds CL layer directories.
+///
+/// Blobs go through the shared blob cache, and one HTTP client serves every request.
+#[derive(Clone)]
+struct ClFetcher {
+    base_url: String,
+    client: Client,
+    blob_cache: BlobCache,
+    /// Concurrent blob downloads per layer.
+    workers: usize,
+}
 
+impl ClFetcher {
     async fn fetch_cl_files(&self, cl_link: &str) -> Result<Vec<ClFileEntry>, ServiceError> {
-        let base_url = crate::util::config::base_url();
-        let url = format!("{base_url}/api/v1/cl/{cl_link}/files-list");
-        let client = Self::http_client()?;
-        let resp = client
+        let url = format!("{}/api/v1/cl/{cl_link}/files-list", self.base_url);
+        let resp = self
+            .client
             .get(url)
             .send()
             .await
@@ -884,26 +1975,61 @@ impl AntaresServiceImpl {
         Ok(body.data.unwrap_or_default())
     }
 
-    async fn download_blob_to_path(
+    /// Put the node described by `entry` at `dest` and return the size of its blob.
+    async fn place_entry(
+        &self,
+        entry: &ClManifestEntry,
+        dest: PathBuf,
+    ) -> Result<u64, ServiceError> {
+        match entry.kind() {
+            ClEntryKind::File { executable } => {
+                self.fetch_blob_to_path(&entry.sha, &dest, executable.then_some(0o755))
+                    .await
+            }
+            ClEntryKind::Symlink => self.place_symlink(&entry.sha, &dest).await,
+            // Markers are created by `build_layer` itself.
+            ClEntryKind::Whiteout | ClEntryKind::OpaqueDir => Ok(0),
+        }
+    }
+
+    /// Place blob `oid` at `dest`, serving it from the shared blob cache when possible.
+    ///
+    /// Downloaded blobs are added to the cache first and linked from there. Cache failures
+    /// are logged and the blob is written straight to `dest` instead. With `perm`, the file
+    /// gets those permission bits without touching the cached copy. Returns the blob size.
+    async fn fetch_blob_to_path(
         &self,
-        client: &Client,
         oid: &str,
         dest: &Path,
-    ) -> Result<(), ServiceError> {
-        let base_url = crate::util::config::base_url();
-        let clean_oid = oid.trim_start_matches("sha1:");
-        let url = format!("{base_url}/api/v1/file/blob/{clean_oid}");
-        let resp = client
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| ServiceError::Internal(format!("failed to download blob: {}", e)))?;
-        if !resp.status().is_success() {
-            return Err(ServiceError::Internal(format!(
-                "failed to download blob {}: HTTP {}",
-                clean_oid,
-                resp.status()
-            )));
+        perm: Option<u32>,
+    ) -> Result<u64, ServiceError> {
+        let materialize = |cache: &BlobCache| match perm {
+            Some(perm) => cache.materialize_with_mode(oid, dest, perm),
+            None => cache.materialize(oid, dest),
+        };
+        let cached_len = || std::fs::metadata(dest).map(|m| m.len()).unwrap_or(0);
+        match materialize(&self.blob_cache) {
+            Ok(Some(_)) => return Ok(cached_len()),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(oid = oid, dest = ?dest, error = %e, "blob cache: lookup failed")
+            }
+        }
+
+        let bytes = self.download_blob(oid).await?;
+        if BlobCache::key(oid).is_some() {
+            let cached = self
+                .blob_cache
+                .insert(oid, &bytes)
+                .and_then(|_| materialize(&self.blob_cache));
+            match cached {
+                Ok(Some(_)) => return Ok(bytes.len() as u64),
+                // Collected between insert and link; fall back to a private copy.
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(oid = oid, dest = ?dest, error = %e, "blob cache: insert failed")
+                }
+            }
         }
 
         if let Some(parent) = dest.parent() {
@@ -915,56 +2041,87 @@ impl AntaresServiceImpl {
             })?;
         }
 
+        // Staged layers share inodes with the live one; never write through an old link.
+        let _ = tokio::fs::remove_file(dest).await;
         let mut file = tokio::fs::File::create(dest).await.map_err(|e| {
             ServiceError::Internal(format!("failed to create file {:?}: {}", dest, e))
         })?;
-        let bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| ServiceError::Internal(format!("failed to read blob data: {}", e)))?;
         file.write_all(&bytes).await.map_err(|e| {
             ServiceError::Internal(format!("failed to write file {:?}: {}", dest, e))
         })?;
-        Ok(())
+        if let Some(perm) = perm {
+            tokio::fs::set_permissions(dest, std::fs::Permissions::from_mode(perm))
+                .await
+                .map_err(|e| {
+                    ServiceError::Internal(format!("failed to chmod file {:?}: {}", dest, e))
+                })?;
+        }
+        Ok(bytes.len() as u64)
     }
 
-    fn create_whiteout(path: &Path) -> Result<(), ServiceError> {
-        if let Some(parent) = path.parent() {
-            if let Err(e) = std::fs::create_dir_all(parent) {
-                return Err(ServiceError::Internal(format!(
-                    "failed to create whiteout parent {:?}: {}",
-                    parent, e
-                )));
+    /// Create a symlink at `dest` whose target is the contents of blob `oid`.
+    async fn place_symlink(&self, oid: &str, dest: &Path) -> Result<u64, ServiceError> {
+        let target = match self.blob_cache.read(oid) {
+            Ok(Some(data)) => Bytes::from(data),
+            other => {
+                if let Err(e) = other {
+                    tracing::warn!(oid = oid, error = %e, "blob cache: lookup failed");
+                }
+                let bytes = self.download_blob(oid).await?;
+                if BlobCache::key(oid).is_some() {
+                    if let Err(e) = self.blob_cache.insert(oid, &bytes) {
+                        tracing::warn!(oid = oid, error = %e, "blob cache: insert failed");
+                    }
+                }
+                bytes
             }
+        };
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                ServiceError::Internal(format!(
+                    "failed to create CL parent dir {:?}: {}",
+                    parent, e
+                ))
+            })?;
         }
+        let _ = tokio::fs::remove_file(dest).await;
+        tokio::fs::symlink(OsStr::from_bytes(&target), dest)
+            .await
+            .map_err(|e| {
+                ServiceError::Internal(format!("failed to create symlink {:?}: {}", dest, e))
+            })?;
+        Ok(target.len() as u64)
+    }
 
-        if path.exists() {
-            let _ = std::fs::remove_file(path);
-            let _ = std::fs::remove_dir_all(path);
-        }
-
-        let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|e| {
-            ServiceError::Internal(format!("invalid whiteout path {:?}: {}", path, e))
-        })?;
-
-        let mode = libc::S_IFCHR;
-        let dev = 0;
-        let res = unsafe { libc::mknod(c_path.as_ptr(), mode, dev) };
-        if res != 0 {
+    async fn download_blob(&self, oid: &str) -> Result<Bytes, ServiceError> {
+        let clean_oid = oid.trim_start_matches("sha1:");
+        let url = format!("{}/api/v1/file/blob/{clean_oid}", self.base_url);
+        let resp = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| ServiceError::Internal(format!("failed to download blob: {}", e)))?;
+        if !resp.status().is_success() {
             return Err(ServiceError::Internal(format!(
-                "failed to create whiteout {:?}: {}",
-                path,
-                std::io::Error::last_os_error()
+                "failed to download blob {}: HTTP {}",
+                clean_oid,
+                resp.status()
             )));
         }
-        Ok(())
+        resp.bytes()
+            .await
+            .map_err(|e| ServiceError::Internal(format!("failed to read blob data: {}", e)))
     }
 
-    async fn build_cl_layer(
+    /// Build `cl_link` into `cl_dir`, recording progress as the `layer`-th layer of the stack.
+    async fn build_layer(
         &self,
-        mount_path: &str,
+        view: &[MountBinding],
         cl_link: &str,
         cl_dir: &Path,
+        layer: usize,
+        progress: &ClProgress,
     ) -> Result<(), ServiceError> {
         if cl_link.trim().is_empty() {
             return Err(ServiceError::InvalidRequest(
@@ -972,1032 +2129,1277 @@ impl AntaresServiceImpl {
             ));
         }
 
-        if cl_dir.exists() {
-            tokio::fs::remove_dir_all(cl_dir).await.map_err(|e| {
+        let files = self.fetch_cl_files(cl_link).await?;
+        let wanted = cl_manifest_from_files(cl_link, view, files);
+        self.apply_manifest(cl_dir, &wanted, layer, progress).await
+    }
+
+    /// Bring `cl_dir` in line with `wanted`, recording progress as the `layer`-th layer.
+    ///
+    /// When `cl_dir` carries a manifest from an earlier build, only the paths whose
+    /// `(sha, action, mode)` changed are rebuilt; everything else is left in place. Without
+    /// a manifest the directory is rebuilt from scratch. Blobs missing from the shared
+    /// cache are downloaded by up to `workers` concurrent requests.
+    async fn apply_manifest(
+        &self,
+        cl_dir: &Path,
+        wanted: &ClManifest,
+        layer: usize,
+        progress: &ClProgress,
+    ) -> Result<(), ServiceError> {
+        if let Some((entry, link)) = wanted.entry_below_symlink() {
+            return Err(ServiceError::InvalidRequest(format!(
+                "CL {} places {} below the symlink {}",
+                wanted.cl, entry, link
+            )));
+        }
+        let previous = cl_dir.is_dir().then(|| cl_manifest::load(cl_dir)).flatten();
+        if previous.is_none() && cl_dir.exists() {
+            cl_manifest::remove_layer(cl_dir).map_err(|e| {
                 ServiceError::Internal(format!("failed to clear CL dir {:?}: {}", cl_dir, e))
             })?;
         }
         tokio::fs::create_dir_all(cl_dir).await.map_err(|e| {
             ServiceError::Internal(format!("failed to create CL dir {:?}: {}", cl_dir, e))
         })?;
+        // Until the new manifest is written the layer may be half-updated.
+        cl_manifest::invalidate(cl_dir).map_err(|e| {
+            ServiceError::Internal(format!("failed to reset CL manifest {:?}: {}", cl_dir, e))
+        })?;
 
-        let files = self.fetch_cl_files(cl_link).await?;
-        if files.is_empty() {
-            return Ok(());
+        let diff = ClDiff::between(previous.as_ref(), wanted);
+        for rel in &diff.remove {
+            cl_manifest::remove_entry(cl_dir, rel).map_err(|e| {
+                ServiceError::Internal(format!(
+                    "failed to remove {:?} from CL dir {:?}: {}",
+                    rel, cl_dir, e
+                ))
+            })?;
         }
-
-        let client = Self::http_client()?;
-        for file in files {
-            let rel_path = match Self::relative_path_for_mount(&file.path, mount_path) {
-                Some(p) => p,
-                None => continue,
-            };
-            let dest = cl_dir.join(rel_path);
-            match file.action.as_str() {
-                "new" | "modified" => {
-                    self.download_blob_to_path(&client, &file.sha, &dest)
-                        .await?;
-                }
-                "deleted" => {
-                    Self::create_whiteout(&dest)?;
-                }
-                other => {
-                    tracing::warn!(
-                        "Unknown CL action '{}' for path {}, skipping",
-                        other,
-                        file.path
-                    );
+        let entry_path = |rel: &str| {
+            cl_manifest::entry_path(cl_dir, rel).map_err(|e| {
+                ServiceError::Internal(format!(
+                    "failed to place {:?} in CL dir {:?}: {}",
+                    rel, cl_dir, e
+                ))
+            })
+        };
+        for rel in &diff.opaque {
+            cl_manifest::set_opaque(&entry_path(rel)?).map_err(|e| {
+                ServiceError::Internal(format!(
+                    "failed to mark {:?} opaque in CL dir {:?}: {}",
+                    rel, cl_dir, e
+                ))
+            })?;
+        }
+        progress.update(|p| {
+            p.layer = layer;
+            p.files_total += (diff.fetch.len() + diff.whiteout.len()) as u64;
+        });
+        let mut downloads =
+            futures::stream::iter(&diff.fetch)
+                .map(|(rel, _)| async move {
+                    self.place_entry(&wanted.files[rel], entry_path(rel)?).await
+                })
+                .buffer_unordered(self.workers);
+        while let Some(result) = downloads.next().await {
+            match result {
+                Ok(bytes) => progress.update(|p| {
+                    p.files_done += 1;
+                    p.bytes_done += bytes;
+                }),
+                Err(e) => {
+                    // Dropping the stream cancels the downloads still in flight.
+                    progress.update(|p| p.failures += 1);
+                    return Err(e);
                 }
             }
         }
+        for rel in &diff.whiteout {
+            AntaresServiceImpl::create_whiteout(&entry_path(rel)?)?;
+            progress.update(|p| p.files_done += 1);
+        }
 
+        cl_manifest::save(cl_dir, wanted).map_err(|e| {
+            ServiceError::Internal(format!("failed to write CL manifest {:?}: {}", cl_dir, e))
+        })?;
+        tracing::debug!(
+            cl = %wanted.cl,
+            cl_dir = ?cl_dir,
+            incremental = previous.is_some(),
+            fetched = diff.fetch.len(),
+            whiteouts = diff.whiteout.len(),
+            opaque = diff.opaque.len(),
+            removed = diff.remove.len(),
+            unchanged = diff.unchanged,
+            "antares svc: CL layer built"
+        );
         Ok(())
     }
+}
 
-    /// Get or create a Dicfuse instance for the given path.
-    ///
-    /// For root path ("/" or empty), returns the shared global instance.
-    /// For subdirectory paths, returns a cached instance or creates a new one.
-    /// This ensures that multiple mounts with the same base_path share the same
-    /// Dicfuse instance, avoiding unnecessary duplication.
-    ///
-    /// IMPORTANT: For newly created instances, this method waits for the Dicfuse
-    /// directory tree to be fully initialized before returning. This prevents
-    /// FUSE mount failures due to root inode not being set up yet.
-    ///
-    /// # TODO(dicfuse-antares-integration)
-    /// - Support incremental directory tree loading to reduce initial wait time
-    /// - Add progress callback for long-running initialization
-    /// - Consider lazy loading for very large subdirectory mounts
-    async fn get_or_create_dicfuse(&self, path: &str) -> Result<Arc<Dicfuse>, ServiceError> {
-        const INIT_TIMEOUT_SECS: u64 = 120;
-
-        // For root path, use the shared global instance (but ensure it's initialized first).
-        if path.is_empty() || path == "/" {
-            tracing::info!(
-                "Waiting for shared Dicfuse instance to initialize for path: / (timeout: {}s)",
-                INIT_TIMEOUT_SECS
-            );
-            match tokio::time::timeout(
-                Duration::from_secs(INIT_TIMEOUT_SECS),
-                self.dicfuse.store.wait_for_ready(),
-            )
-            .await
-            {
-                Ok(_) => {
-                    tracing::info!("Shared Dicfuse initialized successfully for path: /");
-                }
-                Err(_) => {
-                    tracing::error!(
-                        "Shared Dicfuse initialization timed out for path: / after {}s",
-                        INIT_TIMEOUT_SECS
-                    );
-                    return Err(ServiceError::FuseFailure(format!(
-                        "Dicfuse initialization timed out for path '/' after {}s. \
-                         Check network connectivity to the monorepo server.",
-                        INIT_TIMEOUT_SECS
-                    )));
-                }
-            }
-            return Ok(self.dicfuse.clone());
+impl MountEntry {
+    /// Convert to public MountStatus for API responses.
+    fn to_status(&self) -> MountStatus {
+        MountStatus {
+            mount_id: self.mount_id,
+            job_id: self.job_id.clone(),
+            path: self.path.clone(),
+            bindings: self.bindings.clone(),
+            cl: self.cls.last().cloned(),
+            cls: self.cls.clone(),
+            cl_conflicts: self.cl_conflicts.clone(),
+            mountpoint: self.mountpoint.clone(),
+            forked_from: self.forked_from,
+            read_only: self.upper_kind().is_none(),
+            upper_kind: self.upper_kind(),
+            usage: match (&self.mem_upper, &self.quota) {
+                (Some(mem), _) => Some(mem.usage()),
+                (None, quota) => quota.as_ref().map(|q| q.usage()),
+            },
+            tuning: self.tuning.clone(),
+            callback_url: self.callback_url.clone(),
+            created_by: self.created_by.clone(),
+            labels: self.labels.clone(),
+            layers: MountLayers {
+                upper: self.upper_dir.clone(),
+                cl: self.cl_dirs.last().cloned(),
+                cl_dirs: self.cl_dirs.clone(),
+                dicfuse: "shared".to_string(),
+            },
+            state: self.state.clone(),
+            created_at_epoch_ms: self.created_at_epoch_ms,
+            last_seen_epoch_ms: self.last_seen_epoch_ms,
         }
+    }
 
-        // Normalize the path for consistent cache keys
-        let normalized_path = path.trim_end_matches('/').to_string();
+    fn cl_dir_paths(&self) -> Vec<PathBuf> {
+        self.cl_dirs.iter().map(PathBuf::from).collect()
+    }
 
-        // Check cache first - if found, it's already initialized
-        {
-            let cache = self.dicfuse_cache.read().await;
-            if let Some(dicfuse) = cache.get(&normalized_path) {
-                tracing::debug!(
-                    "Using cached Dicfuse instance for path: {}",
-                    normalized_path
-                );
-                return Ok(dicfuse.clone());
-            }
-        }
+    /// Where monorepo paths appear in this mount (see [`mount_view`]).
+    fn view(&self) -> Vec<MountBinding> {
+        mount_view(&self.path, &self.bindings)
+    }
 
-        // Not in cache, create new instance
-        let new_dicfuse = DicfuseManager::for_base_path(&normalized_path).await;
+    /// Path half of this mount's path-index key.
+    fn index_path(&self) -> String {
+        index_path(&self.path, &self.bindings)
+    }
 
-        // CRITICAL: Wait for the Dicfuse directory tree to be fully loaded before
-        // returning. Without this, FUSE mount may fail because the root inode
-        // is not set up yet when import_arc hasn't completed.
-        // TODO(dicfuse-antares-integration): If many concurrent requests initialize DIFFERENT
-        // base paths, we may enqueue a large number of concurrent warmups (network + memory).
-        // Consider adding a global semaphore/queue to cap concurrent initializations.
-        tracing::info!(
-            "Waiting for Dicfuse instance to initialize for path: {} (timeout: {}s)",
-            normalized_path,
-            INIT_TIMEOUT_SECS
-        );
-        match tokio::time::timeout(
-            std::time::Duration::from_secs(INIT_TIMEOUT_SECS),
-            new_dicfuse.store.wait_for_ready(),
-        )
-        .await
-        {
-            Ok(_) => {
-                tracing::info!(
-                    "Dicfuse initialized successfully for path: {}",
-                    normalized_path
-                );
-            }
-            Err(_) => {
-                tracing::error!(
-                    "Dicfuse initialization timed out for path: {} after {}s",
-                    normalized_path,
-                    INIT_TIMEOUT_SECS
-                );
-                return Err(ServiceError::FuseFailure(format!(
-                    "Dicfuse initialization timed out for path '{}' after {}s. \
-                     Check network connectivity to the monorepo server.",
-                    normalized_path, INIT_TIMEOUT_SECS
-                )));
-            }
+    fn upper_kind(&self) -> Option<UpperKind> {
+        match (&self.mem_upper, &self.upper_dir) {
+            (Some(_), _) => Some(UpperKind::Memory),
+            (None, Some(_)) => Some(UpperKind::Disk),
+            (None, None) => None,
         }
+    }
 
-        // Insert into cache
-        {
-            let mut cache = self.dicfuse_cache.write().await;
-            // Double-check in case another task created it while we were waiting
-            if let Some(dicfuse) = cache.get(&normalized_path) {
-                return Ok(dicfuse.clone());
-            }
-            cache.insert(normalized_path.clone(), new_dicfuse.clone());
-            tracing::info!(
-                "Created and cached new Dicfuse instance for path: {}",
-                normalized_path
-            );
+    /// Upper layer of a writable mount.
+    fn require_upper(&self, op: &str) -> Result<UpperHandle, ServiceError> {
+        match (&self.mem_upper, &self.upper_dir, &self.quota) {
+            (Some(mem), _, _) => Ok(UpperHandle::Memory(mem.clone())),
+            (None, Some(upper_dir), Some(quota)) => Ok(UpperHandle::Disk {
+                dir: PathBuf::from(upper_dir),
+                quota: quota.clone(),
+            }),
+            _ => Err(ServiceError::InvalidRequest(format!(
+                "mount {} is read-only and has no upper layer; cannot {}",
+                self.mount_id, op
+            ))),
         }
-
-        Ok(new_dicfuse)
     }
 
-    /// Persist current mount state to file.
-    async fn persist_state(&self) {
-        let mounts = self.mounts.read().await;
-        let state = PersistedState {
-            mounts: mounts
-                .values()
-                .filter(|e| matches!(e.state, MountLifecycle::Mounted | MountLifecycle::Ready))
-                .map(|e| PersistedMountState {
-                    mount_id: e.mount_id,
-                    job_id: e.job_id.clone(),
-                    path: e.path.clone(),
-                    cl: e.cl.clone(),
-                    mountpoint: e.mountpoint.clone(),
-                    upper_dir: e.upper_dir.clone(),
-                    cl_dir: e.cl_dir.clone(),
-                    created_at_epoch_ms: e.created_at_epoch_ms,
-                })
-                .collect(),
-        };
-        drop(mounts);
+    fn deep_preload_options(&self) -> DeepPreloadOptions {
+        self.tuning
+            .as_ref()
+            .map(MountTuning::deep_preload)
+            .unwrap_or_else(DeepPreloadOptions::from_env)
+    }
 
-        // Write state to file
-        if let Some(parent) = self.state_file.parent() {
-            if let Err(e) = std::fs::create_dir_all(parent) {
-                tracing::warn!("Failed to create state directory: {}", e);
-                return;
-            }
-        }
+    /// Move to `state` and announce the transition.
+    fn set_state(&mut self, state: MountLifecycle) {
+        self.state = state.clone();
+        self.events
+            .emit(self.mount_id, MountEventKind::State { state });
+    }
 
-        match toml::to_string_pretty(&state) {
-            Ok(content) => {
-                if let Err(e) = std::fs::write(&self.state_file, content) {
-                    tracing::warn!("Failed to write state file: {}", e);
-                }
-            }
-            Err(e) => {
-                tracing::warn!("Failed to serialize state: {}", e);
-            }
-        }
+    /// Update the last_seen timestamp.
+    fn update_last_seen(&mut self) {
+        self.last_seen_epoch_ms = current_epoch_ms();
     }
+}
 
-    /// Recover mounts from persisted state file.
-    async fn recover_mounts(&self) {
-        if !self.state_file.exists() {
-            tracing::debug!(
-                "No state file found at {:?}, skipping recovery",
-                self.state_file
-            );
-            return;
-        }
-
-        let content = match std::fs::read_to_string(&self.state_file) {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::warn!("Failed to read state file: {}", e);
-                return;
-            }
-        };
-
-        let state: PersistedState = match toml::from_str(&content) {
-            Ok(s) => s,
-            Err(e) => {
-                tracing::warn!("Failed to parse state file: {}", e);
-                tracing::error!(
-                    "Failed to parse state file at {:?}: {}. Skipping mount recovery.",
-                    self.state_file,
-                    e
-                );
-                return;
-            }
-        };
-
-        tracing::info!("Recovering {} mounts from state file", state.mounts.len());
+/// A writable mount's upper layer, as seen by fork, checkpoint, restore and usage.
+enum UpperHandle {
+    Disk {
+        dir: PathBuf,
+        quota: Arc<UpperQuota>,
+    },
+    Memory(Arc<MemUpperLayer>),
+}
 
-        for persisted in state.mounts {
-            // Check if mountpoint still exists
-            let mountpoint = PathBuf::from(&persisted.mountpoint);
-            if !mountpoint.exists() {
-                tracing::info!(
-                    "Skipping recovery of mount {} - mountpoint no longer exists",
-                    persisted.mount_id
-                );
-                continue;
-            }
+impl UpperHandle {
+    fn limits(&self) -> QuotaLimits {
+        match self {
+            UpperHandle::Disk { quota, .. } => quota.limits(),
+            UpperHandle::Memory(mem) => mem.limits(),
+        }
+    }
 
-            // Get or create Dicfuse instance (uses cache for subdirectory paths)
-            let dicfuse = match self.get_or_create_dicfuse(&persisted.path).await {
-                Ok(d) => d,
-                Err(e) => {
-                    tracing::warn!(
-                        "Failed to get Dicfuse for {} during recovery: {}",
-                        persisted.mount_id,
-                        e
-                    );
-                    continue;
-                }
-            };
+    /// Current consumption. Disk uppers are rescanned, so this blocks.
+    fn rescan_usage(&self) -> std::io::Result<UpperUsage> {
+        match self {
+            UpperHandle::Disk { quota, .. } => quota.rescan(),
+            UpperHandle::Memory(mem) => Ok(mem.usage()),
+        }
+    }
 
-            let upper_dir = PathBuf::from(&persisted.upper_dir);
-            let cl_dir = persisted.cl_dir.as_ref().map(PathBuf::from);
-
-            // Try to create and mount AntaresFuse
-            match AntaresFuse::new(mountpoint.clone(), dicfuse, upper_dir, cl_dir.clone()).await {
-                Ok(mut fuse) => {
-                    if let Err(e) = fuse.mount().await {
-                        tracing::warn!(
-                            "Failed to remount {} during recovery: {}",
-                            persisted.mount_id,
-                            e
-                        );
-                        continue;
-                    }
+    /// Write the upper layer into the empty directory `dst`. Blocking.
+    fn copy_to(&self, dst: &Path) -> std::io::Result<CloneStats> {
+        match self {
+            UpperHandle::Disk { dir, .. } => snapshot::clone_tree(dir, dst),
+            UpperHandle::Memory(mem) => mem.export_to(dst),
+        }
+    }
+}
 
-                    // Create entry
-                    let entry = MountEntry {
-                        mount_id: persisted.mount_id,
-                        job_id: persisted.job_id.clone(),
-                        path: persisted.path.clone(),
-                        cl: persisted.cl.clone(),
-                        mountpoint: persisted.mountpoint.clone(),
-                        upper_dir: persisted.upper_dir.clone(),
-                        cl_dir: persisted.cl_dir.clone(),
-                        fuse,
-                        // Dicfuse is ready after AntaresFuse::new() completes import_arc.
-                        state: MountLifecycle::Ready,
-                        created_at_epoch_ms: persisted.created_at_epoch_ms,
-                        last_seen_epoch_ms: current_epoch_ms(),
-                        preload_cancel: Arc::new(AtomicBool::new(false)),
-                    };
+/// `path` with symlinks resolved in the part of it that exists.
+fn resolve_symlinks(path: &Path) -> std::io::Result<PathBuf> {
+    let existing = path
+        .ancestors()
+        .find(|ancestor| ancestor.symlink_metadata().is_ok())
+        .unwrap_or(Path::new("/"));
+    let missing = path.strip_prefix(existing).unwrap_or(Path::new(""));
+    Ok(std::fs::canonicalize(existing)?.join(missing))
+}
 
-                    let mut mounts = self.mounts.write().await;
-                    let mut index = self.path_index.write().await;
-                    let mut job_index = self.job_index.write().await;
-                    mounts.insert(persisted.mount_id, entry);
-                    if let Some(job_id) = persisted.job_id {
-                        job_index.insert(job_id, persisted.mount_id);
-                    } else {
-                        index.insert((persisted.path, persisted.cl), persisted.mount_id);
-                    }
+/// How the mount supervisor probes live mounts.
+#[derive(Debug, Clone)]
+pub struct SupervisorConfig {
+    /// Delay between sweeps over the live mounts.
+    pub interval: Duration,
+    /// How long a mountpoint `stat` may take; a slow answer is not a broken mount.
+    pub probe_timeout: Duration,
+    /// Remount broken mounts on their existing layers instead of leaving them `Failed`.
+    pub remount: bool,
+}
 
-                    tracing::info!("Recovered mount {} at {:?}", persisted.mount_id, mountpoint);
-                }
-                Err(e) => {
-                    tracing::warn!(
-                        "Failed to create AntaresFuse for recovery of {}: {}",
-                        persisted.mount_id,
-                        e
-                    );
-                }
-            }
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(10),
+            probe_timeout: Duration::from_secs(5),
+            remount: false,
         }
     }
+}
 
-    /// Validate the create mount request.
-    fn validate_request(request: &CreateMountRequest) -> Result<(), ServiceError> {
-        if request.path.is_empty() {
-            return Err(ServiceError::InvalidRequest("path cannot be empty".into()));
-        }
-        Ok(())
+impl SupervisorConfig {
+    /// Sweep interval and remount policy from the global config; `None` when the
+    /// supervisor is disabled (`antares_supervisor_interval_secs = 0`).
+    pub fn from_global_config() -> Option<Self> {
+        let secs = crate::util::config::antares_supervisor_interval_secs();
+        (secs > 0).then(|| Self {
+            interval: Duration::from_secs(secs),
+            remount: crate::util::config::antares_supervisor_remount(),
+            ..Self::default()
+        })
     }
+}
 
-    /// Check if a path+cl combination is already mounted.
-    async fn is_path_already_mounted(&self, path: &str, cl: Option<&str>) -> bool {
-        let index = self.path_index.read().await;
-        index.contains_key(&(path.to_string(), cl.map(|s| s.to_string())))
+/// `stat` a mountpoint off the runtime; `None` when it answered, or did not answer in time.
+async fn probe_mountpoint(mountpoint: PathBuf, probe_timeout: Duration) -> Option<std::io::Error> {
+    let probe = tokio::task::spawn_blocking(move || std::fs::metadata(&mountpoint));
+    match timeout(probe_timeout, probe).await {
+        Ok(Ok(Err(e))) => Some(e),
+        _ => None,
     }
+}
 
-    /// Get service health information.
-    pub async fn health_info_impl(&self) -> HealthResponse {
-        let mounts = self.mounts.read().await;
-        HealthResponse {
-            status: "healthy".to_string(),
-            mount_count: mounts.len(),
-            uptime_secs: self.start_time.elapsed().as_secs(),
+/// Why a `Mounted` / `Ready` mount is no longer served, if it is not.
+fn broken_mount_reason(session_ended: bool, probe: Option<&std::io::Error>) -> Option<String> {
+    if session_ended {
+        return Some("FUSE session exited".to_string());
+    }
+    match probe {
+        Some(e) if e.raw_os_error() == Some(libc::ENOTCONN) => {
+            Some(format!("mountpoint is not connected: {}", e))
         }
+        _ => None,
     }
+}
 
-    /// Cleanup all mounts during shutdown.
-    pub async fn shutdown_cleanup_impl(&self) -> Result<(), ServiceError> {
-        let mut mounts = self.mounts.write().await;
-        let mut index = self.path_index.write().await;
-        let mut job_index = self.job_index.write().await;
-
-        for (mount_id, mut entry) in mounts.drain() {
-            tracing::info!("Unmounting {} during shutdown", mount_id);
-            entry.preload_cancel.store(true, Ordering::Relaxed);
-            if let Err(e) = entry.fuse.unmount().await {
-                tracing::warn!("Failed to unmount {} during shutdown: {}", mount_id, e);
-                // Continue with other mounts even if one fails
-            }
-        }
-        // All mounts drained; clear indices.
-        // TODO(antares): If we ever decide to keep failed-unmount mounts in memory/state for
-        // later retry, revisit index cleanup to avoid inconsistencies.
-        index.clear();
-        job_index.clear();
+/// Detach a FUSE mount whose serving process is gone.
+async fn detach_stale_mount(mountpoint: &Path) -> std::io::Result<()> {
+    let output = tokio::process::Command::new("fusermount")
+        .arg("-uz")
+        .arg(mountpoint)
+        .output()
+        .await?;
+    if output.status.success() {
         Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "fusermount -uz failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
     }
 }
 
-#[async_trait]
-impl AntaresService for AntaresServiceImpl {
-    async fn create_mount(
-        &self,
-        request: CreateMountRequest,
-    ) -> Result<MountCreated, ServiceError> {
-        let start = Instant::now();
-        let mut request = request;
-        request.path = Self::normalize_mount_path(&request.path);
-
-        // 1. Validate request
-        Self::validate_request(&request)?;
-
-        // Derive a task identifier (job/build id) if provided.
-        let task_id: Option<String> = request
-            .job_id
-            .clone()
-            .or(request.build_id.clone())
-            .and_then(|s| {
-                let trimmed = s.trim().to_string();
-                if trimmed.is_empty() {
-                    None
-                } else {
-                    Some(trimmed)
-                }
-            });
+/// Make a recorded mountpoint usable again when its mount is recovered.
+///
+/// A daemon that was killed leaves its FUSE mounts behind, answering `ENOTCONN`; they are
+/// detached so the same path can be mounted again.
+async fn reclaim_mountpoint(mountpoint: &Path) -> Result<(), String> {
+    let probe = probe_mountpoint(mountpoint.to_path_buf(), Duration::from_secs(5)).await;
+    match probe {
+        None => Ok(()),
+        Some(e) if e.raw_os_error() == Some(libc::ENOTCONN) => {
+            tracing::info!(mountpoint = ?mountpoint, "detaching mount left by a previous daemon");
+            detach_stale_mount(mountpoint)
+                .await
+                .map_err(|e| format!("failed to detach stale mount: {}", e))
+        }
+        Some(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err("mountpoint no longer exists".to_string())
+        }
+        Some(e) => Err(format!("mountpoint is not usable: {}", e)),
+    }
+}
 
-        tracing::info!(
-            task_id = ?task_id,
-            path = %request.path,
-            cl = ?request.cl,
-            "antares svc: create_mount start"
-        );
+/// Mount table of this process, read off the runtime.
+async fn read_mount_table() -> Result<Vec<gc::MountInfo>, ServiceError> {
+    tokio::task::spawn_blocking(gc::mounts)
+        .await
+        .map_err(|e| ServiceError::Internal(format!("mount table task failed: {}", e)))?
+        .map_err(|e| ServiceError::Internal(format!("failed to read mount table: {}", e)))
+}
 
-        // 2. Idempotency / de-dup policy:
-        // - If task_id is provided: treat create as idempotent for the same task id.
-        //   This supports build-task-granularity mounts.
-        // - If task_id is NOT provided: keep legacy behavior and reject duplicate (path, cl).
-        if let Some(ref job_id) = task_id {
-            // Fast path: already mounted for this task id -> return existing mount.
-            if let Some(existing_id) = { self.job_index.read().await.get(job_id).cloned() } {
-                let mut mounts = self.mounts.write().await;
-                if let Some(entry) = mounts.get_mut(&existing_id) {
-                    // Guard against job_id reuse with different request params.
-                    if entry.path != request.path || entry.cl != request.cl {
-                        return Err(ServiceError::InvalidRequest(format!(
-                            "job_id/build_id '{}' already mounted with different path/cl",
-                            job_id
-                        )));
-                    }
-                    // If the mount is being torn down, do NOT treat this as an idempotent success.
-                    // Otherwise we may return a mount_id that is about to be removed, causing
-                    // follow-up describe/delete calls to 404.
-                    if !matches!(entry.state, MountLifecycle::Mounted | MountLifecycle::Ready) {
-                        return Err(ServiceError::InvalidRequest(format!(
-                            "job_id/build_id '{}' is currently in state {:?}; retry after unmount completes",
-                            job_id, entry.state
-                        )));
-                    }
-                    entry.update_last_seen();
-                    tracing::info!(
-                        task_id = %job_id,
-                        mount_id = %existing_id,
-                        mountpoint = %entry.mountpoint,
-                        elapsed_ms = start.elapsed().as_millis(),
-                        "antares svc: create_mount idempotent hit"
-                    );
-                    return Ok(MountCreated {
-                        mount_id: existing_id,
-                        mountpoint: entry.mountpoint.clone(),
-                    });
-                } else {
-                    // Stale index entry: remove and continue with fresh mount creation.
-                    self.job_index.write().await.remove(job_id);
-                }
-            }
-        } else if self
-            .is_path_already_mounted(&request.path, request.cl.as_deref())
-            .await
-        {
-            return Err(ServiceError::InvalidRequest(format!(
-                "path {} with cl {:?} is already mounted",
-                request.path, request.cl
-            )));
-        }
+/// Get current time as milliseconds since UNIX epoch.
+fn current_epoch_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
 
-        // 3. Generate UUID and auto-generate all paths
-        let mount_id = Uuid::new_v4();
-        let id_str = mount_id.to_string();
+/// Type alias for path index: maps (monorepo_path, cl_stack) to mount_id.
+type PathIndex = Arc<RwLock<HashMap<(String, Vec<String>), Uuid>>>;
+/// Type alias for job index: maps a build task id (job_id/build_id) to mount_id.
+type JobIndex = Arc<RwLock<HashMap<String, Uuid>>>;
 
-        // Get base paths from config
-        let mount_root = crate::util::config::antares_mount_root();
-        let upper_root = crate::util::config::antares_upper_root();
-        let cl_root = crate::util::config::antares_cl_root();
+/// Bindings placing monorepo paths in a mount: those of a composite mount, or `path`
+/// at the mount root.
+fn mount_view(path: &str, bindings: &[MountBinding]) -> Vec<MountBinding> {
+    if bindings.is_empty() {
+        vec![MountBinding::whole(path)]
+    } else {
+        bindings.to_vec()
+    }
+}
 
-        // Auto-generate paths based on UUID
-        let mountpoint_str = format!("{}/{}", mount_root, id_str);
-        let upper_dir_str = format!("{}/{}", upper_root, id_str);
-        let cl_dir_str = request
-            .cl
-            .as_ref()
-            .map(|_| format!("{}/{}", cl_root, id_str));
+/// Path half of the path-index key: `path`, or the bindings of a composite mount.
+fn index_path(path: &str, bindings: &[MountBinding]) -> String {
+    if bindings.is_empty() {
+        return path.to_string();
+    }
+    bindings
+        .iter()
+        .map(|b| format!("{}={}", b.source, b.target))
+        .collect::<Vec<_>>()
+        .join(",")
+}
 
-        let mountpoint = PathBuf::from(&mountpoint_str);
-        let upper_dir = PathBuf::from(&upper_dir_str);
-        let cl_dir = cl_dir_str.as_ref().map(PathBuf::from);
+/// Remove `key` from the path index only if it still points at `mount_id`.
+///
+/// Forked mounts share (path, cl) with their source but are never registered in the
+/// path index, so tearing one down must not evict the source's entry. Returns whether
+/// the entry was removed.
+fn remove_path_index_entry(
+    index: &mut HashMap<(String, Vec<String>), Uuid>,
+    key: &(String, Vec<String>),
+    mount_id: Uuid,
+) -> bool {
+    if index.get(key) == Some(&mount_id) {
+        index.remove(key);
+        true
+    } else {
+        false
+    }
+}
 
-        tracing::debug!(
-            mount_id = %mount_id,
-            task_id = ?task_id,
-            mountpoint = %mountpoint_str,
-            upper_dir = %upper_dir_str,
-            cl_dir = ?cl_dir_str,
-            "antares svc: create_mount paths generated"
-        );
+/// Persisted mount state for recovery across restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistedMountState {
+    pub mount_id: Uuid,
+    #[serde(default)]
+    pub job_id: Option<String>,
+    pub path: String,
+    #[serde(default)]
+    pub bindings: Vec<MountBinding>,
+    /// Single CL written by older versions; read only, superseded by `cls`.
+    #[serde(default, skip_serializing)]
+    pub cl: Option<String>,
+    #[serde(default)]
+    pub cls: Vec<String>,
+    pub mountpoint: String,
+    /// `None` for read-only mounts.
+    #[serde(default)]
+    pub upper_dir: Option<String>,
+    /// Directory of `cl` in older state files; read only, superseded by `cl_dirs`.
+    #[serde(default, skip_serializing)]
+    pub cl_dir: Option<String>,
+    #[serde(default)]
+    pub cl_dirs: Vec<String>,
+    #[serde(default)]
+    pub cl_conflicts: Vec<ClConflict>,
+    #[serde(default)]
+    pub forked_from: Option<Uuid>,
+    /// Effective upper-layer limits. Missing in older state files: global config applies.
+    #[serde(default)]
+    pub quota: Option<QuotaLimits>,
+    #[serde(default)]
+    pub tuning: Option<MountTuning>,
+    #[serde(default)]
+    pub callback_url: Option<String>,
+    #[serde(default)]
+    pub created_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Labels::is_empty")]
+    pub labels: Labels,
+    pub created_at_epoch_ms: u64,
+}
 
-        if let (Some(cl_link), Some(ref cl_dir_str)) = (request.cl.as_deref(), cl_dir_str.as_ref())
-        {
-            let cl_dir_path = PathBuf::from(cl_dir_str);
-            if let Err(err) = self
-                .build_cl_layer(&request.path, cl_link, &cl_dir_path)
-                .await
-            {
-                let _ = std::fs::remove_dir_all(&mountpoint_str);
-                let _ = std::fs::remove_dir_all(&upper_dir_str);
-                let _ = std::fs::remove_dir_all(cl_dir_str);
-                return Err(err);
-            }
+impl PersistedMountState {
+    /// Move the single-CL fields of older state files into the CL stack.
+    fn migrate_legacy_cl(&mut self) {
+        if self.cls.is_empty() {
+            self.cls.extend(self.cl.take());
+            self.cl_dirs.extend(self.cl_dir.take());
         }
+    }
+}
 
-        // 5. Get or create Dicfuse instance for this mount (uses cache for subdirectory paths)
-        // If a specific base path is requested (not root), get from cache or create a dedicated
-        // Dicfuse with path remapping. Otherwise, use the shared global instance.
-        // This may take time for new subdirectory paths as it waits for import_arc to complete.
-        let dicfuse = self.get_or_create_dicfuse(&request.path).await?;
-
-        // 6. Create AntaresFuse instance (may take time, not holding lock)
-        let mut fuse = AntaresFuse::new(mountpoint, dicfuse, upper_dir, cl_dir)
-            .await
-            .map_err(|e| ServiceError::FuseFailure(format!("failed to create fuse: {}", e)))?;
+/// Persisted state file structure.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PersistedState {
+    /// Snapshot generation; journal records of other generations are stale (see
+    /// [`crate::antares::state_store`]). Missing in older state files.
+    #[serde(default)]
+    pub generation: u64,
+    pub mounts: Vec<PersistedMountState>,
+}
 
-        // 7. Mount the filesystem
-        fuse.mount()
-            .await
-            .map_err(|e| ServiceError::FuseFailure(format!("failed to mount: {}", e)))?;
+/// Concrete implementation of AntaresService.
+///
+/// Cloning is cheap and yields a handle to the same mounts, e.g. for background tasks.
+#[derive(Clone)]
+pub struct AntaresServiceImpl {
+    /// Shared Dicfuse instance for root path (read-only base layer).
+    dicfuse: Arc<Dicfuse>,
+    /// Cache of Dicfuse instances keyed by base_path (plus the tuning fingerprint for
+    /// tuned views). This avoids creating duplicate instances for the same view.
+    dicfuse_cache: Arc<RwLock<HashMap<String, Arc<Dicfuse>>>>,
+    /// Active mounts indexed by UUID.
+    mounts: Arc<RwLock<HashMap<Uuid, MountEntry>>>,
+    /// Fast lookup for (path, cl) -> mount_id to avoid linear scans.
+    path_index: PathIndex,
+    /// Fast lookup for (job_id/build_id) -> mount_id for task-granularity mounts.
+    job_index: JobIndex,
+    /// Service start time for uptime calculation.
+    start_time: Instant,
+    /// Layer roots and the state file used for persistence.
+    paths: AntaresPaths,
+    /// Upper-layer checkpoints, stored under `antares_checkpoint_root`.
+    checkpoints: CheckpointStore,
+    /// Serializes checkpoint creation per mount, so a budget check and the copy it
+    /// admits never interleave with another creation.
+    checkpoint_locks: Arc<Mutex<HashMap<Uuid, Arc<tokio::sync::Mutex<()>>>>>,
+    /// Blobs shared by the CL layers of all mounts, stored under `antares_blob_cache_root`.
+    blob_cache: BlobCache,
+    /// Progress of the current or most recent CL build of each mount.
+    cl_progress: Arc<RwLock<HashMap<Uuid, ClProgress>>>,
+    /// Mega CL API client, built on first use.
+    cl_fetcher: Arc<OnceLock<ClFetcher>>,
+    /// Asynchronous creations not yet in `mounts`: still provisioning, or failed and
+    /// kept until deleted so callers can see why.
+    provisioning: Arc<RwLock<HashMap<Uuid, MountStatus>>>,
+    /// State transitions and preload progress of every mount.
+    events: MountEvents,
+    /// Delivers lifecycle events to the configured and per-mount webhooks.
+    webhooks: Webhooks,
+    /// Caller-chosen mountpoints of creations still in flight.
+    claimed_mountpoints: Arc<Mutex<HashSet<PathBuf>>>,
+    /// Ids of creations and forks, synchronous or not, whose entry is not in `mounts`
+    /// yet; GC leaves their directories alone.
+    creating: Arc<Mutex<HashSet<Uuid>>>,
+    /// Snapshot and journal of `paths.state_file`, plus the lock on its directory.
+    state_store: Arc<tokio::sync::Mutex<StateStore>>,
+}
 
-        // 8. Record timestamps. We'll only construct MountEntry after passing the duplicate check
-        // so we can rollback the FUSE mount safely on race losers.
-        let now = current_epoch_ms();
+/// A caller-chosen mountpoint reserved by one creation; released on drop.
+struct MountpointClaim {
+    claims: Arc<Mutex<HashSet<PathBuf>>>,
+    path: PathBuf,
+    /// The directory did not exist before and is removed again if the creation fails.
+    created: bool,
+}
 
-        // 9. Insert into mounts map
-        let mut mounts = self.mounts.write().await;
-        let mut index = self.path_index.write().await;
-        let mut job_index = self.job_index.write().await;
+impl Drop for MountpointClaim {
+    fn drop(&mut self) {
+        self.claims.lock().unwrap().remove(&self.path);
+    }
+}
 
-        // Double-check for races after acquiring write locks (match the policy above).
-        if let Some(ref job_id) = task_id {
-            if job_index.contains_key(job_id) {
-                // IMPORTANT: rollback the freshly mounted FUSE session before returning.
-                // Under concurrent POST /mounts for the same job_id/build_id, the losing request
-                // may have already mounted a FUSE session but not yet inserted it into mounts /
-                // job_index. Returning early here would leak an orphan mount that cannot be
-                // tracked or cleaned up.
-                let err = ServiceError::InvalidRequest(format!(
-                    "job_id/build_id '{}' is already mounted",
-                    job_id
-                ));
-                drop(mounts);
-                drop(index);
-                drop(job_index);
+/// The id of a mount being created; released on drop, once its entry is in `mounts`
+/// or the creation failed.
+struct CreationClaim {
+    creating: Arc<Mutex<HashSet<Uuid>>>,
+    mount_id: Uuid,
+}
 
-                tracing::warn!(
-                    "create_mount duplicate task_id detected after mount; rolling back orphan mount {}",
-                    mount_id
-                );
-                let _ = fuse.unmount().await;
-                let _ = std::fs::remove_dir_all(&mountpoint_str);
-                let _ = std::fs::remove_dir_all(&upper_dir_str);
-                if let Some(c) = cl_dir_str.as_deref() {
-                    let _ = std::fs::remove_dir_all(c);
-                }
-                return Err(err);
-            }
-        } else if index.contains_key(&(request.path.clone(), request.cl.clone())) {
-            // Same rollback logic as above for legacy (path, cl) duplicates.
-            let err = ServiceError::InvalidRequest(format!(
-                "path {} with cl {:?} is already mounted",
-                request.path, request.cl
-            ));
-            drop(mounts);
-            drop(index);
-            drop(job_index);
+impl Drop for CreationClaim {
+    fn drop(&mut self) {
+        self.creating.lock().unwrap().remove(&self.mount_id);
+    }
+}
 
-            tracing::warn!(
-                "create_mount duplicate (path, cl) detected after mount; rolling back orphan mount {}",
-                mount_id
-            );
-            let _ = fuse.unmount().await;
-            let _ = std::fs::remove_dir_all(&mountpoint_str);
-            let _ = std::fs::remove_dir_all(&upper_dir_str);
-            if let Some(c) = cl_dir_str.as_deref() {
-                let _ = std::fs::remove_dir_all(c);
-            }
-            return Err(err);
-        }
+impl AntaresServiceImpl {
+    /// Create a new service instance.
+    ///
+    /// # Arguments
+    /// * `dicfuse` - Optional shared Dicfuse instance. If None, creates a new one.
+    ///
+    /// # Errors
+    /// Fails when another instance holds the state directory (see [`Self::with_paths`]).
+    ///
+    /// # Note
+    /// Requires config to be initialized via `config::init_config()` before calling.
+    pub async fn new(dicfuse: Option<Arc<Dicfuse>>) -> Result<Self, ServiceError> {
+        Self::with_paths(dicfuse, AntaresPaths::from_global_config()).await
+    }
 
-        // Now it's safe to commit the mount into the in-memory state.
-        let preload_cancel = Arc::new(AtomicBool::new(false));
-        let entry = MountEntry {
-            mount_id,
-            job_id: task_id.clone(),
-            path: request.path.clone(),
-            cl: request.cl.clone(),
-            mountpoint: mountpoint_str.clone(),
-            upper_dir: upper_dir_str.clone(),
-            cl_dir: cl_dir_str.clone(),
-            fuse,
-            state: MountLifecycle::Mounted,
-            created_at_epoch_ms: now,
-            last_seen_epoch_ms: now,
-            preload_cancel: preload_cancel.clone(),
+    /// Create a new service instance placing layers and state under `paths` instead of
+    /// the configured roots.
+    ///
+    /// # Errors
+    /// The directory of `paths.state_file` is locked for as long as the service (or a
+    /// clone of it) lives; fails when another instance, in this process or another one,
+    /// holds it.
+    ///
+    /// # Note
+    /// Requires config to be initialized via `config::init_config()` before calling.
+    pub async fn with_paths(
+        dicfuse: Option<Arc<Dicfuse>>,
+        paths: AntaresPaths,
+    ) -> Result<Self, ServiceError> {
+        let state_file = paths.state_file.clone();
+        let state_store = tokio::task::spawn_blocking(move || StateStore::open(&state_file))
+            .await
+            .map_err(|e| ServiceError::Internal(format!("state store task failed: {}", e)))?
+            .map_err(|e| {
+                ServiceError::Internal(format!(
+                    "failed to open state {:?}: {}",
+                    paths.state_file, e
+                ))
+            })?;
+        let dic = match dicfuse {
+            Some(d) => d,
+            None => DicfuseManager::global().await,
         };
+        let events = MountEvents::new();
+        let webhooks = Webhooks::new(WebhookConfig::from_global_config());
+        webhooks.clone().spawn(events.subscribe());
+        Ok(Self {
+            dicfuse: dic,
+            dicfuse_cache: Arc::new(RwLock::new(HashMap::new())),
+            mounts: Arc::new(RwLock::new(HashMap::new())),
+            path_index: Arc::new(RwLock::new(HashMap::new())),
+            job_index: Arc::new(RwLock::new(HashMap::new())),
+            start_time: Instant::now(),
+            paths,
+            checkpoints: CheckpointStore::new(crate::util::config::antares_checkpoint_root()),
+            checkpoint_locks: Arc::new(Mutex::new(HashMap::new())),
+            blob_cache: BlobCache::new(crate::util::config::antares_blob_cache_root()),
+            cl_progress: Arc::new(RwLock::new(HashMap::new())),
+            cl_fetcher: Arc::new(OnceLock::new()),
+            provisioning: Arc::new(RwLock::new(HashMap::new())),
+            events,
+            webhooks,
+            claimed_mountpoints: Arc::new(Mutex::new(HashSet::new())),
+            creating: Arc::new(Mutex::new(HashSet::new())),
+            state_store: Arc::new(tokio::sync::Mutex::new(state_store)),
+        })
+    }
 
-        // Preserve path/cl for logging before moving into index
-        let path_for_log = request.path.clone();
-        let cl_for_log = request.cl.clone();
-
-        let task_id_for_log = task_id.clone();
+    /// Create a new service instance and recover previous mounts if available.
+    ///
+    /// # Arguments
+    /// * `dicfuse` - Optional shared Dicfuse instance. If None, creates a new one.
+    ///
+    /// # Note
+    /// Requires config to be initialized via `config::init_config()` before calling.
+    pub async fn new_with_recovery(dicfuse: Option<Arc<Dicfuse>>) -> Result<Self, ServiceError> {
+        Self::with_paths_and_recovery(dicfuse, AntaresPaths::from_global_config()).await
+    }
 
-        mounts.insert(mount_id, entry);
-        if let Some(job_id) = task_id {
-            job_index.insert(job_id, mount_id);
-        } else {
-            index.insert((request.path.clone(), request.cl.clone()), mount_id);
+    /// [`Self::with_paths`], then recover the mounts recorded in `paths.state_file` and
+    /// collect what the recovered mounts no longer refer to.
+    pub async fn with_paths_and_recovery(
+        dicfuse: Option<Arc<Dicfuse>>,
+        paths: AntaresPaths,
+    ) -> Result<Self, ServiceError> {
+        let instance = Self::with_paths(dicfuse, paths).await?;
+        instance.recover_mounts().await;
+        match instance.collect_garbage_impl(GcRequest::default()).await {
+            Ok(report) => tracing::info!(
+                removed = report.removed.len(),
+                failed = report.failed.len(),
+                "antares svc: startup gc finished"
+            ),
+            Err(e) => tracing::warn!(error = %e, "antares svc: startup gc failed"),
         }
+        Ok(instance)
+    }
 
-        tracing::info!(
-            mount_id = %mount_id,
-            task_id = ?task_id_for_log,
-            path = %path_for_log,
-            cl = ?cl_for_log,
-            mountpoint = %mountpoint_str,
-            upper_dir = %upper_dir_str,
-            cl_dir = ?cl_dir_str,
-            elapsed_ms = start.elapsed().as_millis(),
-            "antares svc: create_mount success"
-        );
+    /// Layer roots and state file of this service.
+    pub fn paths(&self) -> &AntaresPaths {
+        &self.paths
+    }
 
-        // IMPORTANT: release locks before persisting state.
-        // `persist_state()` acquires `self.mounts.read()`. If we keep holding `mounts.write()`
-        // here, the task deadlocks and the HTTP request never returns (curl hangs at step [1]).
-        drop(mounts);
-        drop(index);
-        drop(job_index);
+    /// The shared Dicfuse instance serving the monorepo root.
+    pub fn dicfuse(&self) -> Arc<Dicfuse> {
+        self.dicfuse.clone()
+    }
 
-        // Persist state to file for recovery
-        self.persist_state().await;
+    fn normalize_mount_path(path: &str) -> String {
+        let trimmed = path.trim();
+        if trimmed.is_empty() {
+            return String::new();
+        }
+        if trimmed == "/" {
+            return "/".to_string();
+        }
+        let mut normalized = if trimmed.starts_with('/') {
+            trimmed.to_string()
+        } else {
+            format!("/{trimmed}")
+        };
+        normalized = normalized.trim_end_matches('/').to_string();
+        if normalized.is_empty() {
+            "/".to_string()
+        } else {
+            normalized
+        }
+    }
 
-        // Transition to Ready immediately.
-        //
-        // By this point Dicfuse's `import_arc()` → `load_dir_depth()` (Phase 1) has
-        // already populated the in-memory directory cache.  Any FUSE `statx` that
-        // arrives now will hit the Dicfuse memory cache (~1 ms) instead of making a
-        // network round-trip (~100 ms).  Waiting for `deep_preload_walk` (Phase 2)
-        // to push those entries into the **kernel** FUSE cache would save ~1 ms per
-        // statx but costs ~140 s of startup latency — unacceptable for CI.
-        //
-        // Phase 2 still runs in the background as a best-effort optimisation.
-        {
-            let mut mounts = self.mounts.write().await;
-            if let Some(entry) = mounts.get_mut(&mount_id) {
-                if matches!(entry.state, MountLifecycle::Mounted) {
-                    entry.state = MountLifecycle::Ready;
-                    entry.update_last_seen();
-                    tracing::info!(
-                        mount_id = %mount_id,
-                        "antares svc: mount is Ready (Dicfuse cache warm, kernel cache warming in background)"
-                    );
-                }
-            }
+    fn normalize_abs_path(path: &str) -> String {
+        let trimmed = path.trim();
+        if trimmed.is_empty() {
+            return "/".to_string();
+        }
+        if trimmed == "/" {
+            return "/".to_string();
+        }
+        let mut normalized = if trimmed.starts_with('/') {
+            trimmed.to_string()
+        } else {
+            format!("/{trimmed}")
+        };
+        normalized = normalized.trim_end_matches('/').to_string();
+        if normalized.is_empty() {
+            "/".to_string()
+        } else {
+            normalized
         }
+    }
 
-        // Best-effort: warm FUSE kernel caches in the background.
-        self.spawn_deep_preload_task(
-            mount_id,
-            mountpoint_str.clone(),
-            preload_cancel.clone(),
-            "create_mount",
-        );
+    fn relative_path_for_mount(entry_path: &str, mount_path: &str) -> Option<PathBuf> {
+        let entry = Self::normalize_abs_path(entry_path);
+        let mount = Self::normalize_abs_path(mount_path);
+        if mount == "/" {
+            let rel = entry.trim_start_matches('/');
+            if rel.is_empty() {
+                return None;
+            }
+            return Self::validated_relative_path(rel);
+        }
 
-        Ok(MountCreated {
-            mount_id,
-            mountpoint: mountpoint_str,
-        })
+        let prefix = format!("{}/", mount);
+        if !entry.starts_with(&prefix) {
+            return None;
+        }
+        let rel = entry[prefix.len()..].trim_start_matches('/');
+        if rel.is_empty() {
+            return None;
+        }
+        Self::validated_relative_path(rel)
     }
 
-    async fn list_mounts(&self) -> Result<Vec<MountStatus>, ServiceError> {
-        let mounts = self.mounts.read().await;
-        let list: Vec<MountStatus> = mounts.values().map(|e| e.to_status()).collect();
-        Ok(list)
+    /// Where `entry_path` appears in a mount with `view`, if it is inside one of its bindings.
+    fn relative_path_in_view(entry_path: &str, view: &[MountBinding]) -> Option<PathBuf> {
+        view.iter().find_map(|binding| {
+            Self::relative_path_for_mount(entry_path, &binding.source)
+                .map(|rel| Path::new(&binding.target).join(rel))
+        })
     }
 
-    async fn describe_mount(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
-        let mounts = self.mounts.read().await;
-        let entry = mounts
-            .get(&mount_id)
-            .ok_or(ServiceError::NotFound(mount_id))?;
-        Ok(entry.to_status())
+    fn validated_relative_path(rel: &str) -> Option<PathBuf> {
+        let rel_path = Path::new(rel);
+        let components = rel_path.components();
+        for component in components {
+            match component {
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    return None;
+                }
+                Component::CurDir | Component::Normal(_) => {}
+            }
+        }
+        Some(rel_path.to_path_buf())
     }
 
-    async fn delete_mount(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
-        let start = Instant::now();
-        // Acquire write locks to update state
-        let mut mounts = self.mounts.write().await;
-        let index = self.path_index.write().await;
+    fn http_client() -> Result<Client, ServiceError> {
+        Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| ServiceError::Internal(format!("failed to build http client: {}", e)))
+    }
 
-        // Get mutable reference to entry (don't remove yet)
-        let entry = mounts
-            .get_mut(&mount_id)
-            .ok_or(ServiceError::NotFound(mount_id))?;
+    /// Client that builds CL layers from the configured Mega server.
+    fn cl_fetcher(&self) -> Result<&ClFetcher, ServiceError> {
+        if let Some(fetcher) = self.cl_fetcher.get() {
+            return Ok(fetcher);
+        }
+        let fetcher = ClFetcher {
+            base_url: crate::util::config::base_url().to_string(),
+            client: Self::http_client()?,
+            blob_cache: self.blob_cache.clone(),
+            workers: Self::cl_download_workers(),
+        };
+        Ok(self.cl_fetcher.get_or_init(|| fetcher))
+    }
 
-        if matches!(
-            entry.state,
-            MountLifecycle::Quiescing | MountLifecycle::Unmounting
-        ) {
+    /// Register a new CL build for `mount_id`, rejecting it if one is already running.
+    async fn begin_cl_progress(
+        &self,
+        mount_id: Uuid,
+        cls: &[String],
+    ) -> Result<ClProgress, ServiceError> {
+        let mut builds = self.cl_progress.write().await;
+        if builds.get(&mount_id).is_some_and(ClProgress::is_running) {
             return Err(ServiceError::InvalidRequest(format!(
-                "mount {} is currently in state {:?}; retry after switch/unmount completes",
-                mount_id, entry.state
+                "a CL build is already running for mount {}",
+                mount_id
             )));
         }
+        let progress = ClProgress::start(mount_id, cls);
+        builds.insert(mount_id, progress.clone());
+        Ok(progress)
+    }
 
-        // Cancel any in-flight deep-preload walk so it stops quickly.
-        entry.preload_cancel.store(true, Ordering::Relaxed);
-
-        // Set state to Unmounting while still in the map
-        entry.state = MountLifecycle::Unmounting;
-        entry.update_last_seen();
-
-        // Store path/cl for index removal, then take ownership of fuse for unmount
-        let path = entry.path.clone();
-        let cl = entry.cl.clone();
-        let job_id = entry.job_id.clone();
-        let job_id_for_log = job_id.clone();
-        tracing::info!(
-            mount_id = %mount_id,
-            task_id = ?job_id_for_log,
-            path = %path,
-            cl = ?cl,
-            mountpoint = %entry.mountpoint,
-            "antares svc: delete_mount start"
-        );
-        let mountpoint = PathBuf::from(&entry.mountpoint);
-        let upper_dir = PathBuf::from(&entry.upper_dir);
-        let cl_dir = entry.cl_dir.as_ref().map(PathBuf::from);
-        let mut fuse = std::mem::replace(&mut entry.fuse, {
-            // Create a placeholder AntaresFuse to replace (will be removed anyway if unmount succeeds)
-            // This is safe because we're about to remove the entry on success, or restore fuse on failure
-            AntaresFuse::new(
-                mountpoint.clone(),
-                self.dicfuse.clone(),
-                upper_dir.clone(),
-                cl_dir.clone(),
-            )
-            .await
-            .map_err(|e| {
-                ServiceError::Internal(format!("failed to create placeholder fuse: {}", e))
-            })?
-        });
-
-        // Release locks before potentially slow unmount operation
-        drop(mounts);
-        drop(index);
-
-        // Unmount the filesystem
-        let unmount_result = fuse.unmount().await;
-
-        // Reacquire locks to update state and remove if needed
-        let mut mounts = self.mounts.write().await;
-        let mut index = self.path_index.write().await;
-        let mut job_index = self.job_index.write().await;
-
-        let entry = match mounts.get_mut(&mount_id) {
-            Some(entry) => entry,
-            None => {
-                tracing::error!(
-                    "Mount entry {} missing during unmount; possible race or state bug",
-                    mount_id
-                );
-                drop(mounts);
-                drop(index);
-                drop(job_index);
-                return Err(ServiceError::Internal(format!(
-                    "Mount entry {} not found during unmount; this should not happen",
-                    mount_id
-                )));
-            }
-        };
-
-        if let Err(e) = unmount_result {
-            tracing::error!(
-                mount_id = %mount_id,
-                task_id = ?job_id_for_log,
-                elapsed_ms = start.elapsed().as_millis(),
-                error = %e,
-                "antares svc: delete_mount unmount failed"
-            );
-            // Put fuse back since unmount failed
-            entry.fuse = fuse;
-            entry.state = MountLifecycle::Failed {
-                reason: format!("unmount failed: {}", e),
-            };
-            entry.update_last_seen();
-            // Do not remove from mounts or index; keep for tracking failed unmounts
-            let status = entry.to_status();
-            drop(mounts);
-            drop(index);
-            drop(job_index);
-            return Ok(status);
-        } else {
-            entry.state = MountLifecycle::Unmounted;
-            entry.update_last_seen();
-            // Remove from mounts and index only after successful unmount
-            let status = entry.to_status();
-            mounts.remove(&mount_id);
-            if let Some(job_id) = job_id {
-                job_index.remove(&job_id);
-            } else {
-                index.remove(&(path, cl));
-            }
-            drop(mounts);
-            drop(index);
-            drop(job_index);
-            tracing::info!(
-                mount_id = %mount_id,
-                task_id = ?job_id_for_log,
-                elapsed_ms = start.elapsed().as_millis(),
-                "antares svc: delete_mount success"
-            );
-
-            // Persist state to file for recovery
-            self.persist_state().await;
+    /// Number of concurrent blob downloads per CL layer (`ANTARES_CL_DOWNLOAD_WORKERS`).
+    fn cl_download_workers() -> usize {
+        const DEFAULT_WORKERS: usize = 8;
+        match std::env::var("ANTARES_CL_DOWNLOAD_WORKERS") {
+            Ok(raw) => match raw.trim().parse::<usize>() {
+                Ok(n) => n.clamp(1, 64),
+                Err(_) => {
+                    tracing::warn!(
+                        value = %raw,
+                        default_workers = DEFAULT_WORKERS,
+                        "invalid ANTARES_CL_DOWNLOAD_WORKERS, using default"
+                    );
+                    DEFAULT_WORKERS
+                }
+            },
+            Err(_) => DEFAULT_WORKERS,
+        }
+    }
 
-            Ok(status)
+    fn cl_quiesce_grace_duration() -> Duration {
+        const DEFAULT_MS: u64 = 150;
+        match std::env::var("ANTARES_CL_QUIESCE_GRACE_MS") {
+            Ok(raw) => match raw.trim().parse::<u64>() {
+                Ok(ms) => Duration::from_millis(ms.clamp(0, 3_000)),
+                Err(_) => {
+                    tracing::warn!(
+                        value = %raw,
+                        default_ms = DEFAULT_MS,
+                        "invalid ANTARES_CL_QUIESCE_GRACE_MS, using default"
+                    );
+                    Duration::from_millis(DEFAULT_MS)
+                }
+            },
+            Err(_) => Duration::from_millis(DEFAULT_MS),
         }
     }
 
-    async fn build_cl(&self, mount_id: Uuid, cl_link: String) -> Result<MountStatus, ServiceError> {
+    /// Quiesce a mount and unmount its FUSE session for offline maintenance of its layers.
+    ///
+    /// Uses the same switch window as `build_cl`: the mount enters `Quiescing`, the
+    /// preload walk is cancelled, and the session is unmounted after the grace period.
+    /// On success the caller owns the returned session and must hand it back through
+    /// [`Self::resume_quiesced`].
+    async fn quiesce_for_maintenance(
+        &self,
+        mount_id: Uuid,
+        op: &'static str,
+    ) -> Result<AntaresFuse, ServiceError> {
         let mut mounts = self.mounts.write().await;
-        let index = self.path_index.write().await;
-
         let entry = mounts
             .get_mut(&mount_id)
             .ok_or(ServiceError::NotFound(mount_id))?;
         if !matches!(entry.state, MountLifecycle::Mounted | MountLifecycle::Ready) {
             return Err(ServiceError::InvalidRequest(format!(
-                "mount {} is currently in state {:?}; cannot build CL",
-                mount_id, entry.state
+                "mount {} is currently in state {:?}; cannot {}",
+                mount_id, entry.state, op
             )));
         }
 
-        let cl_root = crate::util::config::antares_cl_root();
-        let cl_dir_str = format!("{}/{}", cl_root, mount_id);
-        let cl_dir_path = PathBuf::from(&cl_dir_str);
-        let quiesce_grace = Self::cl_quiesce_grace_duration();
-        let path = entry.path.clone();
-        let job_id = entry.job_id.clone();
-        let old_cl = entry.cl.clone();
-        let mountpoint = PathBuf::from(&entry.mountpoint);
-        let upper_dir = PathBuf::from(&entry.upper_dir);
-        let existing_cl_dir = entry.cl_dir.as_ref().map(PathBuf::from);
-        let dicfuse = entry.fuse.dic.clone();
-        // Cancel any in-flight deep-preload walk before unmounting.
+        let placeholder = AntaresFuse::new_with_layers(
+            PathBuf::from(&entry.mountpoint),
+            self.dicfuse.clone(),
+            entry.upper_dir.as_ref().map(PathBuf::from),
+            entry.cl_dir_paths(),
+        )
+        .await
+        .map_err(|e| ServiceError::Internal(format!("failed to create placeholder fuse: {}", e)))?;
         entry.preload_cancel.store(true, Ordering::Relaxed);
-        // Enter a short quiescing window so control-plane operations reject this mount
-        // while we prepare to remount with the new CL layer.
-        entry.state = MountLifecycle::Quiescing;
+        entry.set_state(MountLifecycle::Quiescing);
         entry.update_last_seen();
-        let mut old_fuse = std::mem::replace(&mut entry.fuse, {
-            AntaresFuse::new(
-                mountpoint.clone(),
-                self.dicfuse.clone(),
-                upper_dir.clone(),
-                existing_cl_dir.clone(),
-            )
-            .await
-            .map_err(|e| {
-                ServiceError::Internal(format!("failed to create placeholder fuse: {}", e))
-            })?
-        });
-
+        let mut fuse = std::mem::replace(&mut entry.fuse, placeholder);
         drop(mounts);
-        drop(index);
+
+        let quiesce_grace = Self::cl_quiesce_grace_duration();
         if !quiesce_grace.is_zero() {
             tracing::info!(
                 mount_id = %mount_id,
                 grace_ms = quiesce_grace.as_millis(),
-                "antares svc: build_cl quiescing before remount"
+                op = op,
+                "antares svc: quiescing before unmount"
             );
             sleep(quiesce_grace).await;
         }
 
-        if let Err(e) = old_fuse.unmount().await {
-            tracing::error!("Failed to unmount {}: {}", mount_id, e);
+        if let Err(e) = fuse.unmount().await {
+            tracing::error!(mount_id = %mount_id, op = op, error = %e, "antares svc: unmount failed");
             let mut mounts = self.mounts.write().await;
-            let index = self.path_index.write().await;
-            let _job_index = self.job_index.write().await;
             if let Some(entry) = mounts.get_mut(&mount_id) {
-                entry.fuse = old_fuse;
-                entry.state = MountLifecycle::Failed {
+                entry.fuse = fuse;
+                entry.set_state(MountLifecycle::Failed {
                     reason: format!("unmount failed: {}", e),
-                };
+                });
                 entry.update_last_seen();
             }
-            drop(mounts);
-            drop(index);
-            drop(_job_index);
             return Err(ServiceError::FuseFailure(format!("unmount failed: {}", e)));
         }
+        Ok(fuse)
+    }
 
-        if let Err(e) = self.build_cl_layer(&path, &cl_link, &cl_dir_path).await {
-            tracing::error!("Failed to build CL layer for {}: {}", mount_id, e);
-            let remount_result = old_fuse.mount().await;
-            let mut mounts = self.mounts.write().await;
-            let index = self.path_index.write().await;
-            let _job_index = self.job_index.write().await;
-            if let Some(entry) = mounts.get_mut(&mount_id) {
-                entry.fuse = old_fuse;
-                entry.state = if let Err(remount_err) = remount_result {
-                    MountLifecycle::Failed {
-                        reason: format!("remount after CL failure: {}", remount_err),
-                    }
-                } else {
-                    MountLifecycle::Mounted
-                };
-                entry.update_last_seen();
-            }
-            drop(mounts);
-            drop(index);
-            drop(_job_index);
-            return Err(e);
-        }
-
-        let mut new_fuse = AntaresFuse::new(
-            mountpoint.clone(),
-            dicfuse,
-            upper_dir.clone(),
-            Some(cl_dir_path.clone()),
-        )
-        .await
-        .map_err(|e| ServiceError::FuseFailure(format!("failed to create fuse: {}", e)))?;
-        if let Err(e) = new_fuse.mount().await {
-            tracing::error!("Failed to remount {} with CL: {}", mount_id, e);
-            let remount_result = old_fuse.mount().await;
-            let mut mounts = self.mounts.write().await;
-            let index = self.path_index.write().await;
-            let _job_index = self.job_index.write().await;
-            if let Some(entry) = mounts.get_mut(&mount_id) {
-                entry.fuse = old_fuse;
-                entry.state = if let Err(remount_err) = remount_result {
-                    MountLifecycle::Failed {
-                        reason: format!("remount after CL failure: {}", remount_err),
-                    }
-                } else {
-                    MountLifecycle::Mounted
-                };
-                entry.update_last_seen();
-            }
-            drop(mounts);
-            drop(index);
-            drop(_job_index);
-            return Err(ServiceError::FuseFailure(format!(
-                "failed to mount CL view: {}",
-                e
-            )));
-        }
-
+    /// Remount a session taken by [`Self::quiesce_for_maintenance`] and mark the mount Ready.
+    async fn resume_quiesced(
+        &self,
+        mount_id: Uuid,
+        mut fuse: AntaresFuse,
+        op: &'static str,
+    ) -> Result<MountStatus, ServiceError> {
+        let mount_result = fuse.mount().await;
         let mut mounts = self.mounts.write().await;
-        let mut index = self.path_index.write().await;
-        let _job_index = self.job_index.write().await;
         let entry = mounts
             .get_mut(&mount_id)
             .ok_or(ServiceError::NotFound(mount_id))?;
+        entry.fuse = fuse;
+        if let Err(e) = mount_result {
+            entry.set_state(MountLifecycle::Failed {
+                reason: format!("remount after {} failed: {}", op, e),
+            });
+            entry.update_last_seen();
+            return Err(ServiceError::FuseFailure(format!(
+                "remount after {} failed: {}",
+                op, e
+            )));
+        }
 
-        // Reset the cancel flag and assign a fresh one for the next preload cycle.
         let new_cancel = Arc::new(AtomicBool::new(false));
-        entry.fuse = new_fuse;
-        entry.cl = Some(cl_link.clone());
-        entry.cl_dir = Some(cl_dir_str);
-        // Transition directly to Ready — Dicfuse cache is already warm.
-        entry.state = MountLifecycle::Ready;
+        entry.set_state(MountLifecycle::Ready);
         entry.preload_cancel = new_cancel.clone();
         entry.update_last_seen();
-
-        if job_id.is_none() && old_cl != entry.cl {
-            let path = entry.path.clone();
-            index.remove(&(path.clone(), old_cl));
-            index.insert((path, entry.cl.clone()), mount_id);
-        }
-
-        let mountpoint_for_preload = entry.mountpoint.clone();
+        let mountpoint = entry.mountpoint.clone();
+        let preload = entry.deep_preload_options();
         let status = entry.to_status();
-        tracing::info!(
-            "Built CL layer for mount {} with link {}",
-            mount_id,
-            cl_link
-        );
         drop(mounts);
-        drop(index);
-        drop(_job_index);
 
-        self.persist_state().await;
+        self.spawn_deep_preload_task(mount_id, mountpoint, preload, new_cancel, op);
+        Ok(status)
+    }
 
-        // Best-effort: re-warm kernel FUSE caches after remount.
-        self.spawn_deep_preload_task(mount_id, mountpoint_for_preload, new_cancel, "build_cl");
+    /// Spawn a background deep-preload walk to warm FUSE kernel entry/attr caches.
+    ///
+    /// This is a **best-effort optimisation** — it does NOT block the mount from
+    /// being marked `Ready`.  By the time we call this, Dicfuse's internal
+    /// `load_dir_depth()` (Phase 1) has already populated the in-memory directory
+    /// cache.  Without this walk, FUSE `statx` calls still reach the daemon but
+    /// hit the Dicfuse memory cache (~1 ms each).  The walk pushes those entries
+    /// into the Linux kernel's FUSE cache so subsequent `statx` calls are served
+    /// at ~0 ms — a nice-to-have, not a prerequisite for correctness.
+    fn spawn_deep_preload_task(
+        &self,
+        mount_id: Uuid,
+        mountpoint: String,
+        preload: DeepPreloadOptions,
+        cancel: Arc<AtomicBool>,
+        source: &'static str,
+    ) {
+        let events = self.events.clone();
+        tokio::spawn(async move {
+            let start = Instant::now();
+            events.emit(mount_id, MountEventKind::PreloadStarted);
+            tracing::info!(
+                mount_id = %mount_id,
+                mountpoint = %mountpoint,
+                source = source,
+                "antares svc: starting background kernel cache warm (best-effort)"
+            );
 
-        Ok(status)
+            let mp = mountpoint.clone();
+            let walk_result =
+                tokio::task::spawn_blocking(move || deep_preload_walk(&mp, preload, &cancel)).await;
+
+            match walk_result {
+                Ok(Ok(stats)) => {
+                    tracing::info!(
+                        mount_id = %mount_id,
+                        source = source,
+                        entries_visited = stats.entries_visited,
+                        metadata_touches = stats.metadata_touches,
+                        budget_exhausted = stats.budget_exhausted,
+                        elapsed_ms = start.elapsed().as_millis(),
+                        "antares svc: kernel cache warm completed"
+                    );
+                    events.emit(
+                        mount_id,
+                        MountEventKind::PreloadFinished {
+                            entries_visited: stats.entries_visited,
+                            metadata_touches: stats.metadata_touches,
+                            budget_exhausted: stats.budget_exhausted,
+                            elapsed_ms: start.elapsed().as_millis() as u64,
+                        },
+                    );
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!(
+                        mount_id = %mount_id,
+                        source = source,
+                        error = %e,
+                        elapsed_ms = start.elapsed().as_millis(),
+                        "antares svc: kernel cache warm finished with errors"
+                    );
+                    events.emit(
+                        mount_id,
+                        MountEventKind::PreloadFailed {
+                            error: e.to_string(),
+                        },
+                    );
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        mount_id = %mount_id,
+                        source = source,
+                        error = %e,
+                        "antares svc: kernel cache warm task panicked"
+                    );
+                    events.emit(
+                        mount_id,
+                        MountEventKind::PreloadFailed {
+                            error: e.to_string(),
+                        },
+                    );
+                }
+            }
+        });
     }
 
-    async fn clear_cl(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
+    /// Drop cached blobs no longer referenced by the CL layers of any tracked mount.
+    ///
+    /// References come from the layer manifests. Layers mid-build have no manifest, but
+    /// anything they already linked keeps its data, so at worst a blob is downloaded again.
+    async fn collect_blob_garbage(&self) {
+        let cl_dirs: Vec<PathBuf> = {
+            let mounts = self.mounts.read().await;
+            mounts.values().flat_map(|e| e.cl_dir_paths()).collect()
+        };
+        let cache = self.blob_cache.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let live: HashSet<String> = cl_dirs
+                .iter()
+                .filter_map(|dir| cl_manifest::load(dir))
+                .flat_map(|m| m.files.into_values())
+                .filter(|entry| !entry.is_whiteout())
+                .filter_map(|entry| BlobCache::key(&entry.sha))
+                .collect();
+            cache.gc(&live)
+        })
+        .await;
+        match result {
+            Ok(Ok(stats)) if stats.removed > 0 => tracing::info!(
+                removed = stats.removed,
+                removed_bytes = stats.removed_bytes,
+                kept = stats.kept,
+                "antares svc: blob cache collected"
+            ),
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => tracing::warn!(error = %e, "antares svc: blob cache gc failed"),
+            Err(e) => tracing::warn!(error = %e, "antares svc: blob cache gc task failed"),
+        }
+    }
+
+    fn create_whiteout(path: &Path) -> Result<(), ServiceError> {
+        if let Some(parent) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                return Err(ServiceError::Internal(format!(
+                    "failed to create whiteout parent {:?}: {}",
+                    parent, e
+                )));
+            }
+        }
+
+        if path.exists() {
+            let _ = std::fs::remove_file(path);
+            let _ = std::fs::remove_dir_all(path);
+        }
+
+        let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|e| {
+            ServiceError::Internal(format!("invalid whiteout path {:?}: {}", path, e))
+        })?;
+
+        let mode = libc::S_IFCHR;
+        let dev = 0;
+        let res = unsafe { libc::mknod(c_path.as_ptr(), mode, dev) };
+        if res != 0 {
+            return Err(ServiceError::Internal(format!(
+                "failed to create whiteout {:?}: {}",
+                path,
+                std::io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Build one CL layer per entry of `cls` into a staging copy of the matching `cl_dirs`
+    /// entry, bottom first, and report the paths more than one of them supplies.
+    ///
+    /// With `local`, the single layer is built from that manifest (a local patch or
+    /// archive) instead of fetching `cls` from Mega.
+    ///
+    /// Returns the staging directories in `cl_dirs` order; the live layers are untouched
+    /// until [`Self::commit_cl_stack`]. On failure every staging directory is removed.
+    async fn stage_cl_stack(
+        &self,
+        view: &[MountBinding],
+        cls: &[String],
+        cl_dirs: &[PathBuf],
+        local: Option<&ClManifest>,
+        progress: &ClProgress,
+    ) -> Result<(Vec<PathBuf>, Vec<ClConflict>), ServiceError> {
+        let mut staged = Vec::with_capacity(cl_dirs.len());
+        let result = self
+            .build_staged_layers(view, cls, cl_dirs, local, progress, &mut staged)
+            .await;
+        match result {
+            Ok(conflicts) => Ok((staged, conflicts)),
+            Err(e) => {
+                for staging in &staged {
+                    if let Err(e) = cl_manifest::remove_layer(staging) {
+                        tracing::warn!("Failed to remove CL staging dir {:?}: {}", staging, e);
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn build_staged_layers(
+        &self,
+        view: &[MountBinding],
+        cls: &[String],
+        cl_dirs: &[PathBuf],
+        local: Option<&ClManifest>,
+        progress: &ClProgress,
+        staged: &mut Vec<PathBuf>,
+    ) -> Result<Vec<ClConflict>, ServiceError> {
+        for (layer, (cl_link, cl_dir)) in cls.iter().zip(cl_dirs).enumerate() {
+            let staging = cl_manifest::stage(cl_dir).map_err(|e| {
+                ServiceError::Internal(format!("failed to stage CL dir {:?}: {}", cl_dir, e))
+            })?;
+            staged.push(staging.clone());
+            let fetcher = self.cl_fetcher()?;
+            match local {
+                Some(manifest) => {
+                    fetcher
+                        .apply_manifest(&staging, manifest, layer, progress)
+                        .await?
+                }
+                None => {
+                    fetcher
+                        .build_layer(view, cl_link, &staging, layer, progress)
+                        .await?
+                }
+            }
+        }
+        if cls.len() < 2 {
+            return Ok(Vec::new());
+        }
+        let layers: Vec<(String, PathBuf)> = cls.iter().cloned().zip(staged.clone()).collect();
+        tokio::task::spawn_blocking(move || cl_stack::find_conflicts(&layers))
+            .await
+            .map_err(|e| ServiceError::Internal(format!("CL conflict scan task failed: {}", e)))?
+            .map_err(|e| ServiceError::Internal(format!("failed to scan CL layers: {}", e)))
+    }
+
+    /// Swap the `staged` layers into `cl_dirs` and retire the `stale` layers the new stack
+    /// no longer uses. On failure everything already swapped is rolled back.
+    ///
+    /// The replaced layers are kept until [`Self::finish_cl_stack`] or
+    /// [`Self::rollback_cl_stack`] is called with the same directories.
+    fn commit_cl_stack(
+        staged: &[PathBuf],
+        cl_dirs: &[PathBuf],
+        stale: &[PathBuf],
+    ) -> Result<(), ServiceError> {
+        let result = stale
+            .iter()
+            .try_for_each(|dir| cl_manifest::retire(dir))
+            .and_then(|_| {
+                staged
+                    .iter()
+                    .zip(cl_dirs)
+                    .try_for_each(|(staging, dir)| cl_manifest::commit(staging, dir))
+            });
+        result.map_err(|e| {
+            Self::rollback_cl_stack(cl_dirs.iter().chain(stale));
+            for staging in staged {
+                let _ = cl_manifest::remove_layer(staging);
+            }
+            ServiceError::Internal(format!("failed to swap in CL layers: {}", e))
+        })
+    }
+
+    /// Restore the layers replaced or retired by [`Self::commit_cl_stack`].
+    fn rollback_cl_stack<'a>(dirs: impl IntoIterator<Item = &'a PathBuf>) {
+        for dir in dirs {
+            if let Err(e) = cl_manifest::rollback(dir) {
+                tracing::warn!("Failed to restore CL directory {:?}: {}", dir, e);
+            }
+        }
+    }
+
+    /// Drop the layers replaced or retired by [`Self::commit_cl_stack`].
+    fn finish_cl_stack<'a>(dirs: impl IntoIterator<Item = &'a PathBuf>) {
+        for dir in dirs {
+            if let Err(e) = cl_manifest::finish(dir) {
+                tracing::warn!("Failed to remove previous CL directory {:?}: {}", dir, e);
+            }
+        }
+    }
+
+    /// Build a fresh CL stack directly into `cl_dirs` (e.g. for a new mount).
+    async fn build_cl_stack(
+        &self,
+        view: &[MountBinding],
+        cls: &[String],
+        cl_dirs: &[PathBuf],
+        progress: &ClProgress,
+    ) -> Result<Vec<ClConflict>, ServiceError> {
+        let (staged, conflicts) = self
+            .stage_cl_stack(view, cls, cl_dirs, None, progress)
+            .await?;
+        Self::commit_cl_stack(&staged, cl_dirs, &[])?;
+        Self::finish_cl_stack(cl_dirs);
+        Ok(conflicts)
+    }
+
+    /// Swap the CL stack of a live mount for `cls` (built from `local` if given, see
+    /// [`Self::stage_cl_stack`]).
+    ///
+    /// The new layers are built in staging directories while the mount keeps serving the
+    /// old ones; only the swap itself happens inside the quiescing window. A failed build
+    /// leaves the mount and its previous layers untouched.
+    async fn rebuild_cl(
+        &self,
+        mount_id: Uuid,
+        view: &[MountBinding],
+        cls: Vec<String>,
+        local: Option<&ClManifest>,
+        progress: &ClProgress,
+    ) -> Result<MountStatus, ServiceError> {
+        let cl_root = self.paths.cl_root.to_string_lossy();
+        let id_str = mount_id.to_string();
+        let cl_dirs_str: Vec<String> = (0..cls.len())
+            .map(|i| cl_layer_dir(&cl_root, &id_str, i))
+            .collect();
+        let cl_dirs: Vec<PathBuf> = cl_dirs_str.iter().map(PathBuf::from).collect();
+        let (staged, cl_conflicts) = self
+            .stage_cl_stack(view, &cls, &cl_dirs, local, progress)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to build CL layers for {}: {}", mount_id, e);
+                e
+            })?;
+        let discard_staged = || {
+            for staging in &staged {
+                let _ = cl_manifest::remove_layer(staging);
+            }
+        };
+
         let mut mounts = self.mounts.write().await;
         let index = self.path_index.write().await;
 
-        let entry = mounts
-            .get_mut(&mount_id)
-            .ok_or(ServiceError::NotFound(mount_id))?;
+        let Some(entry) = mounts.get_mut(&mount_id) else {
+            discard_staged();
+            return Err(ServiceError::NotFound(mount_id));
+        };
         if !matches!(entry.state, MountLifecycle::Mounted | MountLifecycle::Ready) {
+            discard_staged();
             return Err(ServiceError::InvalidRequest(format!(
-                "mount {} is currently in state {:?}; cannot clear CL",
+                "mount {} changed to state {:?} during the CL build",
                 mount_id, entry.state
             )));
         }
 
-        if entry.cl.is_none() {
-            return Err(ServiceError::InvalidRequest(
-                "mount has no CL layer to clear".into(),
-            ));
-        }
-
-        let path = entry.path.clone();
-        let job_id = entry.job_id.clone();
-        let old_cl = entry.cl.clone();
         let quiesce_grace = Self::cl_quiesce_grace_duration();
+        let job_id = entry.job_id.clone();
+        let old_cls = entry.cls.clone();
         let mountpoint = PathBuf::from(&entry.mountpoint);
-        let upper_dir = PathBuf::from(&entry.upper_dir);
-        let existing_cl_dir = entry.cl_dir.as_ref().map(PathBuf::from);
+        let upper_dir = entry.upper_dir.as_ref().map(PathBuf::from);
+        let existing_cl_dirs = entry.cl_dir_paths();
         let dicfuse = entry.fuse.dic.clone();
+        let bound = entry.fuse.bindings.clone();
+        let quota = entry.quota.clone();
+        let mem_upper = entry.mem_upper.clone();
+        let placeholder = match AntaresFuse::new_with_layers(
+            mountpoint.clone(),
+            self.dicfuse.clone(),
+            upper_dir.clone(),
+            existing_cl_dirs.clone(),
+        )
+        .await
+        {
+            Ok(fuse) => fuse,
+            Err(e) => {
+                discard_staged();
+                return Err(ServiceError::Internal(format!(
+                    "failed to create placeholder fuse: {}",
+                    e
+                )));
+            }
+        };
         // Cancel any in-flight deep-preload walk before unmounting.
         entry.preload_cancel.store(true, Ordering::Relaxed);
         // Enter a short quiescing window so control-plane operations reject this mount
-        // while we prepare to remount without CL.
-        entry.state = MountLifecycle::Quiescing;
+        // while we swap in the new CL layers and remount.
+        entry.set_state(MountLifecycle::Quiescing);
         entry.update_last_seen();
-        let mut old_fuse = std::mem::replace(&mut entry.fuse, {
-            AntaresFuse::new(
-                mountpoint.clone(),
-                self.dicfuse.clone(),
-                upper_dir.clone(),
-                existing_cl_dir.clone(),
-            )
-            .await
-            .map_err(|e| {
-                ServiceError::Internal(format!("failed to create placeholder fuse: {}", e))
-            })?
-        });
+        let mut old_fuse = std::mem::replace(&mut entry.fuse, placeholder);
 
         drop(mounts);
         drop(index);
@@ -2005,21 +3407,22 @@ impl AntaresService for AntaresServiceImpl {
             tracing::info!(
                 mount_id = %mount_id,
                 grace_ms = quiesce_grace.as_millis(),
-                "antares svc: clear_cl quiescing before remount"
+                "antares svc: build_cl quiescing before remount"
             );
             sleep(quiesce_grace).await;
         }
 
         if let Err(e) = old_fuse.unmount().await {
             tracing::error!("Failed to unmount {}: {}", mount_id, e);
+            discard_staged();
             let mut mounts = self.mounts.write().await;
             let index = self.path_index.write().await;
             let _job_index = self.job_index.write().await;
             if let Some(entry) = mounts.get_mut(&mount_id) {
                 entry.fuse = old_fuse;
-                entry.state = MountLifecycle::Failed {
+                entry.set_state(MountLifecycle::Failed {
                     reason: format!("unmount failed: {}", e),
-                };
+                });
                 entry.update_last_seen();
             }
             drop(mounts);
@@ -2028,42 +3431,74 @@ impl AntaresService for AntaresServiceImpl {
             return Err(ServiceError::FuseFailure(format!("unmount failed: {}", e)));
         }
 
-        if let Some(cl_dir) = &existing_cl_dir {
-            if cl_dir.exists() {
-                if let Err(e) = std::fs::remove_dir_all(cl_dir) {
-                    tracing::warn!("Failed to remove CL directory {:?}: {}", cl_dir, e);
-                }
+        // Layers the new stack no longer uses are retired along with the replaced ones.
+        let stale: Vec<PathBuf> = existing_cl_dirs
+            .iter()
+            .filter(|d| !cl_dirs.contains(d))
+            .cloned()
+            .collect();
+        if let Err(e) = Self::commit_cl_stack(&staged, &cl_dirs, &stale) {
+            tracing::error!("Failed to swap CL layers for {}: {}", mount_id, e);
+            let remount_result = old_fuse.mount().await;
+            let mut mounts = self.mounts.write().await;
+            let index = self.path_index.write().await;
+            let _job_index = self.job_index.write().await;
+            if let Some(entry) = mounts.get_mut(&mount_id) {
+                entry.fuse = old_fuse;
+                entry.set_state(if let Err(remount_err) = remount_result {
+                    MountLifecycle::Failed {
+                        reason: format!("remount after CL failure: {}", remount_err),
+                    }
+                } else {
+                    MountLifecycle::Mounted
+                });
+                entry.update_last_seen();
             }
+            drop(mounts);
+            drop(index);
+            drop(_job_index);
+            return Err(e);
         }
+        let swapped: Vec<PathBuf> = cl_dirs.iter().chain(&stale).cloned().collect();
 
-        let mut new_fuse = AntaresFuse::new(mountpoint.clone(), dicfuse, upper_dir.clone(), None)
-            .await
-            .map_err(|e| ServiceError::FuseFailure(format!("failed to create fuse: {}", e)))?;
+        let mut new_fuse = AntaresFuse::new_with_layers(
+            mountpoint.clone(),
+            dicfuse,
+            upper_dir.clone(),
+            cl_dirs.clone(),
+        )
+        .await
+        .map_err(|e| ServiceError::FuseFailure(format!("failed to create fuse: {}", e)))?
+        .with_quota(quota)
+        .with_memory_upper(mem_upper)
+        .with_bindings(bound);
         if let Err(e) = new_fuse.mount().await {
-            tracing::error!("Failed to remount {} without CL: {}", mount_id, e);
+            tracing::error!("Failed to remount {} with CL: {}", mount_id, e);
+            Self::rollback_cl_stack(&swapped);
             let remount_result = old_fuse.mount().await;
             let mut mounts = self.mounts.write().await;
             let index = self.path_index.write().await;
             let _job_index = self.job_index.write().await;
             if let Some(entry) = mounts.get_mut(&mount_id) {
                 entry.fuse = old_fuse;
-                entry.state = if let Err(remount_err) = remount_result {
+                entry.set_state(if let Err(remount_err) = remount_result {
                     MountLifecycle::Failed {
-                        reason: format!("remount after clear CL failure: {}", remount_err),
+                        reason: format!("remount after CL failure: {}", remount_err),
                     }
                 } else {
                     MountLifecycle::Mounted
-                };
+                });
                 entry.update_last_seen();
             }
             drop(mounts);
             drop(index);
             drop(_job_index);
             return Err(ServiceError::FuseFailure(format!(
-                "failed to remount without CL: {}",
+                "failed to mount CL view: {}",
                 e
             )));
         }
+        Self::finish_cl_stack(&swapped);
 
         let mut mounts = self.mounts.write().await;
         let mut index = self.path_index.write().await;
@@ -2072,1158 +3507,5111 @@ impl AntaresService for AntaresServiceImpl {
             .get_mut(&mount_id)
             .ok_or(ServiceError::NotFound(mount_id))?;
 
+        // Reset the cancel flag and assign a fresh one for the next preload cycle.
         let new_cancel = Arc::new(AtomicBool::new(false));
         entry.fuse = new_fuse;
-        entry.cl = None;
-        entry.cl_dir = None;
+        entry.cls = cls;
+        entry.cl_dirs = cl_dirs_str;
+        entry.cl_conflicts = cl_conflicts;
         // Transition directly to Ready — Dicfuse cache is already warm.
-        entry.state = MountLifecycle::Ready;
+        entry.set_state(MountLifecycle::Ready);
         entry.preload_cancel = new_cancel.clone();
         entry.update_last_seen();
 
-        if job_id.is_none() {
-            index.remove(&(path.clone(), old_cl));
-            index.insert((path, None), mount_id);
+        if job_id.is_none() && old_cls != entry.cls {
+            let path = entry.index_path();
+            if remove_path_index_entry(&mut index, &(path.clone(), old_cls), mount_id) {
+                index.entry((path, entry.cls.clone())).or_insert(mount_id);
+            }
         }
 
         let mountpoint_for_preload = entry.mountpoint.clone();
+        let preload = entry.deep_preload_options();
         let status = entry.to_status();
-        tracing::info!("Cleared CL layer for mount {}", mount_id);
+        tracing::info!(
+            "Built CL layers for mount {} with links {:?} ({} conflicting paths)",
+            mount_id,
+            status.cls,
+            status.cl_conflicts.len()
+        );
         drop(mounts);
         drop(index);
         drop(_job_index);
 
         self.persist_state().await;
+        self.collect_blob_garbage().await;
 
         // Best-effort: re-warm kernel FUSE caches after remount.
-        self.spawn_deep_preload_task(mount_id, mountpoint_for_preload, new_cancel, "clear_cl");
-
-        Ok(status)
-    }
-
-    async fn check_mount_ready(&self, mount_id: Uuid) -> Result<MountReadyResponse, ServiceError> {
-        let mounts = self.mounts.read().await;
-        let entry = mounts
-            .get(&mount_id)
-            .ok_or(ServiceError::NotFound(mount_id))?;
-        let ready = entry.state == MountLifecycle::Ready;
-        Ok(MountReadyResponse {
+        self.spawn_deep_preload_task(
             mount_id,
-            ready,
-            state: entry.state.clone(),
-        })
-    }
-
-    async fn health_info(&self) -> HealthResponse {
-        self.health_info_impl().await
-    }
+            mountpoint_for_preload,
+            preload,
+            new_cancel,
+            "build_cl",
+        );
 
-    async fn shutdown_cleanup(&self) -> Result<(), ServiceError> {
-        self.shutdown_cleanup_impl().await
+        Ok(status)
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-enum DeepPreloadMode {
-    ScanOnly,
-    Full,
-    Hotset,
-    DirsOnly,
-}
-
-impl DeepPreloadMode {
-    fn as_str(self) -> &'static str {
-        match self {
-            DeepPreloadMode::ScanOnly => "scan_only",
-            DeepPreloadMode::Full => "full",
-            DeepPreloadMode::Hotset => "hotset",
-            DeepPreloadMode::DirsOnly => "dirs_only",
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
-struct DeepPreloadStats {
-    entries_visited: usize,
-    metadata_touches: usize,
-    budget_exhausted: bool,
-}
+    /// Get or create a Dicfuse instance for the given path.
+    ///
+    /// For root path ("/" or empty), returns the shared global instance.
+    /// For subdirectory paths, returns a cached instance or creates a new one.
+    /// This ensures that multiple mounts with the same base_path share the same
+    /// Dicfuse instance, avoiding unnecessary duplication.
+    ///
+    /// A non-empty `tuning` always gets its own view (cached per path and tuning),
+    /// including for the root path.
+    ///
+    /// IMPORTANT: For newly created instances, this method waits for the Dicfuse
+    /// directory tree to be fully initialized before returning. This prevents
+    /// FUSE mount failures due to root inode not being set up yet.
+    ///
+    /// # TODO(dicfuse-antares-integration)
+    /// - Support incremental directory tree loading to reduce initial wait time
+    /// - Add progress callback for long-running initialization
+    /// - Consider lazy loading for very large subdirectory mounts
+    async fn get_or_create_dicfuse(
+        &self,
+        path: &str,
+        tuning: &DicfuseTuning,
+    ) -> Result<Arc<Dicfuse>, ServiceError> {
+        const INIT_TIMEOUT_SECS: u64 = 120;
 
-fn deep_preload_mode() -> DeepPreloadMode {
-    match std::env::var("ANTARES_DEEP_PRELOAD_MODE") {
-        Ok(raw) => {
-            let normalized = raw.trim().to_ascii_lowercase();
-            match normalized.as_str() {
-                "scan" | "scan_only" | "readdirplus" => DeepPreloadMode::ScanOnly,
-                "full" => DeepPreloadMode::Full,
-                "dirs" | "dirs_only" => DeepPreloadMode::DirsOnly,
-                "hotset" | "" => DeepPreloadMode::Hotset,
-                _ => {
-                    tracing::warn!(
-                        value = %raw,
-                        "invalid ANTARES_DEEP_PRELOAD_MODE, expected one of: scan|hotset|full|dirs"
+        // For root path, use the shared global instance (but ensure it's initialized first).
+        if (path.is_empty() || path == "/") && tuning.is_empty() {
+            tracing::info!(
+                "Waiting for shared Dicfuse instance to initialize for path: / (timeout: {}s)",
+                INIT_TIMEOUT_SECS
+            );
+            match tokio::time::timeout(
+                Duration::from_secs(INIT_TIMEOUT_SECS),
+                self.dicfuse.store.wait_for_ready(),
+            )
+            .await
+            {
+                Ok(_) => {
+                    tracing::info!("Shared Dicfuse initialized successfully for path: /");
+                }
+                Err(_) => {
+                    tracing::error!(
+                        "Shared Dicfuse initialization timed out for path: / after {}s",
+                        INIT_TIMEOUT_SECS
                     );
-                    DeepPreloadMode::ScanOnly
+                    return Err(ServiceError::FuseFailure(format!(
+                        "Dicfuse initialization timed out for path '/' after {}s. \
+                         Check network connectivity to the monorepo server.",
+                        INIT_TIMEOUT_SECS
+                    )));
                 }
             }
+            return Ok(self.dicfuse.clone());
         }
-        Err(_) => DeepPreloadMode::ScanOnly,
-    }
-}
 
-fn deep_preload_should_touch_metadata(
-    mode: DeepPreloadMode,
-    file_type: &std::fs::FileType,
-    path: &Path,
-) -> bool {
-    match mode {
-        DeepPreloadMode::ScanOnly => false,
-        DeepPreloadMode::Full => true,
-        DeepPreloadMode::DirsOnly => file_type.is_dir(),
-        DeepPreloadMode::Hotset => {
-            if file_type.is_dir() {
-                return true;
-            }
-            let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
-            if matches!(
-                name,
-                "BUCK"
-                    | "BUCK.v2"
-                    | "BUILD"
-                    | "BUILD.bazel"
-                    | "PACKAGE"
-                    | "TARGETS"
-                    | "TARGETS.v2"
-                    | "WORKSPACE"
-                    | "WORKSPACE.bazel"
-                    | ".buckconfig"
-            ) {
-                return true;
+        // Normalize the path for consistent cache keys
+        let normalized_path = match path.trim_end_matches('/') {
+            "" => "/".to_string(),
+            trimmed => trimmed.to_string(),
+        };
+        let cache_key = if tuning.is_empty() {
+            normalized_path.clone()
+        } else {
+            format!("{}#{}", normalized_path, tuning.fingerprint())
+        };
+
+        // Check cache first - if found, it's already initialized
+        {
+            let cache = self.dicfuse_cache.read().await;
+            if let Some(dicfuse) = cache.get(&cache_key) {
+                tracing::debug!(
+                    "Using cached Dicfuse instance for path: {}",
+                    normalized_path
+                );
+                return Ok(dicfuse.clone());
             }
-            matches!(
-                path.extension().and_then(|s| s.to_str()),
-                Some("bzl") | Some("bxl")
-            )
         }
-    }
-}
 
-fn deep_preload_worker_count() -> usize {
-    let default_workers = thread::available_parallelism()
-        .map(|n| n.get())
-        .unwrap_or(4)
-        .clamp(2, 8);
-    match std::env::var("ANTARES_DEEP_PRELOAD_WORKERS") {
-        Ok(raw) => match raw.trim().parse::<usize>() {
-            Ok(n) => n.clamp(1, 64),
-            Err(_) => {
-                tracing::warn!(
-                    value = %raw,
-                    default_workers,
-                    "invalid ANTARES_DEEP_PRELOAD_WORKERS, using default"
+        // Not in cache, create new instance
+        let new_dicfuse = DicfuseManager::for_base_path_with_tuning(&normalized_path, tuning).await;
+
+        // CRITICAL: Wait for the Dicfuse directory tree to be fully loaded before
+        // returning. Without this, FUSE mount may fail because the root inode
+        // is not set up yet when import_arc hasn't completed.
+        // TODO(dicfuse-antares-integration): If many concurrent requests initialize DIFFERENT
+        // base paths, we may enqueue a large number of concurrent warmups (network + memory).
+        // Consider adding a global semaphore/queue to cap concurrent initializations.
+        tracing::info!(
+            "Waiting for Dicfuse instance to initialize for path: {} (timeout: {}s)",
+            normalized_path,
+            INIT_TIMEOUT_SECS
+        );
+        match tokio::time::timeout(
+            std::time::Duration::from_secs(INIT_TIMEOUT_SECS),
+            new_dicfuse.store.wait_for_ready(),
+        )
+        .await
+        {
+            Ok(_) => {
+                tracing::info!(
+                    "Dicfuse initialized successfully for path: {}",
+                    normalized_path
                 );
-                default_workers
             }
-        },
-        Err(_) => default_workers,
-    }
-}
-
-fn deep_preload_max_duration() -> Option<Duration> {
-    const DEFAULT_MS: u64 = 8_000;
-    match std::env::var("ANTARES_DEEP_PRELOAD_MAX_MS") {
-        Ok(raw) => match raw.trim().parse::<u64>() {
-            Ok(0) => None,
-            Ok(ms) => Some(Duration::from_millis(ms.min(120_000))),
             Err(_) => {
-                tracing::warn!(
-                    value = %raw,
-                    default_ms = DEFAULT_MS,
-                    "invalid ANTARES_DEEP_PRELOAD_MAX_MS, using default"
+                tracing::error!(
+                    "Dicfuse initialization timed out for path: {} after {}s",
+                    normalized_path,
+                    INIT_TIMEOUT_SECS
                 );
-                Some(Duration::from_millis(DEFAULT_MS))
+                return Err(ServiceError::FuseFailure(format!(
+                    "Dicfuse initialization timed out for path '{}' after {}s. \
+                     Check network connectivity to the monorepo server.",
+                    normalized_path, INIT_TIMEOUT_SECS
+                )));
             }
-        },
-        Err(_) => Some(Duration::from_millis(DEFAULT_MS)),
-    }
-}
+        }
 
-fn deep_preload_max_depth() -> usize {
-    const DEFAULT_DEPTH: usize = 4;
-    match std::env::var("ANTARES_DEEP_PRELOAD_MAX_DEPTH") {
-        Ok(raw) => match raw.trim().parse::<usize>() {
-            Ok(depth) => depth.min(64),
-            Err(_) => {
-                tracing::warn!(
-                    value = %raw,
-                    default_depth = DEFAULT_DEPTH,
-                    "invalid ANTARES_DEEP_PRELOAD_MAX_DEPTH, using default"
-                );
-                DEFAULT_DEPTH
+        // Insert into cache
+        {
+            let mut cache = self.dicfuse_cache.write().await;
+            // Double-check in case another task created it while we were waiting
+            if let Some(dicfuse) = cache.get(&cache_key) {
+                return Ok(dicfuse.clone());
             }
-        },
-        Err(_) => DEFAULT_DEPTH,
-    }
-}
+            cache.insert(cache_key.clone(), new_dicfuse.clone());
+            tracing::info!(
+                "Created and cached new Dicfuse instance for path: {}",
+                cache_key
+            );
+        }
 
-/// Walk a directory tree with bounded parallelism to warm FUSE kernel caches.
-///
-/// Strategy is configurable:
-/// - `scan` (default): traverse directories only (readdir/readdirplus-driven)
-/// - `hotset`: touch metadata for directories + Buck hot files
-/// - `dirs`: touch metadata only for directories
-/// - `full`: touch metadata for every entry (most expensive)
-/// - `ANTARES_DEEP_PRELOAD_MAX_MS`: cap total background warmup time
-fn deep_preload_walk(root: &str, cancel: &AtomicBool) -> std::io::Result<DeepPreloadStats> {
-    use std::fs;
+        Ok(new_dicfuse)
+    }
 
-    #[derive(Default)]
-    struct WalkState {
-        queue: VecDeque<(PathBuf, usize)>,
-        in_flight: usize,
-        done: bool,
+    /// The Dicfuse view of every binding, keyed by its mount-relative target.
+    async fn bound_dicfuses(
+        &self,
+        bindings: &[MountBinding],
+        tuning: &DicfuseTuning,
+    ) -> Result<Vec<(PathBuf, Arc<Dicfuse>)>, ServiceError> {
+        let mut bound = Vec::with_capacity(bindings.len());
+        for binding in bindings {
+            let dicfuse = self.get_or_create_dicfuse(&binding.source, tuning).await?;
+            bound.push((PathBuf::from(&binding.target), dicfuse));
+        }
+        Ok(bound)
     }
 
-    // Bounded by default, but override-able for host-specific tuning.
-    let workers = deep_preload_worker_count();
-    let mode = deep_preload_mode();
-    let max_depth = deep_preload_max_depth();
-    let max_duration = deep_preload_max_duration();
-    tracing::info!(
-        root = root,
-        workers,
-        mode = mode.as_str(),
-        max_depth,
-        max_ms = max_duration.map(|d| d.as_millis()),
-        "deep_preload_walk: start"
-    );
-    let started_at = Instant::now();
-    let root_path = PathBuf::from(root);
-    let total_entries = Arc::new(AtomicUsize::new(0));
-    let total_touches = Arc::new(AtomicUsize::new(0));
-    let budget_exhausted = Arc::new(AtomicBool::new(false));
-    let state = Arc::new((
-        Mutex::new(WalkState {
-            queue: VecDeque::from([(root_path, 0)]),
-            in_flight: 0,
-            done: false,
-        }),
-        Condvar::new(),
-    ));
+    /// Persist current mount state to the state store.
+    async fn persist_state(&self) {
+        // Held from the snapshot to the write, so states reach disk in the order taken.
+        let mut store = self.state_store.clone().lock_owned().await;
+        let mounts = self.mounts.read().await;
+        let state = PersistedState {
+            generation: 0,
+            mounts: mounts
+                .values()
+                // Failed mounts stay recorded, so their layers outlive restarts and GC
+                // until they are deleted.
+                .filter(|e| {
+                    matches!(
+                        e.state,
+                        MountLifecycle::Mounted
+                            | MountLifecycle::Ready
+                            | MountLifecycle::Failed { .. }
+                    )
+                })
+                // Memory uppers die with the process; recovering them would silently drop writes.
+                .filter(|e| e.mem_upper.is_none())
+                .map(|e| PersistedMountState {
+                    mount_id: e.mount_id,
+                    job_id: e.job_id.clone(),
+                    path: e.path.clone(),
+                    bindings: e.bindings.clone(),
+                    cl: None,
+                    cls: e.cls.clone(),
+                    mountpoint: e.mountpoint.clone(),
+                    upper_dir: e.upper_dir.clone(),
+                    cl_dir: None,
+                    cl_dirs: e.cl_dirs.clone(),
+                    cl_conflicts: e.cl_conflicts.clone(),
+                    forked_from: e.forked_from,
+                    quota: e.quota.as_ref().map(|q| q.limits()),
+                    tuning: e.tuning.clone(),
+                    callback_url: e.callback_url.clone(),
+                    created_by: e.created_by.clone(),
+                    labels: e.labels.clone(),
+                    created_at_epoch_ms: e.created_at_epoch_ms,
+                })
+                .collect(),
+        };
+        drop(mounts);
 
-    thread::scope(|scope| {
-        for _ in 0..workers {
-            let state = Arc::clone(&state);
-            let total_entries = Arc::clone(&total_entries);
-            let total_touches = Arc::clone(&total_touches);
-            let budget_exhausted = Arc::clone(&budget_exhausted);
-            scope.spawn(move || {
-                let mut local_entries = 0usize;
-                let mut local_touches = 0usize;
+        match tokio::task::spawn_blocking(move || store.write(&state)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!("Failed to write state: {}", e),
+            Err(e) => tracing::warn!("State write task failed: {}", e),
+        }
+    }
 
-                loop {
-                    if let Some(max_dur) = max_duration {
-                        if started_at.elapsed() >= max_dur {
-                            budget_exhausted.store(true, Ordering::Relaxed);
-                            let (lock, cv) = &*state;
-                            let mut guard = lock.lock().expect("deep_preload_walk lock poisoned");
-                            guard.done = true;
-                            cv.notify_all();
-                            break;
-                        }
-                    }
-                    if cancel.load(Ordering::Relaxed) {
-                        let (lock, cv) = &*state;
-                        let mut guard = lock.lock().expect("deep_preload_walk lock poisoned");
-                        guard.done = true;
-                        cv.notify_all();
-                        break;
-                    }
+    /// Recover mounts from persisted state file.
+    async fn recover_mounts(&self) {
+        let state = match self.state_store.lock().await.load() {
+            Ok(state) => state,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to load state from {:?}: {}. Skipping mount recovery.",
+                    self.paths.state_file,
+                    e
+                );
+                return;
+            }
+        };
+        if state.mounts.is_empty() {
+            tracing::debug!(
+                "No mounts recorded in {:?}, skipping recovery",
+                self.paths.state_file
+            );
+            return;
+        }
 
-                    let dir = {
-                        let (lock, cv) = &*state;
-                        let mut guard = lock.lock().expect("deep_preload_walk lock poisoned");
-                        loop {
-                            if guard.done {
-                                break None;
-                            }
-                            if let Some(dir) = guard.queue.pop_front() {
-                                guard.in_flight += 1;
-                                break Some(dir);
-                            }
-                            if guard.in_flight == 0 {
-                                guard.done = true;
-                                cv.notify_all();
-                                break None;
-                            }
-                            guard = cv.wait(guard).expect("deep_preload_walk lock poisoned");
-                        }
-                    };
+        tracing::info!("Recovering {} mounts from state file", state.mounts.len());
 
-                    let Some((dir, depth)) = dir else {
-                        break;
+        for mut persisted in state.mounts {
+            persisted.migrate_legacy_cl();
+            let mountpoint = PathBuf::from(&persisted.mountpoint);
+            let (fuse, quota, state) = match self.remount_persisted(&persisted).await {
+                Ok((fuse, quota)) => {
+                    tracing::info!("Recovered mount {} at {:?}", persisted.mount_id, mountpoint);
+                    // Dicfuse is ready after AntaresFuse::new() completes import_arc.
+                    (fuse, quota, MountLifecycle::Ready)
+                }
+                Err(reason) => {
+                    tracing::warn!(
+                        "Failed to recover mount {}: {}; keeping it as Failed",
+                        persisted.mount_id,
+                        reason
+                    );
+                    // Keep the entry, and with it its layers, until it is deleted
+                    // explicitly: the cause may be transient (e.g. Dicfuse at boot).
+                    let fuse = AntaresFuse::unmounted(
+                        mountpoint.clone(),
+                        self.dicfuse.clone(),
+                        persisted.upper_dir.as_ref().map(PathBuf::from),
+                        persisted.cl_dirs.iter().map(PathBuf::from).collect(),
+                    );
+                    let state = MountLifecycle::Failed {
+                        reason: format!("recovery failed: {}", reason),
                     };
+                    (fuse, None, state)
+                }
+            };
 
-                    let mut discovered_dirs: Vec<(PathBuf, usize)> = Vec::new();
-                    let entries = match fs::read_dir(&dir) {
-                        Ok(entries) => entries,
-                        Err(e) => {
-                            tracing::warn!(dir = ?dir, error = %e, "deep_preload_walk: read_dir failed");
-                            let (lock, cv) = &*state;
-                            let mut guard = lock.lock().expect("deep_preload_walk lock poisoned");
-                            guard.in_flight = guard.in_flight.saturating_sub(1);
-                            if guard.queue.is_empty() && guard.in_flight == 0 {
-                                guard.done = true;
-                            }
-                            cv.notify_all();
-                            continue;
-                        }
-                    };
+            let entry = MountEntry {
+                mount_id: persisted.mount_id,
+                job_id: persisted.job_id.clone(),
+                path: persisted.path.clone(),
+                bindings: persisted.bindings.clone(),
+                cls: persisted.cls.clone(),
+                mountpoint: persisted.mountpoint.clone(),
+                upper_dir: persisted.upper_dir.clone(),
+                cl_dirs: persisted.cl_dirs.clone(),
+                cl_conflicts: persisted.cl_conflicts.clone(),
+                forked_from: persisted.forked_from,
+                quota,
+                mem_upper: None,
+                tuning: persisted.tuning.clone(),
+                callback_url: persisted.callback_url.clone(),
+                created_by: persisted.created_by.clone(),
+                labels: persisted.labels.clone(),
+                fuse,
+                state,
+                created_at_epoch_ms: persisted.created_at_epoch_ms,
+                last_seen_epoch_ms: current_epoch_ms(),
+                preload_cancel: Arc::new(AtomicBool::new(false)),
+                events: self.events.clone(),
+            };
+            if let Some(url) = persisted.callback_url.clone() {
+                self.webhooks
+                    .register(persisted.mount_id, url, persisted.job_id.clone())
+                    .await;
+            }
 
-                    for entry in entries {
-                        if let Some(max_dur) = max_duration {
-                            if started_at.elapsed() >= max_dur {
-                                budget_exhausted.store(true, Ordering::Relaxed);
-                                break;
-                            }
-                        }
-                        if cancel.load(Ordering::Relaxed) {
-                            break;
-                        }
-                        let entry = match entry {
-                            Ok(e) => e,
-                            Err(e) => {
-                                tracing::warn!(dir = ?dir, error = %e, "deep_preload_walk: entry error");
-                                continue;
-                            }
-                        };
+            let mut mounts = self.mounts.write().await;
+            let mut index = self.path_index.write().await;
+            let mut job_index = self.job_index.write().await;
+            mounts.insert(persisted.mount_id, entry);
+            if let Some(job_id) = persisted.job_id {
+                job_index.insert(job_id, persisted.mount_id);
+            } else {
+                // Forks share (path, cl) with their source; the first one recovered
+                // keeps the index slot.
+                index
+                    .entry((
+                        index_path(&persisted.path, &persisted.bindings),
+                        persisted.cls,
+                    ))
+                    .or_insert(persisted.mount_id);
+            }
+        }
+    }
 
-                        let path = entry.path();
-                        let file_type = match entry.file_type() {
-                            Ok(ft) => ft,
-                            Err(e) => {
-                                tracing::warn!(
-                                    path = ?path,
-                                    error = %e,
-                                    "deep_preload_walk: file_type error"
-                                );
-                                continue;
-                            }
-                        };
-                        local_entries += 1;
+    /// Mount a persisted mount again on its existing layers; the error says why not.
+    async fn remount_persisted(
+        &self,
+        persisted: &PersistedMountState,
+    ) -> Result<(AntaresFuse, Option<Arc<UpperQuota>>), String> {
+        let mountpoint = PathBuf::from(&persisted.mountpoint);
+        reclaim_mountpoint(&mountpoint).await?;
+
+        // Get or create Dicfuse instance (uses cache for subdirectory paths)
+        let dicfuse_tuning = persisted
+            .tuning
+            .as_ref()
+            .map(MountTuning::dicfuse)
+            .unwrap_or_default();
+        let bound = self
+            .bound_dicfuses(&persisted.bindings, &dicfuse_tuning)
+            .await
+            .map_err(|e| format!("failed to get Dicfuse: {}", e))?;
+        let dicfuse = if bound.is_empty() {
+            self.get_or_create_dicfuse(&persisted.path, &dicfuse_tuning)
+                .await
+                .map_err(|e| format!("failed to get Dicfuse: {}", e))?
+        } else {
+            self.dicfuse.clone()
+        };
 
-                        if file_type.is_dir() && depth < max_depth {
-                            discovered_dirs.push((path.clone(), depth + 1));
-                        }
+        let upper_dir = persisted.upper_dir.as_ref().map(PathBuf::from);
+        let cl_dirs = persisted.cl_dirs.iter().map(PathBuf::from).collect();
+        let quota = match &upper_dir {
+            Some(upper_dir) => {
+                let limits = persisted
+                    .quota
+                    .unwrap_or_else(QuotaLimits::from_global_config);
+                let quota = Self::open_upper_quota(upper_dir.clone(), limits)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Some(quota)
+            }
+            None => None,
+        };
 
-                        if deep_preload_should_touch_metadata(mode, &file_type, &path) {
-                            // Touch metadata to warm FUSE attr cache for selected hot paths.
-                            let _ = entry.metadata();
-                            local_touches += 1;
-                        }
-                    }
+        let fuse = AntaresFuse::new_with_layers(mountpoint, dicfuse, upper_dir, cl_dirs)
+            .await
+            .map_err(|e| format!("failed to create AntaresFuse: {}", e))?;
+        let mut fuse = fuse.with_quota(quota.clone()).with_bindings(bound);
+        fuse.mount()
+            .await
+            .map_err(|e| format!("failed to remount: {}", e))?;
+        Ok((fuse, quota))
+    }
 
-                    let (lock, cv) = &*state;
-                    let mut guard = lock.lock().expect("deep_preload_walk lock poisoned");
-                    for subdir in discovered_dirs {
-                        guard.queue.push_back(subdir);
-                    }
-                    guard.in_flight = guard.in_flight.saturating_sub(1);
-                    if guard.queue.is_empty() && guard.in_flight == 0 {
-                        guard.done = true;
-                    }
-                    cv.notify_all();
-                }
+    /// Validate the create mount request.
+    fn validate_request(request: &CreateMountRequest) -> Result<(), ServiceError> {
+        labels::validate(&request.labels).map_err(ServiceError::InvalidRequest)?;
+        if let Some(mountpoint) = request.mountpoint.as_deref() {
+            let prefixes = if request.unrestricted_mountpoint {
+                vec!["/".to_string()]
+            } else {
+                crate::util::config::antares_mountpoint_prefixes()
+            };
+            Self::check_mountpoint_prefix(mountpoint, &prefixes)?;
+        }
+        if let Some(url) = request.callback_url.as_deref() {
+            let valid = reqwest::Url::parse(url)
+                .map(|u| matches!(u.scheme(), "http" | "https"))
+                .unwrap_or(false);
+            if !valid {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "callback_url {:?} is not an http(s) URL",
+                    url
+                )));
+            }
+        }
+        if !request.bindings.is_empty() {
+            if !request.path.is_empty() {
+                return Err(ServiceError::InvalidRequest(
+                    "set either path or bindings, not both".into(),
+                ));
+            }
+            return Ok(());
+        }
+        if request.path.is_empty() {
+            return Err(ServiceError::InvalidRequest("path cannot be empty".into()));
+        }
+        Ok(())
+    }
 
-                total_entries.fetch_add(local_entries, Ordering::Relaxed);
-                total_touches.fetch_add(local_touches, Ordering::Relaxed);
-            });
+    /// Check a caller-chosen mountpoint against the allowed `prefixes`, lexically.
+    ///
+    /// Symlinks are resolved later by [`Self::claim_mountpoint`].
+    fn check_mountpoint_prefix(mountpoint: &str, prefixes: &[String]) -> Result<(), ServiceError> {
+        let path = Path::new(mountpoint);
+        let plain = path.is_absolute()
+            && path
+                .components()
+                .skip(1)
+                .all(|c| matches!(c, Component::Normal(_)));
+        if !plain {
+            return Err(ServiceError::InvalidRequest(format!(
+                "mountpoint {:?} must be an absolute path without .. components",
+                mountpoint
+            )));
         }
-    });
+        if prefixes.is_empty() {
+            return Err(ServiceError::InvalidRequest(
+                "caller-chosen mountpoints are disabled (antares_mountpoint_prefixes is empty)"
+                    .into(),
+            ));
+        }
+        if !prefixes.iter().any(|prefix| path.starts_with(prefix)) {
+            return Err(ServiceError::InvalidRequest(format!(
+                "mountpoint {} is outside the allowed prefixes {:?}",
+                mountpoint, prefixes
+            )));
+        }
+        Ok(())
+    }
 
-    let stats = DeepPreloadStats {
-        entries_visited: total_entries.load(Ordering::Relaxed),
-        metadata_touches: total_touches.load(Ordering::Relaxed),
-        budget_exhausted: budget_exhausted.load(Ordering::Relaxed),
-    };
-    if stats.budget_exhausted {
-        tracing::info!(
-            root = root,
-            visited = stats.entries_visited,
-            metadata_touches = stats.metadata_touches,
-            "deep_preload_walk: time budget exhausted"
-        );
+    /// Register `mount_id` as being created, before any of its directories exist.
+    fn claim_creation(&self, mount_id: Uuid) -> CreationClaim {
+        self.creating.lock().unwrap().insert(mount_id);
+        CreationClaim {
+            creating: self.creating.clone(),
+            mount_id,
+        }
     }
-    if cancel.load(Ordering::Relaxed) {
-        tracing::info!(
-            root = root,
-            visited = stats.entries_visited,
-            metadata_touches = stats.metadata_touches,
-            "deep_preload_walk: cancelled"
+
+    /// Lock serializing checkpoint creation, deletion and restore of one mount.
+    fn checkpoint_lock(&self, mount_id: Uuid) -> Arc<tokio::sync::Mutex<()>> {
+        self.checkpoint_locks
+            .lock()
+            .unwrap()
+            .entry(mount_id)
+            .or_default()
+            .clone()
+    }
+
+    /// Ids of mounts that exist or are being created. `creating` is read first: a
+    /// creation leaves it only after its entry is in `mounts`, so none is missed.
+    async fn live_mount_ids(&self) -> HashSet<String> {
+        let mut ids: HashSet<String> = self
+            .creating
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        ids.extend(self.mounts.read().await.keys().map(|id| id.to_string()));
+        ids.extend(
+            self.provisioning
+                .read()
+                .await
+                .keys()
+                .map(|id| id.to_string()),
         );
+        ids
     }
-    Ok(stats)
-}
 
-// ============================================================================
-// Tests
-// ============================================================================
+    /// Reserve a caller-chosen mountpoint and make sure it can be mounted over.
+    ///
+    /// After resolving symlinks it must still lie under an allowed prefix, must not
+    /// overlap the mountpoint of another mount or creation, and must be missing (it is
+    /// created) or an empty, readable directory.
+    async fn claim_mountpoint(
+        &self,
+        mountpoint: &str,
+        unrestricted: bool,
+    ) -> Result<MountpointClaim, ServiceError> {
+        let invalid = |reason: String| {
+            ServiceError::InvalidRequest(format!("mountpoint {}: {}", mountpoint, reason))
+        };
+        let resolved =
+            resolve_symlinks(Path::new(mountpoint)).map_err(|e| invalid(e.to_string()))?;
+        let allowed = unrestricted
+            || crate::util::config::antares_mountpoint_prefixes()
+                .iter()
+                .filter_map(|prefix| resolve_symlinks(Path::new(prefix)).ok())
+                .any(|prefix| resolved.starts_with(prefix));
+        if !allowed {
+            return Err(invalid(format!(
+                "resolves to {} outside the allowed prefixes",
+                resolved.display()
+            )));
+        }
+
+        // Claim before looking at live mounts: a creation drops its claim only after
+        // its mount is listed, so one of the two checks sees every competitor.
+        {
+            let mut claims = self.claimed_mountpoints.lock().unwrap();
+            if let Some(other) = claims
+                .iter()
+                .find(|other| composite::overlaps(other, &resolved))
+            {
+                return Err(invalid(format!(
+                    "overlaps {} of a mount being created",
+                    other.display()
+                )));
+            }
+            claims.insert(resolved.clone());
+        }
+        let mut claim = MountpointClaim {
+            claims: self.claimed_mountpoints.clone(),
+            path: resolved,
+            created: false,
+        };
+        let overlapping = self
+            .mounts
+            .read()
+            .await
+            .values()
+            .find(|entry| composite::overlaps(Path::new(&entry.mountpoint), &claim.path))
+            .map(|entry| entry.mount_id);
+        if let Some(other) = overlapping {
+            return Err(invalid(format!(
+                "overlaps the mountpoint of mount {}",
+                other
+            )));
+        }
+        claim.created =
+            crate::server::prepare_mountpoint(&claim.path).map_err(|e| invalid(e.to_string()))?;
+        Ok(claim)
+    }
+
+    /// Normalize the bindings of a composite mount and sort them by target.
+    ///
+    /// Sources become absolute monorepo paths and targets plain relative paths (the
+    /// source path when omitted). Overlapping sources or targets are rejected.
+    fn normalize_bindings(bindings: &[MountBinding]) -> Result<Vec<MountBinding>, ServiceError> {
+        let mut normalized: Vec<MountBinding> = Vec::with_capacity(bindings.len());
+        for binding in bindings {
+            let source = Self::normalize_mount_path(&binding.source);
+            if source.is_empty() {
+                return Err(ServiceError::InvalidRequest(
+                    "binding source cannot be empty".into(),
+                ));
+            }
+            let target = match binding.target.trim().trim_matches('/') {
+                "" => source.trim_start_matches('/').to_string(),
+                target => target.to_string(),
+            };
+            let valid_target = !target.is_empty()
+                && Path::new(&target)
+                    .components()
+                    .all(|c| matches!(c, Component::Normal(_)));
+            if !valid_target {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "invalid target {:?} for binding of {}",
+                    binding.target, source
+                )));
+            }
+            for other in &normalized {
+                if composite::overlaps(Path::new(&other.source), Path::new(&source)) {
+                    return Err(ServiceError::InvalidRequest(format!(
+                        "binding sources {} and {} overlap",
+                        other.source, source
+                    )));
+                }
+                if composite::overlaps(Path::new(&other.target), Path::new(&target)) {
+                    return Err(ServiceError::InvalidRequest(format!(
+                        "binding targets {} and {} overlap",
+                        other.target, target
+                    )));
+                }
+            }
+            normalized.push(MountBinding { source, target });
+        }
+        normalized.sort_by(|a, b| a.target.cmp(&b.target));
+        Ok(normalized)
+    }
+
+    /// Seed usage counters for an upper dir on a blocking thread.
+    async fn open_upper_quota(
+        upper_dir: PathBuf,
+        limits: QuotaLimits,
+    ) -> Result<Arc<UpperQuota>, ServiceError> {
+        tokio::task::spawn_blocking(move || UpperQuota::new(upper_dir, limits))
+            .await
+            .map_err(|e| ServiceError::Internal(format!("quota scan task failed: {}", e)))?
+            .map_err(|e| ServiceError::Internal(format!("failed to scan upper layer: {}", e)))
+    }
+
+    /// Check if a path+cl combination is already mounted.
+    async fn is_path_already_mounted(&self, path: &str, cls: &[String]) -> bool {
+        let index = self.path_index.read().await;
+        index.contains_key(&(path.to_string(), cls.to_vec()))
+    }
+
+    /// Get service health information. The service is degraded while any mount is `Failed`.
+    pub async fn health_info_impl(&self) -> HealthResponse {
+        let mounts = self.mounts.read().await;
+        let failed_mounts = mounts
+            .values()
+            .filter(|entry| matches!(entry.state, MountLifecycle::Failed { .. }))
+            .count();
+        HealthResponse {
+            status: if failed_mounts == 0 {
+                "healthy"
+            } else {
+                "degraded"
+            }
+            .to_string(),
+            mount_count: mounts.len(),
+            failed_mounts,
+            uptime_secs: self.start_time.elapsed().as_secs(),
+        }
+    }
+
+    /// Run [`Self::supervise_mounts`] every `config.interval` until the service shuts down.
+    pub fn spawn_supervisor(&self, config: SupervisorConfig) -> tokio::task::JoinHandle<()> {
+        let service = self.clone();
+        let mut events = self.events.subscribe();
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(config.interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = ticks.tick() => {
+                        service.supervise_mounts(&config).await;
+                    }
+                    event = events.recv() => match event {
+                        Ok(event) if event.kind == MountEventKind::Shutdown => break,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                        _ => {}
+                    },
+                }
+            }
+        })
+    }
+
+    /// Find `Mounted` / `Ready` mounts whose FUSE session exited or whose mountpoint
+    /// answers `ENOTCONN`, mark them `Failed`, and remount them when `config.remount`
+    /// is set. Returns the ids of the broken mounts.
+    pub async fn supervise_mounts(&self, config: &SupervisorConfig) -> Vec<Uuid> {
+        let candidates: Vec<(Uuid, String, bool)> = self
+            .mounts
+            .read()
+            .await
+            .values()
+            .filter(|entry| matches!(entry.state, MountLifecycle::Mounted | MountLifecycle::Ready))
+            .map(|entry| {
+                (
+                    entry.mount_id,
+                    entry.mountpoint.clone(),
+                    entry.fuse.session_ended(),
+                )
+            })
+            .collect();
+
+        let mut broken = Vec::new();
+        for (mount_id, mountpoint, session_ended) in candidates {
+            let probe = if session_ended {
+                None
+            } else {
+                probe_mountpoint(PathBuf::from(&mountpoint), config.probe_timeout).await
+            };
+            let Some(reason) = broken_mount_reason(session_ended, probe.as_ref()) else {
+                continue;
+            };
+
+            {
+                let mut mounts = self.mounts.write().await;
+                let Some(entry) = mounts.get_mut(&mount_id) else {
+                    continue;
+                };
+                // Something else (delete, CL build) took over the mount meanwhile.
+                if !matches!(entry.state, MountLifecycle::Mounted | MountLifecycle::Ready) {
+                    continue;
+                }
+                tracing::warn!(
+                    mount_id = %mount_id,
+                    mountpoint = %mountpoint,
+                    reason = %reason,
+                    "antares supervisor: mount is broken"
+                );
+                entry.preload_cancel.store(true, Ordering::Relaxed);
+                entry.set_state(MountLifecycle::Failed {
+                    reason: reason.clone(),
+                });
+                entry.update_last_seen();
+            }
+            broken.push(mount_id);
+
+            if config.remount {
+                if let Err(e) = self.repair_mount(mount_id, &reason).await {
+                    tracing::error!(
+                        mount_id = %mount_id,
+                        error = %e,
+                        "antares supervisor: remount failed"
+                    );
+                }
+            }
+        }
+        if !broken.is_empty() {
+            self.persist_state().await;
+        }
+        broken
+    }
+
+    /// Lazily unmount a broken mount and serve its existing upper / CL layers again.
+    async fn repair_mount(&self, mount_id: Uuid, reason: &str) -> Result<(), ServiceError> {
+        let failed = MountLifecycle::Failed {
+            reason: reason.to_string(),
+        };
+        let mut mounts = self.mounts.write().await;
+        let entry = mounts
+            .get_mut(&mount_id)
+            .ok_or(ServiceError::NotFound(mount_id))?;
+        if entry.state != failed {
+            return Ok(());
+        }
+        let placeholder = AntaresFuse::new_with_layers(
+            PathBuf::from(&entry.mountpoint),
+            self.dicfuse.clone(),
+            entry.upper_dir.as_ref().map(PathBuf::from),
+            entry.cl_dir_paths(),
+        )
+        .await
+        .map_err(|e| ServiceError::Internal(format!("failed to create placeholder fuse: {}", e)))?;
+        let mut fuse = std::mem::replace(&mut entry.fuse, placeholder);
+        drop(mounts);
+
+        // `unmount` falls back to `fusermount -uz`, which detaches a dead connection.
+        if let Err(e) = fuse.unmount().await {
+            let mut mounts = self.mounts.write().await;
+            if let Some(entry) = mounts.get_mut(&mount_id) {
+                entry.fuse = fuse;
+            }
+            return Err(ServiceError::FuseFailure(format!("unmount failed: {}", e)));
+        }
+        // Mounting panics on a bad mountpoint; keep that from taking the supervisor down.
+        let remount = tokio::spawn(async move {
+            let result = fuse.mount().await;
+            (fuse, result)
+        })
+        .await;
+
+        let mut mounts = self.mounts.write().await;
+        let Some(entry) = mounts.get_mut(&mount_id) else {
+            // Deleted while remounting: do not leave the new session behind.
+            if let Ok((mut fuse, Ok(()))) = remount {
+                drop(mounts);
+                let _ = fuse.unmount().await;
+            }
+            return Err(ServiceError::NotFound(mount_id));
+        };
+        let (fuse, result) = match remount {
+            Ok(remount) => remount,
+            Err(e) => {
+                entry.set_state(MountLifecycle::Failed {
+                    reason: format!("{}; remount panicked: {}", reason, e),
+                });
+                entry.update_last_seen();
+                return Err(ServiceError::FuseFailure(format!(
+                    "remount panicked: {}",
+                    e
+                )));
+            }
+        };
+        entry.fuse = fuse;
+        if let Err(e) = result {
+            entry.set_state(MountLifecycle::Failed {
+                reason: format!("{}; remount failed: {}", reason, e),
+            });
+            entry.update_last_seen();
+            return Err(ServiceError::FuseFailure(format!("remount failed: {}", e)));
+        }
+
+        let new_cancel = Arc::new(AtomicBool::new(false));
+        entry.set_state(MountLifecycle::Ready);
+        entry.preload_cancel = new_cancel.clone();
+        entry.update_last_seen();
+        let mountpoint = entry.mountpoint.clone();
+        let preload = entry.deep_preload_options();
+        drop(mounts);
+
+        tracing::info!(mount_id = %mount_id, "antares supervisor: remounted broken mount");
+        self.persist_state().await;
+        self.spawn_deep_preload_task(mount_id, mountpoint, preload, new_cancel, "supervisor");
+        Ok(())
+    }
+
+    /// Remove what no live or provisioning mount refers to, in this order: stale FUSE
+    /// mounts under the mount root, orphaned upper / CL / mount directories, cached
+    /// Dicfuse views nothing holds, and on-disk Dicfuse stores no process has open.
+    ///
+    /// Directories and stores modified within the retention window are kept, as is any
+    /// directory containing a live path or a mount point.
+    pub async fn collect_garbage_impl(&self, request: GcRequest) -> Result<GcReport, ServiceError> {
+        let retention_secs = request
+            .retention_secs
+            .unwrap_or_else(crate::util::config::antares_gc_retention_secs);
+        let cutoff = SystemTime::now()
+            .checked_sub(Duration::from_secs(retention_secs))
+            .unwrap_or(UNIX_EPOCH);
+        let mut report = GcReport {
+            dry_run: request.dry_run,
+            retention_secs,
+            ..GcReport::default()
+        };
+
+        let mut live_ids = self.live_mount_ids().await;
+        let mut referenced = vec![
+            self.paths.upper_root.clone(),
+            self.paths.cl_root.clone(),
+            self.paths.mount_root.clone(),
+            self.paths.state_file.clone(),
+            self.checkpoints.root().to_path_buf(),
+            self.blob_cache.root().to_path_buf(),
+            PathBuf::from(crate::util::config::store_path()),
+        ];
+        let mut live_mountpoints = HashSet::new();
+        // Addresses only: holding clones would make every view look in use, and raw
+        // pointers would keep this future from being `Send`.
+        let mut used_views: HashSet<usize> = HashSet::new();
+        {
+            let mounts = self.mounts.read().await;
+            for entry in mounts.values() {
+                live_mountpoints.insert(PathBuf::from(&entry.mountpoint));
+                referenced.push(PathBuf::from(&entry.mountpoint));
+                referenced.extend(entry.upper_dir.iter().map(PathBuf::from));
+                referenced.extend(entry.cl_dir_paths());
+                used_views.insert(Arc::as_ptr(&entry.fuse.dic) as usize);
+                used_views.extend(
+                    entry
+                        .fuse
+                        .bindings
+                        .iter()
+                        .map(|(_, d)| Arc::as_ptr(d) as usize),
+                );
+            }
+        }
+        // Mounts recorded on disk but not loaded, e.g. when the daemon started without
+        // recovery, keep their layers until deleted explicitly.
+        let store = self.state_store.clone().lock_owned().await;
+        let persisted = tokio::task::spawn_blocking(move || store.load())
+            .await
+            .map_err(|e| ServiceError::Internal(format!("gc task failed: {}", e)))?
+            .map_err(|e| {
+                ServiceError::Internal(format!(
+                    "cannot read state {}, refusing to collect: {}",
+                    self.paths.state_file.display(),
+                    e
+                ))
+            })?;
+        for mut mount in persisted.mounts {
+            mount.migrate_legacy_cl();
+            live_ids.insert(mount.mount_id.to_string());
+            live_mountpoints.insert(PathBuf::from(&mount.mountpoint));
+            referenced.push(PathBuf::from(&mount.mountpoint));
+            referenced.extend(mount.upper_dir.map(PathBuf::from));
+            referenced.extend(mount.cl_dirs.into_iter().map(PathBuf::from));
+        }
+        {
+            let claims = self.claimed_mountpoints.lock().unwrap();
+            live_mountpoints.extend(claims.iter().cloned());
+            referenced.extend(claims.iter().cloned());
+        }
+
+        // Stale mounts first, so their directories can be collected in the same pass.
+        let mut mount_table = read_mount_table().await?;
+        let probe_timeout = SupervisorConfig::default().probe_timeout;
+        let mut detached = false;
+        for mount in mount_table.iter().filter(|m| {
+            m.is_fuse()
+                && m.mount_point.starts_with(&self.paths.mount_root)
+                && m.mount_point != self.paths.mount_root
+                && !live_mountpoints.contains(&m.mount_point)
+        }) {
+            let probe = probe_mountpoint(mount.mount_point.clone(), probe_timeout).await;
+            if !probe.is_some_and(|e| e.raw_os_error() == Some(libc::ENOTCONN)) {
+                continue;
+            }
+            let entry = GcEntry {
+                kind: GcKind::StaleMount,
+                path: mount.mount_point.to_string_lossy().into_owned(),
+            };
+            if request.dry_run {
+                report.removed.push(entry);
+                continue;
+            }
+            match detach_stale_mount(&mount.mount_point).await {
+                Ok(()) => {
+                    detached = true;
+                    report.removed.push(entry);
+                }
+                Err(e) => report.failed.push(GcFailure {
+                    entry,
+                    error: e.to_string(),
+                }),
+            }
+        }
+        if detached {
+            mount_table = read_mount_table().await?;
+        }
+
+        let scan = GcScan {
+            roots: vec![
+                (GcKind::Upper, self.paths.upper_root.clone()),
+                (GcKind::Cl, self.paths.cl_root.clone()),
+                (GcKind::Mount, self.paths.mount_root.clone()),
+            ],
+            referenced,
+            live_ids,
+            mount_points: mount_table.into_iter().map(|m| m.mount_point).collect(),
+            cutoff,
+        };
+        let orphans = tokio::task::spawn_blocking(move || scan.orphaned_dirs())
+            .await
+            .map_err(|e| ServiceError::Internal(format!("gc task failed: {}", e)))?;
+        // A creation that started after `live_ids` was taken registered its id before
+        // making its directories, so looking again covers everything the scan saw.
+        let live_now = self.live_mount_ids().await;
+        let orphans: Vec<GcEntry> = orphans
+            .into_iter()
+            .filter(|entry| !live_now.contains(&gc::generated_id(Path::new(&entry.path))))
+            .collect();
+        let dry_run = request.dry_run;
+        let (removed, failed) = tokio::task::spawn_blocking(move || {
+            let mut removed = Vec::new();
+            let mut failed = Vec::new();
+            for entry in orphans {
+                if dry_run {
+                    removed.push(entry);
+                    continue;
+                }
+                match std::fs::remove_dir_all(&entry.path) {
+                    Ok(()) => removed.push(entry),
+                    Err(e) => failed.push(GcFailure {
+                        entry,
+                        error: e.to_string(),
+                    }),
+                }
+            }
+            (removed, failed)
+        })
+        .await
+        .map_err(|e| ServiceError::Internal(format!("gc task failed: {}", e)))?;
+        report.removed.extend(removed);
+        report.failed.extend(failed);
+
+        // A view is unused when no mount serves it and only this cache and the
+        // manager's hold it; a creation that just picked it up holds a third reference.
+        let mut evicted = Vec::new();
+        let mut kept_stores = HashSet::new();
+        kept_stores.insert(PathBuf::from(self.dicfuse.store.store_dir()));
+        {
+            let mut cache = self.dicfuse_cache.write().await;
+            let unused: Vec<String> = cache
+                .iter()
+                .filter(|(_, d)| {
+                    !used_views.contains(&(Arc::as_ptr(d) as usize)) && Arc::strong_count(d) <= 2
+                })
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in unused {
+                let path = cache[&key].store.store_dir().to_string();
+                report.removed.push(GcEntry {
+                    kind: GcKind::DicfuseView,
+                    path,
+                });
+                if !dry_run {
+                    evicted.extend(cache.remove(&key));
+                }
+            }
+            kept_stores.extend(cache.values().map(|d| PathBuf::from(d.store.store_dir())));
+        }
+        for dicfuse in evicted {
+            DicfuseManager::evict(&dicfuse);
+            tracing::info!(
+                base_path = %dicfuse.store.base_path(),
+                "antares gc: evicted unused Dicfuse view"
+            );
+        }
+
+        let store_root = PathBuf::from(crate::util::config::store_path());
+        let (removed, failed) = tokio::task::spawn_blocking(move || {
+            let mut removed = Vec::new();
+            let mut failed = Vec::new();
+            for dir in gc::dicfuse_store_dirs(&store_root) {
+                if kept_stores.contains(&dir)
+                    || gc::dicfuse_store_in_use(&dir)
+                    || gc::modified_since(&dir, cutoff)
+                {
+                    continue;
+                }
+                let entry = GcEntry {
+                    kind: GcKind::DicfuseStore,
+                    path: dir.to_string_lossy().into_owned(),
+                };
+                if dry_run {
+                    removed.push(entry);
+                    continue;
+                }
+                match std::fs::remove_dir_all(&dir) {
+                    Ok(()) => removed.push(entry),
+                    Err(e) => failed.push(GcFailure {
+                        entry,
+                        error: e.to_string(),
+                    }),
+                }
+            }
+            (removed, failed)
+        })
+        .await
+        .map_err(|e| ServiceError::Internal(format!("gc task failed: {}", e)))?;
+        report.removed.extend(removed);
+        report.failed.extend(failed);
+
+        if !report.removed.is_empty() || !report.failed.is_empty() {
+            tracing::info!(
+                dry_run,
+                removed = report.removed.len(),
+                failed = report.failed.len(),
+                "antares gc: pass finished"
+            );
+        }
+        Ok(report)
+    }
+
+    /// Cleanup all mounts during shutdown.
+    pub async fn shutdown_cleanup_impl(&self) -> Result<(), ServiceError> {
+        let mut mounts = self.mounts.write().await;
+        let mut index = self.path_index.write().await;
+        let mut job_index = self.job_index.write().await;
+
+        for (mount_id, mut entry) in mounts.drain() {
+            tracing::info!("Unmounting {} during shutdown", mount_id);
+            entry.preload_cancel.store(true, Ordering::Relaxed);
+            if let Err(e) = entry.fuse.unmount().await {
+                tracing::warn!("Failed to unmount {} during shutdown: {}", mount_id, e);
+                // Continue with other mounts even if one fails
+            }
+            self.events.emit(mount_id, MountEventKind::Removed);
+        }
+        // All mounts drained; clear indices.
+        // TODO(antares): If we ever decide to keep failed-unmount mounts in memory/state for
+        // later retry, revisit index cleanup to avoid inconsistencies.
+        index.clear();
+        job_index.clear();
+        // Let event streams end, or they would hold the HTTP server open.
+        self.events.emit(Uuid::nil(), MountEventKind::Shutdown);
+        Ok(())
+    }
+
+    /// Create a mount as `mount_id`, waiting for Dicfuse, the CL layers and the FUSE
+    /// session. An idempotent hit returns the existing mount instead.
+    async fn provision_mount(
+        &self,
+        mount_id: Uuid,
+        request: CreateMountRequest,
+    ) -> Result<MountCreated, ServiceError> {
+        let _creation = self.claim_creation(mount_id);
+        let start = Instant::now();
+        let mut request = request;
+        request.path = Self::normalize_mount_path(&request.path);
+
+        // 1. Validate request
+        let (bindings, cls, upper_kind) = Self::check_create_request(&request)?;
+        let mount_key = index_path(&request.path, &bindings);
+        let task_id = Self::task_id(&request);
+
+        tracing::info!(
+            task_id = ?task_id,
+            path = %request.path,
+            bindings = ?bindings,
+            cls = ?cls,
+            read_only = request.read_only,
+            upper = ?request.upper,
+            "antares svc: create_mount start"
+        );
+
+        // 2. Idempotency / de-dup policy:
+        // - If task_id is provided: treat create as idempotent for the same task id.
+        //   This supports build-task-granularity mounts.
+        // - If task_id is NOT provided: keep legacy behavior and reject duplicate (path, cl).
+        if let Some(ref job_id) = task_id {
+            // Fast path: already mounted for this task id -> return existing mount.
+            if let Some(existing_id) = { self.job_index.read().await.get(job_id).cloned() } {
+                let mut mounts = self.mounts.write().await;
+                if let Some(entry) = mounts.get_mut(&existing_id) {
+                    // Guard against job_id reuse with different request params.
+                    if entry.path != request.path
+                        || entry.bindings != bindings
+                        || entry.cls != cls
+                        || entry.upper_kind() != upper_kind
+                        || request
+                            .mountpoint
+                            .as_deref()
+                            .is_some_and(|m| Path::new(m) != Path::new(&entry.mountpoint))
+                    {
+                        return Err(ServiceError::InvalidRequest(format!(
+                            "job_id/build_id '{}' already mounted with different path/cl/upper/mountpoint",
+                            job_id
+                        )));
+                    }
+                    // If the mount is being torn down, do NOT treat this as an idempotent success.
+                    // Otherwise we may return a mount_id that is about to be removed, causing
+                    // follow-up describe/delete calls to 404.
+                    if !matches!(entry.state, MountLifecycle::Mounted | MountLifecycle::Ready) {
+                        return Err(ServiceError::InvalidRequest(format!(
+                            "job_id/build_id '{}' is currently in state {:?}; retry after unmount completes",
+                            job_id, entry.state
+                        )));
+                    }
+                    entry.update_last_seen();
+                    tracing::info!(
+                        task_id = %job_id,
+                        mount_id = %existing_id,
+                        mountpoint = %entry.mountpoint,
+                        elapsed_ms = start.elapsed().as_millis(),
+                        "antares svc: create_mount idempotent hit"
+                    );
+                    return Ok(MountCreated {
+                        mount_id: existing_id,
+                        mountpoint: entry.mountpoint.clone(),
+                        state: entry.state.clone(),
+                    });
+                } else {
+                    // Stale index entry: remove and continue with fresh mount creation.
+                    self.job_index.write().await.remove(job_id);
+                }
+            }
+        } else if self.is_path_already_mounted(&mount_key, &cls).await {
+            return Err(ServiceError::InvalidRequest(format!(
+                "path {} with cls {:?} is already mounted",
+                mount_key, cls
+            )));
+        }
+
+        // 3. Auto-generate all paths from the mount UUID, except a caller-chosen mountpoint
+        let claim = match request.mountpoint.as_deref() {
+            Some(mountpoint) => Some(
+                self.claim_mountpoint(mountpoint, request.unrestricted_mountpoint)
+                    .await?,
+            ),
+            None => None,
+        };
+        let (mountpoint_str, upper_dir_str, cl_dirs_str) = self.generated_paths(
+            mount_id,
+            claim.as_ref().map(|c| c.path.as_path()),
+            upper_kind,
+            cls.len(),
+        );
+        // A caller-chosen mountpoint is only removed again if this creation made it.
+        let remove_mountpoint = || {
+            if claim.as_ref().is_none_or(|c| c.created) {
+                let _ = std::fs::remove_dir_all(&mountpoint_str);
+            }
+        };
+
+        let mountpoint = PathBuf::from(&mountpoint_str);
+        let upper_dir = upper_dir_str.as_ref().map(PathBuf::from);
+        let cl_dirs: Vec<PathBuf> = cl_dirs_str.iter().map(PathBuf::from).collect();
+
+        tracing::debug!(
+            mount_id = %mount_id,
+            task_id = ?task_id,
+            mountpoint = %mountpoint_str,
+            upper_dir = ?upper_dir_str,
+            cl_dirs = ?cl_dirs_str,
+            "antares svc: create_mount paths generated"
+        );
+
+        // Every failure from here on must undo what this creation made: the mountpoint (if
+        // it created it), the upper and CL directories, and the `/cl/progress` entry.
+        let remove_dirs = || {
+            remove_mountpoint();
+            if let Some(u) = upper_dir_str.as_deref() {
+                let _ = std::fs::remove_dir_all(u);
+            }
+            for c in &cl_dirs_str {
+                let _ = cl_manifest::remove_layer(Path::new(c));
+            }
+        };
+
+        let mounted = async {
+            // Only mounts created with CLs report a build through `/cl/progress`.
+            let progress = if cls.is_empty() {
+                ClProgress::start(mount_id, &cls)
+            } else {
+                self.begin_cl_progress(mount_id, &cls).await?
+            };
+            let built = self
+                .build_cl_stack(
+                    &mount_view(&request.path, &bindings),
+                    &cls,
+                    &cl_dirs,
+                    &progress,
+                )
+                .await;
+            progress.finish(built.as_ref().map(|_| ()));
+            let cl_conflicts = built?;
+
+            // 5. Get or create Dicfuse instance for this mount (uses cache for subdirectory paths)
+            // If a specific base path is requested (not root), get from cache or create a dedicated
+            // Dicfuse with path remapping. Otherwise, use the shared global instance.
+            // This may take time for new subdirectory paths as it waits for import_arc to complete.
+            // Composite mounts get one Dicfuse per binding instead and never serve the root one.
+            let dicfuse_tuning = request
+                .tuning
+                .as_ref()
+                .map(MountTuning::dicfuse)
+                .unwrap_or_default();
+            let bound = self.bound_dicfuses(&bindings, &dicfuse_tuning).await?;
+            let dicfuse = if bound.is_empty() {
+                self.get_or_create_dicfuse(&request.path, &dicfuse_tuning)
+                    .await?
+            } else {
+                self.dicfuse.clone()
+            };
+
+            // 6. Create AntaresFuse instance (may take time, not holding lock)
+            let quota = match &upper_dir {
+                Some(upper_dir) => {
+                    let limits = match &request.quota {
+                        Some(overrides) => {
+                            QuotaLimits::from_global_config().with_overrides(overrides)
+                        }
+                        None => QuotaLimits::from_global_config(),
+                    };
+                    Some(Self::open_upper_quota(upper_dir.clone(), limits).await?)
+                }
+                None => None,
+            };
+            let mem_upper = (upper_kind == Some(UpperKind::Memory)).then(|| {
+                let limits = match &request.quota {
+                    Some(overrides) => QuotaLimits::memory_default().with_overrides(overrides),
+                    None => QuotaLimits::memory_default(),
+                };
+                Arc::new(MemUpperLayer::new(limits))
+            });
+            let mut fuse = AntaresFuse::new_with_layers(mountpoint, dicfuse, upper_dir, cl_dirs)
+                .await
+                .map_err(|e| ServiceError::FuseFailure(format!("failed to create fuse: {}", e)))?
+                .with_quota(quota.clone())
+                .with_memory_upper(mem_upper.clone())
+                .with_bindings(bound);
+
+            // 7. Mount the filesystem
+            fuse.mount()
+                .await
+                .map_err(|e| ServiceError::FuseFailure(format!("failed to mount: {}", e)))?;
+            Ok::<_, ServiceError>((cl_conflicts, quota, mem_upper, fuse))
+        }
+        .await;
+        let (cl_conflicts, quota, mem_upper, fuse) = match mounted {
+            Ok(mounted) => mounted,
+            Err(err) => {
+                remove_dirs();
+                self.cl_progress.write().await.remove(&mount_id);
+                return Err(err);
+            }
+        };
+
+        // 8. Record timestamps. We'll only construct MountEntry after passing the duplicate check
+        // so we can rollback the FUSE mount safely on race losers.
+        let now = current_epoch_ms();
+
+        // 9. Insert into mounts map
+        let mut mounts = self.mounts.write().await;
+        let mut index = self.path_index.write().await;
+        let mut job_index = self.job_index.write().await;
+
+        // Double-check for races after acquiring write locks (match the policy above).
+        if let Some(ref job_id) = task_id {
+            if job_index.contains_key(job_id) {
+                // IMPORTANT: rollback the freshly mounted FUSE session before returning.
+                // Under concurrent POST /mounts for the same job_id/build_id, the losing request
+                // may have already mounted a FUSE session but not yet inserted it into mounts /
+                // job_index. Returning early here would leak an orphan mount that cannot be
+                // tracked or cleaned up.
+                let err = ServiceError::InvalidRequest(format!(
+                    "job_id/build_id '{}' is already mounted",
+                    job_id
+                ));
+                drop(mounts);
+                drop(index);
+                drop(job_index);
+
+                tracing::warn!(
+                    "create_mount duplicate task_id detected after mount; rolling back orphan mount {}",
+                    mount_id
+                );
+                let _ = fuse.unmount().await;
+                remove_dirs();
+                self.cl_progress.write().await.remove(&mount_id);
+                return Err(err);
+            }
+        } else if index.contains_key(&(mount_key.clone(), cls.clone())) {
+            // Same rollback logic as above for legacy (path, cl) duplicates.
+            let err = ServiceError::InvalidRequest(format!(
+                "path {} with cls {:?} is already mounted",
+                mount_key, cls
+            ));
+            drop(mounts);
+            drop(index);
+            drop(job_index);
+
+            tracing::warn!(
+                "create_mount duplicate (path, cl) detected after mount; rolling back orphan mount {}",
+                mount_id
+            );
+            let _ = fuse.unmount().await;
+            remove_dirs();
+            self.cl_progress.write().await.remove(&mount_id);
+            return Err(err);
+        }
+
+        // Now it's safe to commit the mount into the in-memory state.
+        let preload_cancel = Arc::new(AtomicBool::new(false));
+        let entry = MountEntry {
+            mount_id,
+            job_id: task_id.clone(),
+            path: request.path.clone(),
+            bindings,
+            cls: cls.clone(),
+            mountpoint: mountpoint_str.clone(),
+            upper_dir: upper_dir_str.clone(),
+            cl_dirs: cl_dirs_str.clone(),
+            cl_conflicts,
+            forked_from: None,
+            quota,
+            mem_upper,
+            tuning: request.tuning.clone(),
+            callback_url: request.callback_url.clone(),
+            created_by: request.created_by.clone(),
+            labels: request.labels.clone(),
+            fuse,
+            state: MountLifecycle::Mounted,
+            created_at_epoch_ms: now,
+            last_seen_epoch_ms: now,
+            preload_cancel: preload_cancel.clone(),
+            events: self.events.clone(),
+        };
+        let preload = entry.deep_preload_options();
+
+        // Preserve path/cl for logging before moving into index
+        let path_for_log = request.path.clone();
+        let cls_for_log = cls.clone();
+
+        let task_id_for_log = task_id.clone();
+
+        mounts.insert(mount_id, entry);
+        if let Some(job_id) = task_id {
+            job_index.insert(job_id, mount_id);
+        } else {
+            index.insert((mount_key, cls), mount_id);
+        }
+        if let Some(url) = request.callback_url.clone() {
+            self.webhooks
+                .register(mount_id, url, task_id_for_log.clone())
+                .await;
+        }
+        self.events.emit(
+            mount_id,
+            MountEventKind::State {
+                state: MountLifecycle::Mounted,
+            },
+        );
+
+        tracing::info!(
+            mount_id = %mount_id,
+            task_id = ?task_id_for_log,
+            path = %path_for_log,
+            bindings = request.bindings.len(),
+            cls = ?cls_for_log,
+            mountpoint = %mountpoint_str,
+            upper_dir = ?upper_dir_str,
+            cl_dirs = ?cl_dirs_str,
+            elapsed_ms = start.elapsed().as_millis(),
+            "antares svc: create_mount success"
+        );
+
+        // IMPORTANT: release locks before persisting state.
+        // `persist_state()` acquires `self.mounts.read()`. If we keep holding `mounts.write()`
+        // here, the task deadlocks and the HTTP request never returns (curl hangs at step [1]).
+        drop(mounts);
+        drop(index);
+        drop(job_index);
+
+        // Persist state to file for recovery
+        self.persist_state().await;
+
+        // Transition to Ready immediately.
+        //
+        // By this point Dicfuse's `import_arc()` → `load_dir_depth()` (Phase 1) has
+        // already populated the in-memory directory cache.  Any FUSE `statx` that
+        // arrives now will hit the Dicfuse memory cache (~1 ms) instead of making a
+        // network round-trip (~100 ms).  Waiting for `deep_preload_walk` (Phase 2)
+        // to push those entries into the **kernel** FUSE cache would save ~1 ms per
+        // statx but costs ~140 s of startup latency — unacceptable for CI.
+        //
+        // Phase 2 still runs in the background as a best-effort optimisation.
+        let state = {
+            let mut mounts = self.mounts.write().await;
+            match mounts.get_mut(&mount_id) {
+                Some(entry) => {
+                    if matches!(entry.state, MountLifecycle::Mounted) {
+                        entry.set_state(MountLifecycle::Ready);
+                        entry.update_last_seen();
+                        tracing::info!(
+                            mount_id = %mount_id,
+                            "antares svc: mount is Ready (Dicfuse cache warm, kernel cache warming in background)"
+                        );
+                    }
+                    entry.state.clone()
+                }
+                None => MountLifecycle::Unmounted,
+            }
+        };
+
+        // Best-effort: warm FUSE kernel caches in the background.
+        self.spawn_deep_preload_task(
+            mount_id,
+            mountpoint_str.clone(),
+            preload,
+            preload_cancel.clone(),
+            "create_mount",
+        );
+
+        Ok(MountCreated {
+            mount_id,
+            mountpoint: mountpoint_str,
+            state,
+        })
+    }
+
+    /// Validate a create request, returning its normalized bindings, CL stack and upper kind.
+    fn check_create_request(
+        request: &CreateMountRequest,
+    ) -> Result<(Vec<MountBinding>, Vec<String>, Option<UpperKind>), ServiceError> {
+        Self::validate_request(request)?;
+        let bindings = Self::normalize_bindings(&request.bindings)?;
+        let cls = cl_stack_from(request.cl.as_deref(), &request.cls)?;
+        if request.read_only && request.quota.is_some() {
+            return Err(ServiceError::InvalidRequest(
+                "quota does not apply to read-only mounts".into(),
+            ));
+        }
+        if let Some(quota) = &request.quota {
+            if quota.max_bytes == Some(0) || quota.max_inodes == Some(0) {
+                return Err(ServiceError::InvalidRequest(
+                    "quota limits must be positive; omit a field to keep the configured limit"
+                        .into(),
+                ));
+            }
+        }
+        if request.read_only && request.upper == UpperKind::Memory {
+            return Err(ServiceError::InvalidRequest(
+                "read-only mounts have no upper layer; omit upper".into(),
+            ));
+        }
+        Ok((bindings, cls, (!request.read_only).then_some(request.upper)))
+    }
+
+    /// The task identifier (job/build id) of a create request, if provided.
+    fn task_id(request: &CreateMountRequest) -> Option<String> {
+        request
+            .job_id
+            .clone()
+            .or(request.build_id.clone())
+            .and_then(|s| {
+                let trimmed = s.trim().to_string();
+                if trimmed.is_empty() {
+                    None
+                } else {
+                    Some(trimmed)
+                }
+            })
+    }
+
+    /// Mountpoint, upper directory and CL layer directories of a new mount.
+    fn generated_paths(
+        &self,
+        mount_id: Uuid,
+        mountpoint: Option<&Path>,
+        upper_kind: Option<UpperKind>,
+        cl_count: usize,
+    ) -> (String, Option<String>, Vec<String>) {
+        let id_str = mount_id.to_string();
+
+        let mount_root = self.paths.mount_root.display();
+        let upper_root = self.paths.upper_root.display();
+        let cl_root = self.paths.cl_root.to_string_lossy();
+
+        // Auto-generate paths based on UUID
+        let mountpoint = match mountpoint {
+            Some(mountpoint) => mountpoint.to_string_lossy().into_owned(),
+            None => format!("{}/{}", mount_root, id_str),
+        };
+        // Read-only mounts have no upper layer at all; memory uppers have no directory.
+        let upper_dir =
+            (upper_kind == Some(UpperKind::Disk)).then(|| format!("{}/{}", upper_root, id_str));
+        let cl_dirs = (0..cl_count)
+            .map(|i| cl_layer_dir(&cl_root, &id_str, i))
+            .collect();
+        (mountpoint, upper_dir, cl_dirs)
+    }
+
+    /// Start creating a mount in a background task and report it as `Provisioning`.
+    ///
+    /// Only the request itself is validated up front. Duplicate checks and everything slow
+    /// happen in the task; if it fails, the mount stays listed as `Failed` until deleted.
+    async fn create_mount_in_background(
+        &self,
+        mut request: CreateMountRequest,
+    ) -> Result<MountCreated, ServiceError> {
+        request.path = Self::normalize_mount_path(&request.path);
+        request.background = false;
+        let (bindings, cls, upper_kind) = Self::check_create_request(&request)?;
+        let task_id = Self::task_id(&request);
+
+        // A mounted task is answered (or rejected) right away by the regular path.
+        if let Some(ref job_id) = task_id {
+            if self.job_index.read().await.contains_key(job_id) {
+                return self.provision_mount(Uuid::new_v4(), request).await;
+            }
+        }
+
+        let mount_id = Uuid::new_v4();
+        // Reported until the task claims it; symlinks resolved the same way it will.
+        let requested = request
+            .mountpoint
+            .as_deref()
+            .map(|m| resolve_symlinks(Path::new(m)).unwrap_or_else(|_| PathBuf::from(m)));
+        let (mountpoint, upper_dir, cl_dirs) =
+            self.generated_paths(mount_id, requested.as_deref(), upper_kind, cls.len());
+        let now = current_epoch_ms();
+        let status = MountStatus {
+            mount_id,
+            job_id: task_id,
+            path: request.path.clone(),
+            bindings,
+            cl: cls.last().cloned(),
+            cls,
+            cl_conflicts: Vec::new(),
+            mountpoint: mountpoint.clone(),
+            forked_from: None,
+            read_only: upper_kind.is_none(),
+            upper_kind,
+            usage: None,
+            tuning: request.tuning.clone(),
+            callback_url: request.callback_url.clone(),
+            created_by: request.created_by.clone(),
+            labels: request.labels.clone(),
+            layers: MountLayers {
+                upper: upper_dir,
+                cl: cl_dirs.last().cloned(),
+                cl_dirs,
+                dicfuse: "shared".to_string(),
+            },
+            state: MountLifecycle::Provisioning,
+            created_at_epoch_ms: now,
+            last_seen_epoch_ms: now,
+        };
+        // Checked and inserted under one lock so two requests for a task cannot both start.
+        let mut provisioning = self.provisioning.write().await;
+        if let Some(ref job_id) = status.job_id {
+            if let Some(op) = provisioning
+                .values()
+                .find(|op| op.job_id == status.job_id && op.state == MountLifecycle::Provisioning)
+            {
+                // Everything the request sets except who sent it must match.
+                if op.path != status.path
+                    || op.bindings != status.bindings
+                    || op.cls != status.cls
+                    || op.upper_kind != status.upper_kind
+                    || op.read_only != status.read_only
+                    || op.tuning != status.tuning
+                    || op.callback_url != status.callback_url
+                    || op.labels != status.labels
+                    || (request.mountpoint.is_some() && op.mountpoint != status.mountpoint)
+                {
+                    return Err(ServiceError::InvalidRequest(format!(
+                        "job_id/build_id '{}' already mounted with different path/cl/upper/mountpoint",
+                        job_id
+                    )));
+                }
+                return Ok(MountCreated {
+                    mount_id: op.mount_id,
+                    mountpoint: op.mountpoint.clone(),
+                    state: op.state.clone(),
+                });
+            }
+        }
+        provisioning.insert(mount_id, status.clone());
+        drop(provisioning);
+        if let Some(url) = status.callback_url {
+            self.webhooks.register(mount_id, url, status.job_id).await;
+        }
+        self.events.emit(
+            mount_id,
+            MountEventKind::State {
+                state: MountLifecycle::Provisioning,
+            },
+        );
+        tracing::info!(
+            mount_id = %mount_id,
+            mountpoint = %mountpoint,
+            "antares svc: create_mount continues in background"
+        );
+
+        let task = tokio::spawn({
+            let this = self.clone();
+            async move { this.provision_mount(mount_id, request).await }
+        });
+        // Supervised separately so a panic also ends as `Failed` instead of `Provisioning`.
+        let this = self.clone();
+        tokio::spawn(async move {
+            let result = task.await.unwrap_or_else(|e| {
+                Err(ServiceError::Internal(format!(
+                    "create_mount panicked: {}",
+                    e
+                )))
+            });
+            let mut provisioning = this.provisioning.write().await;
+            match result {
+                Ok(created) if created.mount_id == mount_id => {
+                    provisioning.remove(&mount_id);
+                }
+                Ok(created) => {
+                    // Another request for the same task won the race; it owns the mount.
+                    provisioning.remove(&mount_id);
+                    this.events.emit(mount_id, MountEventKind::Removed);
+                    tracing::info!(
+                        mount_id = %mount_id,
+                        existing = %created.mount_id,
+                        "antares svc: background create_mount resolved to an existing mount"
+                    );
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        mount_id = %mount_id,
+                        error = %err,
+                        "antares svc: background create_mount failed"
+                    );
+                    let state = MountLifecycle::Failed {
+                        reason: err.to_string(),
+                    };
+                    if let Some(op) = provisioning.get_mut(&mount_id) {
+                        op.state = state.clone();
+                        op.last_seen_epoch_ms = current_epoch_ms();
+                    }
+                    this.events.emit(mount_id, MountEventKind::State { state });
+                }
+            }
+        });
+
+        Ok(MountCreated {
+            mount_id,
+            mountpoint,
+            state: MountLifecycle::Provisioning,
+        })
+    }
+
+    /// Forget a mount whose background creation failed.
+    ///
+    /// Creations still in progress cannot be deleted; retry once they finish.
+    async fn remove_provisioning(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
+        let mut provisioning = self.provisioning.write().await;
+        let state = provisioning
+            .get(&mount_id)
+            .map(|op| op.state.clone())
+            .ok_or(ServiceError::NotFound(mount_id))?;
+        if !matches!(state, MountLifecycle::Failed { .. }) {
+            return Err(ServiceError::InvalidRequest(format!(
+                "mount {} is currently in state {:?}; retry after it is created",
+                mount_id, state
+            )));
+        }
+        let mut status = provisioning
+            .remove(&mount_id)
+            .ok_or(ServiceError::NotFound(mount_id))?;
+        status.state = MountLifecycle::Unmounted;
+        self.events.emit(mount_id, MountEventKind::Removed);
+        Ok(status)
+    }
+}
+
+#[async_trait]
+impl AntaresService for AntaresServiceImpl {
+    async fn create_mount(
+        &self,
+        request: CreateMountRequest,
+    ) -> Result<MountCreated, ServiceError> {
+        if request.background {
+            self.create_mount_in_background(request).await
+        } else {
+            self.provision_mount(Uuid::new_v4(), request).await
+        }
+    }
+
+    async fn list_mounts(&self) -> Result<Vec<MountStatus>, ServiceError> {
+        let mounts = self.mounts.read().await;
+        let mut list: Vec<MountStatus> = mounts.values().map(|e| e.to_status()).collect();
+        let provisioning = self.provisioning.read().await;
+        list.extend(
+            provisioning
+                .values()
+                .filter(|op| !mounts.contains_key(&op.mount_id))
+                .cloned(),
+        );
+        Ok(list)
+    }
+
+    async fn describe_mount(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
+        let mounts = self.mounts.read().await;
+        if let Some(entry) = mounts.get(&mount_id) {
+            return Ok(entry.to_status());
+        }
+        self.provisioning
+            .read()
+            .await
+            .get(&mount_id)
+            .cloned()
+            .ok_or(ServiceError::NotFound(mount_id))
+    }
+
+    async fn delete_mount(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
+        let start = Instant::now();
+        // Acquire write locks to update state
+        let mut mounts = self.mounts.write().await;
+        let index = self.path_index.write().await;
+
+        // Get mutable reference to entry (don't remove yet)
+        let Some(entry) = mounts.get_mut(&mount_id) else {
+            drop(mounts);
+            drop(index);
+            return self.remove_provisioning(mount_id).await;
+        };
+
+        if matches!(
+            entry.state,
+            MountLifecycle::Quiescing | MountLifecycle::Unmounting
+        ) {
+            return Err(ServiceError::InvalidRequest(format!(
+                "mount {} is currently in state {:?}; retry after switch/unmount completes",
+                mount_id, entry.state
+            )));
+        }
+
+        // Cancel any in-flight deep-preload walk so it stops quickly.
+        entry.preload_cancel.store(true, Ordering::Relaxed);
+
+        // Set state to Unmounting while still in the map
+        entry.set_state(MountLifecycle::Unmounting);
+        entry.update_last_seen();
+
+        // Store path/cls for index removal, then take ownership of fuse for unmount
+        let path = entry.index_path();
+        let cls = entry.cls.clone();
+        let job_id = entry.job_id.clone();
+        let job_id_for_log = job_id.clone();
+        tracing::info!(
+            mount_id = %mount_id,
+            task_id = ?job_id_for_log,
+            path = %path,
+            cls = ?cls,
+            mountpoint = %entry.mountpoint,
+            "antares svc: delete_mount start"
+        );
+        let mountpoint = PathBuf::from(&entry.mountpoint);
+        let upper_dir = entry.upper_dir.as_ref().map(PathBuf::from);
+        let cl_dirs = entry.cl_dir_paths();
+        // Placeholder until the entry is removed, or the fuse is restored on failure. It
+        // touches no directories, so deleting a mount whose layers are gone still works.
+        let mut fuse = std::mem::replace(
+            &mut entry.fuse,
+            AntaresFuse::unmounted(mountpoint, self.dicfuse.clone(), upper_dir, cl_dirs),
+        );
+
+        // Release locks before potentially slow unmount operation
+        drop(mounts);
+        drop(index);
+
+        // Unmount the filesystem
+        let unmount_result = fuse.unmount().await;
+
+        // Reacquire locks to update state and remove if needed
+        let mut mounts = self.mounts.write().await;
+        let mut index = self.path_index.write().await;
+        let mut job_index = self.job_index.write().await;
+
+        let entry = match mounts.get_mut(&mount_id) {
+            Some(entry) => entry,
+            None => {
+                tracing::error!(
+                    "Mount entry {} missing during unmount; possible race or state bug",
+                    mount_id
+                );
+                drop(mounts);
+                drop(index);
+                drop(job_index);
+                return Err(ServiceError::Internal(format!(
+                    "Mount entry {} not found during unmount; this should not happen",
+                    mount_id
+                )));
+            }
+        };
+
+        if let Err(e) = unmount_result {
+            tracing::error!(
+                mount_id = %mount_id,
+                task_id = ?job_id_for_log,
+                elapsed_ms = start.elapsed().as_millis(),
+                error = %e,
+                "antares svc: delete_mount unmount failed"
+            );
+            // Put fuse back since unmount failed
+            entry.fuse = fuse;
+            entry.set_state(MountLifecycle::Failed {
+                reason: format!("unmount failed: {}", e),
+            });
+            entry.update_last_seen();
+            // Do not remove from mounts or index; keep for tracking failed unmounts
+            let status = entry.to_status();
+            drop(mounts);
+            drop(index);
+            drop(job_index);
+            return Ok(status);
+        } else {
+            entry.set_state(MountLifecycle::Unmounted);
+            entry.update_last_seen();
+            // Remove from mounts and index only after successful unmount
+            let status = entry.to_status();
+            mounts.remove(&mount_id);
+            self.events.emit(mount_id, MountEventKind::Removed);
+            if let Some(job_id) = job_id {
+                job_index.remove(&job_id);
+            } else {
+                remove_path_index_entry(&mut index, &(path, cls), mount_id);
+            }
+            drop(mounts);
+            drop(index);
+            drop(job_index);
+            tracing::info!(
+                mount_id = %mount_id,
+                task_id = ?job_id_for_log,
+                elapsed_ms = start.elapsed().as_millis(),
+                "antares svc: delete_mount success"
+            );
+
+            self.checkpoint_locks.lock().unwrap().remove(&mount_id);
+            if let Err(e) = self.checkpoints.delete_all(mount_id) {
+                tracing::warn!(
+                    mount_id = %mount_id,
+                    error = %e,
+                    "antares svc: failed to remove checkpoints of deleted mount"
+                );
+            }
+            self.cl_progress.write().await.remove(&mount_id);
+
+            // Persist state to file for recovery
+            self.persist_state().await;
+            self.collect_blob_garbage().await;
+
+            Ok(status)
+        }
+    }
+
+    async fn build_cl(
+        &self,
+        mount_id: Uuid,
+        cls: Vec<String>,
+    ) -> Result<MountStatus, ServiceError> {
+        if cls.is_empty() {
+            return Err(ServiceError::InvalidRequest(
+                "at least one cl is required; use DELETE to clear CL layers".into(),
+            ));
+        }
+        let view = {
+            let mounts = self.mounts.read().await;
+            let entry = mounts
+                .get(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            if !matches!(entry.state, MountLifecycle::Mounted | MountLifecycle::Ready) {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "mount {} is currently in state {:?}; cannot build CL",
+                    mount_id, entry.state
+                )));
+            }
+            entry.view()
+        };
+        let progress = self.begin_cl_progress(mount_id, &cls).await?;
+        let result = self.rebuild_cl(mount_id, &view, cls, None, &progress).await;
+        progress.finish(result.as_ref().map(|_| ()));
+        result
+    }
+
+    async fn build_local_cl(
+        &self,
+        mount_id: Uuid,
+        request: LocalClRequest,
+    ) -> Result<MountStatus, ServiceError> {
+        let name = request.cl_name()?;
+        let local = request.parse()?;
+        let (view, dicfuse, bound) = {
+            let mounts = self.mounts.read().await;
+            let entry = mounts
+                .get(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            if !matches!(entry.state, MountLifecycle::Mounted | MountLifecycle::Ready) {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "mount {} is currently in state {:?}; cannot build CL",
+                    mount_id, entry.state
+                )));
+            }
+            (
+                entry.view(),
+                entry.fuse.dic.clone(),
+                entry.fuse.bindings.clone(),
+            )
+        };
+        let cls = vec![name.clone()];
+        let progress = self.begin_cl_progress(mount_id, &cls).await?;
+        let result = async {
+            let relocate = |p: &str| {
+                Self::relative_path_in_view(p, &view).map(|rel| rel.to_string_lossy().into_owned())
+            };
+            let layer = if bound.is_empty() {
+                local.resolve(&*dicfuse, relocate).await
+            } else {
+                local.resolve(&bound, relocate).await
+            }
+            .map_err(|report| ServiceError::InvalidRequest(report.to_string()))?;
+            let cache = self.blob_cache.clone();
+            let manifest = tokio::task::spawn_blocking(move || layer.into_manifest(&name, &cache))
+                .await
+                .map_err(|e| ServiceError::Internal(format!("local CL task failed: {}", e)))?
+                .map_err(|e| {
+                    ServiceError::Internal(format!("failed to cache local CL files: {}", e))
+                })?;
+            self.rebuild_cl(mount_id, &view, cls, Some(&manifest), &progress)
+                .await
+        }
+        .await;
+        progress.finish(result.as_ref().map(|_| ()));
+        result
+    }
+
+    async fn clear_cl(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
+        let mut mounts = self.mounts.write().await;
+        let index = self.path_index.write().await;
+
+        let entry = mounts
+            .get_mut(&mount_id)
+            .ok_or(ServiceError::NotFound(mount_id))?;
+        if !matches!(entry.state, MountLifecycle::Mounted | MountLifecycle::Ready) {
+            return Err(ServiceError::InvalidRequest(format!(
+                "mount {} is currently in state {:?}; cannot clear CL",
+                mount_id, entry.state
+            )));
+        }
+
+        if entry.cls.is_empty() {
+            return Err(ServiceError::InvalidRequest(
+                "mount has no CL layer to clear".into(),
+            ));
+        }
+
+        let path = entry.index_path();
+        let job_id = entry.job_id.clone();
+        let old_cls = entry.cls.clone();
+        let quiesce_grace = Self::cl_quiesce_grace_duration();
+        let mountpoint = PathBuf::from(&entry.mountpoint);
+        let upper_dir = entry.upper_dir.as_ref().map(PathBuf::from);
+        let existing_cl_dirs = entry.cl_dir_paths();
+        let dicfuse = entry.fuse.dic.clone();
+        let bound = entry.fuse.bindings.clone();
+        let quota = entry.quota.clone();
+        let mem_upper = entry.mem_upper.clone();
+        // Cancel any in-flight deep-preload walk before unmounting.
+        entry.preload_cancel.store(true, Ordering::Relaxed);
+        // Enter a short quiescing window so control-plane operations reject this mount
+        // while we prepare to remount without CL.
+        entry.set_state(MountLifecycle::Quiescing);
+        entry.update_last_seen();
+        let mut old_fuse = std::mem::replace(&mut entry.fuse, {
+            AntaresFuse::new_with_layers(
+                mountpoint.clone(),
+                self.dicfuse.clone(),
+                upper_dir.clone(),
+                existing_cl_dirs.clone(),
+            )
+            .await
+            .map_err(|e| {
+                ServiceError::Internal(format!("failed to create placeholder fuse: {}", e))
+            })?
+        });
+
+        drop(mounts);
+        drop(index);
+        if !quiesce_grace.is_zero() {
+            tracing::info!(
+                mount_id = %mount_id,
+                grace_ms = quiesce_grace.as_millis(),
+                "antares svc: clear_cl quiescing before remount"
+            );
+            sleep(quiesce_grace).await;
+        }
+
+        if let Err(e) = old_fuse.unmount().await {
+            tracing::error!("Failed to unmount {}: {}", mount_id, e);
+            let mut mounts = self.mounts.write().await;
+            let index = self.path_index.write().await;
+            let _job_index = self.job_index.write().await;
+            if let Some(entry) = mounts.get_mut(&mount_id) {
+                entry.fuse = old_fuse;
+                entry.set_state(MountLifecycle::Failed {
+                    reason: format!("unmount failed: {}", e),
+                });
+                entry.update_last_seen();
+            }
+            drop(mounts);
+            drop(index);
+            drop(_job_index);
+            return Err(ServiceError::FuseFailure(format!("unmount failed: {}", e)));
+        }
+
+        for cl_dir in &existing_cl_dirs {
+            if let Err(e) = cl_manifest::remove_layer(cl_dir) {
+                tracing::warn!("Failed to remove CL directory {:?}: {}", cl_dir, e);
+            }
+        }
+
+        let mut new_fuse =
+            AntaresFuse::new_with_upper(mountpoint.clone(), dicfuse, upper_dir.clone(), None)
+                .await
+                .map_err(|e| ServiceError::FuseFailure(format!("failed to create fuse: {}", e)))?
+                .with_quota(quota)
+                .with_memory_upper(mem_upper)
+                .with_bindings(bound);
+        if let Err(e) = new_fuse.mount().await {
+            tracing::error!("Failed to remount {} without CL: {}", mount_id, e);
+            let remount_result = old_fuse.mount().await;
+            let mut mounts = self.mounts.write().await;
+            let index = self.path_index.write().await;
+            let _job_index = self.job_index.write().await;
+            if let Some(entry) = mounts.get_mut(&mount_id) {
+                entry.fuse = old_fuse;
+                entry.set_state(if let Err(remount_err) = remount_result {
+                    MountLifecycle::Failed {
+                        reason: format!("remount after clear CL failure: {}", remount_err),
+                    }
+                } else {
+                    MountLifecycle::Mounted
+                });
+                entry.update_last_seen();
+            }
+            drop(mounts);
+            drop(index);
+            drop(_job_index);
+            return Err(ServiceError::FuseFailure(format!(
+                "failed to remount without CL: {}",
+                e
+            )));
+        }
+
+        let mut mounts = self.mounts.write().await;
+        let mut index = self.path_index.write().await;
+        let _job_index = self.job_index.write().await;
+        let entry = mounts
+            .get_mut(&mount_id)
+            .ok_or(ServiceError::NotFound(mount_id))?;
+
+        let new_cancel = Arc::new(AtomicBool::new(false));
+        entry.fuse = new_fuse;
+        entry.cls.clear();
+        entry.cl_dirs.clear();
+        entry.cl_conflicts.clear();
+        // Transition directly to Ready — Dicfuse cache is already warm.
+        entry.set_state(MountLifecycle::Ready);
+        entry.preload_cancel = new_cancel.clone();
+        entry.update_last_seen();
+
+        if job_id.is_none()
+            && remove_path_index_entry(&mut index, &(path.clone(), old_cls), mount_id)
+        {
+            index.entry((path, Vec::new())).or_insert(mount_id);
+        }
+
+        let mountpoint_for_preload = entry.mountpoint.clone();
+        let preload = entry.deep_preload_options();
+        let status = entry.to_status();
+        tracing::info!("Cleared CL layer for mount {}", mount_id);
+        drop(mounts);
+        drop(index);
+        drop(_job_index);
+
+        self.persist_state().await;
+        self.collect_blob_garbage().await;
+
+        // Best-effort: re-warm kernel FUSE caches after remount.
+        self.spawn_deep_preload_task(
+            mount_id,
+            mountpoint_for_preload,
+            preload,
+            new_cancel,
+            "clear_cl",
+        );
+
+        Ok(status)
+    }
+
+    async fn fork_mount(
+        &self,
+        mount_id: Uuid,
+        request: ForkMountRequest,
+    ) -> Result<MountCreated, ServiceError> {
+        let start = Instant::now();
+        let task_id = request
+            .job_id
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        // 1. Capture the source layout. Only a read lock is needed: the source keeps serving
+        // while its upper is copied, so the fork is best-effort, not point-in-time.
+        let (
+            path,
+            bindings,
+            cls,
+            cl_conflicts,
+            src_upper,
+            src_cl_dirs,
+            dicfuse,
+            bound,
+            limits,
+            tuning,
+            mut labels,
+        ) = {
+            let mounts = self.mounts.read().await;
+            let entry = mounts
+                .get(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            if !matches!(entry.state, MountLifecycle::Mounted | MountLifecycle::Ready) {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "mount {} is currently in state {:?}; cannot fork",
+                    mount_id, entry.state
+                )));
+            }
+            let src_upper = entry.require_upper("fork")?;
+            let limits = src_upper.limits();
+            (
+                entry.path.clone(),
+                entry.bindings.clone(),
+                entry.cls.clone(),
+                entry.cl_conflicts.clone(),
+                src_upper,
+                entry.cl_dir_paths(),
+                entry.fuse.dic.clone(),
+                entry.fuse.bindings.clone(),
+                limits,
+                entry.tuning.clone(),
+                entry.labels.clone(),
+            )
+        };
+        labels.extend(request.labels);
+        labels::validate(&labels).map_err(ServiceError::InvalidRequest)?;
+
+        if let Some(ref job_id) = task_id {
+            if self.job_index.read().await.contains_key(job_id) {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "job_id/build_id '{}' is already mounted",
+                    job_id
+                )));
+            }
+        }
+
+        tracing::info!(
+            source_mount_id = %mount_id,
+            task_id = ?task_id,
+            path = %path,
+            cls = ?cls,
+            "antares svc: fork_mount start"
+        );
+
+        // 2. Generate fresh paths for the fork.
+        let fork_id = Uuid::new_v4();
+        let _creation = self.claim_creation(fork_id);
+        let id_str = fork_id.to_string();
+        let mountpoint_str = format!("{}/{}", self.paths.mount_root.display(), id_str);
+        // A memory source forks into another memory layer, so there is no upper directory.
+        let upper_dir_str = matches!(src_upper, UpperHandle::Disk { .. })
+            .then(|| format!("{}/{}", self.paths.upper_root.display(), id_str));
+        let cl_root = self.paths.cl_root.to_string_lossy();
+        let cl_dirs_str: Vec<String> = (0..src_cl_dirs.len())
+            .map(|i| cl_layer_dir(&cl_root, &id_str, i))
+            .collect();
+
+        let cleanup = |mountpoint: &str, upper: Option<&str>, cl_dirs: &[String]| {
+            let _ = std::fs::remove_dir_all(mountpoint);
+            if let Some(u) = upper {
+                let _ = std::fs::remove_dir_all(u);
+            }
+            for c in cl_dirs {
+                let _ = cl_manifest::remove_layer(Path::new(c));
+            }
+        };
+
+        // 3. Seed the fork's layers from the source (reflink where supported).
+        let upper_dst = upper_dir_str.as_ref().map(PathBuf::from);
+        let cl_dsts: Vec<PathBuf> = cl_dirs_str.iter().map(PathBuf::from).collect();
+        let copy_result = tokio::task::spawn_blocking(
+            move || -> std::io::Result<(CloneStats, Option<MemUpperLayer>)> {
+                let (mut stats, mem_upper) = match (&src_upper, &upper_dst) {
+                    (UpperHandle::Disk { dir, .. }, Some(dst)) => {
+                        (snapshot::clone_tree(dir, dst)?, None)
+                    }
+                    (UpperHandle::Memory(mem), _) => {
+                        let usage = mem.usage();
+                        let stats = CloneStats {
+                            files: usage.used_inodes,
+                            bytes: usage.used_bytes,
+                            ..CloneStats::default()
+                        };
+                        (stats, Some(mem.fork()))
+                    }
+                    (UpperHandle::Disk { .. }, None) => unreachable!("disk fork without upper_dir"),
+                };
+                for (src, dst) in src_cl_dirs.iter().zip(&cl_dsts) {
+                    if src.exists() {
+                        stats.merge(&snapshot::clone_tree(src, dst)?);
+                        cl_manifest::copy(src, dst)?;
+                    }
+                }
+                Ok((stats, mem_upper))
+            },
+        )
+        .await
+        .map_err(|e| ServiceError::Internal(format!("fork copy task failed: {}", e)))?;
+        let (stats, mem_upper) = match copy_result {
+            Ok((stats, mem_upper)) => (stats, mem_upper.map(Arc::new)),
+            Err(e) => {
+                cleanup(&mountpoint_str, upper_dir_str.as_deref(), &cl_dirs_str);
+                return Err(ServiceError::Internal(format!(
+                    "failed to copy layers of mount {}: {}",
+                    mount_id, e
+                )));
+            }
+        };
+        tracing::debug!(
+            source_mount_id = %mount_id,
+            mount_id = %fork_id,
+            files = stats.files,
+            bytes = stats.bytes,
+            reflinked = stats.reflinked,
+            "antares svc: fork_mount layers copied"
+        );
+
+        // 4. Mount the fork on top of the source's Dicfuse instance. The fork inherits the
+        // source's quota limits; its usage starts at whatever was copied.
+        let quota = match &upper_dir_str {
+            Some(upper_dir) => match Self::open_upper_quota(PathBuf::from(upper_dir), limits).await
+            {
+                Ok(quota) => Some(quota),
+                Err(e) => {
+                    cleanup(&mountpoint_str, Some(upper_dir), &cl_dirs_str);
+                    return Err(e);
+                }
+            },
+            None => None,
+        };
+        let mut fuse = match AntaresFuse::new_with_layers(
+            PathBuf::from(&mountpoint_str),
+            dicfuse,
+            upper_dir_str.as_ref().map(PathBuf::from),
+            cl_dirs_str.iter().map(PathBuf::from).collect(),
+        )
+        .await
+        {
+            Ok(fuse) => fuse
+                .with_quota(quota.clone())
+                .with_memory_upper(mem_upper.clone())
+                .with_bindings(bound),
+            Err(e) => {
+                cleanup(&mountpoint_str, upper_dir_str.as_deref(), &cl_dirs_str);
+                return Err(ServiceError::FuseFailure(format!(
+                    "failed to create fuse: {}",
+                    e
+                )));
+            }
+        };
+        if let Err(e) = fuse.mount().await {
+            cleanup(&mountpoint_str, upper_dir_str.as_deref(), &cl_dirs_str);
+            return Err(ServiceError::FuseFailure(format!("failed to mount: {}", e)));
+        }
+
+        // 5. Commit. Forks are intentionally not registered in the (path, cl) index: that
+        // slot belongs to the source, and a fork is expected to share it.
+        let now = current_epoch_ms();
+        let mut mounts = self.mounts.write().await;
+        let mut job_index = self.job_index.write().await;
+        if let Some(ref job_id) = task_id {
+            if job_index.contains_key(job_id) {
+                drop(mounts);
+                drop(job_index);
+                tracing::warn!(
+                    "fork_mount duplicate task_id detected after mount; rolling back orphan mount {}",
+                    fork_id
+                );
+                let _ = fuse.unmount().await;
+                cleanup(&mountpoint_str, upper_dir_str.as_deref(), &cl_dirs_str);
+                return Err(ServiceError::InvalidRequest(format!(
+                    "job_id/build_id '{}' is already mounted",
+                    job_id
+                )));
+            }
+        }
+
+        let preload_cancel = Arc::new(AtomicBool::new(false));
+        let preload = tuning
+            .as_ref()
+            .map(MountTuning::deep_preload)
+            .unwrap_or_else(DeepPreloadOptions::from_env);
+        mounts.insert(
+            fork_id,
+            MountEntry {
+                mount_id: fork_id,
+                job_id: task_id.clone(),
+                path,
+                bindings,
+                cls,
+                mountpoint: mountpoint_str.clone(),
+                upper_dir: upper_dir_str,
+                cl_dirs: cl_dirs_str,
+                cl_conflicts,
+                forked_from: Some(mount_id),
+                quota,
+                mem_upper,
+                tuning,
+                // The source's callback belongs to the source's creator.
+                callback_url: None,
+                created_by: request.created_by,
+                labels,
+                fuse,
+                state: MountLifecycle::Ready,
+                created_at_epoch_ms: now,
+                last_seen_epoch_ms: now,
+                preload_cancel: preload_cancel.clone(),
+                events: self.events.clone(),
+            },
+        );
+        if let Some(job_id) = task_id.clone() {
+            job_index.insert(job_id, fork_id);
+        }
+        drop(mounts);
+        drop(job_index);
+        self.events.emit(
+            fork_id,
+            MountEventKind::State {
+                state: MountLifecycle::Ready,
+            },
+        );
+
+        tracing::info!(
+            source_mount_id = %mount_id,
+            mount_id = %fork_id,
+            task_id = ?task_id,
+            mountpoint = %mountpoint_str,
+            elapsed_ms = start.elapsed().as_millis(),
+            "antares svc: fork_mount success"
+        );
+
+        self.persist_state().await;
+
+        self.spawn_deep_preload_task(
+            fork_id,
+            mountpoint_str.clone(),
+            preload,
+            preload_cancel,
+            "fork_mount",
+        );
+
+        Ok(MountCreated {
+            mount_id: fork_id,
+            mountpoint: mountpoint_str,
+            state: MountLifecycle::Ready,
+        })
+    }
+
+    async fn create_checkpoint(
+        &self,
+        mount_id: Uuid,
+        request: CreateCheckpointRequest,
+    ) -> Result<CheckpointInfo, ServiceError> {
+        let start = Instant::now();
+        let (upper, cl) = {
+            let mounts = self.mounts.read().await;
+            let entry = mounts
+                .get(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            (
+                Arc::new(entry.require_upper("checkpoint")?),
+                entry.cls.last().cloned(),
+            )
+        };
+        let lock = self.checkpoint_lock(mount_id);
+        let _creating = lock.lock().await;
+
+        // Check the budget before quiescing so an over-limit request never disturbs the mount.
+        let limit = crate::util::config::antares_checkpoint_max_bytes();
+        let store = self.checkpoints.clone();
+        let upper_for_usage = upper.clone();
+        let (used, needed) = tokio::task::spawn_blocking(move || -> std::io::Result<(u64, u64)> {
+            let needed = match upper_for_usage.as_ref() {
+                UpperHandle::Disk { dir, .. } => snapshot::tree_usage(dir)?.bytes,
+                UpperHandle::Memory(mem) => mem.usage().used_bytes,
+            };
+            Ok((store.used_bytes(mount_id)?, needed))
+        })
+        .await
+        .map_err(|e| ServiceError::Internal(format!("checkpoint usage task failed: {}", e)))?
+        .map_err(|e| ServiceError::Internal(format!("failed to measure upper layer: {}", e)))?;
+        if used.saturating_add(needed) > limit {
+            return Err(ServiceError::LimitExceeded(format!(
+                "checkpoint of mount {} needs {} bytes; {} of {} bytes already used",
+                mount_id, needed, used, limit
+            )));
+        }
+
+        let fuse = self.quiesce_for_maintenance(mount_id, "checkpoint").await?;
+        let store = self.checkpoints.clone();
+        let label = request.label;
+        let created = tokio::task::spawn_blocking(move || {
+            store.create_with(mount_id, label, cl, |dst| upper.copy_to(dst))
+        })
+        .await;
+        let resumed = self.resume_quiesced(mount_id, fuse, "checkpoint").await;
+
+        let info = created
+            .map_err(|e| ServiceError::Internal(format!("checkpoint task failed: {}", e)))?
+            .map_err(|e| ServiceError::Internal(format!("failed to write checkpoint: {}", e)))?;
+        // The upper kept growing until the quiesce; what was copied is what counts.
+        if used.saturating_add(info.size_bytes) > limit {
+            if let Err(e) = self.checkpoints.delete(mount_id, info.checkpoint_id) {
+                tracing::warn!(
+                    mount_id = %mount_id,
+                    checkpoint_id = %info.checkpoint_id,
+                    error = %e,
+                    "antares svc: failed to remove over-budget checkpoint"
+                );
+            }
+            resumed?;
+            return Err(ServiceError::LimitExceeded(format!(
+                "checkpoint of mount {} copied {} bytes; {} of {} bytes already used",
+                mount_id, info.size_bytes, used, limit
+            )));
+        }
+        resumed?;
+
+        tracing::info!(
+            mount_id = %mount_id,
+            checkpoint_id = %info.checkpoint_id,
+            size_bytes = info.size_bytes,
+            elapsed_ms = start.elapsed().as_millis(),
+            "antares svc: checkpoint created"
+        );
+        Ok(info)
+    }
+
+    async fn list_checkpoints(&self, mount_id: Uuid) -> Result<CheckpointCollection, ServiceError> {
+        if !self.mounts.read().await.contains_key(&mount_id) {
+            return Err(ServiceError::NotFound(mount_id));
+        }
+        let checkpoints = self
+            .checkpoints
+            .list(mount_id)
+            .map_err(|e| ServiceError::Internal(format!("failed to list checkpoints: {}", e)))?;
+        Ok(CheckpointCollection {
+            mount_id,
+            used_bytes: checkpoints.iter().map(|c| c.size_bytes).sum(),
+            limit_bytes: crate::util::config::antares_checkpoint_max_bytes(),
+            checkpoints,
+        })
+    }
+
+    async fn delete_checkpoint(
+        &self,
+        mount_id: Uuid,
+        checkpoint_id: Uuid,
+    ) -> Result<CheckpointInfo, ServiceError> {
+        if !self.mounts.read().await.contains_key(&mount_id) {
+            return Err(ServiceError::NotFound(mount_id));
+        }
+        let lock = self.checkpoint_lock(mount_id);
+        let _deleting = lock.lock().await;
+        self.checkpoints
+            .delete(mount_id, checkpoint_id)
+            .map_err(|e| ServiceError::Internal(format!("failed to delete checkpoint: {}", e)))?
+            .ok_or(ServiceError::CheckpointNotFound(checkpoint_id))
+    }
+
+    async fn restore_checkpoint(
+        &self,
+        mount_id: Uuid,
+        checkpoint_id: Uuid,
+    ) -> Result<MountStatus, ServiceError> {
+        let start = Instant::now();
+        let upper = {
+            let mounts = self.mounts.read().await;
+            let entry = mounts
+                .get(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            entry.require_upper("restore a checkpoint")?
+        };
+        let lock = self.checkpoint_lock(mount_id);
+        let _restoring = lock.lock().await;
+        self.checkpoints
+            .get(mount_id, checkpoint_id)
+            .map_err(|e| ServiceError::Internal(format!("failed to read checkpoint: {}", e)))?
+            .ok_or(ServiceError::CheckpointNotFound(checkpoint_id))?;
+
+        let fuse = self
+            .quiesce_for_maintenance(mount_id, "restore_checkpoint")
+            .await?;
+        let store = self.checkpoints.clone();
+        let restored = tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+            match upper {
+                UpperHandle::Disk { dir, quota } => {
+                    store.restore_into(mount_id, checkpoint_id, &dir)?;
+                    // The upper was swapped wholesale; recount before writers come back.
+                    if let Err(e) = quota.rescan() {
+                        tracing::warn!(mount_id = %mount_id, error = %e, "quota rescan after restore failed");
+                    }
+                }
+                UpperHandle::Memory(mem) => {
+                    mem.load_from(&store.upper_path(mount_id, checkpoint_id))?;
+                }
+            }
+            Ok(())
+        })
+        .await;
+        // Remount either way: on failure the old upper contents are still in place.
+        let status = self
+            .resume_quiesced(mount_id, fuse, "restore_checkpoint")
+            .await;
+
+        restored
+            .map_err(|e| ServiceError::Internal(format!("restore task failed: {}", e)))?
+            .map_err(|e| ServiceError::Internal(format!("failed to restore checkpoint: {}", e)))?;
+        let status = status?;
+
+        tracing::info!(
+            mount_id = %mount_id,
+            checkpoint_id = %checkpoint_id,
+            elapsed_ms = start.elapsed().as_millis(),
+            "antares svc: checkpoint restored"
+        );
+        Ok(status)
+    }
+
+    async fn mount_usage(&self, mount_id: Uuid) -> Result<MountUsage, ServiceError> {
+        let upper = {
+            let mounts = self.mounts.read().await;
+            let entry = mounts
+                .get(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            entry.require_upper("report usage")?
+        };
+        let usage = tokio::task::spawn_blocking(move || upper.rescan_usage())
+            .await
+            .map_err(|e| ServiceError::Internal(format!("usage scan task failed: {}", e)))?
+            .map_err(|e| ServiceError::Internal(format!("failed to scan upper layer: {}", e)))?;
+        Ok(MountUsage { mount_id, usage })
+    }
+
+    async fn cl_progress(&self, mount_id: Uuid) -> Result<ClBuildProgress, ServiceError> {
+        if let Some(progress) = self.cl_progress.read().await.get(&mount_id) {
+            return Ok(progress.snapshot());
+        }
+        if !self.mounts.read().await.contains_key(&mount_id) {
+            return Err(ServiceError::NotFound(mount_id));
+        }
+        Ok(ClBuildProgress {
+            mount_id,
+            ..ClBuildProgress::default()
+        })
+    }
+
+    async fn check_mount_ready(&self, mount_id: Uuid) -> Result<MountReadyResponse, ServiceError> {
+        let state = self.describe_mount(mount_id).await?.state;
+        Ok(MountReadyResponse {
+            mount_id,
+            ready: state == MountLifecycle::Ready,
+            state,
+        })
+    }
+
+    fn subscribe_events(&self) -> broadcast::Receiver<MountEvent> {
+        self.events.subscribe()
+    }
+
+    async fn collect_garbage(&self, request: GcRequest) -> Result<GcReport, ServiceError> {
+        self.collect_garbage_impl(request).await
+    }
+
+    async fn health_info(&self) -> HealthResponse {
+        self.health_info_impl().await
+    }
+
+    async fn shutdown_cleanup(&self) -> Result<(), ServiceError> {
+        self.shutdown_cleanup_impl().await
+    }
+}
+
+/// What the background kernel-cache warmup touches after a mount becomes ready.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DeepPreloadMode {
+    /// Traverse directories only (readdir/readdirplus-driven).
+    #[serde(rename = "scan", alias = "scan_only")]
+    ScanOnly,
+    /// Touch metadata for every entry.
+    #[serde(rename = "full")]
+    Full,
+    /// Touch metadata for directories and Buck/Bazel hot files.
+    #[serde(rename = "hotset")]
+    Hotset,
+    /// Touch metadata for directories only.
+    #[serde(rename = "dirs", alias = "dirs_only")]
+    DirsOnly,
+}
+
+impl DeepPreloadMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            DeepPreloadMode::ScanOnly => "scan_only",
+            DeepPreloadMode::Full => "full",
+            DeepPreloadMode::Hotset => "hotset",
+            DeepPreloadMode::DirsOnly => "dirs_only",
+        }
+    }
+}
+
+/// Effective settings for one deep-preload walk.
+#[derive(Debug, Clone, Copy)]
+struct DeepPreloadOptions {
+    mode: DeepPreloadMode,
+    workers: usize,
+    max_depth: usize,
+    max_duration: Option<Duration>,
+}
+
+impl DeepPreloadOptions {
+    fn from_env() -> Self {
+        Self {
+            mode: deep_preload_mode(),
+            workers: deep_preload_worker_count(),
+            max_depth: deep_preload_max_depth(),
+            max_duration: deep_preload_max_duration(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DeepPreloadStats {
+    entries_visited: usize,
+    metadata_touches: usize,
+    budget_exhausted: bool,
+}
+
+fn deep_preload_mode() -> DeepPreloadMode {
+    match std::env::var("ANTARES_DEEP_PRELOAD_MODE") {
+        Ok(raw) => {
+            let normalized = raw.trim().to_ascii_lowercase();
+            match normalized.as_str() {
+                "scan" | "scan_only" | "readdirplus" => DeepPreloadMode::ScanOnly,
+                "full" => DeepPreloadMode::Full,
+                "dirs" | "dirs_only" => DeepPreloadMode::DirsOnly,
+                "hotset" | "" => DeepPreloadMode::Hotset,
+                _ => {
+                    tracing::warn!(
+                        value = %raw,
+                        "invalid ANTARES_DEEP_PRELOAD_MODE, expected one of: scan|hotset|full|dirs"
+                    );
+                    DeepPreloadMode::ScanOnly
+                }
+            }
+        }
+        Err(_) => DeepPreloadMode::ScanOnly,
+    }
+}
+
+fn deep_preload_should_touch_metadata(
+    mode: DeepPreloadMode,
+    file_type: &std::fs::FileType,
+    path: &Path,
+) -> bool {
+    match mode {
+        DeepPreloadMode::ScanOnly => false,
+        DeepPreloadMode::Full => true,
+        DeepPreloadMode::DirsOnly => file_type.is_dir(),
+        DeepPreloadMode::Hotset => {
+            if file_type.is_dir() {
+                return true;
+            }
+            let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+            if matches!(
+                name,
+                "BUCK"
+                    | "BUCK.v2"
+                    | "BUILD"
+                    | "BUILD.bazel"
+                    | "PACKAGE"
+                    | "TARGETS"
+                    | "TARGETS.v2"
+                    | "WORKSPACE"
+                    | "WORKSPACE.bazel"
+                    | ".buckconfig"
+            ) {
+                return true;
+            }
+            matches!(
+                path.extension().and_then(|s| s.to_str()),
+                Some("bzl") | Some("bxl")
+            )
+        }
+    }
+}
+
+fn deep_preload_worker_count() -> usize {
+    let default_workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .clamp(2, 8);
+    match std::env::var("ANTARES_DEEP_PRELOAD_WORKERS") {
+        Ok(raw) => match raw.trim().parse::<usize>() {
+            Ok(n) => n.clamp(1, 64),
+            Err(_) => {
+                tracing::warn!(
+                    value = %raw,
+                    default_workers,
+                    "invalid ANTARES_DEEP_PRELOAD_WORKERS, using default"
+                );
+                default_workers
+            }
+        },
+        Err(_) => default_workers,
+    }
+}
+
+fn deep_preload_max_duration() -> Option<Duration> {
+    const DEFAULT_MS: u64 = 8_000;
+    match std::env::var("ANTARES_DEEP_PRELOAD_MAX_MS") {
+        Ok(raw) => match raw.trim().parse::<u64>() {
+            Ok(0) => None,
+            Ok(ms) => Some(Duration::from_millis(ms.min(120_000))),
+            Err(_) => {
+                tracing::warn!(
+                    value = %raw,
+                    default_ms = DEFAULT_MS,
+                    "invalid ANTARES_DEEP_PRELOAD_MAX_MS, using default"
+                );
+                Some(Duration::from_millis(DEFAULT_MS))
+            }
+        },
+        Err(_) => Some(Duration::from_millis(DEFAULT_MS)),
+    }
+}
+
+fn deep_preload_max_depth() -> usize {
+    const DEFAULT_DEPTH: usize = 4;
+    match std::env::var("ANTARES_DEEP_PRELOAD_MAX_DEPTH") {
+        Ok(raw) => match raw.trim().parse::<usize>() {
+            Ok(depth) => depth.min(64),
+            Err(_) => {
+                tracing::warn!(
+                    value = %raw,
+                    default_depth = DEFAULT_DEPTH,
+                    "invalid ANTARES_DEEP_PRELOAD_MAX_DEPTH, using default"
+                );
+                DEFAULT_DEPTH
+            }
+        },
+        Err(_) => DEFAULT_DEPTH,
+    }
+}
+
+/// Walk a directory tree with bounded parallelism to warm FUSE kernel caches.
+///
+/// Strategy is configurable:
+/// - `scan` (default): traverse directories only (readdir/readdirplus-driven)
+/// - `hotset`: touch metadata for directories + Buck hot files
+/// - `dirs`: touch metadata only for directories
+/// - `full`: touch metadata for every entry (most expensive)
+/// - `ANTARES_DEEP_PRELOAD_MAX_MS`: cap total background warmup time
+///
+/// `opts` carries the environment defaults, possibly overridden per mount.
+fn deep_preload_walk(
+    root: &str,
+    opts: DeepPreloadOptions,
+    cancel: &AtomicBool,
+) -> std::io::Result<DeepPreloadStats> {
+    use std::fs;
+
+    #[derive(Default)]
+    struct WalkState {
+        queue: VecDeque<(PathBuf, usize)>,
+        in_flight: usize,
+        done: bool,
+    }
+
+    // Bounded by default, but override-able for host-specific or per-mount tuning.
+    let DeepPreloadOptions {
+        mode,
+        workers,
+        max_depth,
+        max_duration,
+    } = opts;
+    tracing::info!(
+        root = root,
+        workers,
+        mode = mode.as_str(),
+        max_depth,
+        max_ms = max_duration.map(|d| d.as_millis()),
+        "deep_preload_walk: start"
+    );
+    let started_at = Instant::now();
+    let root_path = PathBuf::from(root);
+    let total_entries = Arc::new(AtomicUsize::new(0));
+    let total_touches = Arc::new(AtomicUsize::new(0));
+    let budget_exhausted = Arc::new(AtomicBool::new(false));
+    let state = Arc::new((
+        Mutex::new(WalkState {
+            queue: VecDeque::from([(root_path, 0)]),
+            in_flight: 0,
+            done: false,
+        }),
+        Condvar::new(),
+    ));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let state = Arc::clone(&state);
+            let total_entries = Arc::clone(&total_entries);
+            let total_touches = Arc::clone(&total_touches);
+            let budget_exhausted = Arc::clone(&budget_exhausted);
+            scope.spawn(move || {
+                let mut local_entries = 0usize;
+                let mut local_touches = 0usize;
+
+                loop {
+                    if let Some(max_dur) = max_duration {
+                        if started_at.elapsed() >= max_dur {
+                            budget_exhausted.store(true, Ordering::Relaxed);
+                            let (lock, cv) = &*state;
+                            let mut guard = lock.lock().expect("deep_preload_walk lock poisoned");
+                            guard.done = true;
+                            cv.notify_all();
+                            break;
+                        }
+                    }
+                    if cancel.load(Ordering::Relaxed) {
+                        let (lock, cv) = &*state;
+                        let mut guard = lock.lock().expect("deep_preload_walk lock poisoned");
+                        guard.done = true;
+                        cv.notify_all();
+                        break;
+                    }
+
+                    let dir = {
+                        let (lock, cv) = &*state;
+                        let mut guard = lock.lock().expect("deep_preload_walk lock poisoned");
+                        loop {
+                            if guard.done {
+                                break None;
+                            }
+                            if let Some(dir) = guard.queue.pop_front() {
+                                guard.in_flight += 1;
+                                break Some(dir);
+                            }
+                            if guard.in_flight == 0 {
+                                guard.done = true;
+                                cv.notify_all();
+                                break None;
+                            }
+                            guard = cv.wait(guard).expect("deep_preload_walk lock poisoned");
+                        }
+                    };
+
+                    let Some((dir, depth)) = dir else {
+                        break;
+                    };
+
+                    let mut discovered_dirs: Vec<(PathBuf, usize)> = Vec::new();
+                    let entries = match fs::read_dir(&dir) {
+                        Ok(entries) => entries,
+                        Err(e) => {
+                            tracing::warn!(dir = ?dir, error = %e, "deep_preload_walk: read_dir failed");
+                            let (lock, cv) = &*state;
+                            let mut guard = lock.lock().expect("deep_preload_walk lock poisoned");
+                            guard.in_flight = guard.in_flight.saturating_sub(1);
+                            if guard.queue.is_empty() && guard.in_flight == 0 {
+                                guard.done = true;
+                            }
+                            cv.notify_all();
+                            continue;
+                        }
+                    };
+
+                    for entry in entries {
+                        if let Some(max_dur) = max_duration {
+                            if started_at.elapsed() >= max_dur {
+                                budget_exhausted.store(true, Ordering::Relaxed);
+                                break;
+                            }
+                        }
+                        if cancel.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        let entry = match entry {
+                            Ok(e) => e,
+                            Err(e) => {
+                                tracing::warn!(dir = ?dir, error = %e, "deep_preload_walk: entry error");
+                                continue;
+                            }
+                        };
+
+                        let path = entry.path();
+                        let file_type = match entry.file_type() {
+                            Ok(ft) => ft,
+                            Err(e) => {
+                                tracing::warn!(
+                                    path = ?path,
+                                    error = %e,
+                                    "deep_preload_walk: file_type error"
+                                );
+                                continue;
+                            }
+                        };
+                        local_entries += 1;
+
+                        if file_type.is_dir() && depth < max_depth {
+                            discovered_dirs.push((path.clone(), depth + 1));
+                        }
+
+                        if deep_preload_should_touch_metadata(mode, &file_type, &path) {
+                            // Touch metadata to warm FUSE attr cache for selected hot paths.
+                            let _ = entry.metadata();
+                            local_touches += 1;
+                        }
+                    }
+
+                    let (lock, cv) = &*state;
+                    let mut guard = lock.lock().expect("deep_preload_walk lock poisoned");
+                    for subdir in discovered_dirs {
+                        guard.queue.push_back(subdir);
+                    }
+                    guard.in_flight = guard.in_flight.saturating_sub(1);
+                    if guard.queue.is_empty() && guard.in_flight == 0 {
+                        guard.done = true;
+                    }
+                    cv.notify_all();
+                }
+
+                total_entries.fetch_add(local_entries, Ordering::Relaxed);
+                total_touches.fetch_add(local_touches, Ordering::Relaxed);
+            });
+        }
+    });
+
+    let stats = DeepPreloadStats {
+        entries_visited: total_entries.load(Ordering::Relaxed),
+        metadata_touches: total_touches.load(Ordering::Relaxed),
+        budget_exhausted: budget_exhausted.load(Ordering::Relaxed),
+    };
+    if stats.budget_exhausted {
+        tracing::info!(
+            root = root,
+            visited = stats.entries_visited,
+            metadata_touches = stats.metadata_touches,
+            "deep_preload_walk: time budget exhausted"
+        );
+    }
+    if cancel.load(Ordering::Relaxed) {
+        tracing::info!(
+            root = root,
+            visited = stats.entries_visited,
+            metadata_touches = stats.metadata_touches,
+            "deep_preload_walk: cancelled"
+        );
+    }
+    Ok(stats)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use futures::future::join_all;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    /// Mock service for testing HTTP layer without actual FUSE operations
+    struct MockAntaresService {
+        mounts: Arc<RwLock<HashMap<Uuid, MountStatus>>>,
+        checkpoints: Arc<RwLock<HashMap<Uuid, Vec<CheckpointInfo>>>>,
+        events: MountEvents,
+    }
+
+    impl MockAntaresService {
+        fn new() -> Self {
+            Self {
+                mounts: Arc::new(RwLock::new(HashMap::new())),
+                checkpoints: Arc::new(RwLock::new(HashMap::new())),
+                events: MountEvents::new(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AntaresService for MockAntaresService {
+        async fn create_mount(
+            &self,
+            request: CreateMountRequest,
+        ) -> Result<MountCreated, ServiceError> {
+            let cls = cl_stack_from(request.cl.as_deref(), &request.cls)?;
+            AntaresServiceImpl::validate_request(&request)?;
+            let bindings = AntaresServiceImpl::normalize_bindings(&request.bindings)?;
+
+            let task_id = request.job_id.clone().or(request.build_id.clone());
+
+            // Idempotency / de-dup policy:
+            // - If task_id is provided: idempotent per task id.
+            // - Otherwise: legacy behavior, reject duplicate (path, cl).
+            if let Some(ref job_id) = task_id {
+                let mounts = self.mounts.read().await;
+                if let Some(existing) = mounts
+                    .values()
+                    .find(|m| m.job_id.as_deref() == Some(job_id))
+                {
+                    if existing.path != request.path
+                        || existing.bindings != bindings
+                        || existing.cls != cls
+                    {
+                        return Err(ServiceError::InvalidRequest(format!(
+                            "job_id/build_id '{}' already mounted with different path/cl",
+                            job_id
+                        )));
+                    }
+                    if !matches!(
+                        existing.state,
+                        MountLifecycle::Mounted | MountLifecycle::Ready
+                    ) {
+                        return Err(ServiceError::InvalidRequest(format!(
+                            "job_id/build_id '{}' is currently in state {:?}; retry after unmount completes",
+                            job_id, existing.state
+                        )));
+                    }
+                    return Ok(MountCreated {
+                        mount_id: existing.mount_id,
+                        mountpoint: existing.mountpoint.clone(),
+                        state: existing.state.clone(),
+                    });
+                }
+            } else {
+                let mounts = self.mounts.read().await;
+                if mounts
+                    .values()
+                    .any(|m| m.path == request.path && m.bindings == bindings && m.cls == cls)
+                {
+                    return Err(ServiceError::InvalidRequest(format!(
+                        "path {} with cls {:?} is already mounted",
+                        request.path, cls
+                    )));
+                }
+            }
+
+            // Auto-generate paths based on UUID
+            let mount_id = Uuid::new_v4();
+            let id_str = mount_id.to_string();
+            let mountpoint = request
+                .mountpoint
+                .clone()
+                .unwrap_or_else(|| format!("/tmp/mock_mnt/{}", id_str));
+            if request.read_only && request.upper == UpperKind::Memory {
+                return Err(ServiceError::InvalidRequest(
+                    "read-only mounts have no upper layer; omit upper".into(),
+                ));
+            }
+            let upper_kind = (!request.read_only).then_some(request.upper);
+            let upper_dir = (upper_kind == Some(UpperKind::Disk))
+                .then(|| format!("/tmp/mock_upper/{}", id_str));
+            let cl_dirs: Vec<String> = (0..cls.len())
+                .map(|i| cl_layer_dir("/tmp/mock_cl", &id_str, i))
+                .collect();
+
+            let limits = request.quota.unwrap_or_default();
+            let status = MountStatus {
+                mount_id,
+                job_id: task_id.clone(),
+                path: request.path,
+                bindings,
+                cl: cls.last().cloned(),
+                cls,
+                cl_conflicts: Vec::new(),
+                mountpoint: mountpoint.clone(),
+                forked_from: None,
+                read_only: request.read_only,
+                upper_kind,
+                usage: (!request.read_only).then(|| UpperUsage {
+                    max_bytes: limits.max_bytes,
+                    max_inodes: limits.max_inodes,
+                    ..Default::default()
+                }),
+                tuning: request.tuning,
+                callback_url: request.callback_url,
+                created_by: request.created_by,
+                labels: request.labels,
+                layers: MountLayers {
+                    upper: upper_dir,
+                    cl: cl_dirs.last().cloned(),
+                    cl_dirs,
+                    dicfuse: "mock".into(),
+                },
+                // Background creations stay provisioning; tests drive them by hand.
+                state: if request.background {
+                    MountLifecycle::Provisioning
+                } else {
+                    MountLifecycle::Ready
+                },
+                created_at_epoch_ms: 0,
+                last_seen_epoch_ms: 0,
+            };
+            let state = status.state.clone();
+            self.mounts.write().await.insert(mount_id, status);
+            self.events.emit(
+                mount_id,
+                MountEventKind::State {
+                    state: state.clone(),
+                },
+            );
+
+            Ok(MountCreated {
+                mount_id,
+                mountpoint,
+                state,
+            })
+        }
+
+        async fn list_mounts(&self) -> Result<Vec<MountStatus>, ServiceError> {
+            Ok(self.mounts.read().await.values().cloned().collect())
+        }
+
+        async fn describe_mount(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
+            self.mounts
+                .read()
+                .await
+                .get(&mount_id)
+                .cloned()
+                .ok_or(ServiceError::NotFound(mount_id))
+        }
+
+        async fn delete_mount(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
+            let status = self
+                .mounts
+                .write()
+                .await
+                .remove(&mount_id)
+                .map(|mut s| {
+                    s.state = MountLifecycle::Unmounted;
+                    s
+                })
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            self.events.emit(mount_id, MountEventKind::Removed);
+            Ok(status)
+        }
+
+        async fn build_cl(
+            &self,
+            mount_id: Uuid,
+            cls: Vec<String>,
+        ) -> Result<MountStatus, ServiceError> {
+            if cls.is_empty() {
+                return Err(ServiceError::InvalidRequest(
+                    "at least one cl is required; use DELETE to clear CL layers".into(),
+                ));
+            }
+            let mut mounts = self.mounts.write().await;
+            let status = mounts
+                .get_mut(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            if !matches!(
+                status.state,
+                MountLifecycle::Mounted | MountLifecycle::Ready
+            ) {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "mount {} is currently in state {:?}; cannot build CL",
+                    mount_id, status.state
+                )));
+            }
+            let id_str = mount_id.to_string();
+            status.layers.cl_dirs = (0..cls.len())
+                .map(|i| cl_layer_dir("/tmp/mock_cl", &id_str, i))
+                .collect();
+            status.layers.cl = status.layers.cl_dirs.last().cloned();
+            status.cl = cls.last().cloned();
+            status.cls = cls;
+            Ok(status.clone())
+        }
+
+        async fn build_local_cl(
+            &self,
+            mount_id: Uuid,
+            request: LocalClRequest,
+        ) -> Result<MountStatus, ServiceError> {
+            let name = request.cl_name()?;
+            request.parse()?;
+            self.build_cl(mount_id, vec![name]).await
+        }
+
+        async fn clear_cl(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
+            let mut mounts = self.mounts.write().await;
+            let status = mounts
+                .get_mut(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            if !matches!(
+                status.state,
+                MountLifecycle::Mounted | MountLifecycle::Ready
+            ) {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "mount {} is currently in state {:?}; cannot clear CL",
+                    mount_id, status.state
+                )));
+            }
+            if status.cl.is_none() {
+                return Err(ServiceError::InvalidRequest(
+                    "mount has no CL layer to clear".into(),
+                ));
+            }
+            status.cl = None;
+            status.cls.clear();
+            status.cl_conflicts.clear();
+            status.layers.cl = None;
+            status.layers.cl_dirs.clear();
+            Ok(status.clone())
+        }
+
+        async fn fork_mount(
+            &self,
+            mount_id: Uuid,
+            request: ForkMountRequest,
+        ) -> Result<MountCreated, ServiceError> {
+            let mut mounts = self.mounts.write().await;
+            let source = mounts
+                .get(&mount_id)
+                .cloned()
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            if !matches!(
+                source.state,
+                MountLifecycle::Mounted | MountLifecycle::Ready
+            ) {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "mount {} is currently in state {:?}; cannot fork",
+                    mount_id, source.state
+                )));
+            }
+            if source.read_only {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "mount {} is read-only and has no upper layer; cannot fork",
+                    mount_id
+                )));
+            }
+            if let Some(ref job_id) = request.job_id {
+                if mounts
+                    .values()
+                    .any(|m| m.job_id.as_deref() == Some(job_id.as_str()))
+                {
+                    return Err(ServiceError::InvalidRequest(format!(
+                        "job_id/build_id '{}' is already mounted",
+                        job_id
+                    )));
+                }
+            }
+
+            let mut labels = source.labels.clone();
+            labels.extend(request.labels);
+            labels::validate(&labels).map_err(ServiceError::InvalidRequest)?;
+
+            let fork_id = Uuid::new_v4();
+            let mountpoint = format!("/tmp/mock_mnt/{}", fork_id);
+            let status = MountStatus {
+                mount_id: fork_id,
+                job_id: request.job_id,
+                mountpoint: mountpoint.clone(),
+                forked_from: Some(mount_id),
+                layers: MountLayers {
+                    upper: source
+                        .layers
+                        .upper
+                        .as_ref()
+                        .map(|_| format!("/tmp/mock_upper/{}", fork_id)),
+                    cl: source.layers.cl.as_ref().map(|_| {
+                        cl_layer_dir("/tmp/mock_cl", &fork_id.to_string(), source.cls.len() - 1)
+                    }),
+                    cl_dirs: (0..source.cls.len())
+                        .map(|i| cl_layer_dir("/tmp/mock_cl", &fork_id.to_string(), i))
+                        .collect(),
+                    dicfuse: "mock".into(),
+                },
+                state: MountLifecycle::Ready,
+                callback_url: None,
+                created_by: request.created_by,
+                labels,
+                ..source
+            };
+            mounts.insert(fork_id, status);
+
+            Ok(MountCreated {
+                mount_id: fork_id,
+                mountpoint,
+                state: MountLifecycle::Ready,
+            })
+        }
+
+        async fn create_checkpoint(
+            &self,
+            mount_id: Uuid,
+            request: CreateCheckpointRequest,
+        ) -> Result<CheckpointInfo, ServiceError> {
+            let mounts = self.mounts.read().await;
+            let status = mounts
+                .get(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            if !matches!(
+                status.state,
+                MountLifecycle::Mounted | MountLifecycle::Ready
+            ) {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "mount {} is currently in state {:?}; cannot checkpoint",
+                    mount_id, status.state
+                )));
+            }
+            if status.read_only {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "mount {} is read-only and has no upper layer; cannot checkpoint",
+                    mount_id
+                )));
+            }
+            let info = CheckpointInfo {
+                checkpoint_id: Uuid::new_v4(),
+                mount_id,
+                label: request.label,
+                cl: status.cl.clone(),
+                size_bytes: 0,
+                created_at_epoch_ms: 0,
+            };
+            self.checkpoints
+                .write()
+                .await
+                .entry(mount_id)
+                .or_default()
+                .push(info.clone());
+            Ok(info)
+        }
+
+        async fn list_checkpoints(
+            &self,
+            mount_id: Uuid,
+        ) -> Result<CheckpointCollection, ServiceError> {
+            if !self.mounts.read().await.contains_key(&mount_id) {
+                return Err(ServiceError::NotFound(mount_id));
+            }
+            Ok(CheckpointCollection {
+                mount_id,
+                checkpoints: self
+                    .checkpoints
+                    .read()
+                    .await
+                    .get(&mount_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                used_bytes: 0,
+                limit_bytes: u64::MAX,
+            })
+        }
+
+        async fn delete_checkpoint(
+            &self,
+            mount_id: Uuid,
+            checkpoint_id: Uuid,
+        ) -> Result<CheckpointInfo, ServiceError> {
+            let mut checkpoints = self.checkpoints.write().await;
+            let list = checkpoints
+                .get_mut(&mount_id)
+                .ok_or(ServiceError::CheckpointNotFound(checkpoint_id))?;
+            let pos = list
+                .iter()
+                .position(|c| c.checkpoint_id == checkpoint_id)
+                .ok_or(ServiceError::CheckpointNotFound(checkpoint_id))?;
+            Ok(list.remove(pos))
+        }
+
+        async fn restore_checkpoint(
+            &self,
+            mount_id: Uuid,
+            checkpoint_id: Uuid,
+        ) -> Result<MountStatus, ServiceError> {
+            let known = self
+                .checkpoints
+                .read()
+                .await
+                .get(&mount_id)
+                .is_some_and(|l| l.iter().any(|c| c.checkpoint_id == checkpoint_id));
+            if !known {
+                return Err(ServiceError::CheckpointNotFound(checkpoint_id));
+            }
+            self.describe_mount(mount_id).await
+        }
+
+        async fn health_info(&self) -> HealthResponse {
+            let mounts = self.mounts.read().await;
+            HealthResponse {
+                status: "healthy".to_string(),
+                mount_count: mounts.len(),
+                failed_mounts: 0,
+                uptime_secs: 0,
+            }
+        }
+
+        async fn collect_garbage(&self, request: GcRequest) -> Result<GcReport, ServiceError> {
+            Ok(GcReport {
+                dry_run: request.dry_run,
+                retention_secs: request.retention_secs.unwrap_or(3600),
+                ..GcReport::default()
+            })
+        }
+
+        async fn mount_usage(&self, mount_id: Uuid) -> Result<MountUsage, ServiceError> {
+            let mounts = self.mounts.read().await;
+            let status = mounts
+                .get(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            if status.read_only {
+                return Err(ServiceError::InvalidRequest(format!(
+                    "mount {} is read-only and has no upper layer; cannot report usage",
+                    mount_id
+                )));
+            }
+            Ok(MountUsage {
+                mount_id,
+                usage: status.usage.unwrap_or_default(),
+            })
+        }
+
+        async fn cl_progress(&self, mount_id: Uuid) -> Result<ClBuildProgress, ServiceError> {
+            let mounts = self.mounts.read().await;
+            let status = mounts
+                .get(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            // Mock builds finish synchronously, so any CL stack is a completed build.
+            let done = status.cls.len() as u64;
+            Ok(ClBuildProgress {
+                mount_id,
+                state: if done == 0 {
+                    ClBuildState::Idle
+                } else {
+                    ClBuildState::Succeeded
+                },
+                cls: status.cls.clone(),
+                layer: status.cls.len().saturating_sub(1),
+                files_total: done,
+                files_done: done,
+                ..ClBuildProgress::default()
+            })
+        }
+
+        async fn check_mount_ready(
+            &self,
+            mount_id: Uuid,
+        ) -> Result<MountReadyResponse, ServiceError> {
+            let mounts = self.mounts.read().await;
+            let status = mounts
+                .get(&mount_id)
+                .ok_or(ServiceError::NotFound(mount_id))?;
+            Ok(MountReadyResponse {
+                mount_id,
+                ready: status.state == MountLifecycle::Ready,
+                state: status.state.clone(),
+            })
+        }
+
+        fn subscribe_events(&self) -> broadcast::Receiver<MountEvent> {
+            self.events.subscribe()
+        }
+
+        async fn shutdown_cleanup(&self) -> Result<(), ServiceError> {
+            self.mounts.write().await.clear();
+            self.events.emit(Uuid::nil(), MountEventKind::Shutdown);
+            Ok(())
+        }
+    }
+
+    fn create_test_router() -> Router {
+        let service = Arc::new(MockAntaresService::new());
+        let daemon = AntaresDaemon::new(service);
+        daemon.router()
+    }
+
+    #[tokio::test]
+    async fn test_healthcheck() {
+        let app = create_test_router();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/health")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let health: HealthResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(health.status, "healthy");
+    }
+
+    #[tokio::test]
+    async fn test_create_mount_success() {
+        let app = create_test_router();
+
+        // Simplified request: only path and optional cl
+        let body = serde_json::json!({
+            "path": "/third-party/mega"
+        });
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: MountCreated = serde_json::from_slice(&body).unwrap();
+        // Mountpoint is auto-generated with UUID
+        assert!(created.mountpoint.starts_with("/tmp/mock_mnt/"));
+    }
+
+    #[tokio::test]
+    async fn test_mount_by_job_and_delete_by_job() {
+        let app = create_test_router();
+
+        let body = serde_json::json!({
+            "job_id": "job-1",
+            "path": "/third-party/mega",
+            "cl": "CL123"
+        });
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Describe by job_id
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri("/mounts/by-job/job-1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let status: MountStatus = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status.job_id.as_deref(), Some("job-1"));
+        assert_eq!(status.path, "/third-party/mega");
+        assert_eq!(status.cl.as_deref(), Some("CL123"));
+
+        // Delete by job_id
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/mounts/by-job/job-1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let deleted: MountStatus = serde_json::from_slice(&body).unwrap();
+        assert_eq!(deleted.job_id.as_deref(), Some("job-1"));
+        assert!(matches!(deleted.state, MountLifecycle::Unmounted));
+
+        // Now describe should be 404.
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri("/mounts/by-job/job-1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_list_mounts_empty() {
+        let app = create_test_router();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/mounts")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let collection: MountCollection = serde_json::from_slice(&body).unwrap();
+        assert!(collection.mounts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_collect_garbage_body_is_optional() {
+        let app = create_test_router();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/gc")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let report: GcReport = serde_json::from_slice(&body).unwrap();
+        assert!(!report.dry_run);
+        assert!(report.removed.is_empty());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/gc")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"dry_run":true,"retention_secs":60}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let report: GcReport = serde_json::from_slice(&body).unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.retention_secs, 60);
+    }
+
+    #[tokio::test]
+    async fn test_describe_nonexistent_mount_returns_404() {
+        let app = create_test_router();
+        let fake_id = Uuid::new_v4();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/mounts/{}", fake_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_error_response_format() {
+        let app = create_test_router();
+        let fake_id = Uuid::new_v4();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/mounts/{}", fake_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorBody = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(error.code, "NOT_FOUND");
+        assert!(error.error.contains(&fake_id.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_empty_path_rejected() {
+        let app = create_test_router();
+
+        let body = serde_json::json!({
+            "path": ""
+        });
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorBody = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.code, "INVALID_REQUEST");
+    }
+
+    #[tokio::test]
+    async fn test_create_mount_rejects_mountpoint_without_prefixes() {
+        let app = create_test_router();
+
+        // No `antares_mountpoint_prefixes` in the test config: caller-chosen mountpoints are off.
+        let body = serde_json::json!({
+            "path": "/third-party/mega",
+            "mountpoint": "/sandbox/job-1"
+        });
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorBody = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.code, "INVALID_REQUEST");
+        assert!(error.error.contains("antares_mountpoint_prefixes"));
+    }
+
+    #[test]
+    fn test_mountpoint_prefix_check() {
+        let prefixes = vec!["/sandbox".to_string(), "/build/mnt".to_string()];
+        for (mountpoint, allowed) in [
+            ("/sandbox/job-1", true),
+            ("/sandbox/job-1/", true),
+            ("/build/mnt/a/b", true),
+            ("/sandboxes/job-1", false),
+            ("/build/job-1", false),
+            ("/sandbox/../etc", false),
+            ("/sandbox/./job-1", true),
+            ("sandbox/job-1", false),
+        ] {
+            assert_eq!(
+                AntaresServiceImpl::check_mountpoint_prefix(mountpoint, &prefixes).is_ok(),
+                allowed,
+                "{mountpoint}"
+            );
+        }
+        assert!(AntaresServiceImpl::check_mountpoint_prefix("/sandbox/job-1", &[]).is_err());
+    }
+
+    #[test]
+    fn test_broken_mount_reason() {
+        let enotconn = std::io::Error::from_raw_os_error(libc::ENOTCONN);
+        let enoent = std::io::Error::from_raw_os_error(libc::ENOENT);
+
+        assert_eq!(
+            broken_mount_reason(true, None).as_deref(),
+            Some("FUSE session exited")
+        );
+        assert!(broken_mount_reason(false, Some(&enotconn))
+            .unwrap()
+            .starts_with("mountpoint is not connected"));
+        // Other probe errors (e.g. a lookup racing Dicfuse loading) are not breakage.
+        assert_eq!(broken_mount_reason(false, Some(&enoent)), None);
+        assert_eq!(broken_mount_reason(false, None), None);
+    }
+
+    #[tokio::test]
+    async fn test_probe_mountpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let timeout = Duration::from_secs(5);
+
+        assert!(probe_mountpoint(dir.path().to_path_buf(), timeout)
+            .await
+            .is_none());
+        let missing = probe_mountpoint(dir.path().join("missing"), timeout).await;
+        assert_eq!(
+            missing.map(|e| e.kind()),
+            Some(std::io::ErrorKind::NotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reclaim_mountpoint() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(reclaim_mountpoint(dir.path()).await, Ok(()));
+        assert_eq!(
+            reclaim_mountpoint(&dir.path().join("missing")).await,
+            Err("mountpoint no longer exists".to_string())
+        );
+    }
+
+    #[test]
+    fn test_prepare_caller_mountpoint() {
+        let tmp = tempfile::tempdir().unwrap();
+        let allowed = tmp.path().join("allowed");
+        let outside = tmp.path().join("outside");
+        std::fs::create_dir_all(&allowed).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+
+        // Missing directories are created; existing ones must be empty directories.
+        let fresh = allowed.join("fresh");
+        assert!(crate::server::prepare_mountpoint(&fresh).unwrap());
+        assert!(!crate::server::prepare_mountpoint(&fresh).unwrap());
+        std::fs::write(fresh.join("file"), b"x").unwrap();
+        assert!(crate::server::prepare_mountpoint(&fresh).is_err());
+        assert!(crate::server::prepare_mountpoint(&fresh.join("file")).is_err());
+
+        // A symlink under the prefix cannot lead the mountpoint out of it.
+        std::os::unix::fs::symlink(&outside, allowed.join("link")).unwrap();
+        let resolved = resolve_symlinks(&allowed.join("link/job")).unwrap();
+        let outside = std::fs::canonicalize(&outside).unwrap();
+        assert_eq!(resolved, outside.join("job"));
+        assert!(!resolved.starts_with(std::fs::canonicalize(&allowed).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_create_mount_with_cl() {
+        let app = create_test_router();
+
+        // Request with CL identifier
+        let body = serde_json::json!({
+            "path": "/third-party/mega",
+            "cl": "CL12345"
+        });
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_string(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_mount_requests() {
+        let service = Arc::new(MockAntaresService::new());
+
+        let handles: Vec<_> = (0..10)
+            .map(|i| {
+                let svc = service.clone();
+                tokio::spawn(async move {
+                    svc.create_mount(CreateMountRequest {
+                        job_id: None,
+                        build_id: None,
+                        path: format!("/project/path{}", i),
+                        cl: None,
+                        ..Default::default()
+                    })
+                    .await
+                })
+            })
+            .collect();
+
+        for h in handles {
+            assert!(h.await.unwrap().is_ok());
+        }
+
+        // All 10 mounts should exist
+        let mounts = service.list_mounts().await.unwrap();
+        assert_eq!(mounts.len(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_path_cl_rejected() {
+        let service = Arc::new(MockAntaresService::new());
+
+        let request = CreateMountRequest {
+            job_id: None,
+            build_id: None,
+            path: "/third-party/mega".into(),
+            cl: Some("CL123".into()),
+            ..Default::default()
+        };
+
+        // First mount should succeed
+        let result1 = service.create_mount(request.clone()).await;
+        assert!(result1.is_ok());
+
+        // Second mount with same path+cl should fail
+        let result2 = service.create_mount(request).await;
+        assert!(matches!(result2, Err(ServiceError::InvalidRequest(_))));
+    }
 
-#[cfg(test)]
-mod tests {
-    use axum::{
-        body::Body,
-        http::{Request, StatusCode},
-    };
-    use futures::future::join_all;
-    use tower::ServiceExt;
+    #[tokio::test]
+    async fn test_job_id_idempotent() {
+        let service = Arc::new(MockAntaresService::new());
 
-    use super::*;
+        let request = CreateMountRequest {
+            job_id: Some("job-123".into()),
+            build_id: None,
+            path: "/third-party/mega".into(),
+            cl: Some("CL123".into()),
+            ..Default::default()
+        };
 
-    /// Mock service for testing HTTP layer without actual FUSE operations
-    struct MockAntaresService {
-        mounts: Arc<RwLock<HashMap<Uuid, MountStatus>>>,
+        let first = service.create_mount(request.clone()).await.unwrap();
+        let second = service.create_mount(request).await.unwrap();
+
+        assert_eq!(first.mount_id, second.mount_id);
+        assert_eq!(first.mountpoint, second.mountpoint);
     }
 
-    impl MockAntaresService {
-        fn new() -> Self {
-            Self {
-                mounts: Arc::new(RwLock::new(HashMap::new())),
-            }
+    #[tokio::test]
+    async fn test_job_id_idempotent_rejected_when_unmounting() {
+        let service = Arc::new(MockAntaresService::new());
+
+        let request = CreateMountRequest {
+            job_id: Some("job-123".into()),
+            build_id: None,
+            path: "/third-party/mega".into(),
+            cl: Some("CL123".into()),
+            ..Default::default()
+        };
+
+        let first = service.create_mount(request.clone()).await.unwrap();
+
+        // Simulate a concurrent teardown where job_id is still present but mount is unmounting.
+        {
+            let mut mounts = service.mounts.write().await;
+            let s = mounts.get_mut(&first.mount_id).unwrap();
+            s.state = MountLifecycle::Unmounting;
         }
+
+        let second = service.create_mount(request).await;
+        assert!(matches!(second, Err(ServiceError::InvalidRequest(_))));
     }
 
-    #[async_trait]
-    impl AntaresService for MockAntaresService {
-        async fn create_mount(
-            &self,
-            request: CreateMountRequest,
-        ) -> Result<MountCreated, ServiceError> {
-            if request.path.is_empty() {
-                return Err(ServiceError::InvalidRequest("path cannot be empty".into()));
-            }
+    #[tokio::test]
+    async fn test_same_path_cl_different_job_id_allowed() {
+        let service = Arc::new(MockAntaresService::new());
 
-            let task_id = request.job_id.clone().or(request.build_id.clone());
+        let req1 = CreateMountRequest {
+            job_id: Some("job-a".into()),
+            build_id: None,
+            path: "/third-party/mega".into(),
+            cl: Some("CL123".into()),
+            ..Default::default()
+        };
+        let req2 = CreateMountRequest {
+            job_id: Some("job-b".into()),
+            build_id: None,
+            path: "/third-party/mega".into(),
+            cl: Some("CL123".into()),
+            ..Default::default()
+        };
 
-            // Idempotency / de-dup policy:
-            // - If task_id is provided: idempotent per task id.
-            // - Otherwise: legacy behavior, reject duplicate (path, cl).
-            if let Some(ref job_id) = task_id {
-                let mounts = self.mounts.read().await;
-                if let Some(existing) = mounts
-                    .values()
-                    .find(|m| m.job_id.as_deref() == Some(job_id))
-                {
-                    if existing.path != request.path || existing.cl != request.cl {
-                        return Err(ServiceError::InvalidRequest(format!(
-                            "job_id/build_id '{}' already mounted with different path/cl",
-                            job_id
-                        )));
-                    }
-                    if !matches!(
-                        existing.state,
-                        MountLifecycle::Mounted | MountLifecycle::Ready
-                    ) {
-                        return Err(ServiceError::InvalidRequest(format!(
-                            "job_id/build_id '{}' is currently in state {:?}; retry after unmount completes",
-                            job_id, existing.state
-                        )));
-                    }
-                    return Ok(MountCreated {
-                        mount_id: existing.mount_id,
-                        mountpoint: existing.mountpoint.clone(),
-                    });
-                }
-            } else {
-                let mounts = self.mounts.read().await;
-                if mounts
-                    .values()
-                    .any(|m| m.path == request.path && m.cl == request.cl)
-                {
-                    return Err(ServiceError::InvalidRequest(format!(
-                        "path {} with cl {:?} is already mounted",
-                        request.path, request.cl
-                    )));
-                }
-            }
+        let r1 = service.create_mount(req1).await;
+        let r2 = service.create_mount(req2).await;
+        assert!(r1.is_ok());
+        assert!(r2.is_ok());
 
-            // Auto-generate paths based on UUID
-            let mount_id = Uuid::new_v4();
-            let id_str = mount_id.to_string();
-            let mountpoint = format!("/tmp/mock_mnt/{}", id_str);
-            let upper_dir = format!("/tmp/mock_upper/{}", id_str);
-            let cl_dir = request
-                .cl
-                .as_ref()
-                .map(|_| format!("/tmp/mock_cl/{}", id_str));
+        let mounts = service.list_mounts().await.unwrap();
+        assert_eq!(mounts.len(), 2);
+    }
 
-            let status = MountStatus {
-                mount_id,
-                job_id: task_id.clone(),
-                path: request.path,
-                cl: request.cl,
-                mountpoint: mountpoint.clone(),
-                layers: MountLayers {
-                    upper: upper_dir,
-                    cl: cl_dir,
-                    dicfuse: "mock".into(),
-                },
-                state: MountLifecycle::Ready,
-                created_at_epoch_ms: 0,
-                last_seen_epoch_ms: 0,
-            };
-            self.mounts.write().await.insert(mount_id, status);
+    #[tokio::test]
+    async fn test_delete_mount_success() {
+        let service = Arc::new(MockAntaresService::new());
 
-            Ok(MountCreated {
-                mount_id,
-                mountpoint,
+        // Create a mount
+        let created = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/third-party/mega".into(),
+                cl: None,
+                ..Default::default()
             })
-        }
+            .await
+            .unwrap();
 
-        async fn list_mounts(&self) -> Result<Vec<MountStatus>, ServiceError> {
-            Ok(self.mounts.read().await.values().cloned().collect())
-        }
+        let mount_id = created.mount_id;
 
-        async fn describe_mount(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
-            self.mounts
-                .read()
-                .await
-                .get(&mount_id)
-                .cloned()
-                .ok_or(ServiceError::NotFound(mount_id))
-        }
+        // Delete it
+        let deleted = service.delete_mount(mount_id).await.unwrap();
+        assert!(matches!(deleted.state, MountLifecycle::Unmounted));
 
-        async fn delete_mount(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
-            self.mounts
-                .write()
-                .await
-                .remove(&mount_id)
-                .map(|mut s| {
-                    s.state = MountLifecycle::Unmounted;
-                    s
-                })
-                .ok_or(ServiceError::NotFound(mount_id))
-        }
+        // Verify it's gone
+        let result = service.describe_mount(mount_id).await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+    }
 
-        async fn build_cl(
-            &self,
-            mount_id: Uuid,
-            cl_link: String,
-        ) -> Result<MountStatus, ServiceError> {
-            let mut mounts = self.mounts.write().await;
-            let status = mounts
-                .get_mut(&mount_id)
-                .ok_or(ServiceError::NotFound(mount_id))?;
-            if !matches!(
-                status.state,
-                MountLifecycle::Mounted | MountLifecycle::Ready
-            ) {
-                return Err(ServiceError::InvalidRequest(format!(
-                    "mount {} is currently in state {:?}; cannot build CL",
-                    mount_id, status.state
-                )));
-            }
-            status.cl = Some(cl_link);
-            status.layers.cl = Some(format!("/tmp/mock_cl/{}", mount_id));
-            Ok(status.clone())
-        }
+    #[tokio::test]
+    async fn test_same_path_different_cl_allowed() {
+        let service = Arc::new(MockAntaresService::new());
 
-        async fn clear_cl(&self, mount_id: Uuid) -> Result<MountStatus, ServiceError> {
-            let mut mounts = self.mounts.write().await;
-            let status = mounts
-                .get_mut(&mount_id)
-                .ok_or(ServiceError::NotFound(mount_id))?;
-            if !matches!(
-                status.state,
-                MountLifecycle::Mounted | MountLifecycle::Ready
-            ) {
-                return Err(ServiceError::InvalidRequest(format!(
-                    "mount {} is currently in state {:?}; cannot clear CL",
-                    mount_id, status.state
-                )));
-            }
-            if status.cl.is_none() {
-                return Err(ServiceError::InvalidRequest(
-                    "mount has no CL layer to clear".into(),
-                ));
-            }
-            status.cl = None;
-            status.layers.cl = None;
-            Ok(status.clone())
+        // Mount with CL1
+        let result1 = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/third-party/mega".into(),
+                cl: Some("CL1".into()),
+                ..Default::default()
+            })
+            .await;
+        assert!(result1.is_ok());
+
+        // Mount with CL2 (same path, different CL) should succeed
+        let result2 = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/third-party/mega".into(),
+                cl: Some("CL2".into()),
+                ..Default::default()
+            })
+            .await;
+        assert!(result2.is_ok());
+
+        // Should have 2 mounts
+        let mounts = service.list_mounts().await.unwrap();
+        assert_eq!(mounts.len(), 2);
+    }
+
+    /// Test concurrent mount creation to verify thread safety.
+    /// This validates that multiple Antares instances can safely share
+    /// the same service and create mounts concurrently.
+    #[tokio::test]
+    async fn test_concurrent_mount_creation() {
+        let service = Arc::new(MockAntaresService::new());
+
+        // Spawn 10 concurrent mount creation tasks
+        let mut handles = Vec::new();
+        for i in 0..10 {
+            let svc = service.clone();
+            let handle = tokio::spawn(async move {
+                let request = CreateMountRequest {
+                    job_id: None,
+                    build_id: None,
+                    path: format!("/concurrent-path-{}", i),
+                    cl: None,
+                    ..Default::default()
+                };
+                svc.create_mount(request).await
+            });
+            handles.push(handle);
         }
 
-        async fn health_info(&self) -> HealthResponse {
-            let mounts = self.mounts.read().await;
-            HealthResponse {
-                status: "healthy".to_string(),
-                mount_count: mounts.len(),
-                uptime_secs: 0,
+        // Wait for all tasks to complete
+        let results: Vec<_> = join_all(handles).await;
+
+        // All should succeed
+        let mut success_count = 0;
+        for result in results {
+            match result {
+                Ok(Ok(_)) => success_count += 1,
+                Ok(Err(e)) => panic!("Mount creation failed: {:?}", e),
+                Err(e) => panic!("Task panicked: {:?}", e),
             }
         }
+        assert_eq!(success_count, 10, "All 10 concurrent mounts should succeed");
 
-        async fn check_mount_ready(
-            &self,
-            mount_id: Uuid,
-        ) -> Result<MountReadyResponse, ServiceError> {
-            let mounts = self.mounts.read().await;
-            let status = mounts
-                .get(&mount_id)
-                .ok_or(ServiceError::NotFound(mount_id))?;
-            Ok(MountReadyResponse {
-                mount_id,
-                ready: status.state == MountLifecycle::Ready,
-                state: status.state.clone(),
-            })
-        }
+        // Verify all mounts are listed
+        let mounts = service.list_mounts().await.unwrap();
+        assert_eq!(
+            mounts.len(),
+            10,
+            "Should have 10 mounts after concurrent creation"
+        );
 
-        async fn shutdown_cleanup(&self) -> Result<(), ServiceError> {
-            self.mounts.write().await.clear();
-            Ok(())
-        }
+        // Verify paths are unique
+        let paths: std::collections::HashSet<_> = mounts.iter().map(|m| m.path.clone()).collect();
+        assert_eq!(paths.len(), 10, "All paths should be unique");
     }
 
-    fn create_test_router() -> Router {
+    /// Test concurrent operations on the same mount.
+    #[tokio::test]
+    async fn test_concurrent_operations_same_mount() {
         let service = Arc::new(MockAntaresService::new());
-        let daemon = AntaresDaemon::new(service);
-        daemon.router()
+
+        // Create a mount
+        let request = CreateMountRequest {
+            job_id: None,
+            build_id: None,
+            path: "/test-concurrent-ops".to_string(),
+            cl: None,
+            ..Default::default()
+        };
+        let created = service.create_mount(request).await.unwrap();
+        let mount_id = created.mount_id;
+
+        // Spawn multiple concurrent describe operations
+        let mut handles = Vec::new();
+        for _ in 0..20 {
+            let svc = service.clone();
+            let id = mount_id;
+            let handle = tokio::spawn(async move { svc.describe_mount(id).await });
+            handles.push(handle);
+        }
+
+        // All describe operations should succeed
+        let results: Vec<_> = join_all(handles).await;
+        for result in results {
+            assert!(
+                result.is_ok() && result.unwrap().is_ok(),
+                "All describe operations should succeed"
+            );
+        }
     }
 
+    /// Test build_cl API - successfully add CL layer to mount
     #[tokio::test]
-    async fn test_healthcheck() {
-        let app = create_test_router();
+    async fn test_build_cl_success() {
+        let service = Arc::new(MockAntaresService::new());
 
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/health")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+        // Create a mount without CL
+        let created = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/third-party/mega".into(),
+                cl: None,
+                ..Default::default()
+            })
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::OK);
+        let mount_id = created.mount_id;
 
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        // Build CL layer
+        let status = service
+            .build_cl(mount_id, vec!["CL123".into()])
             .await
             .unwrap();
-        let health: HealthResponse = serde_json::from_slice(&body).unwrap();
-        assert_eq!(health.status, "healthy");
+        assert_eq!(status.cl, Some("CL123".into()));
+        assert!(status.layers.cl.is_some());
     }
 
     #[tokio::test]
-    async fn test_create_mount_success() {
-        let app = create_test_router();
-
-        // Simplified request: only path and optional cl
-        let body = serde_json::json!({
-            "path": "/third-party/mega"
-        });
+    async fn test_build_cl_rejected_when_unmounting() {
+        let service = Arc::new(MockAntaresService::new());
 
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/mounts")
-                    .header("content-type", "application/json")
-                    .body(Body::from(serde_json::to_string(&body).unwrap()))
-                    .unwrap(),
-            )
+        let created = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/third-party/mega".into(),
+                cl: None,
+                ..Default::default()
+            })
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::OK);
+        {
+            let mut mounts = service.mounts.write().await;
+            let s = mounts.get_mut(&created.mount_id).unwrap();
+            s.state = MountLifecycle::Unmounting;
+        }
 
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-            .await
-            .unwrap();
-        let created: MountCreated = serde_json::from_slice(&body).unwrap();
-        // Mountpoint is auto-generated with UUID
-        assert!(created.mountpoint.starts_with("/tmp/mock_mnt/"));
+        let result = service
+            .build_cl(created.mount_id, vec!["CL123".into()])
+            .await;
+        assert!(matches!(result, Err(ServiceError::InvalidRequest(_))));
     }
 
     #[tokio::test]
-    async fn test_mount_by_job_and_delete_by_job() {
-        let app = create_test_router();
-
-        let body = serde_json::json!({
-            "job_id": "job-1",
-            "path": "/third-party/mega",
-            "cl": "CL123"
-        });
+    async fn test_build_cl_rejected_when_quiescing() {
+        let service = Arc::new(MockAntaresService::new());
 
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/mounts")
-                    .header("content-type", "application/json")
-                    .body(Body::from(serde_json::to_string(&body).unwrap()))
-                    .unwrap(),
-            )
+        let created = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/third-party/mega".into(),
+                cl: None,
+                ..Default::default()
+            })
             .await
             .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
 
-        // Describe by job_id
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("GET")
-                    .uri("/mounts/by-job/job-1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-            .await
-            .unwrap();
-        let status: MountStatus = serde_json::from_slice(&body).unwrap();
-        assert_eq!(status.job_id.as_deref(), Some("job-1"));
-        assert_eq!(status.path, "/third-party/mega");
-        assert_eq!(status.cl.as_deref(), Some("CL123"));
+        {
+            let mut mounts = service.mounts.write().await;
+            let s = mounts.get_mut(&created.mount_id).unwrap();
+            s.state = MountLifecycle::Quiescing;
+        }
 
-        // Delete by job_id
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("DELETE")
-                    .uri("/mounts/by-job/job-1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-            .await
-            .unwrap();
-        let deleted: MountStatus = serde_json::from_slice(&body).unwrap();
-        assert_eq!(deleted.job_id.as_deref(), Some("job-1"));
-        assert!(matches!(deleted.state, MountLifecycle::Unmounted));
+        let result = service
+            .build_cl(created.mount_id, vec!["CL123".into()])
+            .await;
+        assert!(matches!(result, Err(ServiceError::InvalidRequest(_))));
+    }
 
-        // Now describe should be 404.
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("GET")
-                    .uri("/mounts/by-job/job-1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    /// Test build_cl API - mount not found
+    #[tokio::test]
+    async fn test_build_cl_not_found() {
+        let service = Arc::new(MockAntaresService::new());
+        let fake_id = Uuid::new_v4();
+
+        let result = service.build_cl(fake_id, vec!["CL123".into()]).await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
     }
 
+    /// Test clear_cl API - successfully clear CL layer
     #[tokio::test]
-    async fn test_list_mounts_empty() {
-        let app = create_test_router();
+    async fn test_clear_cl_success() {
+        let service = Arc::new(MockAntaresService::new());
 
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/mounts")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+        // Create a mount with CL
+        let created = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/third-party/mega".into(),
+                cl: Some("CL123".into()),
+                ..Default::default()
+            })
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::OK);
+        let mount_id = created.mount_id;
 
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-            .await
-            .unwrap();
-        let collection: MountCollection = serde_json::from_slice(&body).unwrap();
-        assert!(collection.mounts.is_empty());
+        // Clear CL layer
+        let status = service.clear_cl(mount_id).await.unwrap();
+        assert_eq!(status.cl, None);
+        assert!(status.layers.cl.is_none());
     }
 
+    /// Test clear_cl API - no CL layer to clear
     #[tokio::test]
-    async fn test_describe_nonexistent_mount_returns_404() {
-        let app = create_test_router();
-        let fake_id = Uuid::new_v4();
+    async fn test_clear_cl_no_layer() {
+        let service = Arc::new(MockAntaresService::new());
 
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .uri(format!("/mounts/{}", fake_id))
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+        // Create a mount without CL
+        let created = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/third-party/mega".into(),
+                cl: None,
+                ..Default::default()
+            })
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let mount_id = created.mount_id;
+
+        // Try to clear non-existent CL layer
+        let result = service.clear_cl(mount_id).await;
+        assert!(matches!(result, Err(ServiceError::InvalidRequest(_))));
     }
 
     #[tokio::test]
-    async fn test_error_response_format() {
-        let app = create_test_router();
-        let fake_id = Uuid::new_v4();
+    async fn test_clear_cl_rejected_when_quiescing() {
+        let service = Arc::new(MockAntaresService::new());
 
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .uri(format!("/mounts/{}", fake_id))
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+        let created = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/third-party/mega".into(),
+                cl: Some("CL123".into()),
+                ..Default::default()
+            })
             .await
             .unwrap();
 
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-            .await
-            .unwrap();
-        let error: ErrorBody = serde_json::from_slice(&body).unwrap();
+        {
+            let mut mounts = service.mounts.write().await;
+            let s = mounts.get_mut(&created.mount_id).unwrap();
+            s.state = MountLifecycle::Quiescing;
+        }
 
-        assert_eq!(error.code, "NOT_FOUND");
-        assert!(error.error.contains(&fake_id.to_string()));
+        let result = service.clear_cl(created.mount_id).await;
+        assert!(matches!(result, Err(ServiceError::InvalidRequest(_))));
     }
 
+    /// Test HTTP endpoint for build_cl
     #[tokio::test]
-    async fn test_empty_path_rejected() {
-        let app = create_test_router();
+    async fn test_http_build_cl() {
+        let service = Arc::new(MockAntaresService::new());
 
-        let body = serde_json::json!({
-            "path": ""
-        });
+        // First create a mount
+        let created = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/test/path".into(),
+                cl: None,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let daemon = AntaresDaemon::new(service);
+        let app = daemon.router();
 
         let response = app
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri("/mounts")
+                    .uri(format!("/mounts/{}/cl", created.mount_id))
                     .header("content-type", "application/json")
-                    .body(Body::from(serde_json::to_string(&body).unwrap()))
+                    .body(Body::from(r#"{"cl":"CL456"}"#))
                     .unwrap(),
             )
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.status(), StatusCode::OK);
 
         let body = axum::body::to_bytes(response.into_body(), usize::MAX)
             .await
             .unwrap();
-        let error: ErrorBody = serde_json::from_slice(&body).unwrap();
-        assert_eq!(error.code, "INVALID_REQUEST");
+        let status: MountStatus = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status.cl, Some("CL456".into()));
     }
 
+    /// Test building a CL layer from a local source over HTTP
     #[tokio::test]
-    async fn test_create_mount_with_cl() {
-        let app = create_test_router();
+    async fn test_http_build_local_cl() {
+        let service = Arc::new(MockAntaresService::new());
+        let created = service
+            .create_mount(CreateMountRequest {
+                path: "/test/path".into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let app = AntaresDaemon::new(service).router();
+        let post = |body: &'static str| {
+            let app = app.clone();
+            let uri = format!("/mounts/{}/cl", created.mount_id);
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(uri)
+                        .header("content-type", "application/json")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+            }
+        };
 
-        // Request with CL identifier
-        let body = serde_json::json!({
-            "path": "/third-party/mega",
-            "cl": "CL12345"
-        });
+        let response = post(r#"{"local":{"name":"wip","deleted":["/test/path/old.rs"]}}"#).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let status: MountStatus = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status.cls, vec!["wip".to_string()]);
+
+        for body in [
+            r#"{"cl":"CL1","local":{"deleted":["/a"]}}"#,
+            r#"{"local":{}}"#,
+            r#"{"local":{"name":" ","deleted":["/a"]}}"#,
+            r#"{"local":{"archive":"not base64!"}}"#,
+            r#"{"local":{"patch":"--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n a\n"}}"#,
+        ] {
+            let response = post(body).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{body}");
+        }
+    }
+
+    /// Test CL build progress reporting over HTTP
+    #[tokio::test]
+    async fn test_http_cl_progress() {
+        let service = Arc::new(MockAntaresService::new());
+        let created = service
+            .create_mount(CreateMountRequest {
+                path: "/test/path".into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let app = AntaresDaemon::new(service).router();
+
+        let get_progress = |uri: String| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let response = get_progress(format!("/mounts/{}/cl/progress", created.mount_id)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let progress: ClBuildProgress = serde_json::from_slice(&body).unwrap();
+        assert_eq!(progress.state, ClBuildState::Idle);
 
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri("/mounts")
+                    .uri(format!("/mounts/{}/cl", created.mount_id))
                     .header("content-type", "application/json")
-                    .body(Body::from(serde_json::to_string(&body).unwrap()))
+                    .body(Body::from(r#"{"cls":["CL1","CL2"]}"#))
                     .unwrap(),
             )
             .await
             .unwrap();
-
         assert_eq!(response.status(), StatusCode::OK);
-    }
-
-    #[tokio::test]
-    async fn test_concurrent_mount_requests() {
-        let service = Arc::new(MockAntaresService::new());
-
-        let handles: Vec<_> = (0..10)
-            .map(|i| {
-                let svc = service.clone();
-                tokio::spawn(async move {
-                    svc.create_mount(CreateMountRequest {
-                        job_id: None,
-                        build_id: None,
-                        path: format!("/project/path{}", i),
-                        cl: None,
-                    })
-                    .await
-                })
-            })
-            .collect();
 
-        for h in handles {
-            assert!(h.await.unwrap().is_ok());
-        }
-
-        // All 10 mounts should exist
-        let mounts = service.list_mounts().await.unwrap();
-        assert_eq!(mounts.len(), 10);
+        let response = get_progress(format!("/mounts/{}/cl/progress", created.mount_id)).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(value["state"], "succeeded");
+        let progress: ClBuildProgress = serde_json::from_value(value).unwrap();
+        assert_eq!(progress.cls, vec!["CL1".to_string(), "CL2".to_string()]);
+        assert_eq!(progress.layer, 1);
+        assert_eq!(progress.files_done, progress.files_total);
+
+        let response = get_progress(format!("/mounts/{}/cl/progress", Uuid::new_v4())).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
-    #[tokio::test]
-    async fn test_duplicate_path_cl_rejected() {
-        let service = Arc::new(MockAntaresService::new());
-
-        let request = CreateMountRequest {
-            job_id: None,
-            build_id: None,
-            path: "/third-party/mega".into(),
-            cl: Some("CL123".into()),
-        };
-
-        // First mount should succeed
-        let result1 = service.create_mount(request.clone()).await;
-        assert!(result1.is_ok());
+    /// Serve CL files-lists (keyed by CL link) and blobs like the Mega CL API does.
+    async fn spawn_mock_cl_api(
+        files: HashMap<&'static str, serde_json::Value>,
+        blobs: HashMap<&'static str, &'static [u8]>,
+    ) -> String {
+        let files = Arc::new(files);
+        let blobs = Arc::new(blobs);
+        let app = Router::new()
+            .route(
+                "/api/v1/cl/{cl}/files-list",
+                get(move |AxumPath(cl): AxumPath<String>| {
+                    let files = files.clone();
+                    async move {
+                        Json(serde_json::json!({
+                            "req_result": true,
+                            "data": files.get(cl.as_str()).cloned().unwrap_or_default(),
+                            "err_message": "",
+                        }))
+                    }
+                }),
+            )
+            .route(
+                "/api/v1/file/blob/{oid}",
+                get(move |AxumPath(oid): AxumPath<String>| {
+                    let blobs = blobs.clone();
+                    async move {
+                        match blobs.get(oid.as_str()) {
+                            Some(data) => data.to_vec().into_response(),
+                            None => StatusCode::NOT_FOUND.into_response(),
+                        }
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
 
-        // Second mount with same path+cl should fail
-        let result2 = service.create_mount(request).await;
-        assert!(matches!(result2, Err(ServiceError::InvalidRequest(_))));
+    fn mock_cl_fetcher(base_url: String, cache_root: &Path) -> ClFetcher {
+        ClFetcher {
+            base_url,
+            client: Client::new(),
+            blob_cache: BlobCache::new(cache_root),
+            workers: 4,
+        }
     }
 
+    /// Test renames, modes, symlinks and directory deletions against a mock CL API
     #[tokio::test]
-    async fn test_job_id_idempotent() {
-        let service = Arc::new(MockAntaresService::new());
+    async fn test_cl_layer_change_semantics() {
+        use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+        let files = HashMap::from([
+            (
+                "CL1",
+                serde_json::json!([
+                    { "path": "/proj/src/main.rs", "sha": "aaaa01", "action": "new" },
+                    { "path": "/proj/run.sh", "sha": "aaaa02", "action": "modified", "mode": "100755" },
+                    { "path": "/proj/link", "sha": "aaaa03", "action": "new", "mode": "120000" },
+                    { "path": "/proj/new_name.rs", "sha": "aaaa01", "action": "renamed", "old_path": "/proj/old_name.rs" },
+                    { "path": "/proj/legacy", "sha": "", "action": "deleted", "mode": "040000" },
+                    { "path": "/proj/legacy/a.rs", "sha": "", "action": "deleted" },
+                    { "path": "/proj/gen", "sha": "", "action": "deleted", "mode": "040000" },
+                    { "path": "/proj/gen/out.rs", "sha": "aaaa01", "action": "new" },
+                    { "path": "/proj/sub", "sha": "bbbb01", "action": "new", "mode": "160000" },
+                    { "path": "/other/ignored.rs", "sha": "aaaa01", "action": "new" },
+                ]),
+            ),
+            (
+                "CL2",
+                serde_json::json!([
+                    { "path": "/proj/src/main.rs", "sha": "aaaa01", "action": "new" },
+                    { "path": "/proj/run.sh", "sha": "aaaa02", "action": "modified", "mode": "100644" },
+                ]),
+            ),
+        ]);
+        let blobs: HashMap<&'static str, &'static [u8]> = HashMap::from([
+            ("aaaa01", b"fn main() {}".as_slice()),
+            ("aaaa02", b"#!/bin/sh\n".as_slice()),
+            ("aaaa03", b"src/main.rs".as_slice()),
+        ]);
+        let base_url = spawn_mock_cl_api(files, blobs).await;
+        let tmp = tempfile::tempdir().unwrap();
+        let fetcher = mock_cl_fetcher(base_url, &tmp.path().join("blobs"));
+        let cl_dir = tmp.path().join("cl");
+
+        let progress = ClProgress::start(Uuid::new_v4(), &["CL1".to_string()]);
+        fetcher
+            .build_layer(
+                &[MountBinding::whole("/proj")],
+                "CL1",
+                &cl_dir,
+                0,
+                &progress,
+            )
+            .await
+            .unwrap();
+        let snapshot = progress.snapshot();
+        assert_eq!(snapshot.files_done, snapshot.files_total);
+        assert_eq!(snapshot.failures, 0);
 
-        let request = CreateMountRequest {
-            job_id: Some("job-123".into()),
-            build_id: None,
-            path: "/third-party/mega".into(),
-            cl: Some("CL123".into()),
+        assert_eq!(
+            fs::read(cl_dir.join("src/main.rs")).unwrap(),
+            b"fn main() {}"
+        );
+        assert_eq!(
+            fs::read(cl_dir.join("new_name.rs")).unwrap(),
+            b"fn main() {}"
+        );
+        let mode = |rel: &str| fs::metadata(cl_dir.join(rel)).unwrap().mode() & 0o777;
+        assert_eq!(mode("run.sh"), 0o755);
+        assert_eq!(mode("src/main.rs") & 0o111, 0);
+        assert_eq!(
+            fs::read_link(cl_dir.join("link")).unwrap(),
+            Path::new("src/main.rs")
+        );
+        let is_whiteout = |rel: &str| {
+            fs::symlink_metadata(cl_dir.join(rel))
+                .map(|m| m.file_type().is_char_device())
+                .unwrap_or(false)
         };
-
-        let first = service.create_mount(request.clone()).await.unwrap();
-        let second = service.create_mount(request).await.unwrap();
-
-        assert_eq!(first.mount_id, second.mount_id);
-        assert_eq!(first.mountpoint, second.mountpoint);
+        assert!(is_whiteout("old_name.rs"));
+        assert!(is_whiteout("legacy"));
+        assert!(cl_manifest::is_opaque(&cl_dir.join("gen")));
+        assert_eq!(
+            fs::read(cl_dir.join("gen/out.rs")).unwrap(),
+            b"fn main() {}"
+        );
+        assert!(!cl_dir.join("sub").exists());
+        assert!(!cl_dir.join("ignored.rs").exists());
+
+        // Rebuilding flips the mode of an unchanged blob and drops everything else.
+        let progress = ClProgress::start(Uuid::new_v4(), &["CL2".to_string()]);
+        fetcher
+            .build_layer(
+                &[MountBinding::whole("/proj")],
+                "CL2",
+                &cl_dir,
+                0,
+                &progress,
+            )
+            .await
+            .unwrap();
+        assert_eq!(mode("run.sh") & 0o111, 0);
+        assert_eq!(fs::read(cl_dir.join("run.sh")).unwrap(), b"#!/bin/sh\n");
+        for gone in ["link", "new_name.rs", "old_name.rs", "legacy", "gen"] {
+            assert!(fs::symlink_metadata(cl_dir.join(gone)).is_err(), "{gone}");
+        }
+        let manifest = cl_manifest::load(&cl_dir).unwrap();
+        assert_eq!(manifest.cl, "CL2");
+        assert_eq!(manifest.files.len(), 2);
     }
 
+    /// Test that a local layer is built from cached blobs without touching the CL API
     #[tokio::test]
-    async fn test_job_id_idempotent_rejected_when_unmounting() {
-        let service = Arc::new(MockAntaresService::new());
-
-        let request = CreateMountRequest {
-            job_id: Some("job-123".into()),
-            build_id: None,
-            path: "/third-party/mega".into(),
-            cl: Some("CL123".into()),
+    async fn test_local_cl_layer_from_cache() {
+        use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+        use crate::antares::local_cl::{LocalFile, LocalLayer, MODE_REGULAR};
+
+        let tmp = tempfile::tempdir().unwrap();
+        // Nothing listens here; any download would fail the build.
+        let fetcher = mock_cl_fetcher("http://127.0.0.1:9".into(), &tmp.path().join("blobs"));
+        let cl_dir = tmp.path().join("cl");
+        let file = |data: &[u8], mode| {
+            Some(LocalFile {
+                data: data.to_vec(),
+                mode,
+            })
         };
+        let layer = LocalLayer {
+            files: std::collections::BTreeMap::from([
+                (
+                    "src/lib.rs".to_string(),
+                    file(b"pub fn f() {}\n", MODE_REGULAR),
+                ),
+                (
+                    "run.sh".to_string(),
+                    file(b"#!/bin/sh\n", cl_manifest::MODE_EXECUTABLE),
+                ),
+                ("old.rs".to_string(), None),
+            ]),
+            ..LocalLayer::default()
+        };
+        let manifest = layer.into_manifest("wip", &fetcher.blob_cache).unwrap();
 
-        let first = service.create_mount(request.clone()).await.unwrap();
-
-        // Simulate a concurrent teardown where job_id is still present but mount is unmounting.
-        {
-            let mut mounts = service.mounts.write().await;
-            let s = mounts.get_mut(&first.mount_id).unwrap();
-            s.state = MountLifecycle::Unmounting;
-        }
+        let progress = ClProgress::start(Uuid::new_v4(), &["wip".to_string()]);
+        fetcher
+            .apply_manifest(&cl_dir, &manifest, 0, &progress)
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read(cl_dir.join("src/lib.rs")).unwrap(),
+            b"pub fn f() {}\n"
+        );
+        assert_eq!(
+            fs::metadata(cl_dir.join("run.sh")).unwrap().mode() & 0o111,
+            0o111
+        );
+        assert!(fs::symlink_metadata(cl_dir.join("old.rs"))
+            .unwrap()
+            .file_type()
+            .is_char_device());
+        assert_eq!(cl_manifest::load(&cl_dir).unwrap(), manifest);
+        assert_eq!(progress.snapshot().files_done, 3);
+    }
 
-        let second = service.create_mount(request).await;
-        assert!(matches!(second, Err(ServiceError::InvalidRequest(_))));
+    /// Test that a failed download aborts the layer and is counted in the progress
+    #[tokio::test]
+    async fn test_cl_layer_download_failure() {
+        let files = HashMap::from([(
+            "CL1",
+            serde_json::json!([
+                { "path": "/a.rs", "sha": "aaaa01", "action": "new" },
+                { "path": "/missing.rs", "sha": "dead01", "action": "new" },
+            ]),
+        )]);
+        let blobs: HashMap<&'static str, &'static [u8]> =
+            HashMap::from([("aaaa01", b"a".as_slice())]);
+        let base_url = spawn_mock_cl_api(files, blobs).await;
+        let tmp = tempfile::tempdir().unwrap();
+        let fetcher = mock_cl_fetcher(base_url, &tmp.path().join("blobs"));
+        let cl_dir = tmp.path().join("cl");
+
+        let progress = ClProgress::start(Uuid::new_v4(), &["CL1".to_string()]);
+        let err = fetcher
+            .build_layer(&[MountBinding::whole("/")], "CL1", &cl_dir, 0, &progress)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("dead01"), "{err}");
+        let snapshot = progress.snapshot();
+        assert_eq!(snapshot.failures, 1);
+        assert_eq!(snapshot.files_total, 2);
+        // Without a manifest the half-built layer is never reused.
+        assert!(cl_manifest::load(&cl_dir).is_none());
     }
 
+    /// Test creating and rebuilding a mount with a stack of CLs over HTTP
     #[tokio::test]
-    async fn test_same_path_cl_different_job_id_allowed() {
+    async fn test_http_cl_stack() {
         let service = Arc::new(MockAntaresService::new());
+        let daemon = AntaresDaemon::new(service.clone());
+        let app = daemon.router();
 
-        let req1 = CreateMountRequest {
-            job_id: Some("job-a".into()),
-            build_id: None,
-            path: "/third-party/mega".into(),
-            cl: Some("CL123".into()),
-        };
-        let req2 = CreateMountRequest {
-            job_id: Some("job-b".into()),
-            build_id: None,
-            path: "/third-party/mega".into(),
-            cl: Some("CL123".into()),
-        };
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"path":"/test/path","cls":["CL1","CL2"]}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: MountCreated = serde_json::from_slice(&body).unwrap();
 
-        let r1 = service.create_mount(req1).await;
-        let r2 = service.create_mount(req2).await;
-        assert!(r1.is_ok());
-        assert!(r2.is_ok());
+        let status = service.describe_mount(created.mount_id).await.unwrap();
+        assert_eq!(status.cls, vec!["CL1".to_string(), "CL2".to_string()]);
+        assert_eq!(status.cl.as_deref(), Some("CL2"));
+        let id = created.mount_id.to_string();
+        assert_eq!(
+            status.layers.cl_dirs,
+            vec![
+                format!("/tmp/mock_cl/{}", id),
+                format!("/tmp/mock_cl/{}.1", id)
+            ]
+        );
 
-        let mounts = service.list_mounts().await.unwrap();
-        assert_eq!(mounts.len(), 2);
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/mounts/{}/cl", created.mount_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"cls":["CL3","CL1","CL2"]}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let status: MountStatus = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status.cls.len(), 3);
+        assert_eq!(status.cl.as_deref(), Some("CL2"));
+
+        for body in [
+            r#"{"cl":"CL1","cls":["CL2"]}"#,
+            r#"{"cls":["CL1","CL1"]}"#,
+            "{}",
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/mounts/{}/cl", created.mount_id))
+                        .header("content-type", "application/json")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", body);
+        }
+    }
+
+    #[test]
+    fn test_cl_stack_from_request_fields() {
+        assert_eq!(cl_stack_from(None, &[]).unwrap(), Vec::<String>::new());
+        assert_eq!(
+            cl_stack_from(Some("A"), &[]).unwrap(),
+            vec!["A".to_string()]
+        );
+        let stack = vec!["A".to_string(), "B".to_string()];
+        assert_eq!(cl_stack_from(None, &stack).unwrap(), stack);
+        assert!(cl_stack_from(Some("A"), &stack).is_err());
+        assert!(cl_stack_from(None, &["A".into(), " ".into()]).is_err());
+        assert!(cl_stack_from(None, &["A".into(), "A".into()]).is_err());
     }
 
+    /// Test HTTP endpoint for clear_cl
     #[tokio::test]
-    async fn test_delete_mount_success() {
+    async fn test_http_clear_cl() {
         let service = Arc::new(MockAntaresService::new());
 
-        // Create a mount
+        // First create a mount with CL
         let created = service
             .create_mount(CreateMountRequest {
                 job_id: None,
                 build_id: None,
-                path: "/third-party/mega".into(),
-                cl: None,
+                path: "/test/path".into(),
+                cl: Some("CL123".into()),
+                ..Default::default()
             })
             .await
             .unwrap();
 
-        let mount_id = created.mount_id;
+        let daemon = AntaresDaemon::new(service);
+        let app = daemon.router();
 
-        // Delete it
-        let deleted = service.delete_mount(mount_id).await.unwrap();
-        assert!(matches!(deleted.state, MountLifecycle::Unmounted));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/mounts/{}/cl", created.mount_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
 
-        // Verify it's gone
-        let result = service.describe_mount(mount_id).await;
-        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let status: MountStatus = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status.cl, None);
     }
 
+    /// Test fork_mount API - fork keeps path/cl and records its source
     #[tokio::test]
-    async fn test_same_path_different_cl_allowed() {
+    async fn test_fork_mount_success() {
         let service = Arc::new(MockAntaresService::new());
 
-        // Mount with CL1
-        let result1 = service
+        let created = service
             .create_mount(CreateMountRequest {
                 job_id: None,
                 build_id: None,
                 path: "/third-party/mega".into(),
-                cl: Some("CL1".into()),
+                cl: Some("CL123".into()),
+                ..Default::default()
             })
-            .await;
-        assert!(result1.is_ok());
+            .await
+            .unwrap();
 
-        // Mount with CL2 (same path, different CL) should succeed
-        let result2 = service
-            .create_mount(CreateMountRequest {
-                job_id: None,
-                build_id: None,
-                path: "/third-party/mega".into(),
-                cl: Some("CL2".into()),
-            })
-            .await;
-        assert!(result2.is_ok());
+        let forked = service
+            .fork_mount(
+                created.mount_id,
+                ForkMountRequest {
+                    job_id: Some("job-fork".into()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_ne!(forked.mount_id, created.mount_id);
+        assert_ne!(forked.mountpoint, created.mountpoint);
 
-        // Should have 2 mounts
-        let mounts = service.list_mounts().await.unwrap();
-        assert_eq!(mounts.len(), 2);
+        let status = service.describe_mount(forked.mount_id).await.unwrap();
+        assert_eq!(status.forked_from, Some(created.mount_id));
+        assert_eq!(status.path, "/third-party/mega");
+        assert_eq!(status.cl, Some("CL123".into()));
+        assert_eq!(status.job_id, Some("job-fork".into()));
+
+        // The source mount is left untouched.
+        let source = service.describe_mount(created.mount_id).await.unwrap();
+        assert_eq!(source.state, MountLifecycle::Ready);
+        assert_eq!(source.forked_from, None);
     }
 
-    /// Test concurrent mount creation to verify thread safety.
-    /// This validates that multiple Antares instances can safely share
-    /// the same service and create mounts concurrently.
     #[tokio::test]
-    async fn test_concurrent_mount_creation() {
+    async fn test_fork_mount_rejected_when_quiescing() {
         let service = Arc::new(MockAntaresService::new());
 
-        // Spawn 10 concurrent mount creation tasks
-        let mut handles = Vec::new();
-        for i in 0..10 {
-            let svc = service.clone();
-            let handle = tokio::spawn(async move {
-                let request = CreateMountRequest {
-                    job_id: None,
-                    build_id: None,
-                    path: format!("/concurrent-path-{}", i),
-                    cl: None,
-                };
-                svc.create_mount(request).await
-            });
-            handles.push(handle);
-        }
-
-        // Wait for all tasks to complete
-        let results: Vec<_> = join_all(handles).await;
+        let created = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/third-party/mega".into(),
+                cl: None,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
 
-        // All should succeed
-        let mut success_count = 0;
-        for result in results {
-            match result {
-                Ok(Ok(_)) => success_count += 1,
-                Ok(Err(e)) => panic!("Mount creation failed: {:?}", e),
-                Err(e) => panic!("Task panicked: {:?}", e),
-            }
+        {
+            let mut mounts = service.mounts.write().await;
+            let s = mounts.get_mut(&created.mount_id).unwrap();
+            s.state = MountLifecycle::Quiescing;
         }
-        assert_eq!(success_count, 10, "All 10 concurrent mounts should succeed");
-
-        // Verify all mounts are listed
-        let mounts = service.list_mounts().await.unwrap();
-        assert_eq!(
-            mounts.len(),
-            10,
-            "Should have 10 mounts after concurrent creation"
-        );
 
-        // Verify paths are unique
-        let paths: std::collections::HashSet<_> = mounts.iter().map(|m| m.path.clone()).collect();
-        assert_eq!(paths.len(), 10, "All paths should be unique");
+        let result = service
+            .fork_mount(created.mount_id, ForkMountRequest::default())
+            .await;
+        assert!(matches!(result, Err(ServiceError::InvalidRequest(_))));
     }
 
-    /// Test concurrent operations on the same mount.
+    /// Test HTTP endpoint for fork_mount, with and without a request body
     #[tokio::test]
-    async fn test_concurrent_operations_same_mount() {
+    async fn test_http_fork_mount() {
         let service = Arc::new(MockAntaresService::new());
 
-        // Create a mount
-        let request = CreateMountRequest {
-            job_id: None,
-            build_id: None,
-            path: "/test-concurrent-ops".to_string(),
-            cl: None,
-        };
-        let created = service.create_mount(request).await.unwrap();
-        let mount_id = created.mount_id;
+        let created = service
+            .create_mount(CreateMountRequest {
+                job_id: None,
+                build_id: None,
+                path: "/test/path".into(),
+                cl: None,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
 
-        // Spawn multiple concurrent describe operations
-        let mut handles = Vec::new();
-        for _ in 0..20 {
-            let svc = service.clone();
-            let id = mount_id;
-            let handle = tokio::spawn(async move { svc.describe_mount(id).await });
-            handles.push(handle);
-        }
+        let daemon = AntaresDaemon::new(service);
+        let app = daemon.router();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/mounts/{}/fork", created.mount_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"job_id":"job-a"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let forked: MountCreated = serde_json::from_slice(&body).unwrap();
+        assert_ne!(forked.mount_id, created.mount_id);
 
-        // All describe operations should succeed
-        let results: Vec<_> = join_all(handles).await;
-        for result in results {
-            assert!(
-                result.is_ok() && result.unwrap().is_ok(),
-                "All describe operations should succeed"
-            );
-        }
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/mounts/{}/fork", created.mount_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/mounts/{}/fork", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
-    /// Test build_cl API - successfully add CL layer to mount
+    /// Test checkpoint create/list/restore/delete through the service trait
     #[tokio::test]
-    async fn test_build_cl_success() {
+    async fn test_checkpoint_lifecycle() {
         let service = Arc::new(MockAntaresService::new());
 
-        // Create a mount without CL
         let created = service
             .create_mount(CreateMountRequest {
                 job_id: None,
                 build_id: None,
                 path: "/third-party/mega".into(),
                 cl: None,
+                ..Default::default()
             })
             .await
             .unwrap();
 
-        let mount_id = created.mount_id;
+        let ckpt = service
+            .create_checkpoint(
+                created.mount_id,
+                CreateCheckpointRequest {
+                    label: Some("after-fetch".into()),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(ckpt.mount_id, created.mount_id);
 
-        // Build CL layer
-        let status = service.build_cl(mount_id, "CL123".into()).await.unwrap();
-        assert_eq!(status.cl, Some("CL123".into()));
-        assert!(status.layers.cl.is_some());
+        let listed = service.list_checkpoints(created.mount_id).await.unwrap();
+        assert_eq!(listed.checkpoints.len(), 1);
+        assert_eq!(listed.checkpoints[0].label, Some("after-fetch".into()));
+
+        let status = service
+            .restore_checkpoint(created.mount_id, ckpt.checkpoint_id)
+            .await
+            .unwrap();
+        assert_eq!(status.state, MountLifecycle::Ready);
+
+        service
+            .delete_checkpoint(created.mount_id, ckpt.checkpoint_id)
+            .await
+            .unwrap();
+        let result = service
+            .restore_checkpoint(created.mount_id, ckpt.checkpoint_id)
+            .await;
+        assert!(matches!(result, Err(ServiceError::CheckpointNotFound(_))));
     }
 
     #[tokio::test]
-    async fn test_build_cl_rejected_when_unmounting() {
+    async fn test_checkpoint_rejected_when_quiescing() {
         let service = Arc::new(MockAntaresService::new());
 
         let created = service
@@ -3232,6 +8620,7 @@ mod tests {
                 build_id: None,
                 path: "/third-party/mega".into(),
                 cl: None,
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -3239,194 +8628,935 @@ mod tests {
         {
             let mut mounts = service.mounts.write().await;
             let s = mounts.get_mut(&created.mount_id).unwrap();
-            s.state = MountLifecycle::Unmounting;
+            s.state = MountLifecycle::Quiescing;
         }
 
-        let result = service.build_cl(created.mount_id, "CL123".into()).await;
+        let result = service
+            .create_checkpoint(created.mount_id, CreateCheckpointRequest::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::InvalidRequest(_))));
     }
 
+    /// Test HTTP endpoints for checkpoints
     #[tokio::test]
-    async fn test_build_cl_rejected_when_quiescing() {
+    async fn test_http_checkpoints() {
         let service = Arc::new(MockAntaresService::new());
 
         let created = service
             .create_mount(CreateMountRequest {
                 job_id: None,
                 build_id: None,
-                path: "/third-party/mega".into(),
+                path: "/test/path".into(),
                 cl: None,
+                ..Default::default()
             })
             .await
             .unwrap();
 
-        {
-            let mut mounts = service.mounts.write().await;
-            let s = mounts.get_mut(&created.mount_id).unwrap();
-            s.state = MountLifecycle::Quiescing;
+        let daemon = AntaresDaemon::new(service);
+        let app = daemon.router();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/mounts/{}/checkpoints", created.mount_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"label":"step-1"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let ckpt: CheckpointInfo = serde_json::from_slice(&body).unwrap();
+        assert_eq!(ckpt.label, Some("step-1".into()));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/mounts/{}/checkpoints", created.mount_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let collection: CheckpointCollection = serde_json::from_slice(&body).unwrap();
+        assert_eq!(collection.checkpoints.len(), 1);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!(
+                        "/mounts/{}/checkpoints/{}/restore",
+                        created.mount_id, ckpt.checkpoint_id
+                    ))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!(
+                        "/mounts/{}/checkpoints/{}",
+                        created.mount_id, ckpt.checkpoint_id
+                    ))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!(
+                        "/mounts/{}/checkpoints/{}",
+                        created.mount_id, ckpt.checkpoint_id
+                    ))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_limit_exceeded_maps_to_insufficient_storage() {
+        let response =
+            ApiError::Service(ServiceError::LimitExceeded("full".into())).into_response();
+        assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+    }
+
+    /// Test that per-request quota overrides show up in status and the usage endpoint
+    #[tokio::test]
+    async fn test_http_mount_usage() {
+        let service = Arc::new(MockAntaresService::new());
+        let daemon = AntaresDaemon::new(service.clone());
+        let app = daemon.router();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"path":"/test/path","quota":{"max_bytes":1024,"max_inodes":16}}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: MountCreated = serde_json::from_slice(&body).unwrap();
+
+        let status = service.describe_mount(created.mount_id).await.unwrap();
+        assert_eq!(status.usage.unwrap().max_bytes, Some(1024));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/mounts/{}/usage", created.mount_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let usage: MountUsage = serde_json::from_slice(&body).unwrap();
+        assert_eq!(usage.mount_id, created.mount_id);
+        assert_eq!(usage.usage.max_inodes, Some(16));
+        assert_eq!(usage.usage.used_bytes, 0);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/mounts/{}/usage", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// Test that read-only mounts report no upper layer and refuse upper-layer operations
+    #[tokio::test]
+    async fn test_http_read_only_mount() {
+        let service = Arc::new(MockAntaresService::new());
+        let daemon = AntaresDaemon::new(service.clone());
+        let app = daemon.router();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"path":"/test/path","read_only":true}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: MountCreated = serde_json::from_slice(&body).unwrap();
+
+        let status = service.describe_mount(created.mount_id).await.unwrap();
+        assert!(status.read_only);
+        assert!(status.layers.upper.is_none());
+        assert!(status.usage.is_none());
+
+        for uri in [
+            format!("/mounts/{}/fork", created.mount_id),
+            format!("/mounts/{}/checkpoints", created.mount_id),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(uri)
+                        .header("content-type", "application/json")
+                        .body(Body::from("{}"))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
         }
+    }
+
+    #[tokio::test]
+    async fn test_http_memory_upper_mount() {
+        let service = Arc::new(MockAntaresService::new());
+        let daemon = AntaresDaemon::new(service.clone());
+        let app = daemon.router();
+
+        let post = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri("/mounts")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(post(r#"{"path":"/test/path","upper":"memory"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: MountCreated = serde_json::from_slice(&body).unwrap();
+        let status = service.describe_mount(created.mount_id).await.unwrap();
+        assert!(!status.read_only);
+        assert_eq!(status.upper_kind, Some(UpperKind::Memory));
+        assert!(status.layers.upper.is_none());
+        assert!(status.usage.is_some());
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                r#"{"path":"/other","upper":"memory","read_only":true}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(post(r#"{"path":"/other","upper":"tmpfs"}"#))
+            .await
+            .unwrap();
+        assert!(response.status().is_client_error());
+    }
+
+    #[test]
+    fn test_persisted_single_cl_migrates_to_stack() {
+        let mut legacy: PersistedMountState = toml::from_str(
+            r#"
+            mount_id = "550e8400-e29b-41d4-a716-446655440000"
+            path = "/p"
+            cl = "CL1"
+            mountpoint = "/mnt/a"
+            upper_dir = "/upper/a"
+            cl_dir = "/cl/a"
+            created_at_epoch_ms = 0
+            "#,
+        )
+        .unwrap();
+        legacy.migrate_legacy_cl();
+        assert_eq!(legacy.cls, vec!["CL1".to_string()]);
+        assert_eq!(legacy.cl_dirs, vec!["/cl/a".to_string()]);
+        assert!(legacy.cl.is_none() && legacy.cl_dir.is_none());
+
+        let written = toml::to_string(&legacy).unwrap();
+        assert!(!written.contains("cl_dir ="));
+        let reread: PersistedMountState = toml::from_str(&written).unwrap();
+        assert_eq!(reread.cls, legacy.cls);
+    }
+
+    #[test]
+    fn test_persisted_mount_without_upper_is_read_only() {
+        let writable: PersistedMountState = toml::from_str(
+            r#"
+            mount_id = "550e8400-e29b-41d4-a716-446655440000"
+            path = "/p"
+            mountpoint = "/mnt/a"
+            upper_dir = "/upper/a"
+            created_at_epoch_ms = 0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(writable.upper_dir.as_deref(), Some("/upper/a"));
+
+        let read_only: PersistedMountState = toml::from_str(
+            r#"
+            mount_id = "550e8400-e29b-41d4-a716-446655440000"
+            path = "/p"
+            mountpoint = "/mnt/a"
+            created_at_epoch_ms = 0
+            "#,
+        )
+        .unwrap();
+        assert!(read_only.upper_dir.is_none());
+    }
+
+    #[test]
+    fn test_create_request_rejects_zero_quota() {
+        let request = |quota| CreateMountRequest {
+            path: "/p".into(),
+            quota: Some(quota),
+            ..Default::default()
+        };
+        let zero = QuotaLimits {
+            max_bytes: Some(0),
+            max_inodes: None,
+        };
+        let err = AntaresServiceImpl::check_create_request(&request(zero)).unwrap_err();
+        assert!(matches!(err, ServiceError::InvalidRequest(_)), "{err}");
+
+        let tight = QuotaLimits {
+            max_bytes: Some(1024),
+            max_inodes: None,
+        };
+        assert!(AntaresServiceImpl::check_create_request(&request(tight)).is_ok());
+    }
+
+    /// Test that a tuning block round-trips through create and describe
+    #[tokio::test]
+    async fn test_http_create_mount_with_tuning() {
+        let service = Arc::new(MockAntaresService::new());
+        let daemon = AntaresDaemon::new(service.clone());
+        let app = daemon.router();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"path":"/test/path","tuning":{"stat_mode":"accurate","load_dir_depth":1,"deep_preload_mode":"dirs_only"}}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: MountCreated = serde_json::from_slice(&body).unwrap();
 
-        let result = service.build_cl(created.mount_id, "CL123".into()).await;
-        assert!(matches!(result, Err(ServiceError::InvalidRequest(_))));
+        let tuning = service
+            .describe_mount(created.mount_id)
+            .await
+            .unwrap()
+            .tuning
+            .unwrap();
+        assert_eq!(tuning.deep_preload_mode, Some(DeepPreloadMode::DirsOnly));
+        assert_eq!(
+            tuning.dicfuse(),
+            DicfuseTuning {
+                stat_mode: Some(DicfuseStatMode::Accurate),
+                load_dir_depth: Some(1),
+                ..Default::default()
+            }
+        );
     }
 
-    /// Test build_cl API - mount not found
     #[tokio::test]
-    async fn test_build_cl_not_found() {
+    async fn test_http_create_mount_with_callback_url() {
         let service = Arc::new(MockAntaresService::new());
-        let fake_id = Uuid::new_v4();
+        let app = AntaresDaemon::new(service.clone()).router();
+
+        for callback_url in ["ftp://ci.example/hook", "not a url"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/mounts")
+                        .header("content-type", "application/json")
+                        .body(Body::from(
+                            serde_json::json!({"path": "/test/path", "callback_url": callback_url})
+                                .to_string(),
+                        ))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{callback_url}");
+        }
 
-        let result = service.build_cl(fake_id, "CL123".into()).await;
-        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/mounts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"path":"/test/path","callback_url":"https://ci.example/hook"}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: MountCreated = serde_json::from_slice(&body).unwrap();
+        let status = service.describe_mount(created.mount_id).await.unwrap();
+        assert_eq!(
+            status.callback_url.as_deref(),
+            Some("https://ci.example/hook")
+        );
     }
 
-    /// Test clear_cl API - successfully clear CL layer
     #[tokio::test]
-    async fn test_clear_cl_success() {
+    async fn test_http_auth_records_caller() {
         let service = Arc::new(MockAntaresService::new());
+        let policy = AuthPolicy::from_toml(
+            r#"
+            [[token]]
+            name = "ci"
+            scope = "mount"
+            token = "ci-token"
+
+            [[token]]
+            name = "ops"
+            scope = "admin"
+            token = "ops-token"
+            "#,
+        )
+        .unwrap();
+        let daemon = AntaresDaemon::new(service.clone()).with_auth(Arc::new(policy));
+        let shutdown_requested = daemon.shutdown_requested();
+        let app = daemon.router();
+        let request = |uri: &str, token: Option<&str>, body: &str| {
+            let mut builder = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json");
+            if let Some(token) = token {
+                builder = builder.header("authorization", format!("Bearer {token}"));
+            }
+            builder.body(Body::from(body.to_string())).unwrap()
+        };
 
-        // Create a mount with CL
-        let created = service
-            .create_mount(CreateMountRequest {
-                job_id: None,
-                build_id: None,
-                path: "/third-party/mega".into(),
-                cl: Some("CL123".into()),
-            })
+        let mount = r#"{"path":"/test/path"}"#;
+        let response = app
+            .clone()
+            .oneshot(request("/mounts", None, mount))
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 
-        let mount_id = created.mount_id;
-
-        // Clear CL layer
-        let status = service.clear_cl(mount_id).await.unwrap();
-        assert_eq!(status.cl, None);
-        assert!(status.layers.cl.is_none());
-    }
+        let response = app
+            .clone()
+            .oneshot(request("/mounts", Some("ci-token"), mount))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: MountCreated = serde_json::from_slice(&body).unwrap();
+        let status = service.describe_mount(created.mount_id).await.unwrap();
+        assert_eq!(status.created_by.as_deref(), Some("ci"));
 
-    /// Test clear_cl API - no CL layer to clear
-    #[tokio::test]
-    async fn test_clear_cl_no_layer() {
-        let service = Arc::new(MockAntaresService::new());
+        // A body cannot claim another identity.
+        let response = app
+            .clone()
+            .oneshot(request(
+                "/mounts",
+                Some("ci-token"),
+                r#"{"path":"/other/path","created_by":"ops"}"#,
+            ))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: MountCreated = serde_json::from_slice(&body).unwrap();
+        let status = service.describe_mount(created.mount_id).await.unwrap();
+        assert_eq!(status.created_by.as_deref(), Some("ci"));
 
-        // Create a mount without CL
-        let created = service
-            .create_mount(CreateMountRequest {
-                job_id: None,
-                build_id: None,
-                path: "/third-party/mega".into(),
-                cl: None,
-            })
+        let response = app
+            .clone()
+            .oneshot(request("/shutdown", Some("ci-token"), ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .oneshot(request("/shutdown", Some("ops-token"), ""))
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        timeout(Duration::from_secs(1), shutdown_requested)
+            .await
+            .expect("shutdown was not requested");
+    }
 
-        let mount_id = created.mount_id;
+    #[test]
+    fn test_mount_tuning_deep_preload_overrides_are_clamped() {
+        let opts = MountTuning {
+            deep_preload_mode: Some(DeepPreloadMode::Full),
+            deep_preload_max_depth: Some(1_000),
+            deep_preload_max_ms: Some(0),
+            deep_preload_workers: Some(0),
+            ..Default::default()
+        }
+        .deep_preload();
+        assert_eq!(opts.mode, DeepPreloadMode::Full);
+        assert_eq!(opts.max_depth, 64);
+        assert_eq!(opts.max_duration, None);
+        assert_eq!(opts.workers, 1);
 
-        // Try to clear non-existent CL layer
-        let result = service.clear_cl(mount_id).await;
-        assert!(matches!(result, Err(ServiceError::InvalidRequest(_))));
+        assert!(MountTuning::default().dicfuse().is_empty());
     }
 
     #[tokio::test]
-    async fn test_clear_cl_rejected_when_quiescing() {
+    async fn test_http_composite_mount() {
         let service = Arc::new(MockAntaresService::new());
+        let daemon = AntaresDaemon::new(service.clone());
+        let app = daemon.router();
 
-        let created = service
-            .create_mount(CreateMountRequest {
-                job_id: None,
-                build_id: None,
-                path: "/third-party/mega".into(),
-                cl: Some("CL123".into()),
-            })
+        let post = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri("/mounts")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                r#"{"bindings":[{"source":"/libs/common/"},{"source":"services/foo","target":"foo"}]}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
             .await
             .unwrap();
+        let created: MountCreated = serde_json::from_slice(&body).unwrap();
+        let status = service.describe_mount(created.mount_id).await.unwrap();
+        assert_eq!(
+            status.bindings,
+            vec![
+                MountBinding {
+                    source: "/services/foo".into(),
+                    target: "foo".into(),
+                },
+                MountBinding {
+                    source: "/libs/common".into(),
+                    target: "libs/common".into(),
+                },
+            ]
+        );
 
-        {
-            let mut mounts = service.mounts.write().await;
-            let s = mounts.get_mut(&created.mount_id).unwrap();
-            s.state = MountLifecycle::Quiescing;
-        }
+        let response = app
+            .oneshot(post(
+                r#"{"path":"/libs","bindings":[{"source":"/libs/common"}]}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
 
-        let result = service.clear_cl(created.mount_id).await;
-        assert!(matches!(result, Err(ServiceError::InvalidRequest(_))));
+    #[test]
+    fn test_normalize_bindings_rejects_overlaps() {
+        let binding = |source: &str, target: &str| MountBinding {
+            source: source.into(),
+            target: target.into(),
+        };
+        let normalize = AntaresServiceImpl::normalize_bindings;
+
+        assert!(normalize(&[binding("/a", ""), binding("/a/b", "b")]).is_err());
+        assert!(normalize(&[binding("/a", "x"), binding("/b", "x/y")]).is_err());
+        assert!(normalize(&[binding("/a", "../x")]).is_err());
+        assert!(normalize(&[binding("/", "")]).is_err());
+        assert!(normalize(&[binding("", "x")]).is_err());
+
+        let view = normalize(&[binding("/a", "x"), binding("/b/c", "")]).unwrap();
+        assert_eq!(
+            AntaresServiceImpl::relative_path_in_view("/b/c/d.txt", &view),
+            Some(PathBuf::from("b/c/d.txt"))
+        );
+        assert_eq!(
+            AntaresServiceImpl::relative_path_in_view("/a/e.txt", &view),
+            Some(PathBuf::from("x/e.txt"))
+        );
+        assert_eq!(
+            AntaresServiceImpl::relative_path_in_view("/b/e.txt", &view),
+            None
+        );
+    }
+
+    /// Read SSE frames from `body` until `n` events arrived and decode them.
+    async fn read_sse_events(body: &mut axum::body::BodyDataStream, n: usize) -> Vec<MountEvent> {
+        let mut events = Vec::new();
+        let mut buf = String::new();
+        while events.len() < n {
+            let chunk = tokio::time::timeout(Duration::from_secs(5), body.next())
+                .await
+                .expect("timed out waiting for an event")
+                .expect("stream ended")
+                .unwrap();
+            buf.push_str(std::str::from_utf8(&chunk).unwrap());
+            while let Some(end) = buf.find("\n\n") {
+                let frame: String = buf.drain(..end + 2).collect();
+                if let Some(data) = frame.lines().find_map(|l| l.strip_prefix("data: ")) {
+                    events.push(serde_json::from_str(data).unwrap());
+                }
+            }
+        }
+        events
     }
 
-    /// Test HTTP endpoint for build_cl
     #[tokio::test]
-    async fn test_http_build_cl() {
+    async fn test_http_async_create_and_mount_events() {
         let service = Arc::new(MockAntaresService::new());
-
-        // First create a mount
-        let created = service
-            .create_mount(CreateMountRequest {
-                job_id: None,
-                build_id: None,
-                path: "/test/path".into(),
-                cl: None,
-            })
-            .await
-            .unwrap();
-
-        let daemon = AntaresDaemon::new(service);
-        let app = daemon.router();
+        let app = AntaresDaemon::new(service.clone()).router();
 
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri(format!("/mounts/{}/cl", created.mount_id))
+                    .uri("/mounts")
                     .header("content-type", "application/json")
-                    .body(Body::from(r#"{"cl":"CL456"}"#))
+                    .body(Body::from(r#"{"path":"/third-party/mega","async":true}"#))
                     .unwrap(),
             )
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: MountCreated = serde_json::from_slice(&body).unwrap();
+        assert_eq!(created.state, MountLifecycle::Provisioning);
 
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/mounts/{}/events", created.mount_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let mut body = response.into_body().into_data_stream();
+        let snapshot = read_sse_events(&mut body, 1).await;
+        assert_eq!(
+            snapshot[0].kind,
+            MountEventKind::State {
+                state: MountLifecycle::Provisioning
+            }
+        );
 
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        // Events of other mounts are filtered out; removal ends the stream.
+        service
+            .create_mount(CreateMountRequest {
+                path: "/other".into(),
+                ..Default::default()
+            })
             .await
             .unwrap();
-        let status: MountStatus = serde_json::from_slice(&body).unwrap();
-        assert_eq!(status.cl, Some("CL456".into()));
+        service.delete_mount(created.mount_id).await.unwrap();
+        let events = read_sse_events(&mut body, 1).await;
+        assert_eq!(events[0].mount_id, created.mount_id);
+        assert_eq!(events[0].kind, MountEventKind::Removed);
+        assert!(body.next().await.is_none());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/mounts/{}/events", created.mount_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
-    /// Test HTTP endpoint for clear_cl
     #[tokio::test]
-    async fn test_http_clear_cl() {
+    async fn test_http_all_mount_events() {
         let service = Arc::new(MockAntaresService::new());
-
-        // First create a mount with CL
-        let created = service
+        let app = AntaresDaemon::new(service.clone()).router();
+        let existing = service
             .create_mount(CreateMountRequest {
-                job_id: None,
-                build_id: None,
-                path: "/test/path".into(),
-                cl: Some("CL123".into()),
+                path: "/a".into(),
+                ..Default::default()
             })
             .await
             .unwrap();
 
-        let daemon = AntaresDaemon::new(service);
-        let app = daemon.router();
-
         let response = app
             .oneshot(
                 Request::builder()
-                    .method("DELETE")
-                    .uri(format!("/mounts/{}/cl", created.mount_id))
+                    .uri("/events")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
-
         assert_eq!(response.status(), StatusCode::OK);
+        let mut body = response.into_body().into_data_stream();
 
-        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        let created = service
+            .create_mount(CreateMountRequest {
+                path: "/b".into(),
+                ..Default::default()
+            })
             .await
             .unwrap();
-        let status: MountStatus = serde_json::from_slice(&body).unwrap();
-        assert_eq!(status.cl, None);
+        let events = read_sse_events(&mut body, 2).await;
+        assert_eq!(
+            events
+                .iter()
+                .map(|e| (e.mount_id, e.kind.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    existing.mount_id,
+                    MountEventKind::State {
+                        state: MountLifecycle::Ready
+                    }
+                ),
+                (
+                    created.mount_id,
+                    MountEventKind::State {
+                        state: MountLifecycle::Ready
+                    }
+                ),
+            ]
+        );
+
+        // Shutting down ends the stream so the server can drain.
+        service.shutdown_cleanup().await.unwrap();
+        let events = read_sse_events(&mut body, 1).await;
+        assert_eq!(events[0].kind, MountEventKind::Shutdown);
+        assert!(body.next().await.is_none());
+    }
+
+    #[test]
+    fn test_mount_event_serialization() {
+        let event = MountEvent {
+            mount_id: Uuid::nil(),
+            epoch_ms: 7,
+            kind: MountEventKind::State {
+                state: MountLifecycle::Failed {
+                    reason: "boom".into(),
+                },
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "mount_id": Uuid::nil(),
+                "epoch_ms": 7,
+                "event": "state",
+                "state": { "Failed": { "reason": "boom" } },
+            })
+        );
+        let removed: MountEvent = serde_json::from_value(serde_json::json!({
+            "mount_id": Uuid::nil(),
+            "epoch_ms": 7,
+            "event": "removed",
+        }))
+        .unwrap();
+        assert_eq!(removed.kind, MountEventKind::Removed);
+    }
+
+    #[tokio::test]
+    async fn test_http_labels_filter_and_bulk_delete() {
+        let app = create_test_router();
+        let send = |method: &str, uri: &str, body: Option<serde_json::Value>| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json");
+            let body = body.map_or_else(Body::empty, |b| Body::from(b.to_string()));
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request.body(body).unwrap()).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+                )
+            }
+        };
+        let send = &send;
+        let list = move |query: &str| {
+            let uri = format!("/mounts?{query}");
+            async move {
+                let (status, body) = send("GET", &uri, None).await;
+                assert_eq!(status, StatusCode::OK, "{body}");
+                serde_json::from_value::<MountCollection>(body).unwrap()
+            }
+        };
+
+        for (job, path, labels) in [
+            (
+                "a",
+                "/a/b",
+                serde_json::json!({"pipeline": "1", "team": "infra"}),
+            ),
+            ("b", "/c", serde_json::json!({"pipeline": "1"})),
+            ("c", "/a", serde_json::json!({"pipeline": "2"})),
+        ] {
+            let (status, _) = send(
+                "POST",
+                "/mounts",
+                Some(serde_json::json!({"job_id": job, "path": path, "labels": labels})),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        let (status, _) = send(
+            "POST",
+            "/mounts",
+            Some(serde_json::json!({"path": "/d", "labels": {"bad key": "x"}})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let jobs = |collection: &MountCollection| {
+            let mut jobs: Vec<String> = collection
+                .mounts
+                .iter()
+                .filter_map(|m| m.job_id.clone())
+                .collect();
+            jobs.sort();
+            jobs
+        };
+        assert_eq!(jobs(&list("selector=pipeline%3D1").await), ["a", "b"]);
+        assert_eq!(jobs(&list("selector=pipeline%3D1,!team").await), ["b"]);
+        assert_eq!(jobs(&list("path_prefix=/a").await), ["a", "c"]);
+        assert_eq!(
+            jobs(&list("state=ready,mounted&path_prefix=/c").await),
+            ["b"]
+        );
+        let described = list("selector=team%3Dinfra").await;
+        assert_eq!(described.mounts[0].labels["team"], "infra");
+
+        let first = list("limit=2").await;
+        assert_eq!(
+            (first.mounts.len(), first.total, first.next_offset),
+            (2, 3, Some(2))
+        );
+        let rest = list("limit=2&offset=2").await;
+        assert_eq!((rest.mounts.len(), rest.next_offset), (1, None));
+        let mut seen = jobs(&first);
+        seen.extend(jobs(&rest));
+        seen.sort();
+        assert_eq!(seen, ["a", "b", "c"]);
+
+        let (status, _) = send("GET", "/mounts?state=bogus", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send("GET", "/mounts?limit=0", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send("DELETE", "/mounts", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send("DELETE", "/mounts?selector=pipeline%3D1", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let deleted: BulkDeleteResponse = serde_json::from_value(body).unwrap();
+        assert_eq!(deleted.deleted.len(), 2);
+        assert!(deleted.failed.is_empty());
+        assert_eq!(jobs(&list("").await), ["c"]);
+    }
+
+    #[test]
+    fn test_persisted_labels_round_trip() {
+        let mut state: PersistedMountState = toml::from_str(
+            r#"
+            mount_id = "550e8400-e29b-41d4-a716-446655440000"
+            path = "/p"
+            mountpoint = "/mnt/a"
+            created_at_epoch_ms = 0
+            "#,
+        )
+        .unwrap();
+        assert!(state.labels.is_empty());
+        assert!(!toml::to_string(&state).unwrap().contains("labels"));
+
+        state.labels.insert("pipeline".into(), "1234".into());
+        let written = toml::to_string(&PersistedState {
+            mounts: vec![state],
+            ..Default::default()
+        })
+        .unwrap();
+        let reread: PersistedState = toml::from_str(&written).unwrap();
+        assert_eq!(reread.mounts[0].labels["pipeline"], "1234");
     }
 }
@@ -0,0 +1,1059 @@
+//! Authentication and authorization for the daemon HTTP APIs.
+//!
+//! A caller is identified by a static bearer token (`Authorization: Bearer <token>`) or,
+//! when the daemon serves TLS with `http_tls_client_ca`, by the subject CN of its client
+//! certificate. Both are declared with a [`Scope`] in the TOML file named by
+//! `http_auth_file`:
+//!
+//! ```toml
+//! [[token]]
+//! name = "ci"
+//! scope = "mount"
+//! token = "..."            # or sha256 = "<hex SHA-256 of the token>"
+//!
+//! [[client]]
+//! name = "build-host-1"    # certificate subject CN
+//! scope = "read"
+//! ```
+//!
+//! Every route requires a scope ([`antares_scope`], [`scorpio_scope`]). Without an auth
+//! file or client CA the APIs stay open and requests carry no [`Caller`].
+//!
+//! A daemon can also listen on a unix socket ([`UnixSocketListener`]). Its file mode
+//! decides who may connect; local peers without a token act as `uid:<uid>`. Root and the
+//! daemon's own user get the `admin` scope, every other user the scope configured by
+//! `http_socket_peer_scope` (`mount` by default).
+
+use std::{
+    collections::HashMap,
+    future::{Future, IntoFuture},
+    io,
+    net::SocketAddr,
+    os::unix::fs::{FileTypeExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{connect_info::Connected, ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    serve::{IncomingStream, Listener},
+    Json, Router,
+};
+use futures::{future::BoxFuture, FutureExt};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{
+    net::{TcpListener, TcpStream, UnixListener, UnixStream},
+    sync::{mpsc, watch},
+    task::JoinHandle,
+    time::Duration,
+};
+use tokio_rustls::{
+    rustls::{
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+        server::WebPkiClientVerifier,
+        RootCertStore, ServerConfig,
+    },
+    server::TlsStream,
+    TlsAcceptor,
+};
+
+use super::antares::ErrorBody;
+use crate::util::config;
+
+/// What a caller may do; each scope includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// List and describe mounts; read progress, usage and events.
+    Read,
+    /// Create, fork, rebuild and delete mounts and their checkpoints.
+    Mount,
+    /// Daemon administration: configuration and shutdown.
+    Admin,
+}
+
+impl std::str::FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "read" => Ok(Scope::Read),
+            "mount" => Ok(Scope::Mount),
+            "admin" => Ok(Scope::Admin),
+            other => Err(format!(
+                "unknown scope {other:?}; expected read, mount or admin"
+            )),
+        }
+    }
+}
+
+/// Authenticated caller, available to handlers as `Extension<Caller>`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Caller {
+    pub name: String,
+    pub scope: Scope,
+}
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("authentication required")]
+    Unauthenticated,
+    #[error("unknown or malformed credentials")]
+    InvalidCredentials,
+    #[error("{caller} lacks the {required:?} scope")]
+    Forbidden { caller: String, required: Scope },
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let (status, code) = match self {
+            AuthError::Unauthenticated | AuthError::InvalidCredentials => {
+                (StatusCode::UNAUTHORIZED, "UNAUTHORIZED")
+            }
+            AuthError::Forbidden { .. } => (StatusCode::FORBIDDEN, "FORBIDDEN"),
+        };
+        let body = ErrorBody {
+            error: self.to_string(),
+            code: code.to_string(),
+        };
+        let mut response = (status, Json(body)).into_response();
+        if status == StatusCode::UNAUTHORIZED {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        response
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AuthFile {
+    #[serde(default)]
+    token: Vec<TokenEntry>,
+    #[serde(default)]
+    client: Vec<ClientEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenEntry {
+    name: String,
+    scope: Scope,
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClientEntry {
+    name: String,
+    scope: Scope,
+}
+
+fn token_digest(token: &str) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(digest::digest(&digest::SHA256, token.as_bytes()).as_ref());
+    out
+}
+
+/// Accepted credentials and the callers they identify.
+#[derive(Debug)]
+pub struct AuthPolicy {
+    /// Callers by the SHA-256 of their token, so secrets are never compared directly.
+    tokens: HashMap<[u8; 32], Caller>,
+    /// Callers by the subject CN of their client certificate.
+    clients: HashMap<String, Caller>,
+    /// Reject requests without credentials.
+    required: bool,
+    /// Scope of unix socket peers other than root and the daemon's own user.
+    socket_peer_scope: Scope,
+}
+
+impl Default for AuthPolicy {
+    fn default() -> Self {
+        Self {
+            tokens: HashMap::new(),
+            clients: HashMap::new(),
+            required: false,
+            socket_peer_scope: Scope::Mount,
+        }
+    }
+}
+
+impl AuthPolicy {
+    /// Accept every request without credentials.
+    pub fn open() -> Self {
+        Self::default()
+    }
+
+    /// Parse an auth file. Any auth file, even an empty one, makes credentials mandatory.
+    pub fn from_toml(content: &str) -> Result<Self, String> {
+        let file: AuthFile =
+            toml::from_str(content).map_err(|e| format!("invalid auth file: {e}"))?;
+        let mut policy = Self {
+            required: true,
+            ..Self::default()
+        };
+        for entry in file.token {
+            let digest = match (entry.token.as_deref(), entry.sha256.as_deref()) {
+                (Some(token), None) if !token.is_empty() => token_digest(token),
+                (None, Some(sha256)) => hex::decode(sha256.trim())
+                    .ok()
+                    .and_then(|d| <[u8; 32]>::try_from(d).ok())
+                    .ok_or_else(|| format!("token {}: sha256 must be 64 hex digits", entry.name))?,
+                _ => {
+                    return Err(format!(
+                        "token {}: set exactly one of token or sha256",
+                        entry.name
+                    ))
+                }
+            };
+            if let Some(other) = policy.tokens.get(&digest) {
+                return Err(format!(
+                    "tokens {} and {} are identical",
+                    other.name, entry.name
+                ));
+            }
+            let caller = Caller {
+                name: entry.name,
+                scope: entry.scope,
+            };
+            policy.tokens.insert(digest, caller);
+        }
+        for entry in file.client {
+            if policy.clients.contains_key(&entry.name) {
+                return Err(format!("client {} is declared twice", entry.name));
+            }
+            let caller = Caller {
+                name: entry.name.clone(),
+                scope: entry.scope,
+            };
+            policy.clients.insert(entry.name, caller);
+        }
+        Ok(policy)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read auth file {}: {e}", path.display()))?;
+        Self::from_toml(&content)
+    }
+
+    /// Policy configured by `http_auth_file`, `http_tls_client_ca` and
+    /// `http_socket_peer_scope`.
+    pub fn from_global_config() -> Result<Self, String> {
+        let mut policy = match config::http_auth_file() {
+            Some(path) => Self::load(Path::new(&path))?,
+            None => Self::open(),
+        };
+        policy.required |= config::http_tls_client_ca().is_some();
+        if let Some(scope) = config::http_socket_peer_scope() {
+            policy.socket_peer_scope = scope
+                .parse()
+                .map_err(|e| format!("http_socket_peer_scope: {e}"))?;
+        }
+        Ok(policy)
+    }
+
+    /// Grant `scope` to unix socket peers other than root and the daemon's own user.
+    pub fn with_socket_peer_scope(mut self, scope: Scope) -> Self {
+        self.socket_peer_scope = scope;
+        self
+    }
+
+    /// Scope of a unix socket peer without a token.
+    fn socket_peer_scope(&self, uid: u32) -> Scope {
+        if uid == 0 || uid == unsafe { libc::geteuid() } {
+            Scope::Admin
+        } else {
+            self.socket_peer_scope
+        }
+    }
+
+    /// Whether requests without credentials are accepted.
+    pub fn is_open(&self) -> bool {
+        !self.required
+    }
+
+    /// Identify the caller of a request from its bearer token or, failing that, the
+    /// unix socket peer or CN of the verified client certificate of `peer`. `Ok(None)`
+    /// only under an open policy.
+    pub fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        peer: Option<&PeerInfo>,
+    ) -> Result<Option<Caller>, AuthError> {
+        let has_token = headers.contains_key(header::AUTHORIZATION);
+        if let Some(uid) = peer
+            .and_then(|p| p.uid)
+            .filter(|_| !has_token || !self.required)
+        {
+            return Ok(Some(Caller {
+                name: format!("uid:{uid}"),
+                scope: self.socket_peer_scope(uid),
+            }));
+        }
+        if !self.required {
+            return Ok(None);
+        }
+        if let Some(value) = headers.get(header::AUTHORIZATION) {
+            let token = value
+                .to_str()
+                .ok()
+                .and_then(|v| v.split_once(' '))
+                .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+                .map(|(_, token)| token.trim())
+                .filter(|token| !token.is_empty())
+                .ok_or(AuthError::InvalidCredentials)?;
+            return self
+                .tokens
+                .get(&token_digest(token))
+                .cloned()
+                .map(Some)
+                .ok_or(AuthError::InvalidCredentials);
+        }
+        match peer
+            .and_then(|p| p.client_cn.as_deref())
+            .and_then(|cn| self.clients.get(cn))
+        {
+            Some(caller) => Ok(Some(caller.clone())),
+            None => Err(AuthError::Unauthenticated),
+        }
+    }
+}
+
+/// Scope required by a request; `None` leaves the route public.
+pub type ScopeFn = fn(&Method, &str) -> Option<Scope>;
+
+/// Scopes of the Antares API: `/health` is public, reads need `read`, `/shutdown` and
+/// `/admin/*` need `admin` and every other change `mount`.
+pub fn antares_scope(method: &Method, path: &str) -> Option<Scope> {
+    if path == "/health" {
+        None
+    } else if method == Method::GET || method == Method::HEAD {
+        Some(Scope::Read)
+    } else if path == "/shutdown" || path.starts_with("/admin/") {
+        Some(Scope::Admin)
+    } else {
+        Some(Scope::Mount)
+    }
+}
+
+/// Scopes of the scorpio daemon API: reads need `read`, configuration changes `admin`
+/// and mount/unmount `mount`.
+pub fn scorpio_scope(method: &Method, path: &str) -> Option<Scope> {
+    if method == Method::GET || method == Method::HEAD {
+        Some(Scope::Read)
+    } else if path == "/api/config" {
+        Some(Scope::Admin)
+    } else {
+        Some(Scope::Mount)
+    }
+}
+
+/// Middleware state enforcing a policy on the routes of one router.
+#[derive(Clone)]
+pub struct AuthGuard {
+    policy: Arc<AuthPolicy>,
+    scope_of: ScopeFn,
+}
+
+impl AuthGuard {
+    pub fn new(policy: Arc<AuthPolicy>, scope_of: ScopeFn) -> Self {
+        Self { policy, scope_of }
+    }
+
+    /// Require the scope of each route already added to `router`.
+    pub fn protect<S>(self, router: Router<S>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        router.route_layer(axum::middleware::from_fn_with_state(self, authorize))
+    }
+}
+
+async fn authorize(State(guard): State<AuthGuard>, mut request: Request, next: Next) -> Response {
+    let Some(required) = (guard.scope_of)(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<PeerInfo>>()
+        .map(|ConnectInfo(peer)| peer.clone());
+    let result = match guard.policy.authenticate(request.headers(), peer.as_ref()) {
+        Ok(Some(caller)) if caller.scope < required => Err(AuthError::Forbidden {
+            caller: caller.name,
+            required,
+        }),
+        Ok(caller) => Ok(caller),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(caller) => {
+            if let Some(caller) = caller {
+                request.extensions_mut().insert(caller);
+            }
+            next.run(request).await
+        }
+        Err(e) => {
+            tracing::warn!(
+                method = %request.method(),
+                path = %request.uri().path(),
+                peer = ?peer,
+                error = %e,
+                "http auth: request rejected"
+            );
+            e.into_response()
+        }
+    }
+}
+
+/// Remote end of a connection, available to handlers as `ConnectInfo<PeerInfo>`.
+#[derive(Debug, Clone, Default)]
+pub struct PeerInfo {
+    /// Address of a TCP peer.
+    pub addr: Option<SocketAddr>,
+    /// Subject CN of the verified client certificate, if one was presented.
+    pub client_cn: Option<String>,
+    /// User id of a unix socket peer.
+    pub uid: Option<u32>,
+}
+
+impl PeerInfo {
+    fn tcp(addr: SocketAddr) -> Self {
+        Self {
+            addr: Some(addr),
+            ..Self::default()
+        }
+    }
+}
+
+impl Connected<IncomingStream<'_, TcpListener>> for PeerInfo {
+    fn connect_info(stream: IncomingStream<'_, TcpListener>) -> Self {
+        Self::tcp(*stream.remote_addr())
+    }
+}
+
+impl Connected<IncomingStream<'_, TlsListener>> for PeerInfo {
+    fn connect_info(stream: IncomingStream<'_, TlsListener>) -> Self {
+        stream.remote_addr().clone()
+    }
+}
+
+fn subject_cn(cert: &CertificateDer<'_>) -> Option<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(cert.as_ref()).ok()?;
+    let cn = cert.subject().iter_common_name().next()?;
+    cn.as_str().ok().map(String::from)
+}
+
+/// TCP listener that runs TLS handshakes in the background, so a slow client never
+/// holds up the others.
+pub struct TlsListener {
+    connections: mpsc::Receiver<(TlsStream<TcpStream>, PeerInfo)>,
+    local_addr: SocketAddr,
+    acceptor: JoinHandle<()>,
+}
+
+impl TlsListener {
+    const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn new(listener: TcpListener, config: Arc<ServerConfig>) -> io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (tx, connections) = mpsc::channel(64);
+        let tls = TlsAcceptor::from(config);
+        let acceptor = tokio::spawn(async move {
+            loop {
+                let (stream, addr) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "tls listener: accept failed");
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                };
+                let tls = tls.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(Self::HANDSHAKE_TIMEOUT, tls.accept(stream)).await {
+                        Ok(Ok(stream)) => {
+                            let client_cn = stream
+                                .get_ref()
+                                .1
+                                .peer_certificates()
+                                .and_then(|chain| chain.first())
+                                .and_then(subject_cn);
+                            let peer = PeerInfo {
+                                client_cn,
+                                ..PeerInfo::tcp(addr)
+                            };
+                            let _ = tx.send((stream, peer)).await;
+                        }
+                        Ok(Err(e)) => {
+                            tracing::debug!(peer = %addr, error = %e, "tls handshake failed")
+                        }
+                        Err(_) => tracing::debug!(peer = %addr, "tls handshake timed out"),
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            connections,
+            local_addr,
+            acceptor,
+        })
+    }
+}
+
+impl Drop for TlsListener {
+    fn drop(&mut self) {
+        self.acceptor.abort();
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = PeerInfo;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.connections.recv().await {
+            Some(conn) => conn,
+            // The accept loop only stops when the listener is dropped.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(PeerInfo::tcp(self.local_addr))
+    }
+}
+
+/// Unix socket listener that removes its socket file when dropped.
+#[derive(Debug)]
+pub struct UnixSocketListener {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl UnixSocketListener {
+    /// Bind `path` and set its permission bits to `mode`, which decide who may connect.
+    /// A stale socket from an earlier run is replaced; one still accepting connections
+    /// is left alone.
+    pub fn bind(path: &Path, mode: u32) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        match std::fs::symlink_metadata(path) {
+            Ok(meta) if meta.file_type().is_socket() => {
+                if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AddrInUse,
+                        format!("{} is in use by another daemon", path.display()),
+                    ));
+                }
+                std::fs::remove_file(path)?;
+            }
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+        Ok(Self {
+            listener,
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for UnixSocketListener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl Listener for UnixSocketListener {
+    type Io = UnixStream;
+    type Addr = PeerInfo;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, _)) => {
+                    let uid = match stream.peer_cred() {
+                        Ok(cred) => cred.uid(),
+                        Err(e) => {
+                            tracing::warn!(error = %e, "unix listener: no peer credentials");
+                            continue;
+                        }
+                    };
+                    let peer = PeerInfo {
+                        uid: Some(uid),
+                        ..PeerInfo::default()
+                    };
+                    return (stream, peer);
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "unix listener: accept failed");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(PeerInfo::default())
+    }
+}
+
+impl Connected<IncomingStream<'_, UnixSocketListener>> for PeerInfo {
+    fn connect_info(stream: IncomingStream<'_, UnixSocketListener>) -> Self {
+        stream.remote_addr().clone()
+    }
+}
+
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("failed to read certificates from {}: {e}", path.display()))
+}
+
+/// TLS server settings; with `client_ca`, client certificates signed by it are
+/// verified and identify callers, while clients without one may still use tokens.
+pub fn server_config(
+    cert: &Path,
+    key: &Path,
+    client_ca: Option<&Path>,
+) -> Result<Arc<ServerConfig>, String> {
+    let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+    let certs = read_certs(cert)?;
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|e| format!("failed to read private key {}: {e}", key.display()))?;
+    let verifier = match client_ca {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for ca in read_certs(path)? {
+                roots
+                    .add(ca)
+                    .map_err(|e| format!("invalid CA certificate in {}: {e}", path.display()))?;
+            }
+            WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                .allow_unauthenticated()
+                .build()
+                .map_err(|e| format!("invalid client CA {}: {e}", path.display()))?
+        }
+        None => WebPkiClientVerifier::no_client_auth(),
+    };
+    let mut config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("invalid server certificate: {e}"))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// TLS settings from `http_tls_cert`, `http_tls_key` and `http_tls_client_ca`;
+/// `None` when the daemon serves plain HTTP.
+pub fn tls_from_global_config() -> Result<Option<Arc<ServerConfig>>, String> {
+    match (
+        config::http_tls_cert(),
+        config::http_tls_key(),
+        config::http_tls_client_ca(),
+    ) {
+        (Some(cert), Some(key), ca) => server_config(
+            Path::new(&cert),
+            Path::new(&key),
+            ca.as_deref().map(Path::new),
+        )
+        .map(Some),
+        (None, None, None) => Ok(None),
+        _ => Err("http_tls_cert and http_tls_key must be set together, \
+                  and http_tls_client_ca requires both"
+            .into()),
+    }
+}
+
+/// Sockets a daemon serves its API on.
+#[derive(Default)]
+pub struct Listeners {
+    /// TCP listener, wrapped in TLS when the server has a TLS config.
+    pub tcp: Option<TcpListener>,
+    pub unix: Option<UnixSocketListener>,
+}
+
+/// Serve `router` on `listeners` (TCP over TLS when `tls` is set) until `shutdown`
+/// completes. Handlers see the remote end as `ConnectInfo<PeerInfo>`.
+pub async fn serve<F>(
+    listeners: Listeners,
+    router: Router,
+    tls: Option<Arc<ServerConfig>>,
+    shutdown: F,
+) -> io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let app = router.into_make_service_with_connect_info::<PeerInfo>();
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let stopped = move || {
+        let mut stop_rx = stop_rx.clone();
+        async move {
+            let _ = stop_rx.wait_for(|stop| *stop).await;
+        }
+    };
+
+    let mut servers: Vec<BoxFuture<'static, io::Result<()>>> = Vec::new();
+    match (listeners.tcp, tls) {
+        (Some(listener), Some(config)) => servers.push(
+            axum::serve(TlsListener::new(listener, config)?, app.clone())
+                .with_graceful_shutdown(stopped())
+                .into_future()
+                .boxed(),
+        ),
+        (Some(listener), None) => servers.push(
+            axum::serve(listener, app.clone())
+                .with_graceful_shutdown(stopped())
+                .into_future()
+                .boxed(),
+        ),
+        (None, _) => {}
+    }
+    if let Some(listener) = listeners.unix {
+        servers.push(
+            axum::serve(listener, app)
+                .with_graceful_shutdown(stopped())
+                .into_future()
+                .boxed(),
+        );
+    }
+    if servers.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no TCP address or unix socket to listen on",
+        ));
+    }
+
+    let signal = async move {
+        shutdown.await;
+        let _ = stop_tx.send(true);
+    };
+    let (_, served) = tokio::join!(signal, futures::future::try_join_all(servers));
+    served.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        routing::{get, post},
+        Extension,
+    };
+    use std::os::unix::fs::MetadataExt;
+
+    use tower::ServiceExt;
+
+    use super::*;
+
+    /// Tokens `read-token`, `mount-token` (given by digest) and `admin-token`, and one client.
+    fn auth_file() -> String {
+        format!(
+            r#"
+            [[token]]
+            name = "viewer"
+            scope = "read"
+            token = "read-token"
+
+            [[token]]
+            name = "ci"
+            scope = "mount"
+            sha256 = "{}"
+
+            [[token]]
+            name = "ops"
+            scope = "admin"
+            token = "admin-token"
+
+            [[client]]
+            name = "build-host-1"
+            scope = "mount"
+            "#,
+            hex::encode(token_digest("mount-token"))
+        )
+    }
+
+    async fn whoami(caller: Option<Extension<Caller>>) -> String {
+        caller.map(|Extension(c)| c.name).unwrap_or_default()
+    }
+
+    fn app(policy: AuthPolicy) -> Router {
+        let router = Router::new()
+            .route("/mounts", get(whoami).post(whoami))
+            .route("/shutdown", post(whoami));
+        AuthGuard::new(Arc::new(policy), antares_scope)
+            .protect(router)
+            .route("/health", get(|| async { "ok" }))
+    }
+
+    async fn call(
+        app: &Router,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+    ) -> (StatusCode, String) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn test_auth_file_is_validated() {
+        let policy = AuthPolicy::from_toml(&auth_file()).unwrap();
+        assert!(!policy.is_open());
+        assert_eq!(policy.tokens.len(), 3);
+        assert_eq!(policy.clients["build-host-1"].scope, Scope::Mount);
+        assert!(AuthPolicy::from_toml("").unwrap().tokens.is_empty());
+        assert!(!AuthPolicy::from_toml("").unwrap().is_open());
+
+        for invalid in [
+            "[[token]]\nname = \"a\"\nscope = \"read\"",
+            "[[token]]\nname = \"a\"\nscope = \"read\"\ntoken = \"x\"\nsha256 = \"00\"",
+            "[[token]]\nname = \"a\"\nscope = \"read\"\nsha256 = \"abc\"",
+            "[[token]]\nname = \"a\"\nscope = \"root\"\ntoken = \"x\"",
+            "[[token]]\nname = \"a\"\nscope = \"read\"\ntoken = \"x\"\n\
+             [[token]]\nname = \"b\"\nscope = \"admin\"\ntoken = \"x\"",
+        ] {
+            assert!(AuthPolicy::from_toml(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routes_require_their_scope() {
+        let guarded = app(AuthPolicy::from_toml(&auth_file()).unwrap());
+
+        let (status, _) = call(&guarded, Method::GET, "/mounts", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = call(&guarded, Method::GET, "/mounts", Some("wrong")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            call(&guarded, Method::GET, "/health", None).await,
+            (StatusCode::OK, "ok".to_string())
+        );
+
+        assert_eq!(
+            call(&guarded, Method::GET, "/mounts", Some("read-token")).await,
+            (StatusCode::OK, "viewer".to_string())
+        );
+        let (status, body) = call(&guarded, Method::POST, "/mounts", Some("read-token")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(body.contains("FORBIDDEN"), "{body}");
+
+        assert_eq!(
+            call(&guarded, Method::POST, "/mounts", Some("mount-token")).await,
+            (StatusCode::OK, "ci".to_string())
+        );
+        let (status, _) = call(&guarded, Method::POST, "/shutdown", Some("mount-token")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(
+            call(&guarded, Method::POST, "/shutdown", Some("admin-token")).await,
+            (StatusCode::OK, "ops".to_string())
+        );
+
+        let open = app(AuthPolicy::open());
+        assert_eq!(
+            call(&open, Method::POST, "/shutdown", None).await,
+            (StatusCode::OK, String::new())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate_identifies_caller() {
+        use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, Issuer, KeyPair};
+
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, pem: String| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, pem).unwrap();
+            path
+        };
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let issuer = Issuer::new(ca_params, &ca_key);
+
+        let server_key = KeyPair::generate().unwrap();
+        let server = CertificateParams::new(vec!["localhost".to_string()])
+            .unwrap()
+            .signed_by(&server_key, &issuer)
+            .unwrap();
+
+        let client_key = KeyPair::generate().unwrap();
+        let mut client_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        client_params
+            .distinguished_name
+            .push(DnType::CommonName, "build-host-1");
+        let client = client_params.signed_by(&client_key, &issuer).unwrap();
+
+        let tls = server_config(
+            &write("server.pem", server.pem()),
+            &write("server.key", server_key.serialize_pem()),
+            Some(&write("ca.pem", ca.pem())),
+        )
+        .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve(
+            Listeners {
+                tcp: Some(listener),
+                ..Listeners::default()
+            },
+            app(AuthPolicy::from_toml(&auth_file()).unwrap()),
+            Some(tls),
+            async move {
+                let _ = stopped.await;
+            },
+        ));
+
+        let url = format!("https://localhost:{port}/mounts");
+        let root = reqwest::Certificate::from_pem(ca.pem().as_bytes()).unwrap();
+        let identity = reqwest::Identity::from_pem(
+            format!("{}{}", client.pem(), client_key.serialize_pem()).as_bytes(),
+        )
+        .unwrap();
+        let with_cert = reqwest::Client::builder()
+            .tls_certs_only([root.clone()])
+            .identity(identity)
+            .build()
+            .unwrap();
+        let without_cert = reqwest::Client::builder()
+            .tls_certs_only([root])
+            .build()
+            .unwrap();
+
+        let response = with_cert.post(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "build-host-1");
+
+        let response = without_cert.post(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = without_cert
+            .get(&url)
+            .bearer_auth("read-token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "viewer");
+
+        stop.send(()).unwrap();
+        server.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_unix_socket_peer_scope_depends_on_uid() {
+        let policy = AuthPolicy::from_toml(&auth_file()).unwrap();
+        let scope_of = |policy: &AuthPolicy, uid: u32| {
+            let peer = PeerInfo {
+                uid: Some(uid),
+                ..PeerInfo::default()
+            };
+            policy
+                .authenticate(&HeaderMap::new(), Some(&peer))
+                .unwrap()
+                .unwrap()
+                .scope
+        };
+        let euid = unsafe { libc::geteuid() };
+        let other = if euid == 4242 { 4243 } else { 4242 };
+
+        assert_eq!(scope_of(&policy, 0), Scope::Admin);
+        assert_eq!(scope_of(&policy, euid), Scope::Admin);
+        assert_eq!(scope_of(&policy, other), Scope::Mount);
+        let policy = policy.with_socket_peer_scope(Scope::Read);
+        assert_eq!(scope_of(&policy, other), Scope::Read);
+
+        assert_eq!(" Admin ".parse::<Scope>(), Ok(Scope::Admin));
+        assert!("root".parse::<Scope>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket_peers_are_local_users() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("api.sock");
+        let listener = UnixSocketListener::bind(&path, 0o600).unwrap();
+        let meta = std::fs::metadata(&path).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+        assert_eq!(
+            UnixSocketListener::bind(&path, 0o600).unwrap_err().kind(),
+            io::ErrorKind::AddrInUse
+        );
+
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve(
+            Listeners {
+                unix: Some(listener),
+                ..Listeners::default()
+            },
+            app(AuthPolicy::from_toml(&auth_file()).unwrap()),
+            None,
+            async move {
+                let _ = stopped.await;
+            },
+        ));
+
+        let client = reqwest::Client::builder()
+            .unix_socket(path.as_path())
+            .build()
+            .unwrap();
+        let uid = std::fs::metadata(dir.path()).unwrap().uid();
+        let response = client
+            .post("http://localhost/shutdown")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), format!("uid:{uid}"));
+
+        // A token still takes precedence over the socket peer.
+        let response = client
+            .post("http://localhost/shutdown")
+            .bearer_auth("read-token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        stop.send(()).unwrap();
+        server.await.unwrap().unwrap();
+        assert!(!path.exists());
+    }
+}
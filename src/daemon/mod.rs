@@ -17,6 +17,8 @@ use crate::{
     util::{config, GPath},
 };
 pub mod antares;
+pub mod auth;
+pub mod webhook;
 //mod git;
 
 const SUCCESS: &str = "Success";
@@ -170,14 +172,45 @@ pub async fn daemon_main(
     fuse: Arc<MegaFuse>,
     manager: ScorpioManager,
     shutdown_rx: oneshot::Receiver<()>,
-    bind_addr: SocketAddr,
+    bind_addr: Option<SocketAddr>,
 ) {
     let inner = ScoState {
         fuse,
         manager: Arc::new(Mutex::new(manager)),
         tasks: Arc::new(DashMap::new()), // Initialize empty task tracking map
     };
-    let mut app = Router::new()
+    let (policy, tls) = match auth::AuthPolicy::from_global_config()
+        .and_then(|policy| Ok((policy, auth::tls_from_global_config()?)))
+    {
+        Ok(settings) => settings,
+        Err(e) => {
+            tracing::error!("Invalid HTTP auth/TLS configuration: {}", e);
+            return;
+        }
+    };
+    if let Some(addr) = bind_addr.filter(|addr| policy.is_open() && !addr.ip().is_loopback()) {
+        tracing::warn!(
+            "HTTP API on {} accepts unauthenticated requests; set http_auth_file to restrict it",
+            addr
+        );
+    }
+    let mut listeners = auth::Listeners::default();
+    if let Some(path) = config::scorpio_socket() {
+        match auth::UnixSocketListener::bind(path.as_ref(), config::http_socket_mode()) {
+            Ok(listener) => listeners.unix = Some(listener),
+            Err(e) => {
+                tracing::error!("Failed to bind HTTP API socket {}: {}", path, e);
+                return;
+            }
+        }
+    }
+    if bind_addr.is_none() && listeners.unix.is_none() {
+        tracing::error!("HTTP API has no TCP address and scorpio_socket is not set");
+        return;
+    }
+    let policy = Arc::new(policy);
+
+    let app = Router::new()
         .route("/api/fs/mount", post(mount_handler))
         .route("/api/fs/mpoint", get(mounts_handler))
         .route("/api/fs/select/{request_id}", get(select_handler))
@@ -188,32 +221,44 @@ pub async fn daemon_main(
         // and are currently disabled here. To enable them, merge the
         // router returned by `daemon::git::router()` into this `app`.
         .with_state(inner);
+    let app = auth::AuthGuard::new(policy.clone(), auth::scorpio_scope).protect(app);
 
     // Antares route - create service with new Dicfuse instance
-    let antares_service = Arc::new(antares::AntaresServiceImpl::new(None).await);
+    let antares_service = match antares::AntaresServiceImpl::new(None).await {
+        Ok(service) => Arc::new(service),
+        Err(e) => {
+            tracing::error!("Failed to start Antares service: {}", e);
+            return;
+        }
+    };
     let antares_service_for_shutdown = antares_service.clone();
-    let antares_daemon = antares::AntaresDaemon::new(antares_service);
+    let antares_daemon = antares::AntaresDaemon::new(antares_service).with_auth(policy);
     let antares_router = antares_daemon.router();
+    let shutdown_requested = antares_daemon.shutdown_requested();
     let app = app.nest("/antares", antares_router);
 
-    let listener = tokio::net::TcpListener::bind(bind_addr).await.unwrap();
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            let _ = shutdown_rx.await;
-            tracing::info!("HTTP server shutdown requested; running Antares shutdown cleanup");
-            match tokio::time::timeout(
-                std::time::Duration::from_secs(15),
-                antares_service_for_shutdown.shutdown_cleanup_impl(),
-            )
-            .await
-            {
-                Ok(Ok(())) => tracing::info!("Antares shutdown cleanup completed"),
-                Ok(Err(e)) => tracing::warn!("Antares shutdown cleanup failed: {:?}", e),
-                Err(_) => tracing::warn!("Antares shutdown cleanup timed out"),
-            }
-        })
+    if let Some(bind_addr) = bind_addr {
+        listeners.tcp = Some(tokio::net::TcpListener::bind(bind_addr).await.unwrap());
+    }
+    auth::serve(listeners, app, tls, async move {
+        tokio::select! {
+            _ = shutdown_rx => {}
+            _ = shutdown_requested => {}
+        }
+        tracing::info!("HTTP server shutdown requested; running Antares shutdown cleanup");
+        match tokio::time::timeout(
+            std::time::Duration::from_secs(15),
+            antares_service_for_shutdown.shutdown_cleanup_impl(),
+        )
         .await
-        .unwrap()
+        {
+            Ok(Ok(())) => tracing::info!("Antares shutdown cleanup completed"),
+            Ok(Err(e)) => tracing::warn!("Antares shutdown cleanup failed: {:?}", e),
+            Err(_) => tracing::warn!("Antares shutdown cleanup timed out"),
+        }
+    })
+    .await
+    .unwrap()
 }
 
 /// Asynchronous mount handler for clients.
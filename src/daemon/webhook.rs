@@ -0,0 +1,422 @@
+//! Webhook delivery of Antares mount lifecycle events.
+//!
+//! Every `MountLifecycle` transition and every removal is POSTed as JSON to the
+//! subscribers listed in `antares_webhook_urls` and to the `callback_url` of the mount
+//! it concerns. When `antares_webhook_secret` is set, the body is signed with
+//! HMAC-SHA256 and the signature sent as `X-Antares-Signature: sha256=<hex>`.
+//!
+//! Deliveries run in their own tasks and are retried with exponential backoff on
+//! connection errors, timeouts, `408`, `429` and `5xx`, so a slow subscriber never holds
+//! up the daemon or other subscribers. Receivers must therefore not rely on arrival
+//! order; `epoch_ms` orders the events of one mount.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use reqwest::{Client, StatusCode};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, RwLock};
+use uuid::Uuid;
+
+use super::antares::{MountEvent, MountEventKind};
+
+/// Header carrying `sha256=<hex HMAC of the body>`.
+pub const SIGNATURE_HEADER: &str = "X-Antares-Signature";
+/// Header carrying the event name (`state` or `removed`).
+pub const EVENT_HEADER: &str = "X-Antares-Event";
+/// Header carrying the delivery id, identical across retries of one delivery.
+pub const DELIVERY_HEADER: &str = "X-Antares-Delivery";
+
+/// Where and how lifecycle events are delivered.
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// Subscribers notified about every mount.
+    pub urls: Vec<String>,
+    /// HMAC key for the signature header; unsigned when absent.
+    pub secret: Option<String>,
+    /// Attempts per delivery, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled after each further failure.
+    pub initial_backoff: Duration,
+    /// Cap on the delay between retries.
+    pub max_backoff: Duration,
+    /// Timeout of a single attempt.
+    pub timeout: Duration,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            secret: None,
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Subscribers, secret and attempt count from the global config.
+    pub fn from_global_config() -> Self {
+        Self {
+            urls: crate::util::config::antares_webhook_urls(),
+            secret: crate::util::config::antares_webhook_secret(),
+            max_attempts: crate::util::config::antares_webhook_max_attempts().max(1),
+            ..Self::default()
+        }
+    }
+}
+
+/// Body of a webhook request.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WebhookPayload {
+    /// Unique per delivery; repeated unchanged on retries.
+    pub delivery_id: Uuid,
+    /// Build task the mount belongs to, if any.
+    pub job_id: Option<String>,
+    #[serde(flatten)]
+    pub event: MountEvent,
+}
+
+/// `sha256=<hex>` HMAC-SHA256 of `body` under `secret`.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+    format!("sha256={}", hex::encode(hmac::sign(&key, body).as_ref()))
+}
+
+/// Per-mount delivery details, registered when the mount is created.
+#[derive(Debug, Clone, Default)]
+struct MountHook {
+    callback_url: Option<String>,
+    job_id: Option<String>,
+}
+
+/// Delivers mount events to the configured subscribers and per-mount callbacks.
+#[derive(Clone)]
+pub struct Webhooks {
+    config: Arc<WebhookConfig>,
+    client: Client,
+    mounts: Arc<RwLock<HashMap<Uuid, MountHook>>>,
+}
+
+impl Webhooks {
+    pub fn new(config: WebhookConfig) -> Self {
+        let client = Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            config: Arc::new(config),
+            client,
+            mounts: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Remember the callback URL and job id of a mount until it is removed.
+    pub async fn register(
+        &self,
+        mount_id: Uuid,
+        callback_url: Option<String>,
+        job_id: Option<String>,
+    ) {
+        self.mounts.write().await.insert(
+            mount_id,
+            MountHook {
+                callback_url,
+                job_id,
+            },
+        );
+    }
+
+    /// Deliver `events` until the channel closes.
+    pub fn spawn(self, mut events: broadcast::Receiver<MountEvent>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => self.dispatch(event).await,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!(
+                            missed,
+                            "antares webhook: dropped events, subscriber lagged"
+                        );
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Start delivering one event to everyone interested in it.
+    async fn dispatch(&self, event: MountEvent) {
+        let hook = match event.kind {
+            MountEventKind::State { .. } => self.mounts.read().await.get(&event.mount_id).cloned(),
+            MountEventKind::Removed => self.mounts.write().await.remove(&event.mount_id),
+            _ => return,
+        }
+        .unwrap_or_default();
+
+        let targets: Vec<String> = self
+            .config
+            .urls
+            .iter()
+            .cloned()
+            .chain(hook.callback_url)
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+
+        let name = event.kind.name();
+        let payload = WebhookPayload {
+            delivery_id: Uuid::new_v4(),
+            job_id: hook.job_id,
+            event,
+        };
+        let body = match serde_json::to_vec(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::error!(error = %e, "antares webhook: failed to encode event");
+                return;
+            }
+        };
+        let signature = self
+            .config
+            .secret
+            .as_ref()
+            .map(|secret| sign(secret.as_bytes(), &body));
+
+        for url in targets {
+            let delivery = Delivery {
+                client: self.client.clone(),
+                config: self.config.clone(),
+                url,
+                name,
+                delivery_id: payload.delivery_id,
+                mount_id: payload.event.mount_id,
+                body: body.clone(),
+                signature: signature.clone(),
+            };
+            tokio::spawn(delivery.run());
+        }
+    }
+}
+
+/// One event on its way to one URL.
+struct Delivery {
+    client: Client,
+    config: Arc<WebhookConfig>,
+    url: String,
+    name: &'static str,
+    delivery_id: Uuid,
+    mount_id: Uuid,
+    body: Vec<u8>,
+    signature: Option<String>,
+}
+
+impl Delivery {
+    /// Send with retries; returns whether the subscriber accepted the event.
+    async fn run(self) -> bool {
+        let mut backoff = self.config.initial_backoff;
+        for attempt in 1..=self.config.max_attempts {
+            let (error, retryable) = match self.attempt().await {
+                Ok(status) if status.is_success() => {
+                    tracing::info!(
+                        url = %self.url,
+                        mount_id = %self.mount_id,
+                        delivery_id = %self.delivery_id,
+                        event = self.name,
+                        attempt,
+                        "antares webhook: delivered"
+                    );
+                    return true;
+                }
+                Ok(status) => (
+                    format!("status {}", status),
+                    status.is_server_error()
+                        || status == StatusCode::REQUEST_TIMEOUT
+                        || status == StatusCode::TOO_MANY_REQUESTS,
+                ),
+                Err(e) => (e.to_string(), true),
+            };
+            if !retryable || attempt == self.config.max_attempts {
+                tracing::error!(
+                    url = %self.url,
+                    mount_id = %self.mount_id,
+                    delivery_id = %self.delivery_id,
+                    event = self.name,
+                    attempt,
+                    error = %error,
+                    "antares webhook: giving up"
+                );
+                return false;
+            }
+            tracing::warn!(
+                url = %self.url,
+                mount_id = %self.mount_id,
+                delivery_id = %self.delivery_id,
+                event = self.name,
+                attempt,
+                error = %error,
+                retry_in_ms = backoff.as_millis(),
+                "antares webhook: delivery failed, retrying"
+            );
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(self.config.max_backoff);
+        }
+        false
+    }
+
+    async fn attempt(&self) -> reqwest::Result<StatusCode> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, self.name)
+            .header(DELIVERY_HEADER, self.delivery_id.to_string())
+            .body(self.body.clone());
+        if let Some(signature) = &self.signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+        Ok(request.send().await?.status())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use axum::{extract::State, http::HeaderMap, routing::post, Router};
+
+    use super::*;
+    use crate::daemon::antares::{MountEvents, MountLifecycle};
+
+    #[test]
+    fn test_sign_matches_rfc4231() {
+        assert_eq!(
+            sign(b"Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    /// Headers and body of one accepted request.
+    type Received = (HeaderMap, Vec<u8>);
+
+    /// Subscriber that fails the first `failures` requests and records the rest.
+    #[derive(Clone, Default)]
+    struct Subscriber {
+        failures: Arc<Mutex<u32>>,
+        received: Arc<Mutex<Vec<Received>>>,
+    }
+
+    async fn receive(
+        State(sub): State<Subscriber>,
+        headers: HeaderMap,
+        body: axum::body::Bytes,
+    ) -> StatusCode {
+        let mut failures = sub.failures.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+        sub.received.lock().unwrap().push((headers, body.to_vec()));
+        StatusCode::NO_CONTENT
+    }
+
+    async fn serve(sub: Subscriber) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/hook", post(receive)).with_state(sub);
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}/hook", addr)
+    }
+
+    fn test_config(urls: Vec<String>) -> WebhookConfig {
+        WebhookConfig {
+            urls,
+            secret: Some("s3cret".into()),
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(20),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_delivery_is_retried_until_accepted() {
+        let global = Subscriber::default();
+        *global.failures.lock().unwrap() = 2;
+        let callback = Subscriber::default();
+        let global_url = serve(global.clone()).await;
+        let callback_url = serve(callback.clone()).await;
+
+        let webhooks = Webhooks::new(test_config(vec![global_url]));
+        let events = MountEvents::new();
+        let handle = webhooks.clone().spawn(events.subscribe());
+
+        let mount_id = Uuid::new_v4();
+        webhooks
+            .register(mount_id, Some(callback_url), Some("job-1".into()))
+            .await;
+        events.emit(
+            mount_id,
+            MountEventKind::State {
+                state: MountLifecycle::Ready,
+            },
+        );
+        events.emit(mount_id, MountEventKind::PreloadStarted);
+        events.emit(mount_id, MountEventKind::Removed);
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while global.received.lock().unwrap().len() < 2
+            || callback.received.lock().unwrap().len() < 2
+        {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "deliveries timed out"
+            );
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let received = global.received.lock().unwrap().clone();
+        let mut names = Vec::new();
+        for (headers, body) in &received {
+            assert_eq!(
+                headers[SIGNATURE_HEADER].to_str().unwrap(),
+                sign(b"s3cret", body)
+            );
+            let payload: WebhookPayload = serde_json::from_slice(body).unwrap();
+            assert_eq!(payload.event.mount_id, mount_id);
+            assert_eq!(payload.job_id.as_deref(), Some("job-1"));
+            names.push(headers[EVENT_HEADER].to_str().unwrap().to_string());
+        }
+        names.sort();
+        assert_eq!(names, ["removed", "state"]);
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let url = serve(Subscriber::default())
+            .await
+            .replace("/hook", "/missing");
+        let config = WebhookConfig {
+            initial_backoff: Duration::from_secs(30),
+            ..test_config(Vec::new())
+        };
+        let delivery = Delivery {
+            client: Client::new(),
+            config: Arc::new(config),
+            url,
+            name: "state",
+            delivery_id: Uuid::new_v4(),
+            mount_id: Uuid::new_v4(),
+            body: b"{}".to_vec(),
+            signature: None,
+        };
+        let started = std::time::Instant::now();
+        assert!(!delivery.run().await);
+        assert!(started.elapsed() < Duration::from_secs(30));
+    }
+}
@@ -7,7 +7,7 @@ use std::{
 use dashmap::DashMap;
 use tokio::sync::OnceCell;
 
-use super::Dicfuse;
+use super::{Dicfuse, DicfuseTuning};
 use crate::util::config;
 
 /// Global Dicfuse instance manager.
@@ -48,11 +48,15 @@ static DICFUSE_CACHE: OnceCell<DashMap<DicfuseCacheKey, Arc<OnceCell<Arc<Dicfuse
 struct DicfuseCacheKey {
     store_root: String,
     base_path: String,
+    /// `DicfuseTuning::fingerprint()`; empty for untuned views.
+    tuning: String,
 }
 
 impl PartialEq for DicfuseCacheKey {
     fn eq(&self, other: &Self) -> bool {
-        self.store_root == other.store_root && self.base_path == other.base_path
+        self.store_root == other.store_root
+            && self.base_path == other.base_path
+            && self.tuning == other.tuning
     }
 }
 
@@ -60,6 +64,7 @@ impl Hash for DicfuseCacheKey {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.store_root.hash(state);
         self.base_path.hash(state);
+        self.tuning.hash(state);
     }
 }
 
@@ -137,13 +142,39 @@ impl DicfuseManager {
         Self::for_base_path_with_store_root(base_path, &store_root).await
     }
 
+    /// Same as `for_base_path`, but with per-view overrides of the global Dicfuse knobs.
+    ///
+    /// An empty `tuning` is exactly `for_base_path`. Otherwise the view is shared only with
+    /// callers asking for the same `(base_path, tuning)` pair, and gets its own on-disk store
+    /// (even for "/"), since load depth and refresh TTL shape what the store holds.
+    pub async fn for_base_path_with_tuning(
+        base_path: &str,
+        tuning: &DicfuseTuning,
+    ) -> Arc<Dicfuse> {
+        let store_root = config::store_path().to_string();
+        Self::for_base_path_with_store_root_and_tuning(base_path, &store_root, tuning).await
+    }
+
     /// Same as `for_base_path`, but allows explicitly specifying the store root directory.
     /// Useful for tests that want isolated on-disk state.
     pub async fn for_base_path_with_store_root(base_path: &str, store_root: &str) -> Arc<Dicfuse> {
+        Self::for_base_path_with_store_root_and_tuning(
+            base_path,
+            store_root,
+            &DicfuseTuning::default(),
+        )
+        .await
+    }
+
+    async fn for_base_path_with_store_root_and_tuning(
+        base_path: &str,
+        store_root: &str,
+        tuning: &DicfuseTuning,
+    ) -> Arc<Dicfuse> {
         let normalized = normalize_base_path(base_path);
 
         // For the root view, prefer the global singleton when using the default store_root.
-        if normalized == "/" && store_root == config::store_path() {
+        if normalized == "/" && store_root == config::store_path() && tuning.is_empty() {
             return Self::global().await;
         }
 
@@ -152,6 +183,7 @@ impl DicfuseManager {
         let key = DicfuseCacheKey {
             store_root: store_root.to_string(),
             base_path: normalized.clone(),
+            tuning: tuning.fingerprint(),
         };
 
         let cell = cache
@@ -162,12 +194,16 @@ impl DicfuseManager {
         cell.get_or_init(|| async move {
             // Use a deterministic per-base_path directory so multiple mounts can share it.
             // Keep it stable across process restarts for cache reuse.
-            let store_path =
-                super::compute_store_dir_for_base_path_with_store_root(store_root, &normalized);
+            let store_path = if tuning.is_empty() {
+                super::compute_store_dir_for_base_path_with_store_root(store_root, &normalized)
+            } else {
+                super::compute_store_dir_for_tuned_view(store_root, &normalized, tuning)
+            };
             let _ = std::fs::create_dir_all(&store_path);
 
             let dicfuse = Arc::new(
-                Dicfuse::new_with_base_path_and_store_path(&normalized, &store_path).await,
+                Dicfuse::new_with_base_path_store_path_and_tuning(&normalized, &store_path, tuning)
+                    .await,
             );
 
             // IMPORTANT: Trigger import_arc immediately so the directory tree starts loading.
@@ -187,6 +223,20 @@ impl DicfuseManager {
         .clone()
     }
 
+    /// Forget a per-base_path instance so the next request for its view opens a fresh
+    /// one. Returns whether `dicfuse` was cached; the global instance never is.
+    ///
+    /// Only evict instances nothing else holds: the sled DBs stay locked until the last
+    /// reference is dropped, and reopening a view before that fails.
+    pub fn evict(dicfuse: &Arc<Dicfuse>) -> bool {
+        let Some(cache) = DICFUSE_CACHE.get() else {
+            return false;
+        };
+        let before = cache.len();
+        cache.retain(|_, cell| !cell.get().is_some_and(|d| Arc::ptr_eq(d, dicfuse)));
+        cache.len() != before
+    }
+
     /// Create a new Dicfuse instance (for testing or special cases).
     ///
     /// This method creates a new, isolated Dicfuse instance that is not
@@ -5,6 +5,7 @@ pub mod manager;
 mod size_store;
 pub mod store;
 mod tree_store;
+pub mod tuning;
 
 use std::{
     ffi::{OsStr, OsString},
@@ -13,6 +14,7 @@ use std::{
 };
 
 pub use manager::DicfuseManager;
+pub use tuning::DicfuseTuning;
 
 use crate::{manager::fetch::fetch_tree, util::config};
 
@@ -39,6 +41,30 @@ pub(crate) fn compute_store_dir_for_base_path_with_store_root(
     }
 }
 
+/// Compute the backing store directory for a tuned view of `base_path`.
+///
+/// Tuned views never share a store with the untuned one: their sled DBs live under
+/// "{store_root}/dicfuse/tuned/{sha256(base_path)[:16]}-{fingerprint}".
+pub(crate) fn compute_store_dir_for_tuned_view(
+    store_root: &str,
+    base_path: &str,
+    tuning: &DicfuseTuning,
+) -> String {
+    let normalized = if base_path.is_empty() || base_path == "/" {
+        "/".to_string()
+    } else {
+        base_path.trim_end_matches('/').to_string()
+    };
+    let digest = ring::digest::digest(&ring::digest::SHA256, normalized.as_bytes());
+    let hex = hex::encode(digest.as_ref());
+    format!(
+        "{}/dicfuse/tuned/{}-{}",
+        store_root,
+        &hex[..16],
+        tuning.fingerprint()
+    )
+}
+
 use async_trait::async_trait;
 use git_internal::internal::object::tree::TreeItemMode;
 use libfuse_fs::{
@@ -55,6 +81,8 @@ use tree_store::StorageItem;
 
 pub struct Dicfuse {
     readable: bool,
+    /// Per-view kernel TTL override; `None` uses the global config.
+    reply_ttl: Option<Duration>,
     pub store: Arc<DictionaryStore>,
 }
 unsafe impl Sync for Dicfuse {}
@@ -202,6 +230,7 @@ impl Dicfuse {
     pub async fn new() -> Self {
         Self {
             readable: config::dicfuse_readable(),
+            reply_ttl: None,
             store: DictionaryStore::new().await.into(), // Assuming DictionaryStore has a new() method
         }
     }
@@ -209,6 +238,7 @@ impl Dicfuse {
     pub async fn new_with_store_path(store_path: &str) -> Self {
         Self {
             readable: config::dicfuse_readable(),
+            reply_ttl: None,
             store: DictionaryStore::new_with_store_path(store_path)
                 .await
                 .into(),
@@ -222,12 +252,30 @@ impl Dicfuse {
     pub async fn new_with_base_path_and_store_path(base_path: &str, store_path: &str) -> Self {
         Self {
             readable: config::dicfuse_readable(),
+            reply_ttl: None,
             store: DictionaryStore::new_with_base_path_and_store_path(base_path, store_path)
                 .await
                 .into(),
         }
     }
 
+    /// Same as [`Self::new_with_base_path_and_store_path`], with per-view overrides applied.
+    pub async fn new_with_base_path_store_path_and_tuning(
+        base_path: &str,
+        store_path: &str,
+        tuning: &DicfuseTuning,
+    ) -> Self {
+        Self {
+            readable: config::dicfuse_readable(),
+            reply_ttl: tuning.reply_ttl(),
+            store: DictionaryStore::new_with_base_path_store_path_and_tuning(
+                base_path, store_path, tuning,
+            )
+            .await
+            .into(),
+        }
+    }
+
     /// Create a new Dicfuse instance with a base path for subdirectory mounting.
     ///
     /// When `base_path` is set (e.g., "/third-party/mega"), the filesystem will:
@@ -248,6 +296,7 @@ impl Dicfuse {
     pub async fn new_with_base_path(base_path: &str) -> Self {
         Self {
             readable: config::dicfuse_readable(),
+            reply_ttl: None,
             store: DictionaryStore::new_with_base_path(base_path).await.into(),
         }
     }
@@ -260,6 +309,9 @@ impl Dicfuse {
     }
 
     pub(crate) fn reply_ttl(&self) -> Duration {
+        if let Some(ttl) = self.reply_ttl {
+            return ttl;
+        }
         let is_subdir_mount = !(self.base_path().is_empty() || self.base_path() == "/");
         let ttl_secs = if is_subdir_mount {
             config::antares_dicfuse_reply_ttl_secs()
@@ -27,6 +27,7 @@ use super::{
     content_store::ContentStorage,
     size_store::SizeStorage,
     tree_store::{StorageItem, TreeStorage},
+    tuning::DicfuseTuning,
 };
 use crate::{
     util::{config, GPath},
@@ -884,25 +885,46 @@ impl DictionaryStore {
     /// (e.g., different Antares mounts) are created concurrently. Each instance can use a
     /// dedicated `store_path` directory to keep its sled DB files isolated.
     pub async fn new_with_base_path_and_store_path(base_path: &str, store_path: &str) -> Self {
+        Self::new_with_base_path_store_path_and_tuning(
+            base_path,
+            store_path,
+            &DicfuseTuning::default(),
+        )
+        .await
+    }
+
+    /// Same as [`Self::new_with_base_path_and_store_path`], with per-view overrides applied
+    /// on top of the global config.
+    pub async fn new_with_base_path_store_path_and_tuning(
+        base_path: &str,
+        store_path: &str,
+        tuning: &DicfuseTuning,
+    ) -> Self {
         let tree_store =
             TreeStorage::new_with_path(store_path).expect("Failed to create TreeStorage");
         let store_dir = store_path.to_string();
         let is_subdir_mount = !(base_path.is_empty() || base_path == "/");
-        let max_depth = if is_subdir_mount {
-            config::antares_load_dir_depth()
-        } else {
-            config::load_dir_depth()
-        };
-        let stat_mode = if is_subdir_mount {
-            config::antares_dicfuse_stat_mode()
-        } else {
-            config::dicfuse_stat_mode()
-        };
-        let dir_sync_ttl = if is_subdir_mount {
-            Duration::from_secs(config::antares_dicfuse_dir_sync_ttl_secs())
-        } else {
-            Duration::from_secs(config::dicfuse_dir_sync_ttl_secs())
-        };
+        let max_depth = tuning.load_dir_depth.unwrap_or_else(|| {
+            if is_subdir_mount {
+                config::antares_load_dir_depth()
+            } else {
+                config::load_dir_depth()
+            }
+        });
+        let stat_mode = tuning.stat_mode.unwrap_or_else(|| {
+            if is_subdir_mount {
+                config::antares_dicfuse_stat_mode()
+            } else {
+                config::dicfuse_stat_mode()
+            }
+        });
+        let dir_sync_ttl = tuning.dir_sync_ttl().unwrap_or_else(|| {
+            if is_subdir_mount {
+                Duration::from_secs(config::antares_dicfuse_dir_sync_ttl_secs())
+            } else {
+                Duration::from_secs(config::dicfuse_dir_sync_ttl_secs())
+            }
+        });
         let open_buff_max_bytes = if is_subdir_mount {
             config::antares_dicfuse_open_buff_max_bytes()
         } else {
@@ -973,6 +995,11 @@ impl DictionaryStore {
         &self.base_path
     }
 
+    /// Directory holding this store's sled DBs.
+    pub fn store_dir(&self) -> &str {
+        &self.store_dir
+    }
+
     /// Convert a user-visible path to the real path in the monorepo.
     ///
     /// When base_path = "/third-party/mega":
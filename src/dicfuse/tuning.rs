@@ -0,0 +1,98 @@
+//! Per-view overrides for Dicfuse knobs that are otherwise process-global.
+//!
+//! An empty [`DicfuseTuning`] means "use `scorpio.toml`". A non-empty one gets its own
+//! Dicfuse instance (and on-disk store) per `(base_path, tuning)` pair, because the
+//! directory cache depth and refresh TTL are properties of the store, not of a mount.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::config::DicfuseStatMode;
+
+/// Optional overrides applied when building a Dicfuse view.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DicfuseTuning {
+    /// `fast` never probes sizes over the network; `accurate` does.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stat_mode: Option<DicfuseStatMode>,
+    /// Directory depth loaded into memory before the view is reported ready.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load_dir_depth: Option<usize>,
+    /// Kernel entry/attr TTL handed out by this view.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_ttl_secs: Option<u64>,
+    /// How long a loaded directory is trusted before it is refreshed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir_sync_ttl_secs: Option<u64>,
+}
+
+impl DicfuseTuning {
+    /// Whether every knob falls back to the global config.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn reply_ttl(&self) -> Option<Duration> {
+        self.reply_ttl_secs.map(Duration::from_secs)
+    }
+
+    pub fn dir_sync_ttl(&self) -> Option<Duration> {
+        self.dir_sync_ttl_secs.map(Duration::from_secs)
+    }
+
+    /// Stable identifier used to key caches and on-disk store directories.
+    ///
+    /// Empty for an empty tuning so untuned views keep their existing keys.
+    pub fn fingerprint(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let opt = |v: Option<u64>| v.map(|v| v.to_string()).unwrap_or_else(|| "-".into());
+        let stat_mode = match self.stat_mode {
+            Some(DicfuseStatMode::Fast) => "fast",
+            Some(DicfuseStatMode::Accurate) => "accurate",
+            None => "-",
+        };
+        let canonical = format!(
+            "stat_mode={};load_dir_depth={};reply_ttl={};dir_sync_ttl={}",
+            stat_mode,
+            opt(self.load_dir_depth.map(|v| v as u64)),
+            opt(self.reply_ttl_secs),
+            opt(self.dir_sync_ttl_secs),
+        );
+        let digest = ring::digest::digest(&ring::digest::SHA256, canonical.as_bytes());
+        hex::encode(digest.as_ref())[..16].to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_is_stable_and_distinguishes_tunings() {
+        assert_eq!(DicfuseTuning::default().fingerprint(), "");
+
+        let a = DicfuseTuning {
+            load_dir_depth: Some(1),
+            ..Default::default()
+        };
+        let b = DicfuseTuning {
+            load_dir_depth: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(a.fingerprint(), a.clone().fingerprint());
+        assert_eq!(a.fingerprint().len(), 16);
+        assert_ne!(a.fingerprint(), b.fingerprint());
+    }
+
+    #[test]
+    fn test_stat_mode_deserializes_lowercase() {
+        let tuning: DicfuseTuning =
+            serde_json::from_str(r#"{"stat_mode":"accurate","reply_ttl_secs":5}"#).unwrap();
+        assert_eq!(tuning.stat_mode, Some(DicfuseStatMode::Accurate));
+        assert_eq!(tuning.reply_ttl(), Some(Duration::from_secs(5)));
+        assert!(!tuning.is_empty());
+    }
+}
@@ -15,14 +15,13 @@
 //!     scorpio::util::config::init_config("scorpio.toml")?;
 //!     
 //!     // Create Antares service for managing mounts
-//!     let service = AntaresServiceImpl::new(None).await;
+//!     let daemon_service = std::sync::Arc::new(AntaresServiceImpl::new(None).await?);
 //!     
 //!     // Create HTTP daemon
-//!     let daemon = AntaresDaemon::new(std::sync::Arc::new(service));
+//!     let daemon = AntaresDaemon::new(daemon_service.clone());
 //!     
-//!     // Or use AntaresManager for direct mount operations
-//!     let paths = AntaresPaths::from_global_config();
-//!     let manager = AntaresManager::new(paths).await;
+//!     // Or use AntaresManager for direct mount operations on the same engine
+//!     let manager = AntaresManager::from_service(daemon_service);
 //!     
 //!     Ok(())
 //! }
@@ -39,7 +38,7 @@
 //! use std::path::PathBuf;
 //!
 //! #[tokio::main]
-//! async fn main() -> std::io::Result<()> {
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     // Initialize configuration first
 //!     scorpio::util::config::init_config("scorpio.toml").unwrap();
 //!     
@@ -52,11 +51,11 @@
 //!     );
 //!     
 //!     // Create manager
-//!     let manager = AntaresManager::new(paths).await;
+//!     let manager = AntaresManager::new(paths).await?;
 //!     
-//!     // Mount a job instance (mountpoint auto-generated at {mount_root}/{job_id})
-//!     let config = manager.mount_job("build-job-123", Some("cl-456")).await?;
-//!     println!("Mounted at: {}", config.mountpoint.display());
+//!     // Mount a job instance (mountpoint auto-generated at {mount_root}/{mount_id})
+//!     let status = manager.mount_job("build-job-123", Some("cl-456")).await?;
+//!     println!("Mounted at: {}", status.mountpoint);
 //!     
 //!     // ... do build work ...
 //!     
@@ -76,20 +75,20 @@
 //! use std::path::PathBuf;
 //!
 //! #[tokio::main]
-//! async fn main() -> std::io::Result<()> {
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     scorpio::util::config::init_config("scorpio.toml").unwrap();
 //!     
 //!     let paths = AntaresPaths::from_global_config();
-//!     let manager = AntaresManager::new(paths).await;
+//!     let manager = AntaresManager::new(paths).await?;
 //!     
 //!     // Mount to a custom directory (any path you choose)
-//!     let config = manager.mount_job_at(
+//!     let status = manager.mount_job_at(
 //!         "my-build",
 //!         "/home/user/workspace/my-project",  // custom mountpoint
 //!         None,                               // no CL layer
 //!     ).await?;
 //!     
-//!     println!("Mounted at: {}", config.mountpoint.display());
+//!     println!("Mounted at: {}", status.mountpoint);
 //!     // Output: Mounted at: /home/user/workspace/my-project
 //!     
 //!     // Unmount when done
@@ -150,7 +149,7 @@
 //!     scorpio::util::config::init_config("scorpio.toml")?;
 //!     
 //!     // Create service with mount recovery
-//!     let service = Arc::new(AntaresServiceImpl::new_with_recovery(None).await);
+//!     let service = Arc::new(AntaresServiceImpl::new_with_recovery(None).await?);
 //!     
 //!     // Start HTTP daemon
 //!     let daemon = AntaresDaemon::new(service);
@@ -199,25 +198,28 @@ pub mod util;
 /// ```
 pub mod prelude {
     // Antares core types
-    pub use crate::antares::{AntaresConfig, AntaresManager, AntaresPaths};
+    pub use crate::antares::{AntaresManager, AntaresPaths};
 
     // Antares FUSE layer
-    pub use crate::antares::fuse::AntaresFuse;
+    pub use crate::antares::{cl_stack::ClConflict, fuse::AntaresFuse, mem_upper::MemUpperLayer};
 
     // Dicfuse (read-only base layer)
-    pub use crate::dicfuse::DicfuseManager;
+    pub use crate::dicfuse::{DicfuseManager, DicfuseTuning};
 
     // Daemon types
     pub use crate::daemon::antares::{
         AntaresDaemon, AntaresService, AntaresServiceImpl, ApiError, BuildClRequest,
-        CreateMountRequest, ErrorBody, HealthResponse, MountCollection, MountCreated, MountLayers,
-        MountLifecycle, MountReadyResponse, MountStatus, PersistedMountState, PersistedState,
-        ServiceError,
+        CreateMountRequest, DeepPreloadMode, ErrorBody, ForkMountRequest, HealthResponse,
+        MountCollection, MountCreated, MountEvent, MountEventKind, MountEvents, MountLayers,
+        MountLifecycle, MountReadyResponse, MountStatus, MountTuning, MountUsage,
+        PersistedMountState, PersistedState, ServiceError, UpperKind,
     };
+    pub use crate::daemon::auth::{AuthPolicy, Caller, Scope};
+    pub use crate::daemon::webhook::{WebhookConfig, WebhookPayload, Webhooks};
 }
 
 // Re-export key antares types at crate root for convenience
-pub use antares::{AntaresConfig, AntaresManager, AntaresPaths};
+pub use antares::{AntaresManager, AntaresPaths};
 
 //const VFS_MAX_INO: u64 = 0xff_ffff_ffff_ffff;
 const READONLY_INODE: u64 = 0xffff_ffff;
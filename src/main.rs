@@ -24,6 +24,10 @@ struct Args {
     /// HTTP bind address for the daemon (Antares API lives under /antares/*)
     #[arg(long, default_value = "0.0.0.0:2725")]
     http_addr: SocketAddr,
+
+    /// Serve the HTTP API only on the unix socket set by `scorpio_socket`
+    #[arg(long)]
+    no_tcp: bool,
 }
 
 #[tokio::main]
@@ -60,10 +64,11 @@ async fn main() {
         Arc::new(fuse_interface),
         manager,
         shutdown_rx,
-        args.http_addr,
+        (!args.no_tcp).then_some(args.http_addr),
     ));
 
     let mut mount_finished = false;
+    let mut daemon_finished = false;
     tokio::select! {
         res = &mut mount_handle => {
             mount_finished = true;
@@ -71,19 +76,28 @@ async fn main() {
                 eprintln!("FUSE session ended with error: {e:?}");
             }
         }
+        res = &mut daemon_task => {
+            // Admin shutdown over HTTP (cleanup already ran) or a failed start.
+            daemon_finished = true;
+            if let Err(e) = res {
+                eprintln!("HTTP daemon task join failed: {e}");
+            }
+        }
         _ = shutdown_signal() => {
             // fallthrough to shutdown sequence below
         }
     }
 
     // Stop HTTP server first (this triggers Antares shutdown cleanup), then unmount the main workspace FS.
-    let _ = shutdown_tx.send(());
-    match tokio::time::timeout(std::time::Duration::from_secs(20), &mut daemon_task).await {
-        Ok(Ok(_)) => {}
-        Ok(Err(e)) => eprintln!("HTTP daemon task join failed: {e}"),
-        Err(_) => {
-            eprintln!("HTTP daemon shutdown timed out; aborting task");
-            daemon_task.abort();
+    if !daemon_finished {
+        let _ = shutdown_tx.send(());
+        match tokio::time::timeout(std::time::Duration::from_secs(20), &mut daemon_task).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => eprintln!("HTTP daemon task join failed: {e}"),
+            Err(_) => {
+                eprintln!("HTTP daemon shutdown timed out; aborting task");
+                daemon_task.abort();
+            }
         }
     }
 
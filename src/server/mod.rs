@@ -84,6 +84,53 @@ pub async fn mount_filesystem_with_antares_cache<
     fs: F,
     mountpoint: &OsStr,
     enable_antares_cache: bool,
+) -> MountHandle {
+    mount_filesystem_with_options(fs, mountpoint, enable_antares_cache, false).await
+}
+
+/// Mount `fs` with the kernel `ro` flag so every write fails with `EROFS` before it
+/// reaches the filesystem.
+pub async fn mount_read_only_filesystem<F: Filesystem + std::marker::Sync + Send + 'static>(
+    fs: F,
+    mountpoint: &OsStr,
+) -> MountHandle {
+    mount_filesystem_with_options(fs, mountpoint, false, true).await
+}
+
+/// Make sure `path` can be mounted over: create it when missing, otherwise require an
+/// empty, readable directory. Returns whether the directory was created here.
+pub fn prepare_mountpoint(path: &std::path::Path) -> std::io::Result<bool> {
+    let created = if path.exists() {
+        false
+    } else {
+        std::fs::create_dir_all(path).map_err(|e| {
+            std::io::Error::new(e.kind(), format!("failed to create mountpoint: {e}"))
+        })?;
+        true
+    };
+    if !path.is_dir() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "mountpoint is not a directory",
+        ));
+    }
+    let has_entries = std::fs::read_dir(path)
+        .map(|mut it| it.next().is_some())
+        .unwrap_or(true);
+    if has_entries {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "mountpoint is not empty or is inaccessible",
+        ));
+    }
+    Ok(created)
+}
+
+async fn mount_filesystem_with_options<F: Filesystem + std::marker::Sync + Send + 'static>(
+    fs: F,
+    mountpoint: &OsStr,
+    enable_antares_cache: bool,
+    read_only: bool,
 ) -> MountHandle {
     if let Err(e) = env_logger::try_init() {
         if !e.to_string().contains("initialized") {
@@ -93,23 +140,8 @@ pub async fn mount_filesystem_with_antares_cache<
     //let logfs = LoggingFileSystem::new(fs);
 
     let mount_path: OsString = OsString::from(mountpoint);
-    let path = std::path::Path::new(&mount_path);
-    if !path.exists() {
-        if let Err(e) = std::fs::create_dir_all(path) {
-            panic!("failed to create mountpoint: {}", e);
-        }
-    }
-    if !path.exists() {
-        panic!("mountpoint does not exist");
-    }
-    if !path.is_dir() {
-        panic!("mountpoint is not a directory");
-    }
-    let has_entries = std::fs::read_dir(path)
-        .map(|mut it| it.next().is_some())
-        .unwrap_or(true);
-    if has_entries {
-        panic!("mountpoint is not empty or is inaccessible");
+    if let Err(e) = prepare_mountpoint(std::path::Path::new(&mount_path)) {
+        panic!("{}", e);
     }
     let uid = unsafe { libc::getuid() };
     let gid = unsafe { libc::getgid() };
@@ -120,6 +152,9 @@ pub async fn mount_filesystem_with_antares_cache<
     if enable_antares_cache {
         apply_antares_cache_mount_options(&mut mount_options);
     }
+    if read_only {
+        mount_options.read_only(true);
+    }
 
     eprintln!(
         "[DEBUG] About to mount FUSE filesystem at: {:?}",
@@ -25,7 +25,6 @@ const DEFAULT_DICFUSE_IMPORT_CONCURRENCY: usize = 4;
 // TODO(perf):
 // - Re-tune TTL/timeout defaults from production lookup metrics.
 // - Split timeout knobs by request class (tree listing vs blob fetch).
-// - Support per-mount overrides instead of process-global defaults.
 
 /// Directory refresh TTL for base Dicfuse mounts.
 const DEFAULT_DICFUSE_DIR_SYNC_TTL_SECS: u64 = 5;
@@ -59,10 +58,30 @@ const DEFAULT_ANTARES_DICFUSE_REPLY_TTL_SECS: u64 = 60;
 const DEFAULT_ANTARES_DICFUSE_OPEN_BUFF_MAX_BYTES: u64 = 64 * 1024 * 1024; // 64MiB
 const DEFAULT_ANTARES_DICFUSE_OPEN_BUFF_MAX_FILES: usize = 1024;
 
+/// Per-mount budget for upper-layer checkpoints.
+const DEFAULT_ANTARES_CHECKPOINT_MAX_BYTES: u64 = 10 * 1024 * 1024 * 1024; // 10GiB
+
+/// Per-mount upper-layer quotas. `0` disables the limit.
+const DEFAULT_ANTARES_UPPER_MAX_BYTES: u64 = 0;
+const DEFAULT_ANTARES_UPPER_MAX_INODES: u64 = 0;
+
+/// Byte limit of in-memory upper layers when the request sets none (0 = unlimited).
+const DEFAULT_ANTARES_MEMORY_UPPER_MAX_BYTES: u64 = 1024 * 1024 * 1024; // 1GiB
+
+/// Attempts per webhook delivery, including the first one.
+const DEFAULT_ANTARES_WEBHOOK_MAX_ATTEMPTS: u32 = 5;
+/// Seconds between supervisor sweeps over live mounts (0 = no supervisor).
+const DEFAULT_ANTARES_SUPERVISOR_INTERVAL_SECS: u64 = 10;
+/// Orphans modified within this many seconds survive garbage collection.
+const DEFAULT_ANTARES_GC_RETENTION_SECS: u64 = 3600;
+/// Owner and group may connect to the daemon unix sockets.
+const DEFAULT_HTTP_SOCKET_MODE: u32 = 0o660;
+
 // Global configuration management
 static SCORPIO_CONFIG: OnceLock<ScorpioConfig> = OnceLock::new();
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum DicfuseStatMode {
     Fast,
     Accurate,
@@ -368,6 +387,30 @@ fn get_config() -> &'static ScorpioConfig {
             "antares_state_file".to_string(),
             format!("{base_path}/{DEFAULT_ANTARES_SUBDIR}/state.toml"),
         );
+        config.insert(
+            "antares_checkpoint_root".to_string(),
+            format!("{base_path}/{DEFAULT_ANTARES_SUBDIR}/checkpoints"),
+        );
+        config.insert(
+            "antares_blob_cache_root".to_string(),
+            format!("{base_path}/{DEFAULT_ANTARES_SUBDIR}/blobs"),
+        );
+        config.insert(
+            "antares_checkpoint_max_bytes".to_string(),
+            DEFAULT_ANTARES_CHECKPOINT_MAX_BYTES.to_string(),
+        );
+        config.insert(
+            "antares_upper_max_bytes".to_string(),
+            DEFAULT_ANTARES_UPPER_MAX_BYTES.to_string(),
+        );
+        config.insert(
+            "antares_upper_max_inodes".to_string(),
+            DEFAULT_ANTARES_UPPER_MAX_INODES.to_string(),
+        );
+        config.insert(
+            "antares_memory_upper_max_bytes".to_string(),
+            DEFAULT_ANTARES_MEMORY_UPPER_MAX_BYTES.to_string(),
+        );
 
         // Create required directories
         for path in [config["workspace"].as_str(), config["store_path"].as_str()] {
@@ -589,6 +632,201 @@ config_accessor!(
     DEFAULT_ANTARES_DICFUSE_REPLY_TTL_SECS
 );
 
+/// Root directory for upper-layer checkpoints.
+///
+/// Optional: falls back to a `checkpoints` directory next to `antares_upper_root`.
+pub fn antares_checkpoint_root() -> String {
+    match get_config().config.get("antares_checkpoint_root") {
+        Some(v) if !v.is_empty() => v.clone(),
+        _ => Path::new(antares_upper_root())
+            .parent()
+            .unwrap_or_else(|| Path::new("/"))
+            .join("checkpoints")
+            .to_string_lossy()
+            .into_owned(),
+    }
+}
+
+/// Root directory of the shared CL blob cache.
+///
+/// Optional: falls back to a `blobs` directory next to `antares_state_file`.
+pub fn antares_blob_cache_root() -> String {
+    match get_config().config.get("antares_blob_cache_root") {
+        Some(v) if !v.is_empty() => v.clone(),
+        _ => Path::new(antares_state_file())
+            .parent()
+            .unwrap_or_else(|| Path::new("/"))
+            .join("blobs")
+            .to_string_lossy()
+            .into_owned(),
+    }
+}
+
+config_accessor!(
+    antares_checkpoint_max_bytes,
+    "antares_checkpoint_max_bytes",
+    u64,
+    DEFAULT_ANTARES_CHECKPOINT_MAX_BYTES
+);
+
+config_accessor!(
+    antares_upper_max_bytes,
+    "antares_upper_max_bytes",
+    u64,
+    DEFAULT_ANTARES_UPPER_MAX_BYTES
+);
+
+config_accessor!(
+    antares_upper_max_inodes,
+    "antares_upper_max_inodes",
+    u64,
+    DEFAULT_ANTARES_UPPER_MAX_INODES
+);
+
+config_accessor!(
+    antares_memory_upper_max_bytes,
+    "antares_memory_upper_max_bytes",
+    u64,
+    DEFAULT_ANTARES_MEMORY_UPPER_MAX_BYTES
+);
+
+/// Webhook subscribers notified of every Antares mount lifecycle event.
+///
+/// Optional: a comma-separated list of URLs; empty by default.
+pub fn antares_webhook_urls() -> Vec<String> {
+    get_config()
+        .config
+        .get("antares_webhook_urls")
+        .map(|v| {
+            v.split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Directories under which `POST /mounts` may place a caller-chosen `mountpoint`.
+///
+/// Optional: a comma-separated list of absolute paths; caller-chosen mountpoints are
+/// rejected while it is empty.
+pub fn antares_mountpoint_prefixes() -> Vec<String> {
+    get_config()
+        .config
+        .get("antares_mountpoint_prefixes")
+        .map(|v| {
+            v.split(',')
+                .map(str::trim)
+                .filter(|prefix| !prefix.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Key used to sign webhook bodies with HMAC-SHA256. Optional: unsigned when unset.
+pub fn antares_webhook_secret() -> Option<String> {
+    get_config()
+        .config
+        .get("antares_webhook_secret")
+        .filter(|v| !v.is_empty())
+        .cloned()
+}
+
+config_accessor!(
+    antares_webhook_max_attempts,
+    "antares_webhook_max_attempts",
+    u32,
+    DEFAULT_ANTARES_WEBHOOK_MAX_ATTEMPTS
+);
+
+config_accessor!(
+    antares_supervisor_interval_secs,
+    "antares_supervisor_interval_secs",
+    u64,
+    DEFAULT_ANTARES_SUPERVISOR_INTERVAL_SECS
+);
+
+// Remount broken mounts on their existing layers instead of leaving them `Failed`.
+config_accessor!(
+    antares_supervisor_remount,
+    "antares_supervisor_remount",
+    bool,
+    false
+);
+
+config_accessor!(
+    antares_gc_retention_secs,
+    "antares_gc_retention_secs",
+    u64,
+    DEFAULT_ANTARES_GC_RETENTION_SECS
+);
+
+fn non_empty(key: &str) -> Option<String> {
+    get_config()
+        .config
+        .get(key)
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(String::from)
+}
+
+/// TOML file declaring the bearer tokens and client certificates accepted by the
+/// daemon HTTP APIs, with their scopes. Optional: the APIs are open when unset.
+pub fn http_auth_file() -> Option<String> {
+    non_empty("http_auth_file")
+}
+
+/// PEM certificate chain served by the daemon HTTP APIs. Optional: plain HTTP when unset.
+pub fn http_tls_cert() -> Option<String> {
+    non_empty("http_tls_cert")
+}
+
+/// PEM private key matching `http_tls_cert`.
+pub fn http_tls_key() -> Option<String> {
+    non_empty("http_tls_key")
+}
+
+/// PEM bundle of the CAs whose client certificates identify callers (mTLS).
+/// Optional: client certificates are not requested when unset.
+pub fn http_tls_client_ca() -> Option<String> {
+    non_empty("http_tls_client_ca")
+}
+
+/// Unix socket of the Antares daemon API and default endpoint of the `antares` CLI.
+/// Optional: falls back to `antares.sock` next to `antares_state_file`.
+pub fn antares_socket() -> String {
+    non_empty("antares_socket").unwrap_or_else(|| {
+        Path::new(antares_state_file())
+            .parent()
+            .unwrap_or_else(|| Path::new("/"))
+            .join("antares.sock")
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+/// Unix socket of the Scorpio daemon API. Optional: TCP only when unset.
+pub fn scorpio_socket() -> Option<String> {
+    non_empty("scorpio_socket")
+}
+
+/// Permission bits, in octal, of the daemon unix sockets; they decide who may connect.
+/// Optional: defaults to `660`, or when the value is not an octal mode.
+pub fn http_socket_mode() -> u32 {
+    non_empty("http_socket_mode")
+        .and_then(|v| u32::from_str_radix(v.trim_start_matches("0o"), 8).ok())
+        .filter(|mode| *mode <= 0o777)
+        .unwrap_or(DEFAULT_HTTP_SOCKET_MODE)
+}
+
+/// Scope (`read`, `mount` or `admin`) of unix socket peers without a token, other than
+/// root and the daemon's own user, who get `admin`. Optional: defaults to `mount`.
+pub fn http_socket_peer_scope() -> Option<String> {
+    non_empty("http_socket_peer_scope")
+}
+
 pub fn antares_dicfuse_stat_mode() -> DicfuseStatMode {
     parse_stat_mode(
         get_config().config.get("antares_dicfuse_stat_mode"),
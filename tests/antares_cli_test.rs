@@ -0,0 +1,151 @@
+//! Exit codes and JSON output of the `antares` daemon client subcommands,
+//! checked against a canned HTTP daemon.
+
+use std::net::SocketAddr;
+use std::path::Path;
+
+use axum::{http::StatusCode, routing::get, Json, Router};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+const MOUNT_ID: &str = "6f9619ff-8b86-4d01-b42d-00c04fc964ff";
+
+/// Serve a fake daemon on an ephemeral port for the lifetime of the test process.
+fn spawn_daemon() -> SocketAddr {
+    let app = Router::new()
+        .route(
+            "/health",
+            get(|| async {
+                Json(json!({"status": "healthy", "mount_count": 1, "uptime_secs": 7}))
+            }),
+        )
+        .route(
+            "/mounts/{mount_id}",
+            get(|| async {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({"error": "mount not found", "code": "NOT_FOUND"})),
+                )
+            }),
+        )
+        .route(
+            "/mounts/{mount_id}/ready",
+            get(|| async {
+                Json(json!({"mount_id": MOUNT_ID, "ready": false, "state": "Provisioning"}))
+            }),
+        );
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            axum::serve(listener, app).await.unwrap();
+        });
+    });
+    addr
+}
+
+/// Write a complete config under `dir` so the CLI never touches shared paths.
+fn write_config(dir: &Path) -> String {
+    let root = dir.display();
+    let config = format!(
+        r#"base_url = "http://127.0.0.1:1"
+lfs_url = "http://127.0.0.1:1"
+workspace = "{root}/mount"
+store_path = "{root}/store"
+config_file = "{root}/config.toml"
+git_author = "MEGA"
+git_email = "admin@mega.org"
+dicfuse_readable = "true"
+load_dir_depth = "3"
+fetch_file_thread = "10"
+antares_upper_root = "{root}/antares/upper"
+antares_cl_root = "{root}/antares/cl"
+antares_mount_root = "{root}/antares/mnt"
+antares_state_file = "{root}/antares/state.toml"
+"#
+    );
+    let path = dir.join("scorpio.toml");
+    std::fs::write(&path, config).unwrap();
+    path.display().to_string()
+}
+
+fn antares(dir: &TempDir, args: &[&str]) -> assert_cmd::assert::Assert {
+    let config = write_config(dir.path());
+    assert_cmd::Command::cargo_bin("antares")
+        .unwrap()
+        .args(["--config-path", config.as_str(), "--json"])
+        .args(args)
+        .env_remove("ANTARES_TOKEN")
+        .assert()
+}
+
+fn stdout_json(assert: &assert_cmd::assert::Assert) -> Value {
+    serde_json::from_slice(&assert.get_output().stdout).unwrap()
+}
+
+#[test]
+fn test_cli_health_prints_json() {
+    let addr = spawn_daemon();
+    let dir = tempfile::tempdir().unwrap();
+    let endpoint = format!("http://{addr}");
+
+    let assert = antares(&dir, &["health", "--endpoint", &endpoint]).code(0);
+    let body = stdout_json(&assert);
+    assert_eq!(body["status"], "healthy");
+    assert_eq!(body["mount_count"], 1);
+}
+
+#[test]
+fn test_cli_describe_missing_mount_exits_not_found() {
+    let addr = spawn_daemon();
+    let dir = tempfile::tempdir().unwrap();
+    let endpoint = format!("http://{addr}");
+
+    let assert = antares(&dir, &["describe", MOUNT_ID, "--endpoint", &endpoint]).code(3);
+    let body = stdout_json(&assert);
+    assert_eq!(body["code"], "NOT_FOUND");
+}
+
+#[test]
+fn test_cli_wait_ready_times_out() {
+    let addr = spawn_daemon();
+    let dir = tempfile::tempdir().unwrap();
+    let endpoint = format!("http://{addr}");
+
+    let assert = antares(
+        &dir,
+        &[
+            "wait-ready",
+            MOUNT_ID,
+            "--timeout",
+            "0",
+            "--interval-ms",
+            "10",
+            "--endpoint",
+            &endpoint,
+        ],
+    )
+    .code(6);
+    let body = stdout_json(&assert);
+    assert_eq!(body["code"], "TIMEOUT");
+}
+
+#[test]
+fn test_cli_unreachable_daemon_fails() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let assert = antares(&dir, &["health", "--endpoint", "http://127.0.0.1:1"]).code(1);
+    let body = stdout_json(&assert);
+    assert_eq!(body["code"], "CONNECTION_ERROR");
+}
+
+#[test]
+fn test_cli_requires_mount_target() {
+    let dir = tempfile::tempdir().unwrap();
+
+    antares(&dir, &["describe", "--endpoint", "http://127.0.0.1:1"]).code(2);
+}
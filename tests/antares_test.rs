@@ -1,7 +1,7 @@
 //! Integration tests for Antares mount management.
 //!
-//! These tests verify the `AntaresManager` and `AntaresFuse` functionality,
-//! including custom mountpoint support via `mount_job_at`.
+//! These tests verify that `AntaresManager` drives the same mount engine and state as
+//! the daemon, including custom mountpoint support via `mount_job_at`.
 //!
 //! ## Running Tests
 //!
@@ -15,15 +15,20 @@
 //! sudo -E cargo test --test antares_test -- --ignored --nocapture --test-threads=1
 //! ```
 
+use axum::body::Body;
+use http::{Request, StatusCode};
 use scorpiofs::{
-    antares::fuse::AntaresFuse,
     antares::{AntaresManager, AntaresPaths},
+    daemon::antares::{
+        AntaresDaemon, AntaresServiceImpl, GcRequest, MountCollection, MountLifecycle, ServiceError,
+    },
     util::config,
 };
 use serial_test::serial;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tempfile::tempdir;
 use tokio::time::{sleep, Duration};
+use tower::ServiceExt;
 use uuid::Uuid;
 
 /// Helper to initialize config, ignoring "already initialized" errors.
@@ -35,159 +40,168 @@ fn init_config() {
     }
 }
 
+fn paths_under(root: &Path) -> AntaresPaths {
+    AntaresPaths::new(
+        root.join("upper"),
+        root.join("cl"),
+        root.join("mnt"),
+        root.join("state.toml"),
+    )
+}
+
 // =============================================================================
 // AntaresManager Unit Tests (no root required)
 // =============================================================================
 
 #[tokio::test]
-async fn test_mount_and_list_registers_instance() {
+#[serial]
+async fn test_manager_starts_from_empty_state() {
     init_config();
 
     let root = tempdir().unwrap();
-    let paths = AntaresPaths::new(
-        root.path().join("upper"),
-        root.path().join("cl"),
-        root.path().join("mnt"),
-        root.path().join("state.toml"),
-    );
-    let manager = AntaresManager::new(paths).await;
+    let manager = AntaresManager::new(paths_under(root.path())).await.unwrap();
 
-    let instance = manager.mount_job("job1", Some("cl1")).await.unwrap();
-    assert_eq!(instance.job_id, "job1");
-    assert!(instance.mountpoint.starts_with(root.path().join("mnt")));
+    assert!(manager.list().await.unwrap().is_empty());
+    assert_eq!(
+        manager.service().paths().state_file,
+        root.path().join("state.toml")
+    );
+    assert!(manager.umount_job("missing").await.unwrap().is_none());
+}
 
-    // Verify state persistence
-    let state_content = std::fs::read_to_string(root.path().join("state.toml")).unwrap();
-    assert!(state_content.contains("job1"));
+#[tokio::test]
+#[serial]
+async fn test_mount_job_at_rejects_busy_mountpoint() {
+    init_config();
 
-    // Verify listing
-    let listed = manager.list().await;
-    assert_eq!(listed.len(), 1);
-    assert_eq!(listed[0].job_id, "job1");
+    let root = tempdir().unwrap();
+    let manager = AntaresManager::new(paths_under(root.path())).await.unwrap();
+
+    // Existing mountpoints must be empty directories; nothing is mounted otherwise.
+    let busy = root.path().join("busy");
+    std::fs::create_dir_all(&busy).unwrap();
+    std::fs::write(busy.join("keep.txt"), b"keep").unwrap();
+    let err = manager.mount_job_at("job1", &busy, None).await.unwrap_err();
+    assert!(matches!(err, ServiceError::InvalidRequest(_)), "{err}");
+
+    let file = root.path().join("file");
+    std::fs::write(&file, b"x").unwrap();
+    let err = manager.mount_job_at("job2", &file, None).await.unwrap_err();
+    assert!(matches!(err, ServiceError::InvalidRequest(_)), "{err}");
+
+    assert_eq!(std::fs::read(busy.join("keep.txt")).unwrap(), b"keep");
+    assert!(manager.list().await.unwrap().is_empty());
+}
 
-    // Cleanup
-    let removed = manager.umount_job("job1").await.unwrap();
-    assert!(removed.is_some());
-    assert!(manager.list().await.is_empty());
+/// Write a state file recording a writable and a read-only job mount.
+fn write_job_state(paths: &AntaresPaths) -> (Uuid, Uuid) {
+    let (rw, ro) = (Uuid::new_v4(), Uuid::new_v4());
+    let mountpoint = |id: Uuid| paths.mount_root.join(id.to_string());
+    std::fs::write(
+        &paths.state_file,
+        format!(
+            "[[mounts]]\nmount_id = \"{rw}\"\njob_id = \"job1\"\npath = \"/\"\n\
+             mountpoint = \"{}\"\nupper_dir = \"{}\"\ncreated_at_epoch_ms = 0\n\n\
+             [[mounts]]\nmount_id = \"{ro}\"\njob_id = \"job_ro\"\npath = \"/\"\n\
+             mountpoint = \"{}\"\ncreated_at_epoch_ms = 0\n",
+            mountpoint(rw).display(),
+            paths.upper_root.join(rw.to_string()).display(),
+            mountpoint(ro).display(),
+        ),
+    )
+    .unwrap();
+    (rw, ro)
 }
 
 #[tokio::test]
-async fn test_mount_job_at_custom_path() {
+#[serial]
+async fn test_manager_lists_persisted_mounts_it_cannot_remount() {
     init_config();
 
     let root = tempdir().unwrap();
-    let custom_mount = root.path().join("custom_mountpoint");
+    let paths = paths_under(root.path());
+    let (rw, ro) = write_job_state(&paths);
+    let manager = AntaresManager::new(paths).await.unwrap();
 
-    let paths = AntaresPaths::new(
-        root.path().join("upper"),
-        root.path().join("cl"),
-        root.path().join("mnt"),
-        root.path().join("state.toml"),
+    // The mountpoints are gone, so the mounts come back as Failed instead of vanishing.
+    let listed = manager.list().await.unwrap();
+    assert_eq!(listed.len(), 2);
+    let job1 = listed.iter().find(|m| m.mount_id == rw).unwrap();
+    assert_eq!(job1.job_id.as_deref(), Some("job1"));
+    assert!(!job1.read_only);
+    assert!(
+        matches!(&job1.state, MountLifecycle::Failed { reason } if reason.starts_with("recovery failed")),
+        "{:?}",
+        job1.state
     );
-    let manager = AntaresManager::new(paths).await;
-
-    // Mount to custom path (outside of mount_root)
-    let instance = manager
-        .mount_job_at("job_custom", custom_mount.clone(), None)
-        .await
-        .unwrap();
-
-    // Verify mountpoint is at custom location
-    assert_eq!(instance.mountpoint, custom_mount);
-    assert!(!instance.mountpoint.starts_with(root.path().join("mnt")));
-
-    // Verify directory was created
-    assert!(custom_mount.exists());
-
-    // Verify state persistence includes custom mountpoint
-    let state_content = std::fs::read_to_string(root.path().join("state.toml")).unwrap();
-    assert!(state_content.contains("custom_mountpoint"));
-
-    // Verify listing
-    let listed = manager.list().await;
-    assert_eq!(listed.len(), 1);
-    assert_eq!(listed[0].mountpoint, custom_mount);
-
-    // Cleanup
-    let removed = manager.umount_job("job_custom").await.unwrap();
-    assert!(removed.is_some());
+    let job_ro = listed.iter().find(|m| m.mount_id == ro).unwrap();
+    assert!(job_ro.read_only);
 }
 
 #[tokio::test]
-async fn test_mount_job_at_multiple_custom_paths() {
+#[serial]
+async fn test_manager_umount_job_updates_list_and_state_file() {
     init_config();
 
     let root = tempdir().unwrap();
-    let paths = AntaresPaths::new(
-        root.path().join("upper"),
-        root.path().join("cl"),
-        root.path().join("mnt"),
-        root.path().join("state.toml"),
-    );
-    let manager = AntaresManager::new(paths).await;
-
-    // Mount multiple jobs to different custom paths
-    let custom1 = root.path().join("workspace1");
-    let custom2 = root.path().join("deeply/nested/workspace2");
-
-    let inst1 = manager
-        .mount_job_at("job1", custom1.clone(), None)
-        .await
-        .unwrap();
-    let inst2 = manager
-        .mount_job_at("job2", custom2.clone(), Some("cl-123"))
-        .await
-        .unwrap();
-
-    assert_eq!(inst1.mountpoint, custom1);
-    assert_eq!(inst2.mountpoint, custom2);
-    assert!(inst2.cl_dir.is_some()); // CL layer was requested
-
-    // Verify both directories were created (including nested path)
-    assert!(custom1.exists());
-    assert!(custom2.exists());
-
-    // Verify listing shows both
-    let listed = manager.list().await;
-    assert_eq!(listed.len(), 2);
+    let paths = paths_under(root.path());
+    let (_, ro) = write_job_state(&paths);
+    let manager = AntaresManager::new(paths.clone()).await.unwrap();
+
+    let removed = manager.umount_job("job1").await.unwrap().unwrap();
+    assert_eq!(removed.state, MountLifecycle::Unmounted);
+    assert!(manager.umount_job("job1").await.unwrap().is_none());
+    let listed = manager.list().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].mount_id, ro);
 
-    // Cleanup
-    manager.umount_job("job1").await.unwrap();
-    manager.umount_job("job2").await.unwrap();
-    assert!(manager.list().await.is_empty());
+    // The removal is persisted: a restarted manager only sees the remaining mount.
+    drop(manager);
+    let manager = AntaresManager::new(paths).await.unwrap();
+    let listed = manager.list().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].job_id.as_deref(), Some("job_ro"));
+    assert!(manager.umount_job("job1").await.unwrap().is_none());
 }
 
 #[tokio::test]
-async fn test_mount_job_at_with_pathbuf() {
+#[serial]
+async fn test_gc_keeps_layers_of_unrecovered_persisted_mounts() {
     init_config();
 
     let root = tempdir().unwrap();
-    let paths = AntaresPaths::new(
-        root.path().join("upper"),
-        root.path().join("cl"),
-        root.path().join("mnt"),
-        root.path().join("state.toml"),
-    );
-    let manager = AntaresManager::new(paths).await;
-
-    // Test with PathBuf
-    let custom = root.path().join("custom_path");
-    let inst = manager
-        .mount_job_at("job_pathbuf", custom.clone(), None)
-        .await
-        .unwrap();
-    assert_eq!(inst.mountpoint, custom);
-
-    // Test with &str
-    let custom_str = root.path().join("string_path");
-    let inst2 = manager
-        .mount_job_at("job_str", custom_str.clone(), None)
+    let paths = paths_under(root.path());
+    let mount_id = Uuid::new_v4();
+    let upper = paths.upper_root.join(mount_id.to_string());
+    let orphan = paths.upper_root.join(Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&upper).unwrap();
+    std::fs::write(upper.join("work.txt"), b"work").unwrap();
+    std::fs::create_dir_all(&orphan).unwrap();
+    std::fs::write(
+        &paths.state_file,
+        format!(
+            "[[mounts]]\nmount_id = \"{mount_id}\"\npath = \"/\"\nmountpoint = \"{}\"\n\
+             upper_dir = \"{}\"\ncreated_at_epoch_ms = 0\n",
+            paths.mount_root.join(mount_id.to_string()).display(),
+            upper.display()
+        ),
+    )
+    .unwrap();
+    sleep(Duration::from_millis(50)).await;
+
+    // Restart without recovery: the mount is only known from the state file.
+    let service = AntaresServiceImpl::with_paths(None, paths).await.unwrap();
+    let report = service
+        .collect_garbage_impl(GcRequest {
+            dry_run: false,
+            retention_secs: Some(0),
+        })
         .await
         .unwrap();
-    assert_eq!(inst2.mountpoint, custom_str);
 
-    manager.umount_job("job_pathbuf").await.unwrap();
-    manager.umount_job("job_str").await.unwrap();
+    assert!(report.failed.is_empty(), "{:?}", report.failed);
+    assert_eq!(std::fs::read(upper.join("work.txt")).unwrap(), b"work");
+    assert!(!orphan.exists());
 }
 
 // =============================================================================
@@ -219,7 +233,7 @@ fn fuse_prereqs_available() -> bool {
     true
 }
 
-/// Test that mount_job_at can mount to an arbitrary directory and file operations work.
+/// Test that mount_job_at mounts at an arbitrary directory and file operations work.
 ///
 /// Run with:
 ///   sudo -E cargo test --test antares_test test_fuse_mount_job_at_custom_path -- --exact --ignored --nocapture
@@ -239,63 +253,32 @@ async fn test_fuse_mount_job_at_custom_path() {
         let _ = std::fs::remove_dir_all(&base);
 
         let custom_mount = base.join("my_custom_workspace");
-        let paths = AntaresPaths::new(
-            base.join("upper"),
-            base.join("cl"),
-            base.join("mnt"),
-            base.join("state.toml"),
-        );
-
-        let manager = AntaresManager::new(paths).await;
+        let manager = AntaresManager::new(paths_under(&base)).await.unwrap();
 
         // Mount to custom path
         println!("Mounting job to custom path: {}", custom_mount.display());
-        let config = manager
+        let status = manager
             .mount_job_at("test-job", custom_mount.clone(), None)
             .await
             .expect("mount_job_at should succeed");
 
-        assert_eq!(config.mountpoint, custom_mount);
-        println!(
-            "✓ Job mounted at custom path: {}",
-            config.mountpoint.display()
-        );
-
-        // Create AntaresFuse and mount it
-        let dic = manager.dicfuse();
-        let mut fuse = AntaresFuse::new(
-            custom_mount.clone(),
-            dic,
-            config.upper_dir.clone(),
-            config.cl_dir.clone(),
-        )
-        .await
-        .expect("AntaresFuse::new should succeed");
-
-        fuse.mount().await.expect("FUSE mount should succeed");
-        println!("✓ FUSE filesystem mounted");
+        assert_eq!(PathBuf::from(&status.mountpoint), custom_mount);
+        assert!(status.layers.upper.is_some());
+        println!("✓ Job mounted at custom path: {}", status.mountpoint);
 
         sleep(Duration::from_millis(500)).await;
 
-        // Test basic file operations
-        println!("Testing file operations...");
-
         // Directory listing
         let read_result = tokio::fs::read_dir(&custom_mount).await;
         assert!(read_result.is_ok(), "should be able to read directory");
-        println!("✓ Directory listing works");
 
-        // File write
+        // File write and read
         let test_file = custom_mount.join("test_file.txt");
         tokio::fs::write(&test_file, b"Hello from custom mountpoint!")
             .await
             .unwrap();
-        println!("✓ File write works");
-
-        // File read
         let read_content = tokio::fs::read(&test_file).await.unwrap();
         assert_eq!(read_content, b"Hello from custom mountpoint!");
-        println!("✓ File read works");
 
         // Subdirectory
         let subdir = custom_mount.join("subdir");
@@ -304,15 +287,20 @@ async fn test_fuse_mount_job_at_custom_path() {
         tokio::fs::write(&subfile, b"nested content").await.unwrap();
         let nested = tokio::fs::read(&subfile).await.unwrap();
         assert_eq!(nested, b"nested content");
-        println!("✓ Subdirectory operations work");
+        println!("✓ File operations work");
+
+        // The mount is recorded in the daemon's state file format.
+        let state = std::fs::read_to_string(base.join("state.toml")).unwrap();
+        assert!(state.contains("test-job"));
+        assert!(state.contains("my_custom_workspace"));
 
         // Cleanup
-        println!("Unmounting...");
-        fuse.unmount().await.expect("unmount should succeed");
-        manager
+        let removed = manager
             .umount_job("test-job")
             .await
             .expect("manager umount should succeed");
+        assert!(removed.is_some());
+        assert!(manager.list().await.unwrap().is_empty());
         let _ = std::fs::remove_dir_all(&base);
         println!("✓ Test completed successfully");
     };
@@ -323,14 +311,14 @@ async fn test_fuse_mount_job_at_custom_path() {
     }
 }
 
-/// Test mounting multiple jobs to different custom paths with FUSE.
+/// Test that mounts of the manager and of the HTTP API are one set.
 ///
 /// Run with:
-///   sudo -E cargo test --test antares_test test_fuse_multiple_custom_mounts -- --exact --ignored --nocapture
+///   sudo -E cargo test --test antares_test test_fuse_manager_and_http_share_mounts -- --exact --ignored --nocapture
 #[tokio::test]
 #[ignore]
 #[serial]
-async fn test_fuse_multiple_custom_mounts() {
+async fn test_fuse_manager_and_http_share_mounts() {
     let test_future = async {
         init_config();
 
@@ -342,74 +330,65 @@ async fn test_fuse_multiple_custom_mounts() {
         let base = PathBuf::from(format!("/tmp/antares_multi_mount_test_{test_id}"));
         let _ = std::fs::remove_dir_all(&base);
 
-        let paths = AntaresPaths::new(
-            base.join("upper"),
-            base.join("cl"),
-            base.join("mnt"),
-            base.join("state.toml"),
-        );
-
-        let manager = AntaresManager::new(paths).await;
-        let dic = manager.dicfuse();
+        let manager = AntaresManager::new(paths_under(&base)).await.unwrap();
+        let app = AntaresDaemon::new(manager.service()).router();
 
         let mount1 = base.join("workspace_a");
         let mount2 = base.join("workspace_b");
-
-        // Mount first job
-        let config1 = manager
+        manager
             .mount_job_at("job-a", mount1.clone(), None)
             .await
             .unwrap();
-        let mut fuse1 =
-            AntaresFuse::new(mount1.clone(), dic.clone(), config1.upper_dir.clone(), None)
-                .await
-                .unwrap();
-        fuse1.mount().await.unwrap();
-        println!("✓ Job A mounted at {}", mount1.display());
-
-        // Mount second job
-        let config2 = manager
-            .mount_job_at("job-b", mount2.clone(), Some("cl-test"))
+        manager
+            .mount_job_at_read_only("job-b", mount2.clone(), None)
             .await
             .unwrap();
-        let mut fuse2 = AntaresFuse::new(
-            mount2.clone(),
-            dic.clone(),
-            config2.upper_dir.clone(),
-            config2.cl_dir.clone(),
-        )
-        .await
-        .unwrap();
-        fuse2.mount().await.unwrap();
-        println!("✓ Job B mounted at {}", mount2.display());
-
         sleep(Duration::from_millis(500)).await;
 
-        // Write different files to each mount
+        // Writes stay in their own mount; the read-only one rejects them.
         let file1 = mount1.join("file_from_job_a.txt");
-        let file2 = mount2.join("file_from_job_b.txt");
-
         tokio::fs::write(&file1, b"Written by job A").await.unwrap();
-        tokio::fs::write(&file2, b"Written by job B").await.unwrap();
-
-        // Verify isolation
         assert!(file1.exists());
-        assert!(file2.exists());
-        assert!(!mount1.join("file_from_job_b.txt").exists());
         assert!(!mount2.join("file_from_job_a.txt").exists());
-        println!("✓ Mount isolation verified");
+        let err = tokio::fs::write(mount2.join("denied.txt"), b"x")
+            .await
+            .unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EROFS));
 
-        // Verify both are tracked
-        let listed = manager.list().await;
-        assert_eq!(listed.len(), 2);
-        println!("✓ Both mounts tracked");
+        // Both are listed over HTTP ...
+        let response = app
+            .clone()
+            .oneshot(Request::get("/mounts").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let listed: MountCollection = serde_json::from_slice(&body).unwrap();
+        let mut jobs: Vec<_> = listed
+            .mounts
+            .iter()
+            .filter_map(|m| m.job_id.clone())
+            .collect();
+        jobs.sort();
+        assert_eq!(jobs, ["job-a", "job-b"]);
+
+        // ... and a mount deleted over HTTP is gone for the manager too.
+        let response = app
+            .oneshot(
+                Request::delete("/mounts/by-job/job-b")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let remaining = manager.list().await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].job_id.as_deref(), Some("job-a"));
 
-        // Cleanup
-        fuse1.unmount().await.unwrap();
-        fuse2.unmount().await.unwrap();
         manager.umount_job("job-a").await.unwrap();
-        manager.umount_job("job-b").await.unwrap();
-
         let _ = std::fs::remove_dir_all(&base);
         println!("✓ Test completed successfully");
     };